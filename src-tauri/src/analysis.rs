@@ -0,0 +1,393 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::quotecard::filter_path;
+use crate::supervisor::{run_supervised, Supervision};
+
+/// Length of one level measurement
+const FRAME_SECONDS: f64 = 0.05;
+/// Frames per classification window (1 second)
+const WINDOW_FRAMES: usize = 20;
+/// Windows quieter than this are silence, not speech
+const SILENCE_DB: f64 = -45.0;
+/// Mean frame-to-frame level change above which a window counts as speech.
+/// Syllables make speech levels jump by several dB every few frames, while
+/// mastered music stays within a dB or two.
+const SPEECH_MODULATION_DB: f64 = 3.0;
+/// Consecutive speech windows that end an intro (or start an outro)
+const SPEECH_RUN_WINDOWS: usize = 3;
+/// Seconds of voiced audio compared on each side of a candidate speaker change
+const CHANGE_CONTEXT_SECONDS: usize = 3;
+/// Jump in mean voiced level that suggests a different voice or microphone
+const CHANGE_LEVEL_DB: f64 = 6.0;
+/// Relative jump in mean zero-crossing rate (a cheap brightness measure)
+const CHANGE_BRIGHTNESS_RATIO: f64 = 0.35;
+/// Hints closer together than this are collapsed into the first one
+const MIN_CHANGE_SPACING_SECONDS: f64 = 5.0;
+
+/// What to do with music-only intros and outros.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MusicDetection {
+    #[default]
+    Off,
+    /// Leave the regions out of the chunks
+    Skip,
+    /// Chunk everything but report the regions
+    Mark,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegionKind {
+    Intro,
+    Outro,
+}
+
+/// A region of the source recording, in seconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AudioRegion {
+    pub kind: RegionKind,
+    pub start: f64,
+    pub end: f64,
+    /// Whether the region was left out of the chunks
+    pub skipped: bool,
+}
+
+/// A range of the source recording to leave out of chunking and transcription,
+/// such as a recorded ad or a break.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExcludedRange {
+    pub start: f64,
+    pub end: f64,
+    /// Marker text in the merged transcript; "[ad break]" when not set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+impl ExcludedRange {
+    pub fn duration(&self) -> f64 {
+        (self.end - self.start).max(0.0)
+    }
+}
+
+/// Looks for music-only stretches of at least `min_seconds` at the start and
+/// end of the recording, searching up to `max_search_seconds` from each edge.
+/// This is a level-modulation heuristic, not a trained VAD: sung vocals count
+/// as speech, which keeps the intro in rather than losing content.
+pub async fn detect_music_regions(
+    ffmpeg_path: &Path,
+    input_path: &str,
+    total_duration: f64,
+    min_seconds: f64,
+    max_search_seconds: f64,
+    supervision: Supervision,
+) -> Result<Vec<AudioRegion>> {
+    let search = max_search_seconds.min(total_duration);
+    let mut regions = Vec::new();
+
+    let head = measure_levels(ffmpeg_path, input_path, 0.0, search, supervision).await?;
+    let head_speech = classify_windows(&head);
+    let intro_end = leading_non_speech(&head_speech) as f64;
+    if intro_end >= min_seconds {
+        regions.push(AudioRegion { kind: RegionKind::Intro, start: 0.0, end: intro_end, skipped: false });
+    }
+
+    // The tail is only searched beyond the intro so the two cannot overlap
+    let tail_start = (total_duration - search).max(intro_end);
+    if total_duration - tail_start >= min_seconds {
+        let tail = measure_levels(ffmpeg_path, input_path, tail_start, total_duration - tail_start, supervision).await?;
+        let mut tail_speech = classify_windows(&tail);
+        tail_speech.reverse();
+        let outro_length = leading_non_speech(&tail_speech) as f64;
+        if outro_length >= min_seconds {
+            let start = (tail_start + tail_speech.len() as f64 - outro_length).max(intro_end);
+            regions.push(AudioRegion { kind: RegionKind::Outro, start, end: total_duration, skipped: false });
+        }
+    }
+
+    Ok(regions)
+}
+
+/// RMS level (dB) of each 50 ms frame of `duration` seconds from `start`.
+async fn measure_levels(
+    ffmpeg_path: &Path,
+    input_path: &str,
+    start: f64,
+    duration: f64,
+    supervision: Supervision,
+) -> Result<Vec<f64>> {
+    let filter = format!(
+        "aresample=16000,asetnsamples=n={}:p=0,astats=metadata=1:reset=1,\
+         ametadata=print:key=lavfi.astats.Overall.RMS_level",
+        (16000.0 * FRAME_SECONDS) as u32
+    );
+    let input_args = vec![
+        "-ss".to_string(), start.to_string(),
+        "-t".to_string(), duration.to_string(),
+        "-i".to_string(), input_path.to_string(),
+    ];
+
+    let output = print_metadata(ffmpeg_path, &input_args, &filter, supervision)
+        .await
+        .inspect_err(|e| println!("FFmpeg level analysis failed: {}", e))?;
+    Ok(parse_levels(&output))
+}
+
+/// Runs `filter` (ending in an `ametadata=print` without a target) over the
+/// input and returns what it printed. The metadata goes to a temporary file,
+/// as the supervisor does not keep FFmpeg's stdout.
+async fn print_metadata(ffmpeg_path: &Path, input_args: &[String], filter: &str, supervision: Supervision) -> Result<String> {
+    let metadata_path = std::env::temp_dir().join(format!("analysis_{}.txt", uuid::Uuid::new_v4()));
+    let filter = format!("{}:file={}", filter, filter_path(&metadata_path));
+
+    let run = run_supervised(
+        || {
+            let mut cmd = Command::new(ffmpeg_path);
+            cmd.args(input_args);
+            cmd.args(["-af", &filter, "-f", "null", "-"]);
+            cmd
+        },
+        supervision,
+    )
+    .await;
+    let output = tokio::fs::read_to_string(&metadata_path).await;
+    let _ = tokio::fs::remove_file(&metadata_path).await;
+    run?;
+    output.map_err(|e| anyhow!("Failed to read FFmpeg analysis output: {}", e))
+}
+
+/// Reads `lavfi.astats.Overall.RMS_level=-23.5` lines; `-inf` (digital silence) becomes -120 dB.
+fn parse_levels(output: &str) -> Vec<f64> {
+    output
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("lavfi.astats.Overall.RMS_level="))
+        .map(|value| value.parse::<f64>().ok().filter(|level| level.is_finite()).unwrap_or(-120.0))
+        .collect()
+}
+
+/// Speech flag per one-second window of frame levels.
+fn classify_windows(levels: &[f64]) -> Vec<bool> {
+    levels
+        .chunks(WINDOW_FRAMES)
+        .map(|window| {
+            let loudest = window.iter().cloned().fold(f64::MIN, f64::max);
+            if loudest < SILENCE_DB || window.len() < 2 {
+                return false;
+            }
+            let modulation = window
+                .windows(2)
+                .map(|pair| (pair[1].max(SILENCE_DB) - pair[0].max(SILENCE_DB)).abs())
+                .sum::<f64>()
+                / (window.len() - 1) as f64;
+            modulation > SPEECH_MODULATION_DB
+        })
+        .collect()
+}
+
+/// Number of windows before the first sustained run of speech. Short speech-like
+/// blips (a drum fill, a one-word sting) do not end the region.
+fn leading_non_speech(speech: &[bool]) -> usize {
+    let mut run = 0;
+    for (index, &is_speech) in speech.iter().enumerate() {
+        if is_speech {
+            run += 1;
+            if run == SPEECH_RUN_WINDOWS {
+                return index + 1 - SPEECH_RUN_WINDOWS;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    speech.len()
+}
+
+/// Level and brightness of one 50 ms frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct FrameStats {
+    level: f64,
+    zero_crossings: f64,
+}
+
+/// Times (seconds) where the speaker possibly changes: the end of a pause of at
+/// least `min_pause_seconds`, or an abrupt change of voiced level or brightness
+/// between the few seconds before and after. Meant as a readability hint for
+/// transcripts without diarization, not as speaker identification.
+pub async fn detect_speaker_changes(
+    ffmpeg_path: &Path,
+    input_path: &str,
+    min_pause_seconds: f64,
+    supervision: Supervision,
+) -> Result<Vec<f64>> {
+    let filter = format!(
+        "aresample=16000,aformat=channel_layouts=mono,asetnsamples=n={}:p=0,astats=metadata=1:reset=1,\
+         ametadata=print",
+        (16000.0 * FRAME_SECONDS) as u32
+    );
+    let input_args = vec!["-i".to_string(), input_path.to_string()];
+
+    let output = print_metadata(ffmpeg_path, &input_args, &filter, supervision)
+        .await
+        .inspect_err(|e| println!("FFmpeg speaker change analysis failed: {}", e))?;
+    let frames = parse_frame_stats(&output);
+    Ok(find_change_points(&frames, min_pause_seconds))
+}
+
+/// Pairs each frame's `Overall.RMS_level` with the mono channel's `Zero_crossings_rate`.
+fn parse_frame_stats(output: &str) -> Vec<FrameStats> {
+    let mut frames = Vec::new();
+    let mut zero_crossings = 0.0;
+    for line in output.lines().map(str::trim) {
+        if let Some(value) = line.strip_prefix("lavfi.astats.1.Zero_crossings_rate=") {
+            zero_crossings = value.parse().unwrap_or(0.0);
+        } else if let Some(value) = line.strip_prefix("lavfi.astats.Overall.RMS_level=") {
+            let level = value.parse::<f64>().ok().filter(|level| level.is_finite()).unwrap_or(-120.0);
+            frames.push(FrameStats { level, zero_crossings });
+        }
+    }
+    frames
+}
+
+fn find_change_points(frames: &[FrameStats], min_pause_seconds: f64) -> Vec<f64> {
+    let frames_per_second = (1.0 / FRAME_SECONDS) as usize;
+    let context = CHANGE_CONTEXT_SECONDS * frames_per_second;
+    let min_pause_frames = (min_pause_seconds / FRAME_SECONDS).ceil() as usize;
+    let is_voiced = |frame: &&FrameStats| frame.level >= SILENCE_DB;
+    let mean = |frames: &[FrameStats]| {
+        let voiced: Vec<&FrameStats> = frames.iter().filter(is_voiced).collect();
+        // Mostly silent context says nothing about the voice
+        if voiced.is_empty() || voiced.len() < frames.len() / 3 {
+            return None;
+        }
+        let count = voiced.len() as f64;
+        Some((
+            voiced.iter().map(|frame| frame.level).sum::<f64>() / count,
+            voiced.iter().map(|frame| frame.zero_crossings).sum::<f64>() / count,
+        ))
+    };
+
+    let mut candidates = Vec::new();
+
+    let mut silent_run = 0;
+    for (index, frame) in frames.iter().enumerate() {
+        if frame.level < SILENCE_DB {
+            silent_run += 1;
+            continue;
+        }
+        if index > 0 && silent_run >= min_pause_frames {
+            candidates.push(index as f64 * FRAME_SECONDS);
+        }
+        silent_run = 0;
+    }
+
+    // Contrast between the context before and after each second boundary,
+    // 1.0 being the threshold for either measure
+    let contrast: Vec<f64> = (0..=frames.len() / frames_per_second)
+        .map(|second| {
+            let index = second * frames_per_second;
+            // Changes across a pause are left to the pause check above
+            if index < context || index + context > frames.len() || frames[index].level < SILENCE_DB {
+                return 0.0;
+            }
+            match (mean(&frames[index - context..index]), mean(&frames[index..index + context])) {
+                (Some((level_before, zcr_before)), Some((level_after, zcr_after))) => {
+                    let brightness = (zcr_after - zcr_before).abs() / zcr_before.max(zcr_after).max(f64::EPSILON);
+                    ((level_after - level_before).abs() / CHANGE_LEVEL_DB).max(brightness / CHANGE_BRIGHTNESS_RATIO)
+                }
+                _ => 0.0,
+            }
+        })
+        .collect();
+    // The context overlaps the change for a few seconds around it; keep the peak
+    for (second, &score) in contrast.iter().enumerate() {
+        let previous = if second > 0 { contrast[second - 1] } else { 0.0 };
+        let next = contrast.get(second + 1).copied().unwrap_or(0.0);
+        if score >= 1.0 && score > previous && score >= next {
+            candidates.push(second as f64);
+        }
+    }
+
+    candidates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let mut changes: Vec<f64> = Vec::new();
+    for time in candidates {
+        if changes.last().is_none_or(|&last| time - last >= MIN_CHANGE_SPACING_SECONDS) {
+            changes.push(time);
+        }
+    }
+    changes
+}
+
+/// Splits `[0, total]` into the spans left after removing `excluded` ranges.
+pub fn kept_spans(total: f64, excluded: &[(f64, f64)]) -> Vec<(f64, f64)> {
+    let mut excluded = excluded.to_vec();
+    excluded.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut spans = Vec::new();
+    let mut position = 0.0;
+    for (start, end) in excluded {
+        let start = start.clamp(0.0, total);
+        if start > position {
+            spans.push((position, start));
+        }
+        position = position.max(end.min(total));
+    }
+    if position < total {
+        spans.push((position, total));
+    }
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_music_then_speech() {
+        // 5 s of steady music, a one-second blip, 3 s of steady music, then speech
+        let mut levels = vec![-18.0; 5 * WINDOW_FRAMES];
+        levels.extend((0..WINDOW_FRAMES).map(|i| if i % 2 == 0 { -15.0 } else { -30.0 }));
+        levels.extend(vec![-19.0; 3 * WINDOW_FRAMES]);
+        levels.extend((0..4 * WINDOW_FRAMES).map(|i| if i % 3 == 0 { -40.0 } else { -20.0 }));
+
+        let speech = classify_windows(&levels);
+        assert_eq!(speech, vec![false, false, false, false, false, true, false, false, false, true, true, true, true]);
+        assert_eq!(leading_non_speech(&speech), 9);
+    }
+
+    #[test]
+    fn test_parse_levels_and_spans() {
+        let output = "frame:0    pts:0       pts_time:0\n\
+                      lavfi.astats.Overall.RMS_level=-23.5\n\
+                      frame:1    pts:800     pts_time:0.05\n\
+                      lavfi.astats.Overall.RMS_level=-inf\n";
+        assert_eq!(parse_levels(output), vec![-23.5, -120.0]);
+
+        assert_eq!(kept_spans(100.0, &[(90.0, 100.0), (0.0, 12.0)]), vec![(12.0, 90.0)]);
+        assert_eq!(kept_spans(100.0, &[(10.0, 20.0), (15.0, 30.0)]), vec![(0.0, 10.0), (30.0, 100.0)]);
+    }
+
+    #[test]
+    fn test_speaker_change_points() {
+        let output = "lavfi.astats.1.Zero_crossings_rate=0.12\n\
+                      lavfi.astats.Overall.RMS_level=-20.0\n";
+        assert_eq!(parse_frame_stats(output), vec![FrameStats { level: -20.0, zero_crossings: 0.12 }]);
+
+        let voice = |level: f64, zero_crossings: f64, seconds: usize| {
+            vec![FrameStats { level, zero_crossings }; seconds * WINDOW_FRAMES]
+        };
+        // A low voice, a brighter and louder one without a pause, 3 s of silence, the first voice again
+        let mut frames = voice(-25.0, 0.05, 10);
+        frames.extend(voice(-17.0, 0.12, 10));
+        frames.extend(voice(-90.0, 0.0, 3));
+        frames.extend(voice(-25.0, 0.05, 10));
+        assert_eq!(find_change_points(&frames, 2.0), vec![10.0, 23.0]);
+
+        // Steady speech with short breaths gives no hints
+        let steady: Vec<FrameStats> = (0..20 * WINDOW_FRAMES)
+            .map(|i| FrameStats { level: if i % 40 < 2 { -60.0 } else { -22.0 }, zero_crossings: 0.08 })
+            .collect();
+        assert!(find_change_points(&steady, 2.0).is_empty());
+    }
+}
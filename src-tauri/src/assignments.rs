@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{ChunkAssignment, ChunkManifest, ChunkStatus};
+
+/// One chunk as shown on the team dashboard.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkJob {
+    pub chunk_number: usize,
+    pub file_name: String,
+    pub start_time: f64,
+    pub duration: f64,
+    pub assigned_to: Option<String>,
+    pub status: ChunkStatus,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssignmentDashboard {
+    pub jobs: Vec<ChunkJob>,
+    pub pending: usize,
+    pub in_progress: usize,
+    pub done: usize,
+    /// Chunks nobody has taken yet
+    pub unassigned: usize,
+}
+
+/// Changes who works on a chunk and/or its status; `None` keeps the current
+/// value, an empty name clears the assignee.
+pub fn update_assignment(
+    manifest: &mut ChunkManifest,
+    chunk_number: usize,
+    assigned_to: Option<String>,
+    status: Option<ChunkStatus>,
+) -> Result<ChunkAssignment> {
+    if !manifest.chunks.iter().any(|chunk| chunk.chunk_number == chunk_number) {
+        return Err(anyhow!("Chunk {} is not in the manifest", chunk_number));
+    }
+
+    let index = match manifest.assignments.iter().position(|assignment| assignment.chunk_number == chunk_number) {
+        Some(index) => index,
+        None => {
+            manifest.assignments.push(ChunkAssignment { chunk_number, assigned_to: None, status: ChunkStatus::Pending });
+            manifest.assignments.len() - 1
+        }
+    };
+    let assignment = &mut manifest.assignments[index];
+    if let Some(assigned_to) = assigned_to {
+        let assigned_to = assigned_to.trim();
+        assignment.assigned_to = (!assigned_to.is_empty()).then(|| assigned_to.to_string());
+    }
+    if let Some(status) = status {
+        assignment.status = status;
+    }
+    Ok(assignment.clone())
+}
+
+/// Every chunk with its assignment, in chunk order, optionally only those of
+/// one person, with counts per status over the listed chunks.
+pub fn dashboard(manifest: &ChunkManifest, assigned_to: Option<&str>) -> AssignmentDashboard {
+    let mut jobs: Vec<ChunkJob> = manifest
+        .chunks
+        .iter()
+        .map(|chunk| {
+            let assignment = manifest.assignments.iter().find(|assignment| assignment.chunk_number == chunk.chunk_number);
+            ChunkJob {
+                chunk_number: chunk.chunk_number,
+                file_name: chunk.file_name.clone(),
+                start_time: chunk.start_time,
+                duration: chunk.duration,
+                assigned_to: assignment.and_then(|assignment| assignment.assigned_to.clone()),
+                status: assignment.map(|assignment| assignment.status).unwrap_or_default(),
+            }
+        })
+        .filter(|job| {
+            assigned_to.is_none_or(|name| {
+                job.assigned_to.as_deref().is_some_and(|assignee| assignee.to_lowercase() == name.trim().to_lowercase())
+            })
+        })
+        .collect();
+    jobs.sort_by_key(|job| job.chunk_number);
+
+    let count = |status: ChunkStatus| jobs.iter().filter(|job| job.status == status).count();
+    AssignmentDashboard {
+        pending: count(ChunkStatus::Pending),
+        in_progress: count(ChunkStatus::InProgress),
+        done: count(ChunkStatus::Done),
+        unassigned: jobs.iter().filter(|job| job.assigned_to.is_none()).count(),
+        jobs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ManifestChunk;
+
+    #[test]
+    fn test_assignments_dashboard() {
+        let chunk = |chunk_number: usize| ManifestChunk {
+            file_name: format!("chunk_{:03}.mp3", chunk_number),
+            chunk_number,
+            start_time: (chunk_number - 1) as f64 * 600.0,
+            duration: 600.0,
+        };
+        let mut manifest = ChunkManifest {
+            source: "meeting.mp3".to_string(),
+            chunks: vec![chunk(1), chunk(2), chunk(3)],
+            ..ChunkManifest::default()
+        };
+
+        update_assignment(&mut manifest, 2, Some("Анна".to_string()), Some(ChunkStatus::InProgress)).unwrap();
+        update_assignment(&mut manifest, 1, Some("Борис".to_string()), None).unwrap();
+        update_assignment(&mut manifest, 1, None, Some(ChunkStatus::Done)).unwrap();
+        assert!(update_assignment(&mut manifest, 4, None, Some(ChunkStatus::Done)).is_err());
+
+        let board = dashboard(&manifest, None);
+        assert_eq!((board.pending, board.in_progress, board.done, board.unassigned), (1, 1, 1, 1));
+        assert_eq!(board.jobs[0].assigned_to.as_deref(), Some("Борис"));
+
+        update_assignment(&mut manifest, 1, Some(" ".to_string()), None).unwrap();
+        let board = dashboard(&manifest, Some("Анна"));
+        assert_eq!(board.jobs.iter().map(|job| job.chunk_number).collect::<Vec<_>>(), vec![2]);
+        assert_eq!(dashboard(&manifest, None).unassigned, 2);
+    }
+}
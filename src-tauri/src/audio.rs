@@ -1,8 +1,21 @@
 use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use tokio::process::Command;
 use tokio::fs;
+use crate::analysis::{detect_music_regions, detect_speaker_changes, kept_spans, AudioRegion, ExcludedRange, MusicDetection};
 use crate::ffmpeg::FFmpegManager;
+use crate::power::{power_source, BatteryMode, PowerSource};
+use crate::supervisor::{run_supervised, Supervision};
+
+/// How far from each end of a recording music intros/outros are searched for
+const MUSIC_SEARCH_SECONDS: f64 = 180.0;
+
+/// How often a paused run checks whether AC power is back
+const POWER_POLL_SECONDS: u64 = 30;
+
+/// Written next to the chunks by [`AudioProcessor::process_audio_file`]
+pub const CHUNK_MANIFEST_FILE: &str = "chunks.json";
 
 #[derive(Debug, Clone)]
 pub struct AudioChunk {
@@ -10,13 +23,145 @@ pub struct AudioChunk {
     pub start_time: f64,
     pub duration: f64,
     pub chunk_number: usize,
+    /// FFmpeg runs killed for hanging before the chunk was written
+    pub retries: u32,
+}
+
+/// Chunks written for a recording and the regions found while analysing it.
+#[derive(Debug, Clone)]
+pub struct ProcessedAudio {
+    pub chunks: Vec<AudioChunk>,
+    pub regions: Vec<AudioRegion>,
+    /// Possible speaker changes in the source, in seconds
+    pub speaker_changes: Vec<f64>,
+    pub manifest_path: PathBuf,
+}
+
+/// Where each chunk sits in the source recording, so the merge can place the
+/// chunk transcripts exactly instead of estimating file durations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChunkManifest {
+    pub source: String,
+    pub chunks: Vec<ManifestChunk>,
+    /// Silences found in the source when splitting at silence, in source time
+    #[serde(default)]
+    pub silences: Vec<SilenceRange>,
+    /// Who transcribes which chunk, for teams sharing the chunk folder
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub assignments: Vec<ChunkAssignment>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SilenceRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChunkStatus {
+    #[default]
+    Pending,
+    InProgress,
+    Done,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkAssignment {
+    pub chunk_number: usize,
+    pub assigned_to: Option<String>,
+    pub status: ChunkStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestChunk {
+    pub file_name: String,
+    pub chunk_number: usize,
+    /// Seconds from the start of the source recording
+    pub start_time: f64,
+    pub duration: f64,
+}
+
+impl ChunkManifest {
+    pub fn from_chunks(source: &str, chunks: &[AudioChunk]) -> Self {
+        Self {
+            source: source.to_string(),
+            chunks: chunks
+                .iter()
+                .map(|chunk| ManifestChunk {
+                    file_name: chunk.path.file_name().unwrap_or_default().to_string_lossy().to_string(),
+                    chunk_number: chunk.chunk_number,
+                    start_time: chunk.start_time,
+                    duration: chunk.duration,
+                })
+                .collect(),
+            silences: Vec::new(),
+            assignments: Vec::new(),
+        }
+    }
+
+    pub async fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).await?;
+        serde_json::from_str(&content).map_err(|e| anyhow!("Invalid chunk manifest {}: {}", path.display(), e))
+    }
+
+    pub async fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// The chunk a transcript was made from: the one with the same file stem
+    /// ("chunk_003.srt" for "chunk_003.mp3"), otherwise the one whose number
+    /// matches the transcript's sequence number.
+    pub fn chunk_for(&self, transcript_name: &str, sequence_number: Option<usize>) -> Option<&ManifestChunk> {
+        let stem = |name: &str| Path::new(name).file_stem().unwrap_or_default().to_string_lossy().to_lowercase();
+        let transcript_stem = stem(transcript_name);
+        self.chunks
+            .iter()
+            .find(|chunk| stem(&chunk.file_name) == transcript_stem)
+            .or_else(|| self.chunks.iter().find(|chunk| Some(chunk.chunk_number) == sequence_number))
+    }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ProcessingOptions {
     pub max_duration_seconds: u32,
     pub use_silence_detection: bool,
     pub output_format: String,
+    /// Name of the chunk folder next to the source; `<source>_segments` when not set
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub folder_name: Option<String>,
+    /// Detect music-only intros/outros and skip or just report them
+    pub music_detection: MusicDetection,
+    /// Shortest stretch of music treated as an intro/outro
+    pub min_music_seconds: f64,
+    /// Ranges (ads, breaks) left out of the chunks; pass the same list to the
+    /// merge so timestamps line up with the original recording again
+    pub excluded_ranges: Vec<ExcludedRange>,
+    /// Look for pauses and voice changes to hint at speaker changes in the merge
+    pub speaker_change_hints: bool,
+    /// Shortest pause counted as a possible speaker change
+    pub speaker_change_pause_seconds: f64,
+    /// Longest FFmpeg may take to cut one chunk before it is killed and retried
+    pub chunk_timeout_seconds: u64,
+    /// FFmpeg counts as hung when it reports no progress for this long
+    pub stall_timeout_seconds: u64,
+    /// How often a hung FFmpeg run is started again before processing fails
+    pub max_retries: u32,
+    /// Battery behaviour, loaded from the settings per run
+    #[serde(skip)]
+    pub battery_mode: BatteryMode,
+}
+
+impl ProcessingOptions {
+    fn supervision(&self, timeout: bool) -> Supervision {
+        Supervision {
+            timeout: timeout.then(|| std::time::Duration::from_secs(self.chunk_timeout_seconds)),
+            stall_timeout: std::time::Duration::from_secs(self.stall_timeout_seconds),
+            max_retries: self.max_retries,
+        }
+    }
 }
 
 impl Default for ProcessingOptions {
@@ -25,6 +170,16 @@ impl Default for ProcessingOptions {
             max_duration_seconds: 1800, // 30 minutes
             use_silence_detection: true,
             output_format: "mp3".to_string(),
+            folder_name: None,
+            music_detection: MusicDetection::Off,
+            min_music_seconds: 8.0,
+            excluded_ranges: Vec::new(),
+            speaker_change_hints: false,
+            speaker_change_pause_seconds: 2.0,
+            chunk_timeout_seconds: 600,
+            stall_timeout_seconds: 120,
+            max_retries: 2,
+            battery_mode: BatteryMode::Off,
         }
     }
 }
@@ -54,7 +209,7 @@ impl AudioProcessor {
         input_path: &str,
         options: ProcessingOptions,
         progress_callback: impl Fn(f32, String) + Clone,
-    ) -> Result<Vec<AudioChunk>> {
+    ) -> Result<ProcessedAudio> {
         println!("Starting audio processing for: {}", input_path);
         progress_callback(0.0, "Анализ аудиофайла...".to_string());
         
@@ -70,7 +225,10 @@ impl AudioProcessor {
         let input_path_buf = Path::new(input_path);
         let output_dir = if let Some(parent) = input_path_buf.parent() {
             let file_stem = input_path_buf.file_stem().unwrap_or_default().to_string_lossy();
-            parent.join(format!("{}_segments", file_stem))
+            match &options.folder_name {
+                Some(folder_name) => parent.join(folder_name),
+                None => parent.join(format!("{}_segments", file_stem)),
+            }
         } else {
             Path::new(".").join("audio_segments")
         };
@@ -78,87 +236,161 @@ impl AudioProcessor {
         fs::create_dir_all(&output_dir).await?;
         println!("Created output directory: {:?}", output_dir);
         
+        let mut regions = Vec::new();
+        if options.music_detection != MusicDetection::Off {
+            progress_callback(5.0, "Поиск музыкальных вставок в начале и конце...".to_string());
+            let ffmpeg_path = self.ffmpeg_manager.get_ffmpeg_path()?;
+            regions = detect_music_regions(
+                &ffmpeg_path,
+                input_path,
+                total_duration,
+                options.min_music_seconds,
+                MUSIC_SEARCH_SECONDS,
+                options.supervision(false),
+            )
+            .await?;
+            for region in regions.iter_mut() {
+                region.skipped = options.music_detection == MusicDetection::Skip;
+            }
+            println!("Detected music regions: {:?}", regions);
+        }
+
         progress_callback(10.0, "Планирование разделения аудио...".to_string());
-        
-        let chunks = if options.use_silence_detection {
+
+        let excluded: Vec<(f64, f64)> = regions
+            .iter()
+            .filter(|region| region.skipped)
+            .map(|region| (region.start, region.end))
+            .chain(options.excluded_ranges.iter().map(|range| (range.start, range.end)))
+            .collect();
+        let spans = kept_spans(total_duration, &excluded);
+
+        let silences = if options.use_silence_detection {
             println!("Using silence detection for splitting");
-            self.split_by_silence(input_path, &options, total_duration, &output_dir, progress_callback.clone()).await?
+            progress_callback(15.0, "Поиск точек тишины...".to_string());
+            self.detect_silences(input_path, options.supervision(false)).await?
         } else {
             println!("Using time-based splitting");
-            self.split_by_time(input_path, &options, total_duration, &output_dir, progress_callback.clone()).await?
+            Vec::new()
         };
+        let silence_points = silence_points(&silences);
+
+        let mut speaker_changes = Vec::new();
+        if options.speaker_change_hints {
+            progress_callback(20.0, "Поиск возможных смен говорящего...".to_string());
+            let ffmpeg_path = self.ffmpeg_manager.get_ffmpeg_path()?;
+            speaker_changes =
+                detect_speaker_changes(&ffmpeg_path, input_path, options.speaker_change_pause_seconds, options.supervision(false))
+                    .await?;
+            println!("Detected {} possible speaker changes", speaker_changes.len());
+        }
+
+        let mut chunks = Vec::new();
+        for span in spans {
+            let first_chunk = chunks.len() + 1;
+            let span_chunks = if options.use_silence_detection {
+                self.split_by_silence(input_path, &options, span, first_chunk, &silence_points, &output_dir, progress_callback.clone()).await?
+            } else {
+                self.split_by_time(input_path, &options, span, first_chunk, &output_dir, progress_callback.clone()).await?
+            };
+            chunks.extend(span_chunks);
+        }
         
         println!("Created {} chunks", chunks.len());
+
+        let manifest_path = output_dir.join(CHUNK_MANIFEST_FILE);
+        let manifest = ChunkManifest { silences, ..ChunkManifest::from_chunks(input_path, &chunks) };
+        manifest.save(&manifest_path).await?;
+
         progress_callback(100.0, "Обработка аудио завершена!".to_string());
         
-        Ok(chunks)
+        Ok(ProcessedAudio { chunks, regions, speaker_changes, manifest_path })
     }
 
+    /// Splits the `(start, end)` span of the source into equal chunks,
+    /// numbered from `first_chunk`.
+    #[allow(clippy::too_many_arguments)]
     async fn split_by_time(
         &self,
         input_path: &str,
         options: &ProcessingOptions,
-        total_duration: f64,
+        span: (f64, f64),
+        first_chunk: usize,
         output_dir: &Path,
         progress_callback: impl Fn(f32, String),
     ) -> Result<Vec<AudioChunk>> {
+        let (span_start, span_end) = span;
+        let span_duration = span_end - span_start;
         let max_duration = options.max_duration_seconds as f64;
-        let chunk_count = (total_duration / max_duration).ceil() as usize;
+        let chunk_count = (span_duration / max_duration).ceil() as usize;
         let mut chunks = Vec::new();
 
         for i in 0..chunk_count {
-            let start_time = i as f64 * max_duration;
-            let duration = if start_time + max_duration > total_duration {
-                total_duration - start_time
+            let offset = i as f64 * max_duration;
+            let start_time = span_start + offset;
+            let duration = if offset + max_duration > span_duration {
+                span_duration - offset
             } else {
                 max_duration
             };
+            let chunk_number = first_chunk + i;
 
             progress_callback(
                 20.0 + (70.0 * (i as f32 + 1.0) / chunk_count as f32),
                 format!("Обработка сегмента {} из {}...", i + 1, chunk_count),
             );
 
-            let chunk_path = output_dir.join(format!("chunk_{:03}.{}", i + 1, options.output_format));
-            
-            self.extract_audio_segment(input_path, &chunk_path, start_time, duration).await?;
+            let chunk_path = output_dir.join(format!("chunk_{:03}.{}", chunk_number, options.output_format));
+            let low_power = wait_for_power(options, &progress_callback).await;
+
+            let retries = self
+                .extract_audio_segment(input_path, &chunk_path, start_time, duration, options.supervision(true), low_power)
+                .await?;
 
             chunks.push(AudioChunk {
                 path: chunk_path,
                 start_time,
                 duration,
-                chunk_number: i + 1,
+                chunk_number,
+                retries,
             });
         }
 
         Ok(chunks)
     }
 
+    /// Splits the `(start, end)` span at silence points so no chunk exceeds the
+    /// maximum duration, numbering chunks from `first_chunk`.
+    #[allow(clippy::too_many_arguments)]
     async fn split_by_silence(
         &self,
         input_path: &str,
         options: &ProcessingOptions,
-        total_duration: f64,
+        span: (f64, f64),
+        first_chunk: usize,
+        all_silence_points: &[f64],
         output_dir: &Path,
         progress_callback: impl Fn(f32, String),
     ) -> Result<Vec<AudioChunk>> {
-        progress_callback(15.0, "Поиск точек тишины...".to_string());
-        
-        // Detect silence points
-        let silence_points = self.detect_silence_points(input_path).await?;
+        let (span_start, total_duration) = span;
+        let silence_points: Vec<f64> = all_silence_points
+            .iter()
+            .cloned()
+            .filter(|&point| point > span_start && point < total_duration)
+            .collect();
         println!("Found {} silence points: {:?}", silence_points.len(), silence_points);
         
         // If no silence points found or very few, fallback to time-based splitting
         if silence_points.len() < 2 {
             println!("Not enough silence points found, falling back to time-based splitting");
-            return self.split_by_time(input_path, options, total_duration, output_dir, progress_callback).await;
+            return self.split_by_time(input_path, options, span, first_chunk, output_dir, progress_callback).await;
         }
         
         progress_callback(25.0, "Создание сегментов на основе тишины...".to_string());
         
         let mut chunks = Vec::new();
-        let mut current_start = 0.0;
-        let mut chunk_number = 1;
+        let mut current_start = span_start;
+        let mut chunk_number = first_chunk;
         let max_duration = options.max_duration_seconds as f64;
 
         for (i, &silence_point) in silence_points.iter().enumerate() {
@@ -175,14 +407,18 @@ impl AudioProcessor {
                 let actual_duration = end_time - current_start;
 
                 let chunk_path = output_dir.join(format!("chunk_{:03}.{}", chunk_number, options.output_format));
-                
-                self.extract_audio_segment(input_path, &chunk_path, current_start, actual_duration).await?;
+                let low_power = wait_for_power(options, &progress_callback).await;
+
+                let retries = self
+                    .extract_audio_segment(input_path, &chunk_path, current_start, actual_duration, options.supervision(true), low_power)
+                    .await?;
 
                 chunks.push(AudioChunk {
                     path: chunk_path,
                     start_time: current_start,
                     duration: actual_duration,
                     chunk_number,
+                    retries,
                 });
 
                 current_start = silence_point;
@@ -192,64 +428,85 @@ impl AudioProcessor {
 
         // Handle case where no silence was detected
         if chunks.is_empty() {
-            return self.split_by_time(input_path, options, total_duration, output_dir, progress_callback).await;
+            return self.split_by_time(input_path, options, span, first_chunk, output_dir, progress_callback).await;
         }
 
         Ok(chunks)
     }
 
-    async fn detect_silence_points(&self, input_path: &str) -> Result<Vec<f64>> {
+    async fn detect_silences(&self, input_path: &str, supervision: Supervision) -> Result<Vec<SilenceRange>> {
         println!("Detecting silence points in: {}", input_path);
         let ffmpeg_path = self.ffmpeg_manager.get_ffmpeg_path()?;
-        
-        let mut cmd = Command::new(&ffmpeg_path);
-        cmd.args([
-            "-i", input_path,
-            "-af", "silencedetect=noise=-40dB:duration=1",  // More sensitive settings
-            "-f", "null",
-            "-",
-            "-v", "info",
-        ]);
-        
-        #[cfg(target_os = "windows")]
-        {
-            use std::os::windows::process::CommandExt;
-            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        }
-        
-        let output = cmd.output()?;
 
-        let stderr = String::from_utf8_lossy(&output.stderr);
+        let run = run_supervised(
+            || {
+                let mut cmd = Command::new(&ffmpeg_path);
+                cmd.args([
+                    "-i", input_path,
+                    "-af", "silencedetect=noise=-40dB:duration=1",  // More sensitive settings
+                    "-f", "null",
+                    "-",
+                    "-v", "info",
+                ]);
+                cmd
+            },
+            supervision,
+        )
+        .await?;
+
+        let stderr = run.stderr;
         println!("FFmpeg silence detection output: {}", stderr);
         
-        let mut silence_points = Vec::new();
+        let mut silences = Vec::new();
 
         for line in stderr.lines() {
             if line.contains("silence_end") {
                 println!("Found silence_end line: {}", line);
-                if let Some(time_str) = extract_time_from_silence_line(line) {
-                    if let Ok(time) = time_str.parse::<f64>() {
-                        println!("Parsed silence point: {}", time);
-                        silence_points.push(time);
-                    }
+                if let Some(silence) = parse_silence_line(line) {
+                    println!("Parsed silence: {:?}", silence);
+                    silences.push(silence);
                 }
             }
         }
 
-        // Sort and deduplicate
-        silence_points.sort_by(|a, b| a.partial_cmp(b).unwrap());
-        silence_points.dedup_by(|a, b| (*a - *b).abs() < 0.1);
+        Ok(silences)
+    }
+
+    /// Cuts one MP3 clip per `(number, start, end)` range into `output_dir`,
+    /// named by number and start time. `padding` seconds are added on both
+    /// sides so words are not clipped mid-syllable.
+    pub async fn extract_clips(
+        &self,
+        input_path: &str,
+        ranges: &[(usize, f64, f64)],
+        output_dir: &Path,
+        padding: f64,
+    ) -> Result<Vec<PathBuf>> {
+        let mut paths = Vec::new();
+
+        for &(number, start, end) in ranges {
+            let clip_start = (start - padding).max(0.0);
+            let clip_duration = (end + padding - clip_start).max(0.1);
+            let path = output_dir.join(clip_file_name(number, start));
 
-        Ok(silence_points)
+            self.extract_audio_segment(input_path, &path, clip_start, clip_duration, Supervision::default(), false).await?;
+            paths.push(path);
+        }
+
+        Ok(paths)
     }
 
+    /// Cuts one MP3 segment under supervision and returns how many hung
+    /// FFmpeg runs were retried. `low_power` trades file size for less CPU.
     async fn extract_audio_segment(
         &self,
         input_path: &str,
         output_path: &Path,
         start_time: f64,
         duration: f64,
-    ) -> Result<()> {
+        supervision: Supervision,
+        low_power: bool,
+    ) -> Result<u32> {
         println!("Extracting segment: start={}, duration={}, output={:?}", start_time, duration, output_path);
         
         // Ensure temp directory exists
@@ -259,46 +516,85 @@ impl AudioProcessor {
         
         let ffmpeg_path = self.ffmpeg_manager.get_ffmpeg_path()?;
         
-        let mut cmd = Command::new(ffmpeg_path);
-        cmd.args([
-            "-i", input_path,
-            "-ss", &start_time.to_string(),
-            "-t", &duration.to_string(),
-            "-acodec", "libmp3lame",  // MP3 encoder
-            "-b:a", "128k",           // 128 kbps bitrate
-            "-ar", "44100",           // Keep original sample rate
-            "-ac", "2",               // Keep stereo
-            "-y",
-            output_path.to_str().unwrap(),
-        ]);
-        
-        #[cfg(target_os = "windows")]
-        {
-            use std::os::windows::process::CommandExt;
-            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
-        }
-        
-        let output = cmd.output()?;
+        let (start, length) = (start_time.to_string(), duration.to_string());
+        let run = run_supervised(
+            || {
+                let mut cmd = Command::new(&ffmpeg_path);
+                cmd.args([
+                    "-i", input_path,
+                    "-ss", &start,
+                    "-t", &length,
+                    "-acodec", "libmp3lame",  // MP3 encoder
+                    "-b:a", "128k",           // 128 kbps bitrate
+                    "-ar", "44100",           // Keep original sample rate
+                    "-ac", "2",               // Keep stereo
+                    "-y",
+                ]);
+                if low_power {
+                    cmd.args([
+                        "-threads", "1",
+                        "-compression_level", "9",  // Fastest LAME preset
+                    ]);
+                }
+                cmd.arg(output_path);
+                cmd
+            },
+            supervision,
+        )
+        .await
+        .inspect_err(|e| println!("FFmpeg extraction failed: {}", e))?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            println!("FFmpeg extraction failed: {}", stderr);
-            return Err(anyhow!("FFmpeg failed: {}", stderr));
-        }
-        
-        println!("Successfully extracted segment to: {:?}", output_path);
+        println!("Successfully extracted segment to: {:?} ({} retries)", output_path, run.retries);
 
-        Ok(())
+        Ok(run.retries)
     }
 }
 
-fn extract_time_from_silence_line(line: &str) -> Option<String> {
-    // Parse lines like: "[silencedetect @ 0x...] silence_end: 123.456 | silence_duration: 2.345"
-    if let Some(pos) = line.find("silence_end: ") {
-        let after_label = &line[pos + 13..];
-        if let Some(end_pos) = after_label.find(' ') {
-            return Some(after_label[..end_pos].to_string());
+/// Checks the power source before a chunk is cut. In pause mode this waits
+/// until AC power returns; the result says whether to encode in low-power mode.
+async fn wait_for_power(options: &ProcessingOptions, progress_callback: &impl Fn(f32, String)) -> bool {
+    match options.battery_mode {
+        BatteryMode::Off => false,
+        BatteryMode::Throttle => power_source() == PowerSource::Battery,
+        BatteryMode::Pause => {
+            while power_source() == PowerSource::Battery {
+                progress_callback(-1.0, "Пауза: ноутбук работает от батареи, ждём подключения питания...".to_string());
+                tokio::time::sleep(std::time::Duration::from_secs(POWER_POLL_SECONDS)).await;
+            }
+            false
         }
     }
-    None
-}
\ No newline at end of file
+}
+
+fn parse_silence_line(line: &str) -> Option<SilenceRange> {
+    // Parse lines like: "[silencedetect @ 0x...] silence_end: 123.456 | silence_duration: 2.345"
+    let value_after = |label: &str| -> Option<f64> {
+        let after_label = &line[line.find(label)? + label.len()..];
+        after_label.split_whitespace().next()?.parse().ok()
+    };
+    let end = value_after("silence_end: ")?;
+    let duration = value_after("silence_duration: ").unwrap_or(0.0);
+    Some(SilenceRange { start: (end - duration).max(0.0), end })
+}
+
+/// Split points at the ends of silences, sorted and deduplicated.
+fn silence_points(silences: &[SilenceRange]) -> Vec<f64> {
+    let mut silence_points: Vec<f64> = silences.iter().map(|silence| silence.end).collect();
+    silence_points.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    silence_points.dedup_by(|a, b| (*a - *b).abs() < 0.1);
+    silence_points
+}
+
+/// Clip file name from its number and start time, e.g. `clip_7_00-01-02_500.mp3`;
+/// the number keeps clips starting at the same time apart.
+pub fn clip_file_name(number: usize, start_time: f64) -> String {
+    let total_millis = (start_time.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "clip_{}_{:02}-{:02}-{:02}_{:03}.mp3",
+        number,
+        total_millis / 3_600_000,
+        (total_millis % 3_600_000) / 60_000,
+        (total_millis % 60_000) / 1000,
+        total_millis % 1000
+    )
+}
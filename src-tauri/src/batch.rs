@@ -0,0 +1,72 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::export::{add_metadata_header, ExportOptions};
+use crate::merger::{FileFormat, MergeOptions, TranscriptionMerger};
+use crate::project::Project;
+
+/// Outcome of one project in a batch export; a failed project does not stop
+/// the others.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatchExportResult {
+    pub project_name: String,
+    pub project_path: String,
+    pub version: Option<u32>,
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Renders the latest merge version of a project the way a plain-text export
+/// of the merged transcription would, with the project's meeting details in
+/// the header. Returns the version number and the file content.
+pub fn render_latest_version(project: &Project, format: &FileFormat, export_options: &ExportOptions) -> Result<(u32, String)> {
+    let version = project
+        .versions
+        .last()
+        .ok_or_else(|| anyhow!("Project {} has no saved merge versions", project.name))?;
+
+    let mut export_options = export_options.clone();
+    if export_options.line_break_language.is_none() {
+        export_options.line_break_language = version.options.language.clone();
+    }
+    let merger = TranscriptionMerger::new(MergeOptions {
+        output_format: format.clone(),
+        frame_rate: export_options.frame_rate.or(version.options.frame_rate),
+        txt_template: export_options.txt_template.clone().or_else(|| version.options.txt_template.clone()),
+        ..version.options.clone()
+    });
+    let segments = export_options.prepare_segments(version.segments.clone(), format, version.options.default_duration_seconds);
+    let content = merger.format_segments(&segments)?;
+    Ok((version.number, add_metadata_header(&content, format, &project.header_metadata())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merger::TranscriptionSegment;
+    use crate::versions::add_version;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_render_latest_version() {
+        let mut project = Project::new("Интервью", PathBuf::new());
+        let options = ExportOptions { max_line_length: Some(20), ..ExportOptions::default() };
+        assert!(render_latest_version(&project, &FileFormat::Srt, &options).is_err());
+
+        let segment = TranscriptionSegment {
+            start_time: 1.0,
+            end_time: Some(4.0),
+            text: "Добрый день, сегодня обсуждаем бюджет".to_string(),
+            original_filename: "a.srt".to_string(),
+            ..Default::default()
+        };
+        let merge_options = MergeOptions { add_file_markers: false, ..MergeOptions::default() };
+        add_version(&mut project.versions, "Merge", &merge_options, std::slice::from_ref(&segment));
+        let edited = TranscriptionSegment { text: "Добрый день, обсуждаем бюджет".to_string(), ..segment };
+        add_version(&mut project.versions, "Final", &merge_options, &[edited]);
+
+        let (version, content) = render_latest_version(&project, &FileFormat::Srt, &options).unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(content, "1\n00:00:01,000 --> 00:00:04,000\nДобрый день,\nобсуждаем бюджет\n\n");
+    }
+}
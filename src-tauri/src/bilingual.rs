@@ -0,0 +1,84 @@
+use crate::merger::TranscriptionSegment;
+use crate::stats::spoken_text;
+
+/// Adds the translation under each cue of the original, matched by time.
+/// Every translation cue goes to the original cue it overlaps most, or the
+/// nearest one when it overlaps none, so no translated text is lost when the
+/// two transcripts were cut differently. Merger-inserted markers are left as
+/// they are. Returns the cues and how many of them got no translation.
+pub fn pair_translations(
+    originals: &[TranscriptionSegment],
+    translations: &[TranscriptionSegment],
+    default_duration: f64,
+) -> (Vec<TranscriptionSegment>, usize) {
+    let span = |segment: &TranscriptionSegment| {
+        (segment.start_time, segment.end_time.unwrap_or(segment.start_time + default_duration))
+    };
+    let cues: Vec<usize> = (0..originals.len()).filter(|&index| !originals[index].original_filename.is_empty()).collect();
+
+    let mut translated: Vec<Vec<&str>> = vec![Vec::new(); originals.len()];
+    for translation in translations.iter().filter(|segment| !segment.original_filename.is_empty()) {
+        let (start, end) = span(translation);
+        let best = cues.iter().copied().max_by(|&a, &b| {
+            let score = |index: usize| {
+                let (cue_start, cue_end) = span(&originals[index]);
+                let overlap = end.min(cue_end) - start.max(cue_start);
+                // Without any overlap the closer cue wins
+                (overlap.max(0.0), -(cue_start - start).abs())
+            };
+            score(a).partial_cmp(&score(b)).unwrap_or(std::cmp::Ordering::Equal)
+        });
+        if let Some(index) = best {
+            translated[index].push(spoken_text(translation));
+        }
+    }
+
+    let mut untranslated = 0;
+    let segments = originals
+        .iter()
+        .zip(translated)
+        .map(|(original, translation)| {
+            let mut segment = original.clone();
+            if !translation.is_empty() {
+                segment.text = format!("{}\n{}", original.text.trim(), translation.join(" "));
+            } else if !original.original_filename.is_empty() {
+                untranslated += 1;
+            }
+            segment
+        })
+        .collect();
+    (segments, untranslated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pair_translations() {
+        let originals = vec![
+            TranscriptionSegment::test(0.0, 2.0, "Добрый день.").file("ru.srt"),
+            TranscriptionSegment::test(2.0, 5.0, "Начнём с бюджета.").file("ru.srt"),
+            TranscriptionSegment::test(5.0, 6.0, "--- Реклама ---").file(""),
+            TranscriptionSegment::test(6.0, 8.0, "Вопросы?").file("ru.srt"),
+        ];
+        let translations = vec![
+            TranscriptionSegment::test(0.1, 1.9, "Good afternoon.").file("en.srt"),
+            TranscriptionSegment::test(2.2, 3.5, "Let's start").file("en.srt"),
+            TranscriptionSegment::test(3.5, 4.8, "with the budget.").file("en.srt"),
+        ];
+
+        let (segments, untranslated) = pair_translations(&originals, &translations, 3.0);
+        let texts: Vec<&str> = segments.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "Добрый день.\nGood afternoon.",
+                "Начнём с бюджета.\nLet's start with the budget.",
+                "--- Реклама ---",
+                "Вопросы?",
+            ]
+        );
+        assert_eq!(untranslated, 1);
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+use crate::project::ProjectMetadata;
+
+/// Reads meeting title, start and attendees from the first VEVENT of an
+/// iCalendar (.ics) file, as exported by Google Calendar, Outlook or Zoom.
+pub fn parse_ics(content: &str) -> Result<ProjectMetadata> {
+    let mut metadata = ProjectMetadata::default();
+    let mut in_event = false;
+    let mut found_event = false;
+
+    for line in unfold_lines(content) {
+        let (name_and_params, value) = match line.split_once(':') {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let mut params = name_and_params.split(';');
+        let name = params.next().unwrap_or_default().to_uppercase();
+
+        match name.as_str() {
+            "BEGIN" if value.eq_ignore_ascii_case("VEVENT") => {
+                in_event = true;
+                found_event = true;
+            }
+            // Only the first event is used
+            "END" if value.eq_ignore_ascii_case("VEVENT") => break,
+            _ if !in_event => {}
+            "SUMMARY" => metadata.meeting_title = Some(unescape(value)),
+            "LOCATION" if !value.is_empty() => metadata.location = Some(unescape(value)),
+            "DTSTART" => metadata.meeting_date = parse_ics_date(value),
+            "ORGANIZER" | "ATTENDEE" => {
+                let common_name = params
+                    .find_map(|param| param.strip_prefix("CN="))
+                    .map(|cn| cn.trim_matches('"').to_string());
+                let email = value
+                    .strip_prefix("mailto:")
+                    .or_else(|| value.strip_prefix("MAILTO:"))
+                    .unwrap_or(value);
+                let attendee = common_name.unwrap_or_else(|| email.to_string());
+                if !attendee.is_empty() && !metadata.attendees.contains(&attendee) {
+                    metadata.attendees.push(attendee);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !found_event {
+        return Err(anyhow!("The calendar file contains no events"));
+    }
+    Ok(metadata)
+}
+
+/// Joins continuation lines (starting with a space or tab) per RFC 5545.
+fn unfold_lines(content: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for raw in content.lines() {
+        let raw = raw.trim_end_matches('\r');
+        match (raw.strip_prefix(' ').or_else(|| raw.strip_prefix('\t')), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(raw.to_string()),
+        }
+    }
+    lines
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("\\n", "\n")
+        .replace("\\N", "\n")
+        .replace("\\,", ",")
+        .replace("\\;", ";")
+        .replace("\\\\", "\\")
+        .trim()
+        .to_string()
+}
+
+/// `20240115T100000Z` (UTC), `20240115T100000` (local/TZID, kept as is) or
+/// `20240115` (all-day) to an ISO 8601 string.
+fn parse_ics_date(value: &str) -> Option<String> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some(Utc.from_utc_datetime(&naive).to_rfc3339());
+    }
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Some(naive.format("%Y-%m-%dT%H:%M:%S").to_string());
+    }
+    NaiveDate::parse_from_str(value, "%Y%m%d")
+        .ok()
+        .map(|date| date.format("%Y-%m-%d").to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ics_event() {
+        let content = "BEGIN:VCALENDAR\r\n\
+                       BEGIN:VEVENT\r\n\
+                       DTSTART;TZID=Europe/Moscow:20240115T100000\r\n\
+                       SUMMARY:Планёрка\\, отдел продаж\r\n\
+                       ORGANIZER;CN=Анна Петрова:mailto:anna@example.com\r\n\
+                       ATTENDEE;ROLE=REQ-PARTICIPANT;CN=\"Борис\":mailto:boris@example.com\r\n\
+                       ATTENDEE;RSVP=TRUE:mailto:very.long.address.that.gets.fol\r\n \
+                       ded@example.com\r\n\
+                       END:VEVENT\r\n\
+                       BEGIN:VEVENT\r\n\
+                       SUMMARY:Second event\r\n\
+                       END:VEVENT\r\n\
+                       END:VCALENDAR\r\n";
+
+        let metadata = parse_ics(content).unwrap();
+
+        assert_eq!(metadata.meeting_title.as_deref(), Some("Планёрка, отдел продаж"));
+        assert_eq!(metadata.meeting_date.as_deref(), Some("2024-01-15T10:00:00"));
+        assert_eq!(
+            metadata.attendees,
+            vec!["Анна Петрова", "Борис", "very.long.address.that.gets.folded@example.com"]
+        );
+        assert!(parse_ics("BEGIN:VCALENDAR\nEND:VCALENDAR\n").is_err());
+    }
+}
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::quotecard::filter_path;
+use crate::supervisor::{run_supervised, Supervision};
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptionPosition {
+    #[default]
+    Bottom,
+    Middle,
+    Top,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    Left,
+    #[default]
+    Center,
+    Right,
+}
+
+/// Look of burned-in captions, kept as named presets in the settings so each
+/// show has a consistent lower third. Sizes and margins are in pixels of a
+/// 1080p frame and scale with the video.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CaptionStyle {
+    pub name: String,
+    pub font: String,
+    pub font_size: u32,
+    pub bold: bool,
+    pub text_color: String,
+    pub outline_color: String,
+    pub outline: f64,
+    pub shadow: f64,
+    /// Draw an opaque box behind the text instead of an outline
+    #[serde(rename = "box")]
+    pub boxed: bool,
+    pub box_color: String,
+    /// 0.0 (transparent) to 1.0 (solid)
+    pub box_opacity: f64,
+    pub position: CaptionPosition,
+    pub text_align: TextAlign,
+    /// Safe margins from the left/right and top/bottom frame edges
+    pub margin_horizontal: u32,
+    pub margin_vertical: u32,
+}
+
+impl Default for CaptionStyle {
+    fn default() -> Self {
+        Self {
+            name: "Default".to_string(),
+            font: "Arial".to_string(),
+            font_size: 54,
+            bold: false,
+            text_color: "#ffffff".to_string(),
+            outline_color: "#000000".to_string(),
+            outline: 2.0,
+            shadow: 1.0,
+            boxed: false,
+            box_color: "#000000".to_string(),
+            box_opacity: 0.6,
+            position: CaptionPosition::Bottom,
+            text_align: TextAlign::Center,
+            margin_horizontal: 60,
+            margin_vertical: 50,
+        }
+    }
+}
+
+impl CaptionStyle {
+    /// The `Style:` line of an ASS script, always named `Default` so dialogue
+    /// lines need no changes between presets.
+    pub fn ass_style_line(&self) -> String {
+        // With BorderStyle 3 libass fills the box with the outline colour
+        let (border_style, outline_color, outline) = if self.boxed {
+            (3, ass_color(&self.box_color, self.box_opacity), self.outline.max(1.0))
+        } else {
+            (1, ass_color(&self.outline_color, 1.0), self.outline)
+        };
+        let row = match self.position {
+            CaptionPosition::Bottom => 0,
+            CaptionPosition::Middle => 3,
+            CaptionPosition::Top => 6,
+        };
+        let column = match self.text_align {
+            TextAlign::Left => 1,
+            TextAlign::Center => 2,
+            TextAlign::Right => 3,
+        };
+
+        format!(
+            "Style: Default,{},{},{},&H000000FF,{},&H80000000,{},0,0,0,100,100,0,0,{},{},{},{},{},{},{},1",
+            self.font.replace(',', " "),
+            self.font_size,
+            ass_color(&self.text_color, 1.0),
+            outline_color,
+            if self.bold { -1 } else { 0 },
+            border_style,
+            outline,
+            self.shadow,
+            row + column,
+            self.margin_horizontal,
+            self.margin_horizontal,
+            self.margin_vertical,
+        )
+    }
+}
+
+/// `#rrggbb` to ASS `&HAABBGGRR`, where alpha 00 is opaque.
+fn ass_color(color: &str, opacity: f64) -> String {
+    let hex = color.trim_start_matches('#');
+    let channel = |range: std::ops::Range<usize>| hex.get(range).unwrap_or("00").to_uppercase();
+    let alpha = ((1.0 - opacity.clamp(0.0, 1.0)) * 255.0).round() as u8;
+    format!("&H{:02X}{}{}{}", alpha, channel(4..6), channel(2..4), channel(0..2))
+}
+
+/// Burns an ASS script into the video, copying the audio stream.
+/// Re-encoding a long video takes a while, so only a stall ends the run.
+pub async fn burn_subtitles(ffmpeg_path: &Path, video_path: &Path, script_path: &Path, output_path: &Path) -> Result<()> {
+    let filter = format!("ass={}", filter_path(script_path));
+    let run = run_supervised(
+        || {
+            let mut cmd = Command::new(ffmpeg_path);
+            cmd.arg("-i").arg(video_path);
+            cmd.args([
+                "-vf", &filter,
+                "-c:a", "copy",
+                "-y",
+            ]);
+            cmd.arg(output_path);
+            cmd
+        },
+        Supervision { timeout: None, ..Supervision::default() },
+    )
+    .await;
+
+    if let Err(e) = &run {
+        println!("FFmpeg caption burn-in failed: {}", e);
+    }
+    run.map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ass_style_line() {
+        assert_eq!(
+            CaptionStyle::default().ass_style_line(),
+            "Style: Default,Arial,54,&H00FFFFFF,&H000000FF,&H00000000,&H80000000,0,0,0,0,100,100,0,0,1,2,1,2,60,60,50,1"
+        );
+
+        let lower_third = CaptionStyle {
+            font: "Inter".to_string(),
+            bold: true,
+            boxed: true,
+            box_color: "#102030".to_string(),
+            box_opacity: 0.5,
+            text_align: TextAlign::Left,
+            margin_horizontal: 96,
+            margin_vertical: 120,
+            ..CaptionStyle::default()
+        };
+        assert_eq!(
+            lower_third.ass_style_line(),
+            "Style: Default,Inter,54,&H00FFFFFF,&H000000FF,&H80302010,&H80000000,-1,0,0,0,100,100,0,0,3,2,1,1,96,96,120,1"
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextCase {
+    #[default]
+    Original,
+    /// Lowercase with the first letter of each sentence capitalised
+    Sentence,
+    /// ALL CAPS, as broadcast captions are often delivered
+    Upper,
+    Lower,
+}
+
+/// Changes the casing of segment text. Markup tags (`<i>`, `{\an8}`) are left
+/// alone, and glossary terms ("NASA", "iPhone") get their glossary spelling
+/// back afterwards so acronyms and brand names survive the transform.
+pub struct CaseTransform {
+    case: TextCase,
+    glossary: Option<Regex>,
+    spellings: HashMap<String, String>,
+}
+
+impl CaseTransform {
+    pub fn new(case: TextCase, glossary: &[String]) -> Self {
+        let mut terms: Vec<&str> = glossary.iter().map(|term| term.trim()).filter(|term| !term.is_empty()).collect();
+        // Longer terms first so "NASA JPL" wins over "NASA"
+        terms.sort_by_key(|term| std::cmp::Reverse(term.chars().count()));
+
+        let spellings = terms.iter().map(|term| (term.to_lowercase(), term.to_string())).collect();
+        let glossary = if terms.is_empty() {
+            None
+        } else {
+            let alternatives: Vec<String> = terms.iter().map(|term| regex::escape(term)).collect();
+            RegexBuilder::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+                .case_insensitive(true)
+                .build()
+                .ok()
+        };
+        Self { case, glossary, spellings }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        if self.case == TextCase::Original {
+            return text.to_string();
+        }
+
+        let tag_pattern = Regex::new(r"<[^>]*>|\{\\[^}]*\}").unwrap();
+        let mut sentence = SentenceState::default();
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for tag in tag_pattern.find_iter(text) {
+            result.push_str(&self.apply_plain(&text[last..tag.start()], &mut sentence));
+            result.push_str(tag.as_str());
+            last = tag.end();
+        }
+        result.push_str(&self.apply_plain(&text[last..], &mut sentence));
+        result
+    }
+
+    fn apply_plain(&self, text: &str, sentence: &mut SentenceState) -> String {
+        let changed = match self.case {
+            TextCase::Original => text.to_string(),
+            TextCase::Upper => text.to_uppercase(),
+            TextCase::Lower => text.to_lowercase(),
+            TextCase::Sentence => text.chars().map(|ch| sentence.next(ch)).collect(),
+        };
+
+        match &self.glossary {
+            Some(pattern) => pattern
+                .replace_all(&changed, |captures: &Captures| {
+                    self.spellings
+                        .get(&captures[0].to_lowercase())
+                        .cloned()
+                        .unwrap_or_else(|| captures[0].to_string())
+                })
+                .into_owned(),
+            None => changed,
+        }
+    }
+}
+
+/// Tracks sentence boundaries across the pieces of text between tags. A new
+/// sentence starts after `.`, `!`, `?` or `…` followed by whitespace, so
+/// "3.5" and "example.com" stay inside their sentence.
+struct SentenceState {
+    at_start: bool,
+    after_terminator: bool,
+}
+
+impl Default for SentenceState {
+    fn default() -> Self {
+        Self { at_start: true, after_terminator: false }
+    }
+}
+
+impl SentenceState {
+    fn next(&mut self, ch: char) -> String {
+        if ch.is_alphanumeric() {
+            let capitalize = self.at_start && ch.is_alphabetic();
+            self.at_start = false;
+            self.after_terminator = false;
+            return if capitalize { ch.to_uppercase().collect() } else { ch.to_lowercase().collect() };
+        }
+
+        if matches!(ch, '.' | '!' | '?' | '…') {
+            self.after_terminator = true;
+        } else if ch.is_whitespace() && self.after_terminator {
+            self.at_start = true;
+        } else if !ch.is_whitespace() {
+            self.after_terminator = false;
+        }
+        ch.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_case_transforms_keep_glossary_terms() {
+        let glossary = vec!["NASA".to_string(), "iPhone".to_string(), " ".to_string()];
+        let text = "<i>ВЧЕРА nasa показала</i> iphone. версия 3.5 — ОК! сайт example.com";
+
+        assert_eq!(
+            CaseTransform::new(TextCase::Sentence, &glossary).apply(text),
+            "<i>Вчера NASA показала</i> iPhone. Версия 3.5 — ок! Сайт example.com"
+        );
+        assert_eq!(
+            CaseTransform::new(TextCase::Upper, &[]).apply("{\\an8}nasa и iPhone"),
+            "{\\an8}NASA И IPHONE"
+        );
+        assert_eq!(
+            CaseTransform::new(TextCase::Lower, &glossary).apply("NASA И IPHONE"),
+            "NASA и iPhone"
+        );
+        assert_eq!(CaseTransform::new(TextCase::Original, &glossary).apply("nasa"), "nasa");
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::merger::{format_txt_timestamp, TranscriptionSegment};
+
+/// YouTube ignores chapter lists with fewer entries
+const MIN_CHAPTERS: usize = 3;
+/// YouTube's shortest allowed chapter, in seconds
+const MIN_CHAPTER_SECONDS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChapterSource {
+    /// A chapter per merged file, titled by the file name
+    Files,
+    /// A chapter per Markdown heading of the source files
+    Headings,
+    /// Headings where there are any, file boundaries elsewhere
+    #[default]
+    Both,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chapter {
+    pub start_time: f64,
+    pub title: String,
+}
+
+/// Chapters in timeline order, following YouTube's rules: the first starts at
+/// 00:00 and a chapter shorter than ten seconds is folded into the next one.
+pub fn build_chapters(segments: &[TranscriptionSegment], source: ChapterSource) -> Vec<Chapter> {
+    let mut chapters: Vec<Chapter> = Vec::new();
+    let mut previous_file: Option<&str> = None;
+    for segment in segments.iter().filter(|segment| !segment.original_filename.is_empty()) {
+        let new_file = previous_file != Some(segment.original_filename.as_str());
+        previous_file = Some(&segment.original_filename);
+
+        let title = match (source, &segment.heading) {
+            (ChapterSource::Headings | ChapterSource::Both, Some(heading)) => heading.clone(),
+            (ChapterSource::Files | ChapterSource::Both, _) if new_file => file_title(&segment.original_filename),
+            _ => continue,
+        };
+        match chapters.last_mut() {
+            Some(last) if segment.start_time - last.start_time < MIN_CHAPTER_SECONDS => {
+                // Too short to keep: the later title wins, as it usually names what follows
+                last.title = title;
+            }
+            _ => chapters.push(Chapter { start_time: segment.start_time, title }),
+        }
+    }
+
+    if let Some(first) = chapters.first_mut() {
+        first.start_time = 0.0;
+    }
+    chapters
+}
+
+/// The chapter list as pasted into a YouTube description: `00:00 Title` lines.
+pub fn render_youtube_chapters(chapters: &[Chapter]) -> String {
+    chapters
+        .iter()
+        .map(|chapter| format!("{} {}\n", format_txt_timestamp(chapter.start_time), chapter.title))
+        .collect()
+}
+
+/// Reasons YouTube would not show the chapters, empty when it will.
+pub fn chapter_warnings(chapters: &[Chapter]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if chapters.len() < MIN_CHAPTERS {
+        warnings.push(format!(
+            "YouTube needs at least {} chapters, only {} found",
+            MIN_CHAPTERS,
+            chapters.len()
+        ));
+    }
+    warnings
+}
+
+/// "02_Budget_review.md" → "Budget review"
+fn file_title(file_name: &str) -> String {
+    let stem = Path::new(file_name).file_stem().unwrap_or_default().to_string_lossy();
+    let title = stem
+        .trim_start_matches(|ch: char| ch.is_ascii_digit() || ch == '_' || ch == '-' || ch == ' ')
+        .replace('_', " ");
+    if title.trim().is_empty() {
+        stem.to_string()
+    } else {
+        title.trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_youtube_chapters() {
+        let segments = vec![
+            TranscriptionSegment::test(2.0, None, "Текст").file("01_Intro.md"),
+            TranscriptionSegment::test(5.0, None, "Текст").file("01_Intro.md").heading("Вступление"),
+            TranscriptionSegment::test(275.0, None, "Текст").file("01_Intro.md").heading("Бюджет"),
+            TranscriptionSegment::test(600.0, None, "Текст").file("02_Q_and_A.srt"),
+            TranscriptionSegment::test(3700.0, None, "Текст").file("02_Q_and_A.srt"),
+        ];
+
+        let chapters = build_chapters(&segments, ChapterSource::Both);
+        assert_eq!(
+            render_youtube_chapters(&chapters),
+            "00:00 Вступление\n04:35 Бюджет\n10:00 Q and A\n"
+        );
+        assert!(chapter_warnings(&chapters).is_empty());
+
+        let by_file = build_chapters(&segments, ChapterSource::Files);
+        assert_eq!(render_youtube_chapters(&by_file), "00:00 Intro\n10:00 Q and A\n");
+        assert_eq!(chapter_warnings(&by_file).len(), 1);
+        assert_eq!(build_chapters(&segments, ChapterSource::Headings)[0].title, "Вступление");
+    }
+}
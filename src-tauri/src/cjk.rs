@@ -0,0 +1,105 @@
+/// Han characters or kana counted as one word when estimating durations;
+/// with the default 150 words per minute that is 300 characters per minute
+const CJK_CHARS_PER_WORD: f64 = 2.0;
+
+/// Closing punctuation and small kana that must not start a line (kinsoku)
+const NO_LINE_START: &str = "、。，．！？：；）」』】〕〉》”’・…ーゝゞ々ぁぃぅぇぉっゃゅょゎァィゥェォッャュョヮヵヶ,.!?:;)";
+/// Opening brackets that must not end a line
+const NO_LINE_END: &str = "（「『【〔〈《“‘(";
+
+/// Scripts written without spaces between words: Han, kana and CJK punctuation.
+/// Hangul is not among them, Korean separates words with spaces.
+pub fn is_unspaced(ch: char) -> bool {
+    matches!(ch as u32,
+        0x3000..=0x303F      // CJK symbols and punctuation
+        | 0x3040..=0x30FF    // Hiragana, Katakana
+        | 0x31F0..=0x31FF    // Katakana phonetic extensions
+        | 0x3400..=0x4DBF    // CJK extension A
+        | 0x4E00..=0x9FFF    // CJK unified ideographs
+        | 0xF900..=0xFAFF    // CJK compatibility ideographs
+        | 0xFF01..=0xFF60    // Fullwidth forms
+        | 0x20000..=0x3FFFD  // CJK extensions B and later
+    )
+}
+
+/// Columns a character takes on screen: 2 for East Asian wide characters
+/// (including Hangul), 1 otherwise.
+pub fn char_width(ch: char) -> usize {
+    let wide = is_unspaced(ch)
+        || matches!(ch as u32,
+            0x1100..=0x115F      // Hangul Jamo initials
+            | 0x3130..=0x318F    // Hangul compatibility Jamo
+            | 0x3200..=0x32FF    // Enclosed CJK letters
+            | 0xAC00..=0xD7A3    // Hangul syllables
+            | 0xFFE0..=0xFFE6    // Fullwidth signs
+        );
+    if wide {
+        2
+    } else {
+        1
+    }
+}
+
+/// Width of a text in columns; line length limits use this instead of the
+/// number of characters so that CJK lines get half as many characters.
+pub fn display_width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Splits a whitespace-delimited word into the parts a line may break
+/// between: each Han/kana character on its own, runs of other characters
+/// together. Closing punctuation stays with the character before it and
+/// opening brackets with the one after.
+pub fn break_units(word: &str) -> Vec<&str> {
+    let mut units = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<char> = None;
+    for (index, ch) in word.char_indices() {
+        if let Some(previous) = previous {
+            let boundary = (is_unspaced(ch) || is_unspaced(previous))
+                && !NO_LINE_START.contains(ch)
+                && !NO_LINE_END.contains(previous);
+            if boundary {
+                units.push(&word[start..index]);
+                start = index;
+            }
+        }
+        previous = Some(ch);
+    }
+    if start < word.len() {
+        units.push(&word[start..]);
+    }
+    units
+}
+
+/// Word count used for speaking-time estimates. Space-delimited text counts
+/// its words; Han and kana count `1 / CJK_CHARS_PER_WORD` per character.
+pub fn word_count(text: &str) -> f64 {
+    text.split_whitespace()
+        .map(|word| {
+            let cjk_chars = word.chars().filter(|ch| is_unspaced(*ch) && ch.is_alphabetic()).count();
+            if cjk_chars == 0 {
+                return 1.0;
+            }
+            let other_words = break_units(word)
+                .iter()
+                .filter(|unit| unit.chars().any(char::is_alphanumeric) && !unit.chars().any(is_unspaced))
+                .count();
+            other_words as f64 + cjk_chars as f64 / CJK_CHARS_PER_WORD
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cjk_units_and_width() {
+        assert_eq!(break_units("「今日は」、iPhoneを。"), vec!["「今", "日", "は」、", "iPhone", "を。"]);
+        assert_eq!(break_units("северо-западный"), vec!["северо-западный"]);
+        assert_eq!(display_width("안녕 하세요 ok"), 2 * 5 + 4);
+        assert_eq!(word_count("我们今天开会 about 预算"), 3.0 + 1.0 + 1.0);
+        assert_eq!(word_count("Привет — мир"), 3.0);
+    }
+}
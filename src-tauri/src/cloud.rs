@@ -0,0 +1,380 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+
+/// Keychain service name the OAuth credentials are stored under
+pub(crate) const KEYRING_SERVICE: &str = "transcription-assistant";
+
+/// Fixed loopback port; register `http://localhost:53682/` as the redirect URI
+/// in the Google Cloud / Dropbox app console.
+const REDIRECT_PORT: u16 = 53682;
+
+/// How long to wait for the user to finish signing in in the browser
+const AUTHORIZATION_TIMEOUT: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloudProvider {
+    GoogleDrive,
+    Dropbox,
+}
+
+impl CloudProvider {
+    fn keyring_user(&self) -> &'static str {
+        match self {
+            Self::GoogleDrive => "google-drive",
+            Self::Dropbox => "dropbox",
+        }
+    }
+
+    fn token_url(&self) -> &'static str {
+        match self {
+            Self::GoogleDrive => "https://oauth2.googleapis.com/token",
+            Self::Dropbox => "https://api.dropboxapi.com/oauth2/token",
+        }
+    }
+}
+
+/// What is kept in the OS keychain per provider. The app has no registered
+/// client of its own, so the user's client id/secret are stored with the token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredCredentials {
+    client_id: String,
+    client_secret: Option<String>,
+    refresh_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+    refresh_token: Option<String>,
+}
+
+// Access tokens obtained from refresh tokens, reused until shortly before expiry
+lazy_static::lazy_static! {
+    static ref ACCESS_TOKENS: Arc<Mutex<HashMap<CloudProvider, (String, Instant)>>> = Arc::new(Mutex::new(HashMap::new()));
+}
+
+fn redirect_uri() -> String {
+    format!("http://localhost:{}/", REDIRECT_PORT)
+}
+
+fn keyring_entry(provider: CloudProvider) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, provider.keyring_user())
+        .map_err(|e| anyhow!("Keychain is not available: {}", e))
+}
+
+pub fn is_connected(provider: CloudProvider) -> bool {
+    keyring_entry(provider)
+        .and_then(|entry| entry.get_password().map_err(|e| anyhow!(e)))
+        .is_ok()
+}
+
+/// Runs the OAuth authorization-code flow with PKCE: `open_url` shows the
+/// consent page in the browser, the code comes back to a loopback listener and
+/// the resulting refresh token is stored in the keychain.
+pub async fn connect(
+    provider: CloudProvider,
+    client_id: &str,
+    client_secret: Option<&str>,
+    open_url: impl FnOnce(&str) -> Result<()>,
+) -> Result<()> {
+    let verifier = format!("{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple());
+    let state = uuid::Uuid::new_v4().simple().to_string();
+
+    // Listen before opening the browser so the redirect cannot be missed
+    let listener = TcpListener::bind(("127.0.0.1", REDIRECT_PORT))
+        .await
+        .map_err(|e| anyhow!("Port {} is busy, cannot receive the sign-in redirect: {}", REDIRECT_PORT, e))?;
+
+    open_url(&authorization_url(provider, client_id, &pkce_challenge(&verifier), &state))?;
+
+    let code = tokio::time::timeout(AUTHORIZATION_TIMEOUT, receive_code(listener, &state))
+        .await
+        .map_err(|_| anyhow!("Sign-in was not completed in time"))??;
+
+    let mut params = vec![
+        ("grant_type", "authorization_code".to_string()),
+        ("code", code),
+        ("client_id", client_id.to_string()),
+        ("code_verifier", verifier),
+        ("redirect_uri", redirect_uri()),
+    ];
+    if let Some(secret) = client_secret {
+        params.push(("client_secret", secret.to_string()));
+    }
+    let tokens = request_tokens(provider, &params).await?;
+
+    let refresh_token = tokens
+        .refresh_token
+        .clone()
+        .ok_or_else(|| anyhow!("The provider did not return a refresh token"))?;
+    let credentials = StoredCredentials {
+        client_id: client_id.to_string(),
+        client_secret: client_secret.map(|secret| secret.to_string()),
+        refresh_token,
+    };
+    keyring_entry(provider)?
+        .set_password(&serde_json::to_string(&credentials)?)
+        .map_err(|e| anyhow!("Failed to store the token in the keychain: {}", e))?;
+
+    cache_access_token(provider, &tokens).await;
+    Ok(())
+}
+
+pub async fn disconnect(provider: CloudProvider) -> Result<()> {
+    ACCESS_TOKENS.lock().await.remove(&provider);
+    match keyring_entry(provider)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(anyhow!("Failed to remove the token from the keychain: {}", e)),
+    }
+}
+
+fn authorization_url(provider: CloudProvider, client_id: &str, challenge: &str, state: &str) -> String {
+    let (base, extra): (&str, &[(&str, &str)]) = match provider {
+        CloudProvider::GoogleDrive => (
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            &[
+                ("scope", "https://www.googleapis.com/auth/drive.file"),
+                ("access_type", "offline"),
+                ("prompt", "consent"),
+            ],
+        ),
+        CloudProvider::Dropbox => (
+            "https://www.dropbox.com/oauth2/authorize",
+            &[("token_access_type", "offline")],
+        ),
+    };
+
+    let redirect = redirect_uri();
+    let mut params: Vec<(&str, &str)> = vec![
+        ("client_id", client_id),
+        ("redirect_uri", &redirect),
+        ("response_type", "code"),
+        ("code_challenge", challenge),
+        ("code_challenge_method", "S256"),
+        ("state", state),
+    ];
+    params.extend_from_slice(extra);
+
+    let query: Vec<String> = params
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, urlencoding::encode(value)))
+        .collect();
+    format!("{}?{}", base, query.join("&"))
+}
+
+fn pkce_challenge(verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Accepts the browser redirect and returns the authorization code.
+async fn receive_code(listener: TcpListener, expected_state: &str) -> Result<String> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        let mut buffer = vec![0u8; 8192];
+        let read = stream.read(&mut buffer).await?;
+        let request = String::from_utf8_lossy(&buffer[..read]).to_string();
+
+        // "GET /?code=...&state=... HTTP/1.1"
+        let target = request.lines().next().and_then(|line| line.split_whitespace().nth(1)).unwrap_or("");
+        let query = match target.split_once('?') {
+            Some((_, query)) => query,
+            // Favicon and similar requests
+            None => {
+                let _ = stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n").await;
+                continue;
+            }
+        };
+        let params: HashMap<String, String> = query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(name, value)| (name.to_string(), urlencoding::decode(value).map(|v| v.into_owned()).unwrap_or_default()))
+            .collect();
+
+        let (result, page) = match (params.get("code"), params.get("state"), params.get("error")) {
+            (_, _, Some(error)) => (Err(anyhow!("Authorization was denied: {}", error)), "Доступ не предоставлен."),
+            (Some(code), Some(state), None) if state == expected_state => {
+                (Ok(code.clone()), "Вход выполнен. Это окно можно закрыть.")
+            }
+            _ => (Err(anyhow!("Unexpected authorization response")), "Некорректный ответ авторизации."),
+        };
+
+        let body = format!("<html><head><meta charset=\"utf-8\"></head><body><p>{}</p></body></html>", page);
+        let response = format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        );
+        let _ = stream.write_all(response.as_bytes()).await;
+        return result;
+    }
+}
+
+async fn request_tokens(provider: CloudProvider, params: &[(&str, String)]) -> Result<TokenResponse> {
+    let response = reqwest::Client::new()
+        .post(provider.token_url())
+        .form(params)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Token request failed ({}): {}", status, text));
+    }
+    Ok(serde_json::from_str(&response.text().await?)?)
+}
+
+async fn cache_access_token(provider: CloudProvider, tokens: &TokenResponse) {
+    // Refresh a minute early so a token never expires mid-upload
+    let lifetime = tokens.expires_in.unwrap_or(3600).saturating_sub(60);
+    ACCESS_TOKENS
+        .lock()
+        .await
+        .insert(provider, (tokens.access_token.clone(), Instant::now() + Duration::from_secs(lifetime)));
+}
+
+async fn access_token(provider: CloudProvider) -> Result<String> {
+    if let Some((token, expires_at)) = ACCESS_TOKENS.lock().await.get(&provider) {
+        if Instant::now() < *expires_at {
+            return Ok(token.clone());
+        }
+    }
+
+    let stored = keyring_entry(provider)?
+        .get_password()
+        .map_err(|_| anyhow!("Not connected to {:?}, please sign in first", provider))?;
+    let credentials: StoredCredentials = serde_json::from_str(&stored)?;
+
+    let mut params = vec![
+        ("grant_type", "refresh_token".to_string()),
+        ("refresh_token", credentials.refresh_token.clone()),
+        ("client_id", credentials.client_id.clone()),
+    ];
+    if let Some(secret) = &credentials.client_secret {
+        params.push(("client_secret", secret.clone()));
+    }
+    let tokens = request_tokens(provider, &params).await?;
+    cache_access_token(provider, &tokens).await;
+    Ok(tokens.access_token)
+}
+
+/// Uploads one file into a Drive folder (by id, empty for My Drive root).
+pub async fn upload_to_google_drive(folder_id: &str, file: &Path) -> Result<String> {
+    let token = access_token(CloudProvider::GoogleDrive).await?;
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let content = tokio::fs::read(file).await?;
+
+    let mut metadata = serde_json::json!({ "name": file_name });
+    if !folder_id.is_empty() {
+        metadata["parents"] = serde_json::json!([folder_id]);
+    }
+
+    let boundary = format!("upload-{}", uuid::Uuid::new_v4().simple());
+    let mut body = Vec::with_capacity(content.len() + 512);
+    body.extend_from_slice(
+        format!(
+            "--{b}\r\nContent-Type: application/json; charset=UTF-8\r\n\r\n{m}\r\n--{b}\r\nContent-Type: application/octet-stream\r\n\r\n",
+            b = boundary,
+            m = metadata
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(&content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+
+    let response = reqwest::Client::new()
+        .post("https://www.googleapis.com/upload/drive/v3/files?uploadType=multipart&fields=id")
+        .bearer_auth(token)
+        .header("Content-Type", format!("multipart/related; boundary={}", boundary))
+        .body(body)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Google Drive returned {}: {}", status, text));
+    }
+    let created: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+    Ok(format!("gdrive://{}", created["id"].as_str().unwrap_or(&file_name)))
+}
+
+/// Uploads one file into a Dropbox folder path (e.g. `/Transcripts`), overwriting.
+pub async fn upload_to_dropbox(folder: &str, file: &Path) -> Result<String> {
+    let token = access_token(CloudProvider::Dropbox).await?;
+    let file_name = file.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let remote_path = format!("/{}/{}", folder.trim_matches('/'), file_name).replace("//", "/");
+    let content = tokio::fs::read(file).await?;
+
+    let argument = serde_json::json!({ "path": remote_path, "mode": "overwrite", "mute": true });
+    let response = reqwest::Client::new()
+        .post("https://content.dropboxapi.com/2/files/upload")
+        .bearer_auth(token)
+        .header("Content-Type", "application/octet-stream")
+        .header("Dropbox-API-Arg", ascii_json(&argument.to_string()))
+        .body(content)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("Dropbox returned {}: {}", status, text));
+    }
+    Ok(format!("dropbox://{}", remote_path))
+}
+
+/// HTTP headers must be ASCII, so Dropbox expects non-ASCII characters in
+/// `Dropbox-API-Arg` as JSON `\uXXXX` escapes.
+fn ascii_json(json: &str) -> String {
+    let mut escaped = String::with_capacity(json.len());
+    for ch in json.chars() {
+        if ch.is_ascii() {
+            escaped.push(ch);
+        } else {
+            let mut units = [0u16; 2];
+            for unit in ch.encode_utf16(&mut units) {
+                escaped.push_str(&format!("\\u{:04x}", unit));
+            }
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pkce_challenge_rfc_example() {
+        // RFC 7636, Appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_authorization_url_and_header_escaping() {
+        let url = authorization_url(CloudProvider::Dropbox, "app key", "challenge", "state1");
+        assert!(url.starts_with("https://www.dropbox.com/oauth2/authorize?client_id=app%20key&"));
+        assert!(url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A53682%2F"));
+        assert!(url.contains("token_access_type=offline"));
+
+        assert_eq!(
+            ascii_json(r#"{"path":"/Выпуск 🎙"}"#),
+            r#"{"path":"/\u0412\u044b\u043f\u0443\u0441\u043a \ud83c\udf99"}"#
+        );
+    }
+}
@@ -1,12 +1,49 @@
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use tauri::Window;
+use std::path::{Path, PathBuf};
+use tauri::{Manager, Window};
 use tokio::sync::Mutex;
 use std::sync::Arc;
 
-use crate::audio::{AudioProcessor, ProcessingOptions};
-use crate::merger::{TranscriptionMerger, MergeOptions, FileFormat};
-use crate::ffmpeg::FFmpegManager;
+use crate::analysis::{AudioRegion, ExcludedRange, MusicDetection};
+use crate::audio::{AudioProcessor, ChunkAssignment, ChunkManifest, ChunkStatus, ProcessingOptions};
+use crate::merger::{TEMPLATE_TOKENS, format_timecode, format_txt_timestamp, parse_decimal, read_text_with_encoding, TranscriptionMerger, TranscriptionSegment, MergeOptions, FileFormat, ParseMode, ParseWarning};
+use crate::ffmpeg::{default_app_data_dir, get_app_data_dir, FFmpegManager, MediaTags};
+use crate::replace::{apply_replace_rules, find_matches, ReplaceRule, RuleMatch, SegmentChange};
+use crate::docx::{render_docx, DocxOptions};
+use crate::html::{compress_audio, is_video, media_data_uri, media_link, render_html, HtmlOptions};
+use crate::export::{add_metadata_header, append_session, options_schema, write_options_sidecar, Compression, ExportOptions, OpenAfterExport};
+use crate::casing::TextCase;
+use crate::editing;
+use crate::corrections::load_corrections;
+use crate::language::{detect_language, mixed_language_warnings};
+use crate::assignments::{self, AssignmentDashboard};
+use crate::batch::{render_latest_version, BatchExportResult};
+use crate::bilingual::pair_translations;
+use crate::retranscription::{self, RetranscriptionOptions};
+use crate::translation::{self, translate_segments, TranslatedTranscript, TranslationOptions, TranslationProvider};
+use crate::versions::{add_version, check_editable, diff_segments, find_version, mark_delivered, MergeVersion, VersionChange, VersionInfo};
+use crate::chapters::{build_chapters, chapter_warnings, render_youtube_chapters, ChapterSource};
+use crate::handoff::{handoff_rows, render_handoff, SheetFormat};
+use crate::workreport::{render_work_report, work_report, WorkRates, WorkReport};
+use crate::qc::{self, QcRules, Violation};
+use crate::calendar::parse_ics;
+use crate::power::{power_source, BatteryMode, PowerSource};
+use crate::schedule::{JobQueue, ProcessingWindow, QueuedJob};
+use crate::settings::{move_data_dir, AppSettings, NamingScheme, Preset};
+use crate::storage::{StorageCategory, StorageLocations, StorageUsage};
+use crate::stats::{chunk_coverage, compute_merge_stats, compute_stats, MergeStats, file_boundaries, FileBoundary, keyword_timeline, retranscription_candidates, ChunkCoverage, RetranscriptionCandidate, KeywordOptions, KeywordTimeline, TranscriptStats};
+use crate::cloud::{self, CloudProvider};
+use crate::upload::{collect_files, upload_files, DestinationTarget, RemoteDestination, UploadedFile};
+use crate::diagnostics::DiagnosticsReport;
+use crate::fixtures::{validate_fixture, validate_fixture_dir, FixtureReport};
+use crate::flashcards::{build_cards, render_table, FlashcardOptions, TableFormat};
+use crate::quotecard::{render_quote_card, QuoteCardOptions};
+use crate::nle::{file_markers, render_markers, Marker, MarkerExportOptions, MarkerSource};
+use crate::profanity::ProfanityList;
+use crate::captions::{burn_subtitles, CaptionStyle};
+use crate::stems::{marker_labels, plan_stems, render_stem, stem_path, StemOptions};
+use crate::minutes::{render_minutes, MinutesOptions, TemplateKind};
+use crate::project::{active_episode_name, parse_deadline, record_operation, sanitize_file_name, upcoming_deadlines, UpcomingDeadline, HistoryEntry, OperationKind, Project, ProjectMetadata, ACTIVE_PROJECT};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileInfo {
@@ -28,6 +65,15 @@ pub struct ProcessingResult {
     pub output_files: Vec<String>,
     pub message: String,
     pub segments: Vec<SegmentInfo>,
+    /// Music intros/outros found during analysis, skipped or only marked
+    #[serde(default)]
+    pub regions: Vec<AudioRegion>,
+    /// Possible speaker changes (seconds), to pass to the merge as `speaker_changes`
+    #[serde(default)]
+    pub speaker_changes: Vec<f64>,
+    /// Chunk start times for `merge_transcriptions`, written next to the chunks
+    #[serde(default)]
+    pub manifest_path: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,13 +82,100 @@ pub struct SegmentInfo {
     pub duration: String,
     pub start_time: f64,
     pub chunk_number: usize,
+    /// Hung FFmpeg runs that were killed and started again for this chunk
+    pub retries: u32,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MergeSummary {
+    pub message: String,
+    pub file_count: usize,
+    pub segment_count: usize,
+    pub warnings: Vec<ParseWarning>,
+    /// Input paths in the order they were merged
+    pub file_order: Vec<String>,
+    /// Segments the hallucination filter flagged or removed
+    pub hallucinations: Vec<TranscriptionSegment>,
+    /// The language given in the options or detected from the text
+    pub language: Option<String>,
+    /// Gaps and overlaps between consecutive files, to spot offset or chunking errors
+    pub file_boundaries: Vec<FileBoundary>,
+    /// Files whose detected language differs from the rest
+    pub language_warnings: Vec<String>,
+}
+
+/// Per-chunk coverage of the merged transcription and a warning for each
+/// chunk below the threshold.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CoverageReport {
+    pub chunks: Vec<ChunkCoverage>,
+    pub warnings: Vec<String>,
+}
+
+/// A placeholder of the TXT line template.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TemplateToken {
+    pub token: String,
+    pub description: String,
+}
+
+/// Speaker labels found in one transcription file, for the speaker mapping table.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileSpeakers {
+    pub file: String,
+    pub speakers: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentClip {
+    pub segment_index: usize,
+    pub start_time: f64,
+    pub end_time: f64,
+    pub text: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SpeakerStem {
+    pub name: String,
+    pub path: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReformatResult {
+    pub input: String,
+    pub output: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConversionOptions {
+    /// Target file; defaults to the input path with the new extension
+    pub output_path: Option<String>,
+    pub remove_timestamps: bool,
+    pub add_file_markers: bool,
+    pub parse_mode: ParseMode,
+    /// Frame rate for MicroDVD input that states none or a wrong one
+    pub microdvd_frame_rate: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConversionResult {
+    pub path: String,
+    pub segment_count: usize,
+    pub warnings: Vec<ParseWarning>,
+}
 
+/// The merged timeline; exports render their format from these segments.
 struct MergedState {
-    content: String,
-    format: String,
-    files: Vec<String>,
+    segments: Vec<TranscriptionSegment>,
+    options: MergeOptions,
+    /// Suspected hallucinations, including those removed from `segments`
+    hallucinations: Vec<TranscriptionSegment>,
+    /// Machine translation made during the merge, exported next to the original
+    translation: Option<TranslatedTranscript>,
+    /// Project version the segments were saved as or restored from, until they are edited
+    version: Option<u32>,
 }
 
 // Global state for merged transcription
@@ -118,15 +251,43 @@ pub async fn start_audio_processing(
     file_path: String,
     max_duration: u32,
     use_silence_detection: bool,
+    music_detection: Option<MusicDetection>,
+    excluded_ranges: Option<Vec<ExcludedRange>>,
+    speaker_change_hints: Option<bool>,
 ) -> Result<ProcessingResult, String> {
+    let processor = AudioProcessor::new().map_err(|e| e.to_string())?;
+    processor.initialize().await.map_err(|e| e.to_string())?;
+
+    // Tags of the source go into the project first, so the chunk folder can be named after them
+    let media = FFmpegManager::new().map_err(|e| e.to_string())?.get_media_tags(&file_path).await;
+    match media {
+        Ok(media) => set_active_media(media).await,
+        Err(e) => println!("Failed to read media tags: {}", e),
+    }
+
     let options = ProcessingOptions {
         max_duration_seconds: max_duration,
         use_silence_detection,
         output_format: "mp3".to_string(),
+        folder_name: active_episode_name().await,
+        music_detection: music_detection.unwrap_or_default(),
+        excluded_ranges: excluded_ranges.unwrap_or_default(),
+        speaker_change_hints: speaker_change_hints.unwrap_or(false),
+        ..ProcessingOptions::default()
     };
+    run_audio_processing(&window, processor, &file_path, options).await
+}
 
-    let processor = AudioProcessor::new().map_err(|e| e.to_string())?;
-    processor.initialize().await.map_err(|e| e.to_string())?;
+/// Splits the file with an initialized processor, reporting progress and the
+/// result to the window and recording the run in the open project.
+async fn run_audio_processing(
+    window: &Window,
+    processor: AudioProcessor,
+    file_path: &str,
+    mut options: ProcessingOptions,
+) -> Result<ProcessingResult, String> {
+    options.battery_mode = AppSettings::load().map_err(|e| e.to_string())?.battery_mode;
+    let recorded_options = options.clone();
 
     let progress_callback = {
         let window = window.clone();
@@ -138,8 +299,9 @@ pub async fn start_audio_processing(
         }
     };
 
-    match processor.process_audio_file(&file_path, options, progress_callback).await {
-        Ok(chunks) => {
+    match processor.process_audio_file(file_path, options, progress_callback).await {
+        Ok(processed) => {
+            let chunks = processed.chunks;
             let output_files: Vec<String> = chunks
                 .iter()
                 .map(|chunk| chunk.path.to_string_lossy().to_string())
@@ -152,16 +314,38 @@ pub async fn start_audio_processing(
                     duration: format!("{:.1}s", chunk.duration),
                     start_time: chunk.start_time,
                     chunk_number: chunk.chunk_number,
+                    retries: chunk.retries,
                 })
                 .collect();
+            let retries: u32 = chunks.iter().map(|chunk| chunk.retries).sum();
+            let mut message = format!("Successfully created {} audio chunks", chunks.len());
+            if retries > 0 {
+                message.push_str(&format!(" ({} hung FFmpeg runs retried)", retries));
+            }
 
             let result = ProcessingResult {
                 success: true,
                 output_files,
                 segments,
-                message: format!("Successfully created {} audio chunks", chunks.len()),
+                message,
+                regions: processed.regions,
+                speaker_changes: processed.speaker_changes,
+                manifest_path: Some(processed.manifest_path.to_string_lossy().to_string()),
             };
 
+            record_operation(
+                OperationKind::Processing,
+                format!("Split {} into {} chunks", file_path, chunks.len()),
+                serde_json::json!({
+                    "input": file_path,
+                    "options": recorded_options,
+                    "outputs": result.output_files,
+                    "regions": result.regions,
+                    "audio_seconds": chunks.iter().map(|chunk| chunk.duration).sum::<f64>(),
+                    "ffmpeg_retries": retries,
+                }),
+            ).await;
+
             let _ = window.emit("processing-complete", &result);
             Ok(result)
         }
@@ -171,6 +355,9 @@ pub async fn start_audio_processing(
                 output_files: vec![],
                 segments: vec![],
                 message: format!("Processing failed: {}", e),
+                regions: vec![],
+                speaker_changes: vec![],
+                manifest_path: None,
             };
 
             let _ = window.emit("processing-complete", &result);
@@ -183,44 +370,119 @@ pub async fn start_audio_processing(
 pub async fn merge_transcriptions(
     files: Vec<String>,
     output_format: String,
-) -> Result<String, String> {
+    options: Option<MergeOptions>,
+    chunk_manifest: Option<String>,
+    term_corrections: Option<String>,
+    translation: Option<TranslationOptions>,
+) -> Result<MergeSummary, String> {
     if files.is_empty() {
         return Err("No transcription files provided".to_string());
     }
 
-    let format = match output_format.to_lowercase().as_str() {
-        "srt" => FileFormat::Srt,
-        "md" | "markdown" => FileFormat::Markdown,
-        _ => FileFormat::Txt,
-    };
+    let format = FileFormat::from_name(&output_format).unwrap_or(FileFormat::Txt);
 
-    let options = MergeOptions {
+    let mut options = MergeOptions {
         output_format: format,
-        time_offset_seconds: 0.0,
-        remove_timestamps: false,
-        add_file_markers: true,
+        ..options.unwrap_or_default()
     };
+    if let Some(manifest_path) = chunk_manifest {
+        let manifest = ChunkManifest::load(Path::new(&manifest_path)).await.map_err(|e| e.to_string())?;
+        options.chunk_manifest = Some(manifest);
+    }
+    if let Some(corrections_path) = &term_corrections {
+        options.term_corrections = load_corrections(Path::new(corrections_path))
+            .await
+            .map_err(|e| format!("Failed to load term corrections: {}", e))?;
+    }
 
-    let mut merger = TranscriptionMerger::new(options);
+    let mut merger = TranscriptionMerger::new(options.clone());
     
     match merger.add_files(files.clone()).await {
         Ok(_) => {
-            match merger.merge().await {
-                Ok(merged_content) => {
-                    // Store merged content, format, and source files for re-merge on format change
+            let (mut segments, mut hallucinations) = merger.merged_segments_with_hallucinations();
+            if options.language.is_none() {
+                options.language = detect_language(&segments);
+                // Untimed lines were estimated at the default rate before the language was known
+                let rate_from_language = options.words_per_minute.is_none() && options.language.is_some();
+                if rate_from_language && segments.iter().any(|segment| segment.end_time_estimated) {
+                    merger = TranscriptionMerger::new(options.clone());
+                    merger
+                        .add_files(files.clone())
+                        .await
+                        .map_err(|e| format!("Failed to load transcription files: {}", e))?;
+                    (segments, hallucinations) = merger.merged_segments_with_hallucinations();
+                }
+            }
+            if !options.profanity_languages.is_empty() {
+                let settings = AppSettings::load().map_err(|e| e.to_string())?;
+                options.profanity_lists = settings.profanity_lists_for(&options.resolved_profanity_languages());
+            }
+            let file_names: Vec<String> = merger
+                .file_order()
+                .iter()
+                .filter_map(|path| Path::new(path).file_name().map(|name| name.to_string_lossy().to_string()))
+                .collect();
+            let boundaries = file_boundaries(&segments, &file_names, options.default_duration_seconds);
+            let totals = compute_merge_stats(&segments, options.default_duration_seconds);
+            // Formatting once surfaces problems now rather than at export time
+            match merger.format_segments(&segments) {
+                Ok(_) => {
+                    let translated = match &translation {
+                        Some(translation) => Some(
+                            translate_segments(&segments, translation)
+                                .await
+                                .map_err(|e| format!("Failed to translate transcription: {}", e))?,
+                        ),
+                        None => None,
+                    };
+
+                    // The raw merge becomes the first version of a new round of edits
+                    let version = store_merge_version("Merge", &options, &segments).await;
+
                     let mut global_transcription = MERGED_TRANSCRIPTION.lock().await;
                     *global_transcription = Some(MergedState {
-                        content: merged_content.clone(),
-                        format: output_format.to_lowercase(),
-                        files: files.clone(),
+                        segments,
+                        options: options.clone(),
+                        hallucinations: hallucinations.clone(),
+                        translation: translated,
+                        version: version.map(|version| version.number),
                     });
 
-                    Ok(format!(
-                        "Successfully merged {} files ({} segments) into {} format", 
-                        merger.get_file_count(),
-                        merger.get_total_segments(),
-                        output_format
-                    ))
+                    record_operation(
+                        OperationKind::Merge,
+                        format!("Merged {} files into {}", files.len(), output_format),
+                        serde_json::json!({
+                            "files": files,
+                            "output_format": output_format,
+                            "options": options,
+                            "term_corrections": term_corrections,
+                            "translation": translation,
+                            "words": totals.words,
+                            "duration_seconds": totals.duration_seconds,
+                        }),
+                    ).await;
+
+                    let warnings = merger.get_warnings();
+                    for warning in &warnings {
+                        println!("Parse warning: {}", warning);
+                    }
+
+                    Ok(MergeSummary {
+                        message: format!(
+                            "Successfully merged {} files ({} segments) into {} format",
+                            merger.get_file_count(),
+                            merger.get_total_segments(),
+                            output_format
+                        ),
+                        file_count: merger.get_file_count(),
+                        segment_count: merger.get_total_segments(),
+                        warnings,
+                        file_order: merger.file_order(),
+                        hallucinations,
+                        language: options.language,
+                        file_boundaries: boundaries,
+                        language_warnings: mixed_language_warnings(&merger.file_languages()),
+                    })
                 }
                 Err(e) => Err(format!("Failed to merge transcriptions: {}", e)),
             }
@@ -229,7 +491,393 @@ pub async fn merge_transcriptions(
     }
 }
 
+/// Merges the original transcripts like `merge_transcriptions`, then the
+/// translation files with the same options, and puts each translated cue
+/// under the original cue it matches by time. Exports then show both
+/// languages in every cue.
+#[tauri::command]
+pub async fn merge_bilingual(
+    files: Vec<String>,
+    translation_files: Vec<String>,
+    output_format: String,
+    options: Option<MergeOptions>,
+    chunk_manifest: Option<String>,
+) -> Result<MergeSummary, String> {
+    if translation_files.is_empty() {
+        return Err("No translation files provided".to_string());
+    }
+    let mut summary = merge_transcriptions(files, output_format, options, chunk_manifest, None, None).await?;
+
+    let options = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        state.options.clone()
+    };
+    // The translation is in another language, so detection and term corrections do not carry over
+    let mut translation_merger = TranscriptionMerger::new(MergeOptions {
+        language: None,
+        term_corrections: Vec::new(),
+        ..options
+    });
+    translation_merger
+        .add_files(translation_files.clone())
+        .await
+        .map_err(|e| format!("Failed to load translation files: {}", e))?;
+    let translations = translation_merger.merged_segments();
+
+    let untranslated = {
+        let mut global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_mut()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        let (segments, untranslated) = pair_translations(&state.segments, &translations, state.options.default_duration_seconds);
+        state.segments = segments;
+        state.version = None;
+        untranslated
+    };
+
+    record_operation(
+        OperationKind::Translation,
+        format!("Added the translation from {} files", translation_files.len()),
+        serde_json::json!({
+            "translation_files": translation_files,
+            "translation_language": detect_language(&translations),
+            "untranslated": untranslated,
+        }),
+    ).await;
+
+    summary.warnings.extend(translation_merger.get_warnings());
+    if untranslated > 0 {
+        summary.message = format!("{}; {} cues have no translation", summary.message, untranslated);
+    }
+    Ok(summary)
+}
+
+/// Stores the API key of a translation provider in the OS keychain; an empty
+/// key removes it.
+#[tauri::command]
+pub async fn set_translation_api_key(provider: TranslationProvider, api_key: String) -> Result<(), String> {
+    translation::set_api_key(provider, &api_key).map_err(|e| e.to_string())
+}
+
+/// Lists the speakers of each file so the frontend can offer per-file renames
+/// (`MergeOptions::speaker_mapping`) before merging.
+#[tauri::command]
+pub async fn list_transcript_speakers(files: Vec<String>) -> Result<Vec<FileSpeakers>, String> {
+    let mut merger = TranscriptionMerger::new(MergeOptions::default());
+    merger.add_files(files).await.map_err(|e| format!("Failed to load transcription files: {}", e))?;
+
+    Ok(merger
+        .speakers_by_file()
+        .into_iter()
+        .map(|(file, speakers)| FileSpeakers { file, speakers })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_merged_segments() -> Result<Vec<TranscriptionSegment>, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    Ok(state.segments.clone())
+}
+
+/// The placeholders a TXT line template (`txt_template`) may use.
+#[tauri::command]
+pub async fn get_template_tokens() -> Result<Vec<TemplateToken>, String> {
+    Ok(TEMPLATE_TOKENS
+        .iter()
+        .map(|(token, description)| TemplateToken { token: format!("{{{}}}", token), description: description.to_string() })
+        .collect())
+}
+
+/// Checks the merged cues against the subtitle rules; an empty list means
+/// everything passed.
+#[tauri::command]
+pub async fn validate_subtitles(rules: Option<QcRules>) -> Result<Vec<Violation>, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    Ok(qc::validate_subtitles(&state.segments, &rules.unwrap_or_default()))
+}
+
+/// Edits one merged segment in place; omitted fields keep their value. Each
+/// editing command returns all segments, as indices may shift.
+#[tauri::command]
+pub async fn update_segment(
+    index: usize,
+    text: Option<String>,
+    start: Option<f64>,
+    end: Option<f64>,
+) -> Result<Vec<TranscriptionSegment>, String> {
+    let details = serde_json::json!({ "edit": "update", "index": index, "text": text, "start": start, "end": end });
+    edit_merged_segments(format!("Edited segment {}", index), details, |segments, _| {
+        editing::update_segment(segments, index, text, start, end)
+    })
+    .await
+}
+
+/// Splits a segment at character `position` of its text, at `time` or at the
+/// time proportional to the position.
+#[tauri::command]
+pub async fn split_segment(index: usize, position: usize, time: Option<f64>) -> Result<Vec<TranscriptionSegment>, String> {
+    let details = serde_json::json!({ "edit": "split", "index": index, "position": position, "time": time });
+    edit_merged_segments(format!("Split segment {}", index), details, |segments, default_duration| {
+        editing::split_segment(segments, index, position, time, default_duration)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn merge_segments(first: usize, last: usize) -> Result<Vec<TranscriptionSegment>, String> {
+    let details = serde_json::json!({ "edit": "merge", "first": first, "last": last });
+    edit_merged_segments(format!("Merged segments {}-{}", first, last), details, |segments, _| {
+        editing::merge_segments(segments, first, last)
+    })
+    .await
+}
+
+#[tauri::command]
+pub async fn delete_segment(index: usize) -> Result<Vec<TranscriptionSegment>, String> {
+    let details = serde_json::json!({ "edit": "delete", "index": index });
+    edit_merged_segments(format!("Deleted segment {}", index), details, |segments, _| {
+        editing::delete_segment(segments, index).map(|_| ())
+    })
+    .await
+}
+
+/// Writes the selected segments (by index) to a file in a temporary folder and
+/// returns its path, for dragging them out of the app into an editor or NLE.
+/// Each call replaces the previous file.
+#[tauri::command]
+pub async fn export_segments_for_drag(indices: Vec<usize>, format: String) -> Result<String, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    let mut indices = indices;
+    indices.sort_unstable();
+    indices.dedup();
+    let segments: Vec<TranscriptionSegment> = indices
+        .iter()
+        .map(|index| state.segments.get(*index).cloned().ok_or(format!("Segment {} does not exist", index)))
+        .collect::<Result<_, _>>()?;
+    let first = segments.first().ok_or("No segments selected")?;
+
+    let format = FileFormat::from_name(&format).unwrap_or(FileFormat::Txt);
+    let merger = TranscriptionMerger::new(MergeOptions {
+        output_format: format.clone(),
+        ..state.options.clone()
+    });
+    let content = merger.format_segments(&segments).map_err(|e| e.to_string())?;
+
+    let directory = std::env::temp_dir().join("transcription-assistant-drag");
+    let _ = std::fs::remove_dir_all(&directory);
+    std::fs::create_dir_all(&directory)
+        .map_err(|e| format!("Failed to create temporary folder: {}", e))?;
+    let file_name = format!(
+        "segments_{}.{}",
+        format_txt_timestamp(first.start_time).replace(':', "-"),
+        format.extension()
+    );
+    let path = directory.join(file_name);
+    std::fs::write(&path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplaceReport {
+    pub total_matches: usize,
+    pub matches: Vec<RuleMatch>,
+    /// The segments as they read after the replacement (or would, on a dry run)
+    pub changes: Vec<SegmentChange>,
+}
+
+/// Find-and-replace over the merged transcription, for fixing a misheard name
+/// everywhere at once. With `dry_run` nothing is changed and the report only
+/// previews the matches.
+#[tauri::command]
+pub async fn replace_in_transcription(rule: ReplaceRule, dry_run: bool) -> Result<ReplaceReport, String> {
+    let (total_matches, matches, changes) = {
+        let mut global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_mut()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+        let matches = find_matches(&state.segments, &rule).map_err(|e| e.to_string())?;
+        let total_matches: usize = matches.iter().map(|m| m.count).sum();
+        let changes = if dry_run {
+            apply_replace_rules(&mut state.segments.clone(), std::slice::from_ref(&rule))
+        } else {
+            ensure_editable(state).await?;
+            state.version = None;
+            apply_replace_rules(&mut state.segments, std::slice::from_ref(&rule))
+        }
+        .map_err(|e| e.to_string())?;
+        (total_matches, matches, changes)
+    };
+
+    if !dry_run && !changes.is_empty() {
+        record_operation(
+            OperationKind::Edit,
+            format!("Replaced \"{}\" with \"{}\" ({} matches)", rule.find, rule.replace, total_matches),
+            serde_json::json!({
+                "edit": "replace",
+                "rule": rule,
+                "matches": total_matches,
+                "indices": changes.iter().map(|change| change.segment_index).collect::<Vec<_>>(),
+            }),
+        ).await;
+    }
+
+    Ok(ReplaceReport { total_matches, matches, changes })
+}
+
+/// Applies `edit` to the merged segments and records it in the project history.
+async fn edit_merged_segments(
+    summary: String,
+    details: serde_json::Value,
+    edit: impl FnOnce(&mut Vec<TranscriptionSegment>, f64) -> anyhow::Result<()>,
+) -> Result<Vec<TranscriptionSegment>, String> {
+    let segments = {
+        let mut global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_mut()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+        ensure_editable(state).await?;
+        edit(&mut state.segments, state.options.default_duration_seconds)
+            .map_err(|e| format!("Failed to edit segments: {}", e))?;
+        state.version = None;
+        state.segments.clone()
+    };
+
+    record_operation(OperationKind::Edit, summary, details).await;
+    Ok(segments)
+}
+
+/// Refuses edits to a merged result that is a delivered version.
+async fn ensure_editable(state: &MergedState) -> Result<(), String> {
+    match ACTIVE_PROJECT.lock().await.as_ref() {
+        Some(project) => check_editable(&project.versions, state.version).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+/// Keeps the segments as the next version of the open project; without an
+/// open project there is nowhere to keep them and nothing is stored.
+async fn store_merge_version(label: &str, options: &MergeOptions, segments: &[TranscriptionSegment]) -> Option<VersionInfo> {
+    let mut active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_mut()?;
+    let info = add_version(&mut project.versions, label, options, segments);
+    if let Err(e) = project.save() {
+        println!("Failed to save project: {}", e);
+    }
+    Some(info)
+}
+
+/// Saves the current merged result as a new version of the open project.
+#[tauri::command]
+pub async fn save_merge_version(label: Option<String>) -> Result<VersionInfo, String> {
+    let mut global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_mut()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    let label = label.filter(|label| !label.trim().is_empty()).unwrap_or_else(|| "Edited".to_string());
+    let info = store_merge_version(&label, &state.options, &state.segments)
+        .await
+        .ok_or_else(|| "No project is open".to_string())?;
+    state.version = Some(info.number);
+    Ok(info)
+}
+
+/// Records that a version went to the client. From then on it is read-only:
+/// while the merged result is that version, editing commands refuse to run.
+#[tauri::command]
+pub async fn mark_version_delivered(number: u32, recipient: Option<String>, note: Option<String>) -> Result<VersionInfo, String> {
+    let info = {
+        let mut active = ACTIVE_PROJECT.lock().await;
+        let project = active.as_mut().ok_or("No project is open")?;
+        mark_delivered(&mut project.versions, number, recipient, note).map_err(|e| e.to_string())?;
+        project.save().map_err(|e| format!("Failed to save project: {}", e))?;
+        find_version(&project.versions, number).map_err(|e| e.to_string())?.info()
+    };
+
+    let delivery = info.delivery.clone();
+    record_operation(
+        OperationKind::Export,
+        match delivery.as_ref().and_then(|delivery| delivery.recipient.as_deref()) {
+            Some(recipient) => format!("Delivered version {} to {}", number, recipient),
+            None => format!("Delivered version {}", number),
+        },
+        serde_json::json!({ "version": number, "delivery": delivery }),
+    ).await;
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn list_merge_versions() -> Result<Vec<VersionInfo>, String> {
+    match ACTIVE_PROJECT.lock().await.as_ref() {
+        Some(project) => Ok(project.versions.iter().map(MergeVersion::info).collect()),
+        None => Err("No project is open".to_string()),
+    }
+}
+
+/// Changes from version `from` to version `to`, or to the current merged
+/// result when `to` is not given.
+#[tauri::command]
+pub async fn diff_merge_versions(from: u32, to: Option<u32>) -> Result<Vec<VersionChange>, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_ref().ok_or("No project is open")?;
+
+    let old = &find_version(&project.versions, from).map_err(|e| e.to_string())?.segments;
+    let new: &[TranscriptionSegment] = match to {
+        Some(to) => &find_version(&project.versions, to).map_err(|e| e.to_string())?.segments,
+        None => &global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?
+            .segments,
+    };
+    Ok(diff_segments(old, new))
+}
+
+/// Makes a stored version the current merged result again. The versions
+/// after it are kept, so a restore can itself be undone.
+#[tauri::command]
+pub async fn restore_merge_version(number: u32) -> Result<Vec<TranscriptionSegment>, String> {
+    let version = {
+        let active = ACTIVE_PROJECT.lock().await;
+        let project = active.as_ref().ok_or("No project is open")?;
+        find_version(&project.versions, number).map_err(|e| e.to_string())?.clone()
+    };
+
+    *MERGED_TRANSCRIPTION.lock().await = Some(MergedState {
+        segments: version.segments.clone(),
+        options: version.options,
+        hallucinations: Vec::new(),
+        translation: None,
+        version: Some(version.number),
+    });
+    record_operation(
+        OperationKind::Restore,
+        format!("Restored version {} ({})", version.number, version.label),
+        serde_json::json!({ "version": version.number }),
+    ).await;
+    Ok(version.segments)
+}
+
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn export_merged_transcription(
     output_path: String,
     file_name: String,
@@ -237,37 +885,147 @@ pub async fn export_merged_transcription(
     timecode_format: String,
     custom_timecode_format: Option<String>,
     include_extended_info: bool,
+    replace_rules: Option<Vec<ReplaceRule>>,
+    export_options: Option<ExportOptions>,
 ) -> Result<serde_json::Value, String> {
     let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let replace_rules = replace_rules.unwrap_or_default();
+    let mut export_options = export_options.unwrap_or_default().resolved();
+    if export_options.text_case != TextCase::Original {
+        export_options.glossary = AppSettings::load().map_err(|e| e.to_string())?.glossary;
+    }
 
     if let Some(state) = global_transcription.as_ref() {
-        // If the export format differs from the merge format, re-merge with the correct format
-        let content = if output_format.to_lowercase() != state.format {
-            let target_format = match output_format.to_lowercase().as_str() {
-                "srt" => FileFormat::Srt,
-                "md" | "markdown" => FileFormat::Markdown,
-                _ => FileFormat::Txt,
-            };
-            let options = MergeOptions {
-                output_format: target_format,
-                time_offset_seconds: 0.0,
-                remove_timestamps: false,
-                add_file_markers: true,
-            };
-            let mut merger = TranscriptionMerger::new(options);
-            merger.add_files(state.files.clone()).await.map_err(|e| e.to_string())?;
-            merger.merge().await.map_err(|e| e.to_string())?
-        } else {
-            state.content.clone()
+        // Wrapping follows the rules of the transcript's language unless another is chosen
+        if export_options.line_break_language.is_none() {
+            export_options.line_break_language = state.options.language.clone();
+        }
+
+        // Meeting details of the open project go into the document header
+        let metadata = active_project_metadata().await;
+
+        // Projects with an episode naming scheme name their exports after the episode;
+        // a given name may use the media tags of the project
+        let file_name = match file_name.trim() {
+            "" => active_episode_name().await.ok_or("Please specify a file name")?,
+            name => active_media().await.fill(name),
         };
 
-        // Build full file path
-        let extension = match output_format.as_str() {
-            "srt" => "srt",
-            "md" => "md",
-            _ => "txt"
+        // Everything that shapes the output, hashed into the sidecar of reproducible exports
+        export_options.reproducible = state.options.reproducible;
+        let shaping_options = serde_json::json!({
+            "merge": state.options,
+            "profanity_lists": state.options.profanity_lists,
+            "term_corrections": state.options.term_corrections,
+            "output_format": output_format,
+            "timecode_format": timecode_format,
+            "custom_timecode_format": custom_timecode_format,
+            "include_extended_info": include_extended_info,
+            "replace_rules": replace_rules,
+            "export": export_options,
+            "glossary": export_options.glossary,
+            "metadata": metadata,
+        });
+        let write_sidecar = |path: &Path| -> Result<Option<String>, String> {
+            if !state.options.reproducible {
+                return Ok(None);
+            }
+            let sidecar = write_options_sidecar(path, &shaping_options).map_err(|e| format!("Failed to write options file: {}", e))?;
+            Ok(Some(sidecar.to_string_lossy().to_string()))
         };
 
+        // Word and HTML documents are built from the segments rather than from formatted text
+        let document_format = output_format.to_lowercase();
+        if document_format == "docx" || document_format == "html" {
+            let mut segments = state.segments.clone();
+            let changes = apply_replace_rules(&mut segments, &replace_rules).map_err(|e| e.to_string())?;
+            export_options.apply_to_segments(&mut segments);
+
+            let title = metadata.meeting_title.clone().unwrap_or_else(|| file_name.clone());
+            let (bytes, description) = if document_format == "docx" {
+                let options = DocxOptions {
+                    title,
+                    show_timestamps: !state.options.remove_timestamps,
+                    file_headings: state.options.add_file_markers,
+                    reproducible: state.options.reproducible,
+                };
+                (render_docx(&segments, &metadata, &options).map_err(|e| e.to_string())?, "Word document")
+            } else {
+                let media_path = export_options.media_path.as_deref().map(Path::new);
+                let media_src = match media_path {
+                    Some(path) if export_options.embed_media => Some(
+                        media_data_uri(path).map_err(|e| format!("Failed to read media file: {}", e))?,
+                    ),
+                    Some(path) => Some(media_link(path, Path::new(&output_path))),
+                    None => None,
+                };
+                let options = HtmlOptions {
+                    title,
+                    show_timestamps: !state.options.remove_timestamps,
+                    file_headings: state.options.add_file_markers,
+                    media_src,
+                    video: media_path.is_some_and(is_video),
+                    search: false,
+                    language: state.options.language.clone(),
+                };
+                (render_html(&segments, &metadata, &options).into_bytes(), "HTML page")
+            };
+
+            let file_name_with_ext = if file_name.contains('.') { file_name } else { format!("{}.{}", file_name, document_format) };
+            let written = export_options.write_output(&Path::new(&output_path).join(file_name_with_ext), &bytes)
+                .map_err(|e| format!("Failed to write file: {}", e))?;
+            let file_path = written.path.to_string_lossy().to_string();
+            println!("Exported transcription to: {}", file_path);
+            let options_path = write_sidecar(&written.path)?;
+            open_exported_file(&written.path, export_options.open_after_export);
+
+            record_operation(
+                OperationKind::Export,
+                format!("Exported {}", file_path),
+                serde_json::json!({
+                    "path": file_path,
+                    "output_format": output_format,
+                    "replace_rules": replace_rules,
+                    "export_options": export_options,
+                    "changed_segments": changes.len(),
+                }),
+            ).await;
+
+            return Ok(serde_json::json!({
+                "path": file_path,
+                "size": written.size,
+                "checksum_path": written.checksum_path,
+                "options_path": options_path,
+                "changes": changes,
+                "message": format!("Successfully exported {} segments to {}", segments.len(), description)
+            }));
+        }
+
+        if timecode_format == "custom" && custom_timecode_format.is_none() {
+            return Err("Custom format specified but no format provided".to_string());
+        }
+
+        // The requested format is rendered from the stored segments, so times keep
+        // their milliseconds and end times whatever format the merge was run with
+        let format = FileFormat::from_name(&output_format).unwrap_or(FileFormat::Txt);
+        let merger = TranscriptionMerger::new(MergeOptions {
+            output_format: format.clone(),
+            frame_rate: export_options.frame_rate.or(state.options.frame_rate),
+            txt_template: export_options.txt_template.clone().or_else(|| state.options.txt_template.clone()),
+            timecode_format: Some(timecode_format.clone()),
+            custom_timecode_format: custom_timecode_format.clone(),
+            // Extended info is the source file name next to each TXT line
+            add_file_markers: state.options.add_file_markers && (include_extended_info || format != FileFormat::Txt),
+            ..state.options.clone()
+        });
+        let mut segments = state.segments.clone();
+        let changes = apply_replace_rules(&mut segments, &replace_rules).map_err(|e| e.to_string())?;
+        let segments = export_options.prepare_segments(segments, &format, state.options.default_duration_seconds);
+        let content = merger.format_segments(&segments).map_err(|e| e.to_string())?;
+
+        // Build full file path
+        let extension = format.extension();
+
         let file_name_with_ext = if file_name.contains('.') {
             file_name.clone()
         } else {
@@ -276,24 +1034,86 @@ pub async fn export_merged_transcription(
 
         let output_file = std::path::Path::new(&output_path).join(&file_name_with_ext);
 
-        // Process content based on options
-        let processed_content = process_transcription_content(
-            &content,
-            &timecode_format,
-            custom_timecode_format.as_deref(),
-            include_extended_info,
-        )?;
-        
-        // Write the processed content to file
-        std::fs::write(&output_file, &processed_content)
+        let mut processed_content = add_metadata_header(&content, &format, &metadata)
+            .map_err(|e| e.to_string())?;
+
+        // Rolling documents get each export as a new session at their end
+        if export_options.append {
+            if !matches!(format, FileFormat::Txt | FileFormat::Markdown) {
+                return Err("Appending is only supported for TXT and Markdown exports".to_string());
+            }
+            if export_options.compression != Compression::None {
+                return Err("Appending cannot be combined with compression".to_string());
+            }
+            if state.options.reproducible {
+                return Err("Appending stamps each session with the export time and cannot be combined with reproducible output".to_string());
+            }
+            let existing = match std::fs::read(&output_file) {
+                Ok(bytes) => read_text_with_encoding(&bytes),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(e) => return Err(format!("Failed to read existing file: {}", e)),
+            };
+            let label = format!(
+                "{} — {}",
+                metadata.meeting_title.as_deref().unwrap_or(&file_name),
+                chrono::Local::now().format("%Y-%m-%d %H:%M")
+            );
+            processed_content = append_session(&existing, &processed_content, &format, &label);
+        }
+
+        let bytes = export_options.encode(&processed_content).map_err(|e| e.to_string())?;
+
+        // Write the processed content to file (compressed and with checksum if requested)
+        let written = export_options.write_output(&output_file, &bytes)
             .map_err(|e| format!("Failed to write file: {}", e))?;
         
-        let file_path = output_file.to_string_lossy().to_string();
+        let file_path = written.path.to_string_lossy().to_string();
         println!("Exported transcription to: {}", file_path);
+        let options_path = write_sidecar(&written.path)?;
+        open_exported_file(&written.path, export_options.open_after_export);
+
+        // A translation made during the merge goes next to it as `<name>.<language>.<ext>`
+        let translation_path = match &state.translation {
+            Some(translation) => {
+                let translated = export_options.prepare_segments(
+                    translation.segments.clone(),
+                    &format,
+                    state.options.default_duration_seconds,
+                );
+                let content = merger.format_segments(&translated).map_err(|e| e.to_string())?;
+                let stem = output_file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+                let path = output_file.with_file_name(format!("{}.{}.{}", stem, translation.language.to_lowercase(), extension));
+                let bytes = export_options.encode(&content).map_err(|e| e.to_string())?;
+                let written = export_options.write_output(&path, &bytes)
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+                Some(written.path.to_string_lossy().to_string())
+            }
+            None => None,
+        };
+
+        record_operation(
+            OperationKind::Export,
+            format!("Exported {}", file_path),
+            serde_json::json!({
+                "path": file_path,
+                "translation_path": translation_path,
+                "output_format": output_format,
+                "timecode_format": timecode_format,
+                "custom_timecode_format": custom_timecode_format,
+                "include_extended_info": include_extended_info,
+                "replace_rules": replace_rules,
+                "export_options": export_options,
+                "changed_segments": changes.len(),
+            }),
+        ).await;
         
         Ok(serde_json::json!({
             "path": file_path,
-            "size": processed_content.len(),
+            "size": written.size,
+            "checksum_path": written.checksum_path,
+            "options_path": options_path,
+            "translation_path": translation_path,
+            "changes": changes,
             "message": format!("Successfully exported {} characters to file", processed_content.len())
         }))
     } else {
@@ -301,69 +1121,1984 @@ pub async fn export_merged_transcription(
     }
 }
 
+/// Re-exports the latest merge version of each project into `output_path`
+/// with one set of export options, e.g. to regenerate every SRT after the
+/// line length rules changed. Files are named after the episode or the
+/// project; a project that fails is reported and the rest still export.
 #[tauri::command]
-pub async fn open_folder(path: String) -> Result<(), String> {
-    println!("Opening folder: {}", path);
-    #[cfg(target_os = "macos")]
-    {
-        std::process::Command::new("open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("Failed to open folder: {}", e))?;
+pub async fn batch_export_projects(
+    project_paths: Vec<String>,
+    output_path: String,
+    output_format: String,
+    export_options: Option<ExportOptions>,
+) -> Result<Vec<BatchExportResult>, String> {
+    if project_paths.is_empty() {
+        return Err("No projects selected".to_string());
     }
-    #[cfg(target_os = "windows")]
-    {
-        use std::os::windows::process::CommandExt;
-        // Use cmd /c start to handle paths with special characters better
-        std::process::Command::new("cmd")
-            .args(["/c", "start", "", &path])
-            .creation_flags(0x08000000) // CREATE_NO_WINDOW
-            .spawn()
-            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    let format = FileFormat::from_name(&output_format).ok_or(format!("Batch export does not support {}", output_format))?;
+    let mut export_options = export_options.unwrap_or_default().resolved();
+    if export_options.text_case != TextCase::Original {
+        export_options.glossary = AppSettings::load().map_err(|e| e.to_string())?.glossary;
     }
-    #[cfg(target_os = "linux")]
-    {
-        std::process::Command::new("xdg-open")
-            .arg(&path)
-            .spawn()
-            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    if export_options.append {
+        return Err("Appending is not supported in batch exports".to_string());
     }
-    
-    Ok(())
-}
+    let active_id = ACTIVE_PROJECT.lock().await.as_ref().map(|project| project.id.clone());
 
-fn process_transcription_content(
-    content: &str,
-    timecode_format: &str,
-    custom_format: Option<&str>,
-    include_extended_info: bool,
-) -> Result<String, String> {
-    use regex::Regex;
-    
-    // Parse and process each line of the transcription
-    let mut processed_lines = Vec::new();
-    
-    for line in content.lines() {
-        let line = line.trim();
-        if line.is_empty() {
-            processed_lines.push(String::new());
-            continue;
+    let mut results = Vec::with_capacity(project_paths.len());
+    let mut used_names = std::collections::HashSet::new();
+    for project_path in project_paths {
+        let mut project = match Project::load(Path::new(&project_path)) {
+            Ok(project) => project,
+            Err(e) => {
+                results.push(BatchExportResult {
+                    project_name: String::new(),
+                    project_path,
+                    version: None,
+                    path: None,
+                    error: Some(e.to_string()),
+                });
+                continue;
+            }
+        };
+
+        // Two projects with the same name must not overwrite each other's file
+        let base_name = project.episode_name().unwrap_or_else(|| sanitize_file_name(&project.name));
+        let mut name = base_name.clone();
+        let mut counter = 2;
+        while !used_names.insert(name.to_lowercase()) {
+            name = format!("{} ({})", base_name, counter);
+            counter += 1;
+        }
+
+        let written = render_latest_version(&project, &format, &export_options).and_then(|(version, content)| {
+            let bytes = export_options.encode(&content)?;
+            let output_file = Path::new(&output_path).join(format!("{}.{}", name, format.extension()));
+            Ok((version, export_options.write_output(&output_file, &bytes)?))
+        });
+        let result = match written {
+            Ok((version, written)) => {
+                let file_path = written.path.to_string_lossy().to_string();
+                let summary = format!("Batch exported version {} to {}", version, file_path);
+                let details = serde_json::json!({
+                    "path": file_path,
+                    "version": version,
+                    "output_format": output_format,
+                    "export_options": export_options,
+                });
+                // The open project is saved from memory, so its history is updated there
+                if active_id.as_deref() == Some(project.id.as_str()) {
+                    record_operation(OperationKind::Export, summary, details).await;
+                } else {
+                    project.record(OperationKind::Export, summary, details);
+                    if let Err(e) = project.save() {
+                        println!("Failed to save project history: {}", e);
+                    }
+                }
+                BatchExportResult {
+                    project_name: project.name.clone(),
+                    project_path,
+                    version: Some(version),
+                    path: Some(file_path),
+                    error: None,
+                }
+            }
+            Err(e) => BatchExportResult {
+                project_name: project.name.clone(),
+                project_path,
+                version: None,
+                path: None,
+                error: Some(e.to_string()),
+            },
+        };
+        results.push(result);
+    }
+    Ok(results)
+}
+
+/// Renders meeting minutes from the merged transcription into a Markdown or HTML
+/// template (the built-in Markdown template when `template_path` is not given).
+#[tauri::command]
+pub async fn export_minutes(
+    output_path: String,
+    template_path: Option<String>,
+    options: Option<MinutesOptions>,
+) -> Result<serde_json::Value, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+    let metadata = active_project_metadata().await;
+    let options = match options {
+        Some(options) => options,
+        None => MinutesOptions {
+            title: metadata.meeting_title.clone().unwrap_or_else(|| MinutesOptions::default().title),
+            ..MinutesOptions::default()
+        },
+    };
+    let options = MinutesOptions {
+        date: options.date.or(metadata.meeting_date),
+        attendees: if options.attendees.is_empty() { metadata.attendees } else { options.attendees },
+        ..options
+    };
+
+    let template = match &template_path {
+        Some(path) => Some(
+            std::fs::read(path)
+                .map(|bytes| read_text_with_encoding(&bytes))
+                .map_err(|e| format!("Failed to read template {}: {}", path, e))?,
+        ),
+        None => None,
+    };
+
+    let is_html = |path: &Path| {
+        path.extension()
+            .map(|ext| matches!(ext.to_string_lossy().to_lowercase().as_str(), "html" | "htm"))
+            .unwrap_or(false)
+    };
+    let kind = match &template_path {
+        Some(path) if is_html(Path::new(path)) => TemplateKind::Html,
+        Some(_) => TemplateKind::Markdown,
+        None if is_html(Path::new(&output_path)) => TemplateKind::Html,
+        None => TemplateKind::Markdown,
+    };
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension(if kind == TemplateKind::Html { "html" } else { "md" });
+    }
+
+    let segments = state.segments.clone();
+    let content = render_minutes(template.as_deref(), kind, &segments, &options);
+
+    std::fs::write(&output_file, &content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let file_path = output_file.to_string_lossy().to_string();
+    println!("Exported minutes to: {}", file_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Exported minutes {}", file_path),
+        serde_json::json!({
+            "path": file_path,
+            "template": template_path,
+            "options": options,
+        }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": file_path,
+        "size": content.len(),
+        "message": format!("Successfully exported minutes to {}", file_path)
+    }))
+}
+
+/// Readability and speaking-rate statistics of the merged transcription; the
+/// rate timeline uses `bucket_minutes` (5 by default) per entry.
+#[tauri::command]
+pub async fn get_transcript_stats(bucket_minutes: Option<f64>) -> Result<TranscriptStats, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    let bucket_seconds = bucket_minutes.unwrap_or(5.0) * 60.0;
+    Ok(compute_stats(&state.segments, state.options.default_duration_seconds, bucket_seconds))
+}
+
+/// Duration, word count, per-file segment counts, speaking rate and speaker
+/// talk time of the merged transcription, as quoted in delivery reports.
+#[tauri::command]
+pub async fn get_merge_stats() -> Result<MergeStats, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    Ok(compute_merge_stats(&state.segments, state.options.default_duration_seconds))
+}
+
+/// The most frequent terms of the merged transcription with their timestamps,
+/// counted per bucket (5 minutes by default) for a topic heatmap.
+#[tauri::command]
+pub async fn get_keyword_timeline(options: Option<KeywordOptions>) -> Result<KeywordTimeline, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    Ok(keyword_timeline(&state.segments, state.options.default_duration_seconds, &options.unwrap_or_default()))
+}
+
+/// Compares the merged segments with the chunks of the manifest the merge was
+/// run with; chunks covered less than `min_coverage` (0.5 by default) get a
+/// warning, as their transcription probably failed or was cut short.
+#[tauri::command]
+pub async fn check_transcript_coverage(min_coverage: Option<f64>) -> Result<CoverageReport, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+    let manifest = state
+        .options
+        .chunk_manifest
+        .as_ref()
+        .ok_or("The transcription was merged without a chunk manifest")?;
+
+    let min_coverage = min_coverage.unwrap_or(0.5);
+    let chunks = chunk_coverage(&state.segments, manifest, state.options.default_duration_seconds);
+    let warnings = chunks
+        .iter()
+        .filter(|chunk| chunk.coverage < min_coverage)
+        .map(|chunk| format!("chunk {}: only {:.0}% covered", chunk.chunk_number, chunk.coverage * 100.0))
+        .collect();
+
+    Ok(CoverageReport { chunks, warnings })
+}
+
+/// Chunks to transcribe again (with a larger model or another service) because
+/// of low coverage or suspected hallucinations. `retranscribe_flagged_chunks`
+/// does the second pass with OpenAI.
+#[tauri::command]
+pub async fn list_retranscription_candidates(min_coverage: Option<f64>) -> Result<Vec<RetranscriptionCandidate>, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+    let manifest = state
+        .options
+        .chunk_manifest
+        .as_ref()
+        .ok_or("The transcription was merged without a chunk manifest")?;
+
+    let coverage = chunk_coverage(&state.segments, manifest, state.options.default_duration_seconds);
+    Ok(retranscription_candidates(&coverage, &state.hallucinations, manifest, min_coverage.unwrap_or(0.5)))
+}
+
+/// Second pass: transcribes the flagged chunks (see
+/// `list_retranscription_candidates`) again with OpenAI and merges `files`
+/// anew with the new transcripts in place of the old ones.
+#[tauri::command]
+pub async fn retranscribe_flagged_chunks(
+    files: Vec<String>,
+    output_format: String,
+    chunk_manifest: String,
+    options: Option<RetranscriptionOptions>,
+) -> Result<MergeSummary, String> {
+    let options = options.unwrap_or_default();
+    let (merge_options, candidates) = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        let manifest = state
+            .options
+            .chunk_manifest
+            .as_ref()
+            .ok_or("The transcription was merged without a chunk manifest")?;
+        let coverage = chunk_coverage(&state.segments, manifest, state.options.default_duration_seconds);
+        let candidates =
+            retranscription_candidates(&coverage, &state.hallucinations, manifest, options.min_coverage.unwrap_or(0.5));
+        (state.options.clone(), candidates)
+    };
+    if candidates.is_empty() {
+        return Err("No chunks need to be transcribed again".to_string());
+    }
+
+    let new_files = retranscription::retranscribe_chunks(Path::new(&chunk_manifest), &candidates, &options)
+        .await
+        .map_err(|e| format!("Failed to transcribe chunks again: {}", e))?;
+    let manifest = merge_options.chunk_manifest.as_ref().ok_or("The transcription was merged without a chunk manifest")?;
+    let files = retranscription::replace_chunk_files(&files, manifest, &new_files);
+
+    let mut summary = merge_transcriptions(files, output_format, Some(merge_options), None, None, None).await?;
+    summary.message = format!("Transcribed {} chunks again. {}", candidates.len(), summary.message);
+    Ok(summary)
+}
+
+/// Stores the OpenAI API key used by `retranscribe_flagged_chunks` in the OS
+/// keychain; an empty key removes it.
+#[tauri::command]
+pub async fn set_transcription_api_key(api_key: String) -> Result<(), String> {
+    retranscription::set_api_key(&api_key).map_err(|e| e.to_string())
+}
+
+/// Writes a single self-contained HTML page to send to people without the app:
+/// the transcript with a search box and, with `media_path`, the audio
+/// re-encoded at `audio_bitrate` kbps (48 by default) and embedded.
+#[tauri::command]
+pub async fn export_viewer_bundle(
+    output_path: String,
+    media_path: Option<String>,
+    audio_bitrate: Option<u32>,
+) -> Result<serde_json::Value, String> {
+    // Downloading FFmpeg and re-encoding the recording take long, so the transcript is not held locked meanwhile
+    let (segments, language) = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        (state.segments.clone(), state.options.language.clone())
+    };
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension("html");
+    }
+
+    let media_src = match &media_path {
+        Some(media_path) => {
+            let ffmpeg_manager = FFmpegManager::new().map_err(|e| e.to_string())?;
+            ffmpeg_manager.ensure_ffmpeg_available().await.map_err(|e| e.to_string())?;
+            let ffmpeg_path = ffmpeg_manager.get_ffmpeg_path().map_err(|e| e.to_string())?;
+
+            let compressed = std::env::temp_dir().join(format!("viewer_audio_{}.mp3", uuid::Uuid::new_v4()));
+            let result = compress_audio(&ffmpeg_path, Path::new(media_path), &compressed, audio_bitrate.unwrap_or(48))
+                .await
+                .map_err(|e| format!("Failed to compress audio: {}", e))
+                .and_then(|_| media_data_uri(&compressed).map_err(|e| format!("Failed to read compressed audio: {}", e)));
+            let _ = std::fs::remove_file(&compressed);
+            Some(result?)
+        }
+        None => None,
+    };
+
+    let metadata = active_project_metadata().await;
+    let title = metadata
+        .meeting_title
+        .clone()
+        .unwrap_or_else(|| output_file.file_stem().unwrap_or_default().to_string_lossy().to_string());
+    let options = HtmlOptions {
+        title,
+        show_timestamps: true,
+        file_headings: false,
+        media_src,
+        video: false,
+        search: true,
+        language,
+    };
+    let html = render_html(&segments, &metadata, &options);
+    std::fs::write(&output_file, &html)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let file_path = output_file.to_string_lossy().to_string();
+    println!("Exported viewer bundle to: {}", file_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Exported viewer bundle {}", file_path),
+        serde_json::json!({
+            "path": file_path,
+            "media_path": media_path,
+            "audio_bitrate": audio_bitrate,
+        }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": file_path,
+        "size": html.len(),
+        "message": format!("Successfully exported viewer with {} segments to {}", segments.len(), file_path)
+    }))
+}
+
+/// Writes an Anki-importable TSV/CSV of the merged transcription. With a source
+/// audio file, a clip per card is cut into `<name>_media` next to the table and
+/// referenced as `[sound:...]`; copy those files into Anki's collection.media.
+#[tauri::command]
+pub async fn export_flashcards(
+    output_path: String,
+    options: Option<FlashcardOptions>,
+) -> Result<serde_json::Value, String> {
+    let options = options.unwrap_or_default();
+    // Cutting a clip per card takes long, so the transcript is not held locked meanwhile
+    let (segments, default_duration) = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        (state.segments.clone(), state.options.default_duration_seconds)
+    };
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension(if options.format == TableFormat::Csv { "csv" } else { "tsv" });
+    }
+
+    let cards = build_cards(&segments, options.unit, default_duration);
+
+    let clips = match &options.source_audio {
+        Some(source_audio) => {
+            let stem = output_file.file_stem().unwrap_or_default().to_string_lossy().to_string();
+            let media_dir = output_file.with_file_name(format!("{}_media", stem));
+
+            let processor = AudioProcessor::new().map_err(|e| e.to_string())?;
+            processor.initialize().await.map_err(|e| e.to_string())?;
+
+            let ranges: Vec<(usize, f64, f64)> =
+                cards.iter().enumerate().map(|(index, card)| (index + 1, card.start_time, card.end_time)).collect();
+            let paths = processor
+                .extract_clips(source_audio, &ranges, &media_dir, 0.0)
+                .await
+                .map_err(|e| format!("Failed to cut audio clips: {}", e))?;
+            Some(
+                paths
+                    .iter()
+                    .map(|path| path.file_name().unwrap_or_default().to_string_lossy().to_string())
+                    .collect::<Vec<_>>(),
+            )
+        }
+        None => None,
+    };
+
+    let table = render_table(&cards, clips.as_deref(), &options);
+    std::fs::write(&output_file, &table)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let file_path = output_file.to_string_lossy().to_string();
+    println!("Exported {} flashcards to: {}", cards.len(), file_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Exported {} flashcards to {}", cards.len(), file_path),
+        serde_json::json!({
+            "path": file_path,
+            "options": options,
+            "clip_count": clips.as_ref().map(|clips| clips.len()).unwrap_or(0),
+        }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": file_path,
+        "card_count": cards.len(),
+        "clip_count": clips.as_ref().map(|clips| clips.len()).unwrap_or(0),
+        "message": format!("Successfully exported {} flashcards", cards.len())
+    }))
+}
+
+/// Writes YouTube chapters (`00:00 Intro` lines) for the merged transcription,
+/// taken from file boundaries and/or the Markdown headings of the sources.
+#[tauri::command]
+pub async fn export_youtube_chapters(
+    output_path: String,
+    source: Option<ChapterSource>,
+) -> Result<serde_json::Value, String> {
+    let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+    let state = global_transcription
+        .as_ref()
+        .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension("txt");
+    }
+
+    let chapters = build_chapters(&state.segments, source.unwrap_or_default());
+    if chapters.is_empty() {
+        return Err("No file boundaries or headings to make chapters from".to_string());
+    }
+    std::fs::write(&output_file, render_youtube_chapters(&chapters))
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let file_path = output_file.to_string_lossy().to_string();
+    println!("Exported {} chapters to: {}", chapters.len(), file_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Exported {} YouTube chapters to {}", chapters.len(), file_path),
+        serde_json::json!({ "path": file_path, "source": source }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": file_path,
+        "chapters": chapters,
+        "warnings": chapter_warnings(&chapters),
+        "message": format!("Successfully exported {} chapters", chapters.len())
+    }))
+}
+
+/// Writes a handoff sheet for splitting transcription among several people:
+/// one row per chunk of the manifest with its time range and, from the merged
+/// transcription if there is one, the last sentence of the previous chunk.
+#[tauri::command]
+pub async fn export_handoff_sheet(
+    manifest_path: String,
+    output_path: String,
+    format: Option<SheetFormat>,
+) -> Result<serde_json::Value, String> {
+    let manifest = ChunkManifest::load(Path::new(&manifest_path)).await.map_err(|e| e.to_string())?;
+    let format = format.unwrap_or_default();
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension(format.extension());
+    }
+
+    let segments = MERGED_TRANSCRIPTION
+        .lock()
+        .await
+        .as_ref()
+        .map(|state| state.segments.clone())
+        .unwrap_or_default();
+    let rows = handoff_rows(&manifest, &segments);
+    std::fs::write(&output_file, render_handoff(&rows, format))
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let file_path = output_file.to_string_lossy().to_string();
+    println!("Exported handoff sheet for {} chunks to: {}", rows.len(), file_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Exported handoff sheet {}", file_path),
+        serde_json::json!({ "path": file_path, "manifest": manifest_path, "format": format }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": file_path,
+        "chunk_count": rows.len(),
+        "message": format!("Successfully exported a handoff sheet for {} chunks", rows.len())
+    }))
+}
+
+/// Assigns a chunk to a transcriber and/or sets its status; the assignment is
+/// saved in the chunk manifest so everyone sharing the chunk folder sees it.
+#[tauri::command]
+pub async fn update_chunk_assignment(
+    manifest_path: String,
+    chunk_number: usize,
+    assigned_to: Option<String>,
+    status: Option<ChunkStatus>,
+) -> Result<ChunkAssignment, String> {
+    let path = Path::new(&manifest_path);
+    let mut manifest = ChunkManifest::load(path).await.map_err(|e| e.to_string())?;
+    let assignment = assignments::update_assignment(&mut manifest, chunk_number, assigned_to, status)
+        .map_err(|e| e.to_string())?;
+    manifest.save(path).await.map_err(|e| format!("Failed to save chunk manifest: {}", e))?;
+    Ok(assignment)
+}
+
+/// The chunks of a manifest with assignee and status, optionally only those
+/// of one transcriber.
+#[tauri::command]
+pub async fn get_assignment_dashboard(
+    manifest_path: String,
+    assigned_to: Option<String>,
+) -> Result<AssignmentDashboard, String> {
+    let manifest = ChunkManifest::load(Path::new(&manifest_path)).await.map_err(|e| e.to_string())?;
+    Ok(assignments::dashboard(&manifest, assigned_to.as_deref()))
+}
+
+/// Cuts an audio clip from `source_audio` for each selected merged segment
+/// (indices as in the merged, time-sorted segment list). Clips go to
+/// `output_dir`, or `<source>_clips` next to the source file.
+#[tauri::command]
+pub async fn extract_segment_clips(
+    source_audio: String,
+    segment_indices: Vec<usize>,
+    output_dir: Option<String>,
+    padding_seconds: Option<f64>,
+) -> Result<Vec<SegmentClip>, String> {
+    if segment_indices.is_empty() {
+        return Err("No segments selected".to_string());
+    }
+
+    // Cutting clips takes long, so only the selected segments are copied out of the lock
+    let selected = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+
+        let mut selected = Vec::new();
+        for &index in &segment_indices {
+            let segment = state
+                .segments
+                .get(index)
+                .ok_or_else(|| format!("Segment index {} is out of range ({} segments)", index, state.segments.len()))?;
+            let end_time = segment.end_time.unwrap_or(segment.start_time + state.options.default_duration_seconds);
+            selected.push((index, segment.clone(), end_time));
+        }
+        selected
+    };
+
+    let source_path = Path::new(&source_audio);
+    let output_dir = match output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let stem = source_path.file_stem().unwrap_or_default().to_string_lossy();
+            source_path.with_file_name(format!("{}_clips", stem))
+        }
+    };
+
+    let processor = AudioProcessor::new().map_err(|e| e.to_string())?;
+    processor.initialize().await.map_err(|e| e.to_string())?;
+
+    let ranges: Vec<(usize, f64, f64)> =
+        selected.iter().map(|(index, segment, end)| (*index, segment.start_time, *end)).collect();
+    let paths = processor
+        .extract_clips(&source_audio, &ranges, &output_dir, padding_seconds.unwrap_or(0.25))
+        .await
+        .map_err(|e| format!("Failed to cut audio clips: {}", e))?;
+
+    let clips: Vec<SegmentClip> = selected
+        .into_iter()
+        .zip(paths)
+        .map(|((segment_index, segment, end_time), path)| SegmentClip {
+            segment_index,
+            start_time: segment.start_time,
+            end_time,
+            text: segment.text,
+            path: path.to_string_lossy().to_string(),
+        })
+        .collect();
+
+    println!("Extracted {} clips to {:?}", clips.len(), output_dir);
+    Ok(clips)
+}
+
+/// Renders a PNG quote card per selected merged segment into `output_dir`.
+#[tauri::command]
+pub async fn render_quote_cards(
+    segment_indices: Vec<usize>,
+    output_dir: String,
+    options: Option<QuoteCardOptions>,
+) -> Result<Vec<String>, String> {
+    if segment_indices.is_empty() {
+        return Err("No segments selected".to_string());
+    }
+    let options = options.unwrap_or_default();
+
+    // FFmpeg may be downloaded first, so the transcript is not held locked meanwhile
+    let segments = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        state.segments.clone()
+    };
+
+    let ffmpeg_manager = FFmpegManager::new().map_err(|e| e.to_string())?;
+    ffmpeg_manager.ensure_ffmpeg_available().await.map_err(|e| e.to_string())?;
+    let ffmpeg_path = ffmpeg_manager.get_ffmpeg_path().map_err(|e| e.to_string())?;
+
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let mut cards = Vec::new();
+    for index in segment_indices {
+        let segment = segments
+            .get(index)
+            .ok_or_else(|| format!("Segment index {} is out of range ({} segments)", index, segments.len()))?;
+        let timestamp = format_txt_timestamp(segment.start_time).replace(':', "-");
+        let output_file = Path::new(&output_dir).join(format!("quote_{}_{}.png", index + 1, timestamp));
+
+        render_quote_card(&ffmpeg_path, segment, &output_file, &options)
+            .await
+            .map_err(|e| format!("Failed to render quote card: {}", e))?;
+        cards.push(output_file.to_string_lossy().to_string());
+    }
+
+    println!("Rendered {} quote cards to {}", cards.len(), output_dir);
+    Ok(cards)
+}
+
+/// Renders one WAV stem per speaker from `source_audio` (by transcript speaker
+/// labels or by input channel) plus a marker file with the merged transcript.
+/// Files go to `output_dir`, or `<source>_stems` next to the source file.
+#[tauri::command]
+pub async fn export_speaker_stems(
+    window: Window,
+    source_audio: String,
+    output_dir: Option<String>,
+    options: Option<StemOptions>,
+) -> Result<serde_json::Value, String> {
+    let options = options.unwrap_or_default();
+
+    // Rendering takes minutes per stem, so the transcript is not held locked meanwhile
+    let (segments, default_duration) = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        (state.segments.clone(), state.options.default_duration_seconds)
+    };
+
+    let plans = plan_stems(&segments, &options, default_duration);
+    if plans.is_empty() {
+        return Err("No speakers to render stems for".to_string());
+    }
+
+    let source_path = Path::new(&source_audio);
+    let output_dir = match output_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let stem = source_path.file_stem().unwrap_or_default().to_string_lossy();
+            source_path.with_file_name(format!("{}_stems", stem))
+        }
+    };
+    std::fs::create_dir_all(&output_dir)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
+
+    let ffmpeg_manager = FFmpegManager::new().map_err(|e| e.to_string())?;
+    ffmpeg_manager.ensure_ffmpeg_available().await.map_err(|e| e.to_string())?;
+    let ffmpeg_path = ffmpeg_manager.get_ffmpeg_path().map_err(|e| e.to_string())?;
+
+    let mut stems = Vec::new();
+    for (index, plan) in plans.iter().enumerate() {
+        let _ = window.emit("processing-progress", ProcessingProgress {
+            progress: index as f32 / plans.len() as f32 * 100.0,
+            message: format!("Дорожка {} из {}: {}", index + 1, plans.len(), plan.name),
+        });
+
+        let path = stem_path(&output_dir, index, &plan.name);
+        render_stem(&ffmpeg_path, source_path, plan, &path)
+            .await
+            .map_err(|e| format!("Failed to render stem for {}: {}", plan.name, e))?;
+        stems.push(SpeakerStem {
+            name: plan.name.clone(),
+            path: path.to_string_lossy().to_string(),
+        });
+    }
+
+    let markers_path = if options.markers {
+        let path = output_dir.join("markers.txt");
+        std::fs::write(&path, marker_labels(&segments, default_duration))
+            .map_err(|e| format!("Failed to write markers: {}", e))?;
+        Some(path.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    println!("Rendered {} stems to {:?}", stems.len(), output_dir);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Rendered {} speaker stems", stems.len()),
+        serde_json::json!({
+            "source_audio": source_audio,
+            "output_dir": output_dir,
+            "options": options,
+        }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "stems": stems,
+        "markers_path": markers_path,
+        "message": format!("Successfully rendered {} stems", stems.len())
+    }))
+}
+
+/// Writes the merged segments, or the starts of their source files, as
+/// markers on a clip of `source_media` in an OpenTimelineIO or FCPXML
+/// timeline, or as a Premiere Pro marker list or DaVinci Resolve marker EDL,
+/// for an editing application.
+#[tauri::command]
+pub async fn export_transcript_markers(
+    source_media: String,
+    output_path: String,
+    options: Option<MarkerExportOptions>,
+) -> Result<serde_json::Value, String> {
+    let options = options.unwrap_or_default();
+    if options.frame_rate <= 0.0 {
+        return Err("Frame rate must be positive".to_string());
+    }
+
+    // The media is probed below, so the transcript is not held locked meanwhile
+    let (segments, default_duration) = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        let default_duration = state.options.default_duration_seconds;
+        let segments = match options.source {
+            MarkerSource::Segments => state.segments.clone(),
+            MarkerSource::FileBoundaries => file_markers(&state.segments, default_duration),
+        };
+        (segments, default_duration)
+    };
+    let markers: Vec<Marker> = segments
+        .iter()
+        .map(|segment| Marker {
+            segment,
+            end: segment.end_time.unwrap_or(segment.start_time + default_duration),
+        })
+        .collect();
+
+    // The clip must cover the whole media; the transcript end is the fallback
+    let transcript_end = markers.iter().map(|marker| marker.end).fold(0.0, f64::max);
+    let ffmpeg_manager = FFmpegManager::new().map_err(|e| e.to_string())?;
+    let media_duration = match ffmpeg_manager.get_file_info(&source_media).await {
+        Ok((_, duration)) if duration > 0.0 => duration.max(transcript_end),
+        _ => transcript_end,
+    };
+
+    let media_path = Path::new(&source_media);
+    let name = match active_episode_name().await {
+        Some(name) => name,
+        None => media_path.file_stem().unwrap_or_default().to_string_lossy().to_string(),
+    };
+
+    let content = render_markers(&markers, media_path, media_duration, &name, &options)
+        .map_err(|e| e.to_string())?;
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension(options.format.extension());
+    }
+    std::fs::write(&output_file, &content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let file_path = output_file.to_string_lossy().to_string();
+    println!("Exported {} markers to: {}", markers.len(), file_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Exported markers {}", file_path),
+        serde_json::json!({
+            "path": file_path,
+            "source_media": source_media,
+            "options": options,
+        }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": file_path,
+        "marker_count": markers.len(),
+        "message": format!("Successfully exported {} markers to {}", markers.len(), file_path)
+    }))
+}
+
+#[tauri::command]
+pub async fn list_caption_styles() -> Result<Vec<CaptionStyle>, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    Ok(settings.caption_styles)
+}
+
+#[tauri::command]
+pub async fn save_caption_style(style: CaptionStyle) -> Result<Vec<CaptionStyle>, String> {
+    if style.name.trim().is_empty() {
+        return Err("Caption style name must not be empty".to_string());
+    }
+
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.upsert_caption_style(style);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.caption_styles)
+}
+
+#[tauri::command]
+pub async fn delete_caption_style(name: String) -> Result<Vec<CaptionStyle>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    if !settings.remove_caption_style(&name) {
+        return Err(format!("Caption style not found: {}", name));
+    }
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.caption_styles)
+}
+
+#[tauri::command]
+pub async fn get_glossary() -> Result<Vec<String>, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    Ok(settings.glossary)
+}
+
+#[tauri::command]
+pub async fn save_glossary(terms: Vec<String>) -> Result<Vec<String>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.set_glossary(terms);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.glossary)
+}
+
+/// How often the scheduler looks at the queue and the processing window
+const QUEUE_POLL_SECONDS: u64 = 60;
+
+lazy_static::lazy_static! {
+    /// Held while `queue.json` is read and written back
+    static ref JOB_QUEUE: Mutex<()> = Mutex::new(());
+}
+
+/// Queues a file for processing in the scheduled window instead of now. The
+/// chunk folder is named from the project open at this point.
+#[tauri::command]
+pub async fn enqueue_audio_processing(file_path: String, options: Option<ProcessingOptions>) -> Result<QueuedJob, String> {
+    let options = ProcessingOptions {
+        folder_name: active_episode_name().await,
+        ..options.unwrap_or_default()
+    };
+    let _guard = JOB_QUEUE.lock().await;
+    let mut queue = JobQueue::load().map_err(|e| e.to_string())?;
+    let job = queue.enqueue(&file_path, options).clone();
+    queue.save().map_err(|e| e.to_string())?;
+    Ok(job)
+}
+
+#[tauri::command]
+pub async fn list_processing_queue() -> Result<Vec<QueuedJob>, String> {
+    let _guard = JOB_QUEUE.lock().await;
+    Ok(JobQueue::load().map_err(|e| e.to_string())?.jobs)
+}
+
+#[tauri::command]
+pub async fn remove_queued_job(id: String) -> Result<Vec<QueuedJob>, String> {
+    let _guard = JOB_QUEUE.lock().await;
+    let mut queue = JobQueue::load().map_err(|e| e.to_string())?;
+    if !queue.remove(&id).map_err(|e| e.to_string())? {
+        return Err(format!("No queued job {}", id));
+    }
+    queue.save().map_err(|e| e.to_string())?;
+    Ok(queue.jobs)
+}
+
+#[tauri::command]
+pub async fn get_processing_window() -> Result<Option<ProcessingWindow>, String> {
+    Ok(AppSettings::load().map_err(|e| e.to_string())?.processing_window)
+}
+
+/// Sets the hours queued jobs run in; without a window they run right away.
+#[tauri::command]
+pub async fn set_processing_window(processing_window: Option<ProcessingWindow>) -> Result<Option<ProcessingWindow>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.processing_window = processing_window;
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.processing_window)
+}
+
+/// Works through the queue while the processing window is open, one job at a
+/// time. Once the queue is empty or the window closes, the jobs run since the
+/// last summary are reported with a `queue-finished` event.
+pub async fn run_job_scheduler(window: Window) {
+    let mut finished: Vec<String> = Vec::new();
+    loop {
+        let job = match next_scheduled_job().await {
+            Ok(job) => job,
+            Err(e) => {
+                println!("Failed to read the job queue: {}", e);
+                None
+            }
+        };
+
+        let Some(job) = job else {
+            if !finished.is_empty() {
+                let _guard = JOB_QUEUE.lock().await;
+                match JobQueue::load() {
+                    Ok(queue) => {
+                        let _ = window.emit("queue-finished", queue.summary(&finished));
+                    }
+                    Err(e) => println!("Failed to read the job queue: {}", e),
+                }
+                finished.clear();
+            }
+            tokio::time::sleep(std::time::Duration::from_secs(QUEUE_POLL_SECONDS)).await;
+            continue;
+        };
+
+        println!("Running queued job {} for {}", job.id, job.file_path);
+        let result = match AudioProcessor::new() {
+            Ok(processor) => match processor.initialize().await {
+                Ok(()) => run_audio_processing(&window, processor, &job.file_path, job.options.clone()).await,
+                Err(e) => Err(e.to_string()),
+            },
+            Err(e) => Err(e.to_string()),
+        };
+        let (success, message) = match result {
+            Ok(result) => (true, result.message),
+            Err(e) => (false, format!("Processing failed: {}", e)),
+        };
+
+        let _guard = JOB_QUEUE.lock().await;
+        let saved = JobQueue::load().and_then(|mut queue| {
+            queue.finish(&job.id, success, message);
+            queue.save()
+        });
+        if let Err(e) = saved {
+            println!("Failed to save the job queue: {}", e);
+        }
+        finished.push(job.id);
+    }
+}
+
+/// The next pending job, marked running, when the processing window is open.
+async fn next_scheduled_job() -> anyhow::Result<Option<QueuedJob>> {
+    let settings = AppSettings::load()?;
+    if let Some(processing_window) = settings.processing_window {
+        if !processing_window.contains(chrono::Local::now().time()) {
+            return Ok(None);
+        }
+    }
+
+    let _guard = JOB_QUEUE.lock().await;
+    let mut queue = JobQueue::load()?;
+    let job = queue.start_next();
+    if job.is_some() {
+        queue.save()?;
+    }
+    Ok(job)
+}
+
+#[derive(Debug, Serialize)]
+pub struct PowerStatus {
+    pub source: PowerSource,
+    pub battery_mode: BatteryMode,
+}
+
+#[tauri::command]
+pub async fn get_power_status() -> Result<PowerStatus, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    Ok(PowerStatus { source: power_source(), battery_mode: settings.battery_mode })
+}
+
+/// Chooses what audio processing does on battery power; applies from the next run.
+#[tauri::command]
+pub async fn set_battery_mode(mode: BatteryMode) -> Result<PowerStatus, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.battery_mode = mode;
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(PowerStatus { source: power_source(), battery_mode: mode })
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataDirInfo {
+    pub path: String,
+    pub default_path: String,
+    pub relocated: bool,
+}
+
+#[tauri::command]
+pub async fn get_data_dir() -> Result<DataDirInfo, String> {
+    let path = get_app_data_dir().map_err(|e| e.to_string())?;
+    let default_path = default_app_data_dir().map_err(|e| e.to_string())?;
+    Ok(DataDirInfo {
+        relocated: path != default_path,
+        path: path.to_string_lossy().to_string(),
+        default_path: default_path.to_string_lossy().to_string(),
+    })
+}
+
+/// Moves FFmpeg, projects and caches to `path` (back to the default folder
+/// without one) and remembers the new location in the settings.
+#[tauri::command]
+pub async fn relocate_data_dir(path: Option<String>) -> Result<DataDirInfo, String> {
+    let current = get_app_data_dir().map_err(|e| e.to_string())?;
+    let default_path = default_app_data_dir().map_err(|e| e.to_string())?;
+    let target = path.map(PathBuf::from).unwrap_or_else(|| default_path.clone());
+    if target == current {
+        return get_data_dir().await;
+    }
+
+    let moved = move_data_dir(&current, &target).map_err(|e| format!("Failed to move data folder: {}", e))?;
+    println!("Moved {} entries from {} to {}", moved.len(), current.display(), target.display());
+
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.data_dir = (target != default_path).then(|| target.clone());
+    settings.save().map_err(|e| e.to_string())?;
+
+    // The open project may have been among the moved files
+    if let Some(project) = ACTIVE_PROJECT.lock().await.as_mut() {
+        if let Ok(relative) = project.path.strip_prefix(&current) {
+            project.path = target.join(relative);
+        }
+    }
+
+    get_data_dir().await
+}
+
+#[tauri::command]
+pub async fn list_profanity_lists() -> Result<Vec<ProfanityList>, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    Ok(settings.profanity_lists)
+}
+
+#[tauri::command]
+pub async fn save_profanity_list(list: ProfanityList) -> Result<Vec<ProfanityList>, String> {
+    if list.language.trim().is_empty() {
+        return Err("Profanity list language must not be empty".to_string());
+    }
+
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.upsert_profanity_list(list);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.profanity_lists)
+}
+
+#[tauri::command]
+pub async fn delete_profanity_list(language: String) -> Result<Vec<ProfanityList>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    if !settings.remove_profanity_list(&language) {
+        return Err(format!("Profanity list not found: {}", language));
+    }
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.profanity_lists)
+}
+
+/// Burns the merged transcription into `source_video` as captions styled by
+/// the named preset (the built-in style when not given).
+#[tauri::command]
+pub async fn burn_captions(
+    source_video: String,
+    output_path: String,
+    style_name: Option<String>,
+) -> Result<serde_json::Value, String> {
+    let style = match &style_name {
+        Some(name) => {
+            let settings = AppSettings::load().map_err(|e| e.to_string())?;
+            settings
+                .caption_style(name)
+                .cloned()
+                .ok_or_else(|| format!("Caption style not found: {}", name))?
+        }
+        None => CaptionStyle::default(),
+    };
+
+    // The burn-in re-encodes the whole video, so the transcript is not held locked meanwhile
+    let (script, caption_count) = {
+        let global_transcription = MERGED_TRANSCRIPTION.lock().await;
+        let state = global_transcription
+            .as_ref()
+            .ok_or("No merged transcription available. Please merge transcriptions first.")?;
+        let merger = TranscriptionMerger::new(MergeOptions {
+            output_format: FileFormat::Ass,
+            add_file_markers: false,
+            ..state.options.clone()
+        });
+        let segments = merger.mask_profanity(&state.segments);
+        (merger.format_as_ass_with_style(&segments, &style), segments.len())
+    };
+
+    let script_path = std::env::temp_dir().join(format!("captions_{}.ass", uuid::Uuid::new_v4()));
+    std::fs::write(&script_path, script)
+        .map_err(|e| format!("Failed to write caption script: {}", e))?;
+
+    let ffmpeg_manager = FFmpegManager::new().map_err(|e| e.to_string())?;
+    ffmpeg_manager.ensure_ffmpeg_available().await.map_err(|e| e.to_string())?;
+    let ffmpeg_path = ffmpeg_manager.get_ffmpeg_path().map_err(|e| e.to_string())?;
+
+    let result = burn_subtitles(&ffmpeg_path, Path::new(&source_video), &script_path, Path::new(&output_path)).await;
+    let _ = std::fs::remove_file(&script_path);
+    result.map_err(|e| format!("Failed to burn captions: {}", e))?;
+
+    println!("Burned captions into: {}", output_path);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Burned captions into {}", output_path),
+        serde_json::json!({
+            "source_video": source_video,
+            "path": output_path,
+            "style": style,
+        }),
+    ).await;
+
+    Ok(serde_json::json!({
+        "path": output_path,
+        "message": format!("Successfully burned {} captions into {}", caption_count, output_path)
+    }))
+}
+
+/// Returns the JSON schema of options accepted when exporting to `format`.
+#[tauri::command]
+pub async fn get_export_options(format: String) -> Result<serde_json::Value, String> {
+    let format = FileFormat::from_name(&format)
+        .ok_or_else(|| format!("Unsupported output format: {}", format))?;
+    Ok(options_schema(&format))
+}
+
+/// Runs the self-check; `directories` are extra folders (e.g. the export folder)
+/// to test for write access.
+#[tauri::command]
+pub async fn run_diagnostics(directories: Option<Vec<String>>) -> Result<DiagnosticsReport, String> {
+    let directories: Vec<PathBuf> = directories
+        .unwrap_or_default()
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+    let report = crate::diagnostics::run_diagnostics(&directories).await;
+    println!("{}", report.text);
+    Ok(report)
+}
+
+/// Development aid for parser changes: parses a fixture file, or every fixture
+/// in a folder, and compares the result with its `<file>.golden.json`, or
+/// rewrites the golden files when `update` is set.
+#[tauri::command]
+pub async fn validate_parser_fixture(path: String, update: Option<bool>) -> Result<Vec<FixtureReport>, String> {
+    let path = Path::new(&path);
+    let update = update.unwrap_or(false);
+    let reports = if path.is_dir() {
+        validate_fixture_dir(path, update).await
+    } else {
+        validate_fixture(path, update).await.map(|report| vec![report])
+    };
+    reports.map_err(|e| e.to_string())
+}
+
+/// Creates a project file (in the app data folder unless `path` is given) and
+/// makes it the active project that operations are recorded into.
+#[tauri::command]
+pub async fn create_project(name: String, path: Option<String>) -> Result<Project, String> {
+    if name.trim().is_empty() {
+        return Err("Project name must not be empty".to_string());
+    }
+
+    let mut project = Project::new(name.trim(), PathBuf::new());
+    project.path = match path {
+        Some(path) => PathBuf::from(path),
+        None => Project::default_path(&project.id).map_err(|e| e.to_string())?,
+    };
+    project.save().map_err(|e| e.to_string())?;
+
+    println!("Created project {} at {}", project.name, project.path.display());
+    *ACTIVE_PROJECT.lock().await = Some(project.clone());
+    Ok(project)
+}
+
+#[tauri::command]
+pub async fn open_project(path: String) -> Result<Project, String> {
+    let project = Project::load(Path::new(&path)).map_err(|e| e.to_string())?;
+    *ACTIVE_PROJECT.lock().await = Some(project.clone());
+    Ok(project)
+}
+
+#[tauri::command]
+pub async fn get_project_history() -> Result<Vec<HistoryEntry>, String> {
+    match ACTIVE_PROJECT.lock().await.as_ref() {
+        Some(project) => Ok(project.history.clone()),
+        None => Err("No project is open".to_string()),
+    }
+}
+
+/// Disk space taken by FFmpeg, the audio chunks recorded in project histories
+/// and leftover temporary files, each of which `clean_storage` can free.
+#[tauri::command]
+pub async fn get_storage_usage() -> Result<Vec<StorageUsage>, String> {
+    let app_data_dir = get_app_data_dir().map_err(|e| e.to_string())?;
+    let projects = known_projects().await;
+    let locations = StorageLocations { app_data_dir: &app_data_dir, temp_dir: &std::env::temp_dir(), projects: &projects };
+    Ok(StorageCategory::ALL.iter().map(|category| locations.usage(*category)).collect())
+}
+
+/// Deletes one storage category and returns the bytes freed.
+#[tauri::command]
+pub async fn clean_storage(category: StorageCategory) -> Result<u64, String> {
+    let app_data_dir = get_app_data_dir().map_err(|e| e.to_string())?;
+    let projects = known_projects().await;
+    let locations = StorageLocations { app_data_dir: &app_data_dir, temp_dir: &std::env::temp_dir(), projects: &projects };
+    let freed = locations.clean(category).map_err(|e| format!("Failed to clean up: {}", e))?;
+    println!("Freed {} in {:?}", format_file_size(freed), category);
+    Ok(freed)
+}
+
+/// Saved projects plus the open one, which may live elsewhere.
+async fn known_projects() -> Vec<Project> {
+    let mut projects = Project::saved_projects();
+    if let Some(active) = ACTIVE_PROJECT.lock().await.as_ref() {
+        projects.retain(|project| project.path != active.path);
+        projects.push(active.clone());
+    }
+    projects
+}
+
+/// Stores the tags of the media being processed in the open project.
+async fn set_active_media(media: MediaTags) {
+    let mut active = ACTIVE_PROJECT.lock().await;
+    if let Some(project) = active.as_mut() {
+        project.media = Some(media);
+        if let Err(e) = project.save() {
+            println!("Failed to save project: {}", e);
+        }
+    }
+}
+
+/// Tags of the open project's media, empty when unknown.
+async fn active_media() -> MediaTags {
+    ACTIVE_PROJECT
+        .lock()
+        .await
+        .as_ref()
+        .and_then(|project| project.media.clone())
+        .unwrap_or_default()
+}
+
+/// Meeting details of the open project, empty when no project is open.
+async fn active_project_metadata() -> ProjectMetadata {
+    ACTIVE_PROJECT
+        .lock()
+        .await
+        .as_ref()
+        .map(Project::header_metadata)
+        .unwrap_or_default()
+}
+
+#[tauri::command]
+pub async fn set_project_metadata(metadata: ProjectMetadata) -> Result<Project, String> {
+    let mut active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_mut().ok_or("No project is open")?;
+    project.metadata = metadata;
+    project.save().map_err(|e| e.to_string())?;
+    Ok(project.clone())
+}
+
+/// Writes an invoice-ready report of the open project: audio minutes
+/// processed, words delivered and the period, priced with `rates`.
+#[tauri::command]
+pub async fn export_work_report(
+    output_path: String,
+    rates: Option<WorkRates>,
+    format: Option<SheetFormat>,
+) -> Result<WorkReport, String> {
+    let rates = rates.unwrap_or_default();
+    let format = format.unwrap_or_default();
+    let report = {
+        let active = ACTIVE_PROJECT.lock().await;
+        let project = active.as_ref().ok_or("No project is open")?;
+        work_report(project, &rates)
+    };
+
+    let mut output_file = PathBuf::from(&output_path);
+    if output_file.extension().is_none() {
+        output_file.set_extension(format.extension());
+    }
+    std::fs::write(&output_file, render_work_report(&report, &rates, format))
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+    println!("Exported work report to: {}", output_file.display());
+    Ok(report)
+}
+
+/// Sets or clears the delivery deadline of the open project; see
+/// `parse_deadline` for the accepted forms.
+#[tauri::command]
+pub async fn set_project_deadline(deadline: Option<String>) -> Result<Project, String> {
+    let deadline = match deadline.filter(|deadline| !deadline.trim().is_empty()) {
+        Some(deadline) => Some(parse_deadline(&deadline).map_err(|e| e.to_string())?),
+        None => None,
+    };
+
+    let mut active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_mut().ok_or("No project is open")?;
+    project.deadline = deadline;
+    project.save().map_err(|e| e.to_string())?;
+    Ok(project.clone())
+}
+
+/// Projects due within `days` (7 by default) plus overdue ones. With `notify`
+/// a desktop notification lists them as well.
+#[tauri::command]
+pub async fn get_upcoming_deadlines(days: Option<u32>, notify: Option<bool>) -> Result<Vec<UpcomingDeadline>, String> {
+    let projects = known_projects().await;
+    let within = chrono::Duration::days(days.unwrap_or(7) as i64);
+    let upcoming = upcoming_deadlines(&projects, chrono::Utc::now(), within);
+
+    if notify.unwrap_or(false) && !upcoming.is_empty() {
+        let body: Vec<String> = upcoming
+            .iter()
+            .map(|deadline| {
+                let due = deadline.deadline.with_timezone(&chrono::Local).format("%d.%m %H:%M");
+                if deadline.overdue {
+                    format!("{}: просрочено ({})", deadline.project_name, due)
+                } else {
+                    format!("{}: до {}", deadline.project_name, due)
+                }
+            })
+            .collect();
+        // A reminder that cannot be shown must not hide the list itself
+        if let Err(e) = send_notification("Сроки сдачи", &body.join("\n")) {
+            println!("Failed to show notification: {}", e);
+        }
+    }
+    Ok(upcoming)
+}
+
+/// Sets the context (names, jargon) the open project gives the transcriber.
+#[tauri::command]
+pub async fn set_transcription_prompt(prompt: Option<String>) -> Result<Project, String> {
+    let mut active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_mut().ok_or("No project is open")?;
+    project.transcription_prompt = prompt.filter(|prompt| !prompt.trim().is_empty());
+    project.save().map_err(|e| e.to_string())?;
+    Ok(project.clone())
+}
+
+/// Prompt to transcribe the next chunk with: the project context plus the end
+/// of the previous chunk's transcript file, when there is one.
+#[tauri::command]
+pub async fn get_chunk_prompt(previous_transcript: Option<String>) -> Result<String, String> {
+    let previous_text = match previous_transcript {
+        Some(path) => {
+            let mut merger = TranscriptionMerger::new(MergeOptions::default());
+            merger
+                .add_files(vec![path])
+                .await
+                .map_err(|e| format!("Failed to load previous transcript: {}", e))?;
+            merger
+                .merged_segments()
+                .iter()
+                .map(|segment| segment.text.trim())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }
+        None => String::new(),
+    };
+
+    let active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_ref().ok_or("No project is open")?;
+    Ok(project.chunk_prompt(&previous_text))
+}
+
+#[tauri::command]
+pub async fn get_naming_scheme() -> Result<NamingScheme, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    Ok(settings.naming)
+}
+
+#[tauri::command]
+pub async fn save_naming_scheme(scheme: NamingScheme) -> Result<NamingScheme, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.naming = scheme;
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.naming)
+}
+
+/// Gives the open project the next episode number from the settings counter.
+/// A project that already has one keeps its number.
+#[tauri::command]
+pub async fn assign_project_episode(show: Option<String>, template: Option<String>) -> Result<Project, String> {
+    let mut active = ACTIVE_PROJECT.lock().await;
+    let project = active.as_mut().ok_or("No project is open")?;
+
+    let naming = match project.naming.clone() {
+        Some(mut naming) => {
+            naming.show = show.unwrap_or(naming.show);
+            naming.template = template.unwrap_or(naming.template);
+            naming
+        }
+        None => {
+            let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+            let naming = settings.assign_episode(show, template);
+            settings.save().map_err(|e| e.to_string())?;
+            naming
+        }
+    };
+
+    project.naming = Some(naming);
+    project.save().map_err(|e| e.to_string())?;
+    Ok(project.clone())
+}
+
+/// Fills the open project's meeting details from the first event of an .ics file.
+#[tauri::command]
+pub async fn import_project_metadata_ics(path: String) -> Result<Project, String> {
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let metadata = parse_ics(&read_text_with_encoding(&bytes)).map_err(|e| e.to_string())?;
+    set_project_metadata(metadata).await
+}
+
+#[tauri::command]
+pub async fn list_presets() -> Result<Vec<Preset>, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    Ok(settings.presets)
+}
+
+/// Returns the whole option bundle of a preset so the UI can apply it in one step.
+#[tauri::command]
+pub async fn load_preset(name: String) -> Result<Preset, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings
+        .preset(&name)
+        .cloned()
+        .ok_or_else(|| format!("Preset not found: {}", name))
+}
+
+#[tauri::command]
+pub async fn save_preset(preset: Preset) -> Result<Vec<Preset>, String> {
+    if preset.name.trim().is_empty() {
+        return Err("Preset name must not be empty".to_string());
+    }
+
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    settings.upsert_preset(preset);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.presets)
+}
+
+#[tauri::command]
+pub async fn delete_preset(name: String) -> Result<Vec<Preset>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    if !settings.remove_preset(&name) {
+        return Err(format!("Preset not found: {}", name));
+    }
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(settings.presets)
+}
+
+/// Saved destinations without their S3 secret keys. Keys that older
+/// versions kept in the settings file are moved to the keychain first.
+#[tauri::command]
+pub async fn list_destinations() -> Result<Vec<RemoteDestination>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    let mut moved = false;
+    for destination in settings.destinations.iter_mut() {
+        moved |= destination.store_secret().map_err(|e| e.to_string())?;
+    }
+    if moved {
+        settings.save().map_err(|e| e.to_string())?;
+    }
+    Ok(redacted_destinations(&settings))
+}
+
+/// Saves a destination; an S3 secret key goes to the keychain, and without
+/// one the key saved before under this name is kept.
+#[tauri::command]
+pub async fn save_destination(mut destination: RemoteDestination) -> Result<Vec<RemoteDestination>, String> {
+    if destination.name.trim().is_empty() {
+        return Err("Destination name must not be empty".to_string());
+    }
+
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    if !destination.store_secret().map_err(|e| e.to_string())? {
+        let previous = settings.destination(&destination.name).and_then(|previous| match &previous.target {
+            DestinationTarget::S3 { secret_ref, .. } => secret_ref.clone(),
+            _ => None,
+        });
+        if let DestinationTarget::S3 { secret_ref, .. } = &mut destination.target {
+            *secret_ref = previous;
+        }
+    }
+    settings.upsert_destination(destination);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(redacted_destinations(&settings))
+}
+
+#[tauri::command]
+pub async fn delete_destination(name: String) -> Result<Vec<RemoteDestination>, String> {
+    let mut settings = AppSettings::load().map_err(|e| e.to_string())?;
+    let destination = settings
+        .destination(&name)
+        .cloned()
+        .ok_or_else(|| format!("Destination not found: {}", name))?;
+    destination.delete_secret().map_err(|e| e.to_string())?;
+    settings.remove_destination(&name);
+    settings.save().map_err(|e| e.to_string())?;
+    Ok(redacted_destinations(&settings))
+}
+
+fn redacted_destinations(settings: &AppSettings) -> Vec<RemoteDestination> {
+    settings.destinations.iter().map(RemoteDestination::redacted).collect()
+}
+
+/// Signs in to Google Drive or Dropbox in the browser and keeps the refresh
+/// token in the OS keychain. The user supplies the OAuth client of their own
+/// Google Cloud project / Dropbox app.
+#[tauri::command]
+pub async fn connect_cloud(
+    window: Window,
+    provider: CloudProvider,
+    client_id: String,
+    client_secret: Option<String>,
+) -> Result<(), String> {
+    cloud::connect(provider, &client_id, client_secret.as_deref(), |url| {
+        tauri::api::shell::open(&window.shell_scope(), url, None).map_err(|e| anyhow::anyhow!("Failed to open browser: {}", e))
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    println!("Connected to {:?}", provider);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn disconnect_cloud(provider: CloudProvider) -> Result<(), String> {
+    cloud::disconnect(provider).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_cloud_status() -> Result<serde_json::Value, String> {
+    Ok(serde_json::json!({
+        "google_drive": cloud::is_connected(CloudProvider::GoogleDrive),
+        "dropbox": cloud::is_connected(CloudProvider::Dropbox),
+    }))
+}
+
+/// Uploads exported files (or whole chunk folders) to a saved destination,
+/// emitting `upload-progress` events per file and attempt.
+#[tauri::command]
+pub async fn upload_export(
+    window: Window,
+    destination: String,
+    files: Vec<String>,
+) -> Result<Vec<UploadedFile>, String> {
+    let settings = AppSettings::load().map_err(|e| e.to_string())?;
+    let destination = settings
+        .destination(&destination)
+        .cloned()
+        .ok_or_else(|| format!("Destination not found: {}", destination))?;
+
+    let files = collect_files(&files).map_err(|e| e.to_string())?;
+    if files.is_empty() {
+        return Err("No files to upload".to_string());
+    }
+
+    let uploaded = upload_files(&destination, &files, |progress| {
+        let _ = window.emit("upload-progress", progress);
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    println!("Uploaded {} files to {}", uploaded.len(), destination.name);
+
+    record_operation(
+        OperationKind::Export,
+        format!("Uploaded {} files to {}", uploaded.len(), destination.name),
+        serde_json::json!({
+            "destination": destination.name,
+            "files": uploaded,
+        }),
+    ).await;
+
+    Ok(uploaded)
+}
+
+#[tauri::command]
+pub async fn convert_transcription(
+    input: String,
+    output_format: String,
+    options: Option<ConversionOptions>,
+) -> Result<ConversionResult, String> {
+    let options = options.unwrap_or_default();
+    let format = FileFormat::from_name(&output_format)
+        .ok_or_else(|| format!("Unsupported output format: {}", output_format))?;
+
+    let output_file = match &options.output_path {
+        Some(path) => PathBuf::from(path),
+        None => default_conversion_path(Path::new(&input), &format),
+    };
+
+    let merge_options = MergeOptions {
+        output_format: format,
+        remove_timestamps: options.remove_timestamps,
+        add_file_markers: options.add_file_markers,
+        parse_mode: options.parse_mode,
+        microdvd_frame_rate: options.microdvd_frame_rate,
+        ..MergeOptions::default()
+    };
+
+    let mut merger = TranscriptionMerger::new(merge_options);
+    merger.add_files(vec![input.clone()]).await
+        .map_err(|e| format!("Failed to load transcription file: {}", e))?;
+    let content = merger.merge().await
+        .map_err(|e| format!("Failed to convert transcription: {}", e))?;
+
+    std::fs::write(&output_file, &content)
+        .map_err(|e| format!("Failed to write file: {}", e))?;
+
+    let path = output_file.to_string_lossy().to_string();
+    println!("Converted {} to {}", input, path);
+
+    record_operation(
+        OperationKind::Conversion,
+        format!("Converted {} to {}", input, path),
+        serde_json::json!({
+            "input": input,
+            "output": path,
+            "output_format": output_format,
+            "options": options,
+        }),
+    ).await;
+
+    Ok(ConversionResult {
+        path,
+        segment_count: merger.get_total_segments(),
+        warnings: merger.get_warnings(),
+    })
+}
+
+#[tauri::command]
+pub async fn reformat_timestamps(
+    files: Vec<String>,
+    timecode_format: String,
+    custom_timecode_format: Option<String>,
+    include_extended_info: bool,
+    output_dir: Option<String>,
+) -> Result<Vec<ReformatResult>, String> {
+    if files.is_empty() {
+        return Err("No files provided".to_string());
+    }
+
+    let mut results = Vec::new();
+
+    for input in files {
+        let input_path = Path::new(&input);
+        let raw_bytes = std::fs::read(input_path)
+            .map_err(|e| format!("Failed to read {}: {}", input, e))?;
+        let content = read_text_with_encoding(&raw_bytes);
+
+        let processed_content = process_transcription_content(
+            &content,
+            &timecode_format,
+            custom_timecode_format.as_deref(),
+            include_extended_info,
+        )?;
+
+        let stem = input_path.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = match input_path.extension() {
+            Some(ext) => format!("{}_reformatted.{}", stem, ext.to_string_lossy()),
+            None => format!("{}_reformatted", stem),
+        };
+        let output_file = match &output_dir {
+            Some(dir) => Path::new(dir).join(file_name),
+            None => input_path.with_file_name(file_name),
+        };
+
+        std::fs::write(&output_file, &processed_content)
+            .map_err(|e| format!("Failed to write file: {}", e))?;
+
+        results.push(ReformatResult {
+            input,
+            output: output_file.to_string_lossy().to_string(),
+        });
+    }
+
+    println!("Reformatted timestamps in {} files", results.len());
+
+    record_operation(
+        OperationKind::Reformat,
+        format!("Reformatted timestamps in {} files", results.len()),
+        serde_json::json!({
+            "files": results,
+            "timecode_format": timecode_format,
+            "custom_timecode_format": custom_timecode_format,
+            "include_extended_info": include_extended_info,
+        }),
+    ).await;
+
+    Ok(results)
+}
+
+#[tauri::command]
+pub async fn open_folder(path: String) -> Result<(), String> {
+    println!("Opening folder: {}", path);
+    #[cfg(target_os = "macos")]
+    {
+        std::process::Command::new("open")
+            .arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        // Use cmd /c start to handle paths with special characters better
+        std::process::Command::new("cmd")
+            .args(["/c", "start", "", &path])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .spawn()
+            .map_err(|e| format!("Failed to open folder: {}", e))?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        open_on_linux(&path, true).map_err(|e| format!("Failed to open folder: {}", e))?;
+    }
+    
+    Ok(())
+}
+
+/// Desktop-neutral openers first, then desktop-specific ones; xdg-utils is
+/// missing on many minimal distributions.
+#[cfg(target_os = "linux")]
+const LINUX_OPENERS: &[(&str, &[&str])] = &[
+    ("xdg-open", &[]),
+    ("gio", &["open"]),
+    ("kde-open5", &[]),
+    ("kde-open", &[]),
+    ("exo-open", &[]),
+];
+
+/// File managers that take a folder to show, tried when no opener is available
+#[cfg(target_os = "linux")]
+const LINUX_FILE_MANAGERS: &[&str] = &["nautilus", "dolphin", "thunar", "nemo", "caja", "pcmanfm"];
+
+/// Opens `path` with the first handler that is installed and succeeds. The
+/// error lists every handler tried and why it failed.
+#[cfg(target_os = "linux")]
+fn open_on_linux(path: &str, is_folder: bool) -> Result<(), String> {
+    let mut attempts = Vec::new();
+
+    for (program, args) in LINUX_OPENERS {
+        // Openers exit right after handing the path over, with an error when nothing handles it
+        match std::process::Command::new(program).args(*args).arg(path).output() {
+            Ok(output) if output.status.success() => return Ok(()),
+            Ok(output) => attempts.push(format!("{} (exit status {})", program, output.status)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => attempts.push(format!("{} (not installed)", program)),
+            Err(e) => attempts.push(format!("{} ({})", program, e)),
+        }
+    }
+
+    if is_folder {
+        // File managers keep running, so only starting them is checked
+        for program in LINUX_FILE_MANAGERS {
+            match std::process::Command::new(program).arg(path).spawn() {
+                Ok(_) => return Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => attempts.push(format!("{} (not installed)", program)),
+                Err(e) => attempts.push(format!("{} ({})", program, e)),
+            }
+        }
+    }
+
+    Err(format!(
+        "no handler could open {}; tried {}. Installing xdg-utils usually fixes this",
+        path,
+        attempts.join(", ")
+    ))
+}
+
+/// Opens a file with its default application, or with `reveal` shows it
+/// selected in the file manager (on Linux: opens the containing folder, as
+/// there is no common way to select a file).
+#[tauri::command]
+pub async fn open_file(path: String, reveal: Option<bool>) -> Result<(), String> {
+    open_path(path, reveal.unwrap_or(false))
+}
+
+fn open_path(path: String, reveal: bool) -> Result<(), String> {
+    println!("Opening file: {}", path);
+    if !Path::new(&path).exists() {
+        return Err(format!("File not found: {}", path));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let mut cmd = std::process::Command::new("open");
+        if reveal {
+            cmd.arg("-R");
+        }
+        cmd.arg(&path)
+            .spawn()
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = if reveal {
+            let mut cmd = std::process::Command::new("explorer");
+            cmd.raw_arg(format!("/select,\"{}\"", path));
+            cmd
+        } else {
+            let mut cmd = std::process::Command::new("cmd");
+            cmd.args(["/c", "start", "", &path]);
+            cmd
+        };
+        cmd.creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .spawn()
+            .map_err(|e| format!("Failed to open file: {}", e))?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        let target = if reveal {
+            Path::new(&path).parent().map(|parent| parent.to_string_lossy().to_string()).unwrap_or(path)
+        } else {
+            path
+        };
+        open_on_linux(&target, reveal).map_err(|e| format!("Failed to open file: {}", e))?;
+    }
+
+    Ok(())
+}
+
+/// Shows a desktop notification with the system's own tools.
+fn send_notification(title: &str, body: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+        std::process::Command::new("osascript")
+            .args(["-e", &format!("display notification {} with title {}", quote(body), quote(title))])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        let quote = |text: &str| format!("'{}'", text.replace('\'', "''"));
+        let script = format!(
+            "Add-Type -AssemblyName System.Windows.Forms; \
+             $icon = New-Object System.Windows.Forms.NotifyIcon; \
+             $icon.Icon = [System.Drawing.SystemIcons]::Information; $icon.Visible = $true; \
+             $icon.ShowBalloonTip(10000, {}, {}, 'Info'); Start-Sleep -Seconds 10; $icon.Dispose()",
+            quote(title),
+            quote(body)
+        );
+        std::process::Command::new("powershell")
+            .args(["-NoProfile", "-Command", &script])
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+    #[cfg(target_os = "linux")]
+    {
+        std::process::Command::new("notify-send")
+            .args(["--app-name=Transcription Assistant", title, body])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// The export itself succeeded, so failing to open the result is only logged.
+fn open_exported_file(path: &Path, action: Option<OpenAfterExport>) {
+    let Some(action) = action else {
+        return;
+    };
+    if let Err(e) = open_path(path.to_string_lossy().to_string(), action == OpenAfterExport::Reveal) {
+        println!("{}", e);
+    }
+}
+
+fn default_conversion_path(input: &Path, format: &FileFormat) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    let candidate = input.with_file_name(format!("{}.{}", stem, format.extension()));
+
+    // Never overwrite the source when converting to the same format
+    if candidate == input {
+        input.with_file_name(format!("{}_converted.{}", stem, format.extension()))
+    } else {
+        candidate
+    }
+}
+
+fn process_transcription_content(
+    content: &str,
+    timecode_format: &str,
+    custom_format: Option<&str>,
+    include_extended_info: bool,
+) -> Result<String, String> {
+    use regex::Regex;
+
+    // Timecode as the merger writes it, optionally with milliseconds: 05:30, 01:05:30.250, 330,
+    // with either decimal separator (0:01:02,5)
+    const TIMECODE: &str = r"\d{1,2}:\d{2}(?::\d{2})?(?:[\.,]\d{1,3})?|\d+(?:[\.,]\d{1,3})?";
+
+    // Try to match different formats that merger might create
+
+    // Format 1: [timecode] [something] [maybe_another_timecode] text
+    // This handles cases like: [00:00:00] [filename] [00:00] text
+    let re_complex = Regex::new(&format!(r"^\[({})\]\s*\[([^\]]+)\]\s*(?:\[([^\]]+)\]\s*)?(.*)$", TIMECODE))
+        .map_err(|e| format!("Regex error: {}", e))?;
+
+    // Format 2: [timecode] [something] text (two brackets)
+    let re_with_file = Regex::new(&format!(r"^\[({})\]\s*\[([^\]]+)\]\s*(.*)$", TIMECODE))
+        .map_err(|e| format!("Regex error: {}", e))?;
+
+    // Format 3: [timecode] text (simple format)
+    let re_simple = Regex::new(&format!(r"^\[({})\]\s*(.*)$", TIMECODE))
+        .map_err(|e| format!("Regex error: {}", e))?;
+
+    // Parse and process each line of the transcription
+    let mut processed_lines = Vec::new();
+    
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            processed_lines.push(String::new());
+            continue;
         }
-        
-        // Try to match different formats that merger might create
-        
-        // Format 1: [timecode] [something] [maybe_another_timecode] text  
-        // This handles cases like: [00:00:00] [filename] [00:00] text
-        let re_complex = Regex::new(r"^\[(\d{1,2}:\d{2}(?::\d{2})?|\d+)\]\s*\[([^\]]+)\]\s*(?:\[([^\]]+)\]\s*)?(.*)$")
-            .map_err(|e| format!("Regex error: {}", e))?;
-            
-        // Format 2: [timecode] [something] text (two brackets)
-        let re_with_file = Regex::new(r"^\[(\d{1,2}:\d{2}(?::\d{2})?|\d+)\]\s*\[([^\]]+)\]\s*(.*)$")
-            .map_err(|e| format!("Regex error: {}", e))?;
-            
-        // Format 3: [timecode] text (simple format)  
-        let re_simple = Regex::new(r"^\[(\d{1,2}:\d{2}(?::\d{2})?|\d+)\]\s*(.*)$")
-            .map_err(|e| format!("Regex error: {}", e))?;
         
         if let Some(captures) = re_complex.captures(line) {
             // Format: [timecode] [info1] [info2] text or [timecode] [info1] text
@@ -434,76 +3169,39 @@ fn convert_timecode(
 ) -> Result<String, String> {
     // Parse various time formats to total seconds
     let total_seconds = parse_timecode_to_seconds(timecode)?;
-    
-    match target_format {
-        "hms" => {
-            // Convert to HH:MM:SS format
-            let hours = total_seconds / 3600;
-            let minutes = (total_seconds % 3600) / 60;
-            let seconds = total_seconds % 60;
-            Ok(format!("{:02}:{:02}:{:02}", hours, minutes, seconds))
-        },
-        "hms_ms" => {
-            // Convert to HH:MM:SS.000 format (no milliseconds available, so .000)
-            let hours = total_seconds / 3600;
-            let minutes = (total_seconds % 3600) / 60;
-            let seconds = total_seconds % 60;
-            Ok(format!("{:02}:{:02}:{:02}.000", hours, minutes, seconds))
-        },
-        "seconds" => {
-            // Just total seconds
-            Ok(total_seconds.to_string())
-        },
-        "seconds_ms" => {
-            // Seconds with .0 (no milliseconds available)
-            Ok(format!("{}.0", total_seconds))
-        },
-        "custom" => {
-            if let Some(custom_fmt) = custom_format {
-                // Simple custom format processing
-                let hours = total_seconds / 3600;
-                let minutes = (total_seconds % 3600) / 60;
-                let seconds = total_seconds % 60;
-                
-                let result = custom_fmt
-                    .replace("HH", &format!("{:02}", hours))
-                    .replace("MM", &format!("{:02}", minutes))
-                    .replace("SS", &format!("{:02}", seconds))
-                    .replace("MS", "000"); // No milliseconds available
-                    
-                Ok(result)
-            } else {
-                Err("Custom format specified but no format provided".to_string())
-            }
-        },
-        _ => {
-            // Default: keep original MM:SS format
-            Ok(timecode.to_string())
-        }
+
+    match format_timecode(total_seconds, target_format, custom_format) {
+        Some(formatted) => Ok(formatted),
+        None if target_format == "custom" => Err("Custom format specified but no format provided".to_string()),
+        // Default: keep original MM:SS format
+        None => Ok(timecode.to_string()),
     }
 }
 
-fn parse_timecode_to_seconds(timecode: &str) -> Result<u32, String> {
+fn parse_timecode_to_seconds(timecode: &str) -> Result<f64, String> {
     let parts: Vec<&str> = timecode.split(':').collect();
-    
+
+    // Only the last component may carry a fractional part (milliseconds),
+    // separated by either '.' or ','
+    let whole = |part: &str, name: &str| -> Result<f64, String> {
+        part.parse::<u32>().map(f64::from).map_err(|_| format!("Invalid {}", name))
+    };
+    let fractional = |part: &str| -> Result<f64, String> {
+        parse_decimal(part).ok_or_else(|| "Invalid seconds".to_string())
+    };
+
     match parts.len() {
         2 => {
             // MM:SS format
-            let minutes: u32 = parts[0].parse().map_err(|_| "Invalid minutes")?;
-            let seconds: u32 = parts[1].parse().map_err(|_| "Invalid seconds")?;
-            Ok(minutes * 60 + seconds)
+            Ok(whole(parts[0], "minutes")? * 60.0 + fractional(parts[1])?)
         },
         3 => {
             // HH:MM:SS format
-            let hours: u32 = parts[0].parse().map_err(|_| "Invalid hours")?;
-            let minutes: u32 = parts[1].parse().map_err(|_| "Invalid minutes")?;
-            let seconds: u32 = parts[2].parse().map_err(|_| "Invalid seconds")?;
-            Ok(hours * 3600 + minutes * 60 + seconds)
+            Ok(whole(parts[0], "hours")? * 3600.0 + whole(parts[1], "minutes")? * 60.0 + fractional(parts[2])?)
         },
         1 => {
-            // Maybe just seconds (e.g., "330")
-            let seconds: u32 = parts[0].parse().map_err(|_| "Invalid seconds")?;
-            Ok(seconds)
+            // Maybe just seconds (e.g., "330" or "330.5")
+            fractional(parts[0])
         },
         _ => {
             Err(format!("Unsupported timecode format: {}", timecode))
@@ -528,4 +3226,35 @@ fn format_file_size(bytes: u64) -> String {
     }
 
     format!("{:.1} {}", size, UNITS[unit_index])
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_timecode_keeps_milliseconds() {
+        assert_eq!(convert_timecode("01:05:30.250", "hms_ms", None).unwrap(), "01:05:30.250");
+        assert_eq!(convert_timecode("05:30.5", "seconds_ms", None).unwrap(), "330.5");
+        assert_eq!(convert_timecode("05:30", "seconds_ms", None).unwrap(), "330.0");
+        assert_eq!(convert_timecode("05:30.999", "hms", None).unwrap(), "00:05:30");
+        assert!(convert_timecode("aa:30", "hms", None).is_err());
+    }
+
+    #[test]
+    fn test_convert_timecode_accepts_comma_decimals() {
+        assert_eq!(convert_timecode("0:01:02,5", "hms_ms", None).unwrap(), "00:01:02.500");
+        assert_eq!(convert_timecode("330,25", "seconds_ms", None).unwrap(), "330.25");
+
+        let content = "[0:01:02,5] Hello";
+        let processed = process_transcription_content(content, "custom", Some("MM:SS,MS"), false).unwrap();
+        assert_eq!(processed, "[01:02,500] Hello");
+    }
+
+    #[test]
+    fn test_process_content_with_millisecond_timecodes() {
+        let content = "[00:01:02.500] [chunk_1.txt] Hello\n[90] Plain seconds";
+        let processed = process_transcription_content(content, "custom", Some("HH:MM:SS,MS"), false).unwrap();
+        assert_eq!(processed, "[00:01:02,500] Hello\n[00:01:30,000] Plain seconds");
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{anyhow, Result};
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A misrecognised term and its correct spelling ("cubernetes" → "Kubernetes").
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TermCorrection {
+    pub wrong: String,
+    pub right: String,
+}
+
+/// Reads a correction dictionary: JSON as `[{"wrong": .., "right": ..}]` or
+/// `{"wrong": "right"}`, anything else as CSV lines `wrong,right` (`;` and tab
+/// separate as well). A `wrong,right` header line is skipped.
+pub async fn load_corrections(path: &Path) -> Result<Vec<TermCorrection>> {
+    let bytes = tokio::fs::read(path).await?;
+    let content = crate::merger::read_text_with_encoding(&bytes);
+    let is_json = path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("json"));
+
+    if is_json {
+        let value: serde_json::Value = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid correction dictionary {}: {}", path.display(), e))?;
+        return match value {
+            serde_json::Value::Object(map) => map
+                .into_iter()
+                .map(|(wrong, right)| match right {
+                    serde_json::Value::String(right) => Ok(TermCorrection { wrong, right }),
+                    _ => Err(anyhow!("Correction for '{}' is not a string", wrong)),
+                })
+                .collect(),
+            value => serde_json::from_value(value)
+                .map_err(|e| anyhow!("Invalid correction dictionary {}: {}", path.display(), e)),
+        };
+    }
+
+    Ok(parse_csv(&content))
+}
+
+fn parse_csv(content: &str) -> Vec<TermCorrection> {
+    let unquote = |field: &str| field.trim().trim_matches('"').trim().to_string();
+    content
+        .lines()
+        .filter_map(|line| line.split_once([',', ';', '\t']))
+        .map(|(wrong, right)| TermCorrection { wrong: unquote(wrong), right: unquote(right) })
+        .filter(|correction| !correction.wrong.is_empty() && !correction.right.is_empty())
+        .filter(|correction| !(correction.wrong.eq_ignore_ascii_case("wrong") && correction.right.eq_ignore_ascii_case("right")))
+        .collect()
+}
+
+/// Replaces whole-word matches of the dictionary regardless of their case.
+/// A correction with capitals (a name, "Kubernetes") is written as given; an
+/// all-lowercase one takes the case of the matched text, so a correction at
+/// the start of a sentence stays capitalised.
+pub struct CorrectionDictionary {
+    pattern: Option<Regex>,
+    corrections: HashMap<String, String>,
+}
+
+impl CorrectionDictionary {
+    pub fn new(corrections: &[TermCorrection]) -> Self {
+        let mut wrong_terms: Vec<&str> = corrections
+            .iter()
+            .map(|correction| correction.wrong.trim())
+            .filter(|wrong| !wrong.is_empty())
+            .collect();
+        // Longer terms first, so "open ai" wins over "open"
+        wrong_terms.sort_by_key(|wrong| std::cmp::Reverse(wrong.chars().count()));
+
+        let pattern = if wrong_terms.is_empty() {
+            None
+        } else {
+            let alternatives: Vec<String> = wrong_terms.iter().map(|wrong| regex::escape(wrong)).collect();
+            RegexBuilder::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+                .case_insensitive(true)
+                .build()
+                .ok()
+        };
+        let corrections = corrections
+            .iter()
+            .map(|correction| (correction.wrong.trim().to_lowercase(), correction.right.clone()))
+            .collect();
+        Self { pattern, corrections }
+    }
+
+    pub fn apply<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.pattern {
+            Some(pattern) => pattern.replace_all(text, |captures: &Captures| {
+                let found = &captures[0];
+                match self.corrections.get(&found.to_lowercase()) {
+                    Some(right) => match_case(found, right),
+                    None => found.to_string(),
+                }
+            }),
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+fn match_case(found: &str, right: &str) -> String {
+    if right.chars().any(char::is_uppercase) {
+        return right.to_string();
+    }
+    let letters: Vec<char> = found.chars().filter(|ch| ch.is_alphabetic()).collect();
+    if letters.len() > 1 && letters.iter().all(|ch| ch.is_uppercase()) {
+        return right.to_uppercase();
+    }
+    if found.chars().next().is_some_and(char::is_uppercase) {
+        let mut chars = right.chars();
+        if let Some(first) = chars.next() {
+            return first.to_uppercase().chain(chars).collect();
+        }
+    }
+    right.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrections_follow_case() {
+        let corrections = parse_csv("wrong,right\ncubernetes,Kubernetes\n\"пайтон\";питон\nкуб,\n");
+        assert_eq!(corrections.len(), 2);
+
+        let dictionary = CorrectionDictionary::new(&corrections);
+        assert_eq!(
+            dictionary.apply("Cubernetes и CUBERNETES, не cubernetesы"),
+            "Kubernetes и Kubernetes, не cubernetesы"
+        );
+        assert_eq!(dictionary.apply("Пайтон, ПАЙТОН и пайтон"), "Питон, ПИТОН и питон");
+        assert!(matches!(CorrectionDictionary::new(&[]).apply("пайтон"), Cow::Borrowed("пайтон")));
+    }
+}
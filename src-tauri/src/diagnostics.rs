@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::ffmpeg::{get_app_data_dir, FFmpegManager};
+use crate::supervisor::{run_supervised, Supervision};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub details: String,
+    pub duration_ms: u128,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticsReport {
+    pub generated_at: String,
+    pub app_version: String,
+    pub os: String,
+    pub arch: String,
+    pub checks: Vec<DiagnosticCheck>,
+    /// Plain-text version of the report, ready to paste into an issue
+    pub text: String,
+}
+
+/// Exercises FFmpeg, directory permissions and download hosts. Each check is
+/// independent: a failure is recorded in the report instead of aborting the run.
+pub async fn run_diagnostics(extra_directories: &[PathBuf]) -> DiagnosticsReport {
+    let mut checks = Vec::new();
+
+    let ffmpeg = FFmpegManager::new();
+    let ffmpeg_path = ffmpeg.as_ref().ok().and_then(|manager| manager.get_ffmpeg_path().ok());
+
+    checks.push(timed("FFmpeg version", || match &ffmpeg_path {
+        Some(path) => ffmpeg_version(path).map(|version| format!("{} ({})", version, path.display())),
+        None => Err(anyhow!("FFmpeg not found (it is downloaded on first processing)")),
+    }));
+
+    match (&ffmpeg, &ffmpeg_path) {
+        (Ok(manager), Some(path)) => {
+            let started = Instant::now();
+            let result = tone_round_trip(manager, path).await;
+            checks.push(check_from("FFmpeg tone generation and probe", result, started));
+        }
+        _ => checks.push(DiagnosticCheck {
+            name: "FFmpeg tone generation and probe".to_string(),
+            status: CheckStatus::Warning,
+            details: "Skipped: FFmpeg is not available".to_string(),
+            duration_ms: 0,
+        }),
+    }
+
+    let mut directories = vec![std::env::temp_dir()];
+    if let Ok(app_data_dir) = get_app_data_dir() {
+        directories.push(app_data_dir);
+    }
+    directories.extend(extra_directories.iter().cloned());
+    for directory in &directories {
+        checks.push(timed(&format!("Write access: {}", directory.display()), || {
+            check_writable(directory).map(|_| "writable".to_string())
+        }));
+    }
+
+    for url in download_hosts() {
+        let started = Instant::now();
+        let result = check_reachable(url).await;
+        checks.push(check_from(&format!("Network: {}", url), result, started));
+    }
+
+    let mut report = DiagnosticsReport {
+        generated_at: chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        checks,
+        text: String::new(),
+    };
+    report.text = format_report(&report);
+    report
+}
+
+fn timed(name: &str, check: impl FnOnce() -> Result<String>) -> DiagnosticCheck {
+    let started = Instant::now();
+    check_from(name, check(), started)
+}
+
+fn check_from(name: &str, result: Result<String>, started: Instant) -> DiagnosticCheck {
+    let (status, details) = match result {
+        Ok(details) => (CheckStatus::Ok, details),
+        Err(e) => (CheckStatus::Failed, e.to_string()),
+    };
+    DiagnosticCheck {
+        name: name.to_string(),
+        status,
+        details,
+        duration_ms: started.elapsed().as_millis(),
+    }
+}
+
+fn ffmpeg_version(path: &Path) -> Result<String> {
+    let mut cmd = Command::new(path);
+    cmd.arg("-version");
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        return Err(anyhow!("ffmpeg -version exited with {:?}", output.status.code()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or_default()
+        .to_string())
+}
+
+/// Generates a 1-second sine tone and reads its duration back.
+async fn tone_round_trip(manager: &FFmpegManager, ffmpeg_path: &Path) -> Result<String> {
+    let tone_path = std::env::temp_dir().join(format!("diagnostics_tone_{}.wav", uuid::Uuid::new_v4()));
+
+    run_supervised(
+        || {
+            let mut cmd = tokio::process::Command::new(ffmpeg_path);
+            cmd.args([
+                "-f", "lavfi",
+                "-i", "sine=frequency=1000:duration=1",
+                "-y",
+            ]);
+            cmd.arg(&tone_path);
+            cmd
+        },
+        Supervision::default(),
+    )
+    .await
+    .map_err(|e| anyhow!("Tone generation failed: {}", e))?;
+
+    let probed = match tone_path.to_str() {
+        Some(path) => manager.get_file_info(path).await,
+        None => Err(anyhow!("Temporary path {} is not valid UTF-8", tone_path.display())),
+    };
+    let _ = std::fs::remove_file(&tone_path);
+    let (_, duration) = probed?;
+
+    if (duration - 1.0).abs() > 0.1 {
+        return Err(anyhow!("Probed duration {:.2}s, expected 1.00s", duration));
+    }
+    Ok(format!("generated and probed a {:.2}s tone", duration))
+}
+
+fn check_writable(directory: &Path) -> Result<()> {
+    std::fs::create_dir_all(directory)?;
+    let probe = directory.join(format!(".write_test_{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"ok")?;
+    std::fs::remove_file(&probe)?;
+    Ok(())
+}
+
+fn download_hosts() -> Vec<&'static str> {
+    let mut hosts = vec!["https://github.com"];
+    if cfg!(target_os = "macos") {
+        hosts.push("https://evermeet.cx");
+    }
+    hosts
+}
+
+async fn check_reachable(url: &str) -> Result<String> {
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(10))
+        .build()?;
+    let response = client.head(url).send().await?;
+    Ok(format!("HTTP {}", response.status().as_u16()))
+}
+
+fn format_report(report: &DiagnosticsReport) -> String {
+    let mut text = format!(
+        "Transcription Assistant {} diagnostics\n{} ({}/{})\n\n",
+        report.app_version, report.generated_at, report.os, report.arch
+    );
+    for check in &report.checks {
+        let marker = match check.status {
+            CheckStatus::Ok => "OK",
+            CheckStatus::Warning => "WARN",
+            CheckStatus::Failed => "FAIL",
+        };
+        text.push_str(&format!("[{}] {} ({} ms): {}\n", marker, check.name, check.duration_ms, check.details));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_check_and_report_text() {
+        let directory = std::env::temp_dir().join(format!("diagnostics_{}", uuid::Uuid::new_v4()));
+        let check = timed("Write access", || check_writable(&directory).map(|_| "writable".to_string()));
+        assert_eq!(check.status, CheckStatus::Ok);
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let mut report = DiagnosticsReport {
+            generated_at: "2024-01-01 00:00:00 UTC".to_string(),
+            app_version: "0.0.0".to_string(),
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            checks: vec![check, timed("Broken", || Err(anyhow!("nope")))],
+            text: String::new(),
+        };
+        report.text = format_report(&report);
+
+        assert!(report.text.contains("[OK] Write access"));
+        assert!(report.text.contains("[FAIL] Broken"));
+    }
+}
@@ -0,0 +1,248 @@
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use std::io::{Read, Write};
+
+use crate::merger::{escape_xml, format_txt_timestamp, TranscriptionSegment};
+use crate::project::ProjectMetadata;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+<Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/>
+<Default Extension="xml" ContentType="application/xml"/>
+<Override PartName="/word/document.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml"/>
+<Override PartName="/word/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml"/>
+<Override PartName="/docProps/core.xml" ContentType="application/vnd.openxmlformats-package.core-properties+xml"/>
+</Types>"#;
+
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="word/document.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties" Target="docProps/core.xml"/>
+</Relationships>"#;
+
+const DOCUMENT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/>
+</Relationships>"#;
+
+/// Title, Heading1 for source files, a body style and a grey character style
+/// for timestamps, so clients can restyle the whole transcript in Word.
+const STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<w:styles xmlns:w="http://schemas.openxmlformats.org/wordprocessingml/2006/main">
+<w:docDefaults>
+<w:rPrDefault><w:rPr><w:rFonts w:ascii="Calibri" w:hAnsi="Calibri" w:cs="Calibri" w:eastAsia="Calibri"/><w:sz w:val="22"/><w:lang w:val="ru-RU"/></w:rPr></w:rPrDefault>
+<w:pPrDefault><w:pPr><w:spacing w:after="120" w:line="276" w:lineRule="auto"/></w:pPr></w:pPrDefault>
+</w:docDefaults>
+<w:style w:type="paragraph" w:default="1" w:styleId="Normal"><w:name w:val="Normal"/><w:qFormat/></w:style>
+<w:style w:type="paragraph" w:styleId="Title"><w:name w:val="Title"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:spacing w:after="240"/></w:pPr><w:rPr><w:sz w:val="48"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Heading1"><w:name w:val="heading 1"/><w:basedOn w:val="Normal"/><w:next w:val="Normal"/><w:qFormat/><w:pPr><w:keepNext/><w:spacing w:before="360" w:after="120"/><w:outlineLvl w:val="0"/></w:pPr><w:rPr><w:b/><w:sz w:val="32"/></w:rPr></w:style>
+<w:style w:type="paragraph" w:styleId="Metadata"><w:name w:val="Metadata"/><w:basedOn w:val="Normal"/><w:rPr><w:color w:val="595959"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Timestamp"><w:name w:val="Timestamp"/><w:rPr><w:b/><w:color w:val="808080"/></w:rPr></w:style>
+<w:style w:type="character" w:styleId="Speaker"><w:name w:val="Speaker"/><w:rPr><w:b/></w:rPr></w:style>
+</w:styles>"#;
+
+#[derive(Debug, Clone)]
+pub struct DocxOptions {
+    pub title: String,
+    pub show_timestamps: bool,
+    /// Start a Heading 1 whenever the source file changes
+    pub file_headings: bool,
+    /// Leave out the creation date so the same transcript gives the same file
+    pub reproducible: bool,
+}
+
+/// Builds a Word document (OOXML package) from the merged segments.
+pub fn render_docx(
+    segments: &[TranscriptionSegment],
+    metadata: &ProjectMetadata,
+    options: &DocxOptions,
+) -> Result<Vec<u8>> {
+    let mut body = String::new();
+    body.push_str(&paragraph(Some("Title"), &[run(None, &options.title)]));
+    for (label, value) in metadata.fields() {
+        body.push_str(&paragraph(Some("Metadata"), &[run(None, &format!("{}: {}", label, value))]));
+    }
+
+    let mut current_file: Option<&str> = None;
+    for segment in segments {
+        let from_file = !segment.original_filename.is_empty();
+        if options.file_headings && from_file && current_file != Some(segment.original_filename.as_str()) {
+            current_file = Some(segment.original_filename.as_str());
+            body.push_str(&paragraph(Some("Heading1"), &[run(None, &segment.original_filename)]));
+        }
+
+        let mut runs = Vec::new();
+        if options.show_timestamps {
+            runs.push(run(Some("Timestamp"), &format!("[{}] ", format_txt_timestamp(segment.start_time))));
+        }
+        let text = segment.text.trim();
+        let label = segment
+            .speaker
+            .as_deref()
+            .filter(|speaker| text.starts_with(&format!("{}:", speaker)));
+        match label {
+            Some(speaker) => {
+                runs.push(run(Some("Speaker"), &format!("{}:", speaker)));
+                runs.push(run(None, &text[speaker.len() + 1..]));
+            }
+            None => runs.push(run(None, text)),
+        }
+        body.push_str(&paragraph(None, &runs));
+    }
+
+    let document = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">\
+         <w:body>{}<w:sectPr><w:pgSz w:w=\"11906\" w:h=\"16838\"/>\
+         <w:pgMar w:top=\"1134\" w:right=\"850\" w:bottom=\"1134\" w:left=\"1701\" w:header=\"708\" w:footer=\"708\" w:gutter=\"0\"/>\
+         </w:sectPr></w:body></w:document>",
+        body
+    );
+
+    let created = if options.reproducible {
+        String::new()
+    } else {
+        format!(
+            "<dcterms:created xsi:type=\"dcterms:W3CDTF\">{}</dcterms:created>",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+        )
+    };
+    let core = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\
+         <dc:title>{}</dc:title><dc:creator>Transcription Assistant</dc:creator>{}</cp:coreProperties>",
+        escape_xml(&options.title),
+        created
+    );
+
+    let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let mut file_options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+    if options.reproducible {
+        file_options = file_options.last_modified_time(zip::DateTime::default());
+    }
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", PACKAGE_RELS.to_string()),
+        ("docProps/core.xml", core),
+        ("word/_rels/document.xml.rels", DOCUMENT_RELS.to_string()),
+        ("word/styles.xml", STYLES.to_string()),
+        ("word/document.xml", document),
+    ];
+    for (name, content) in parts {
+        archive.start_file(name, file_options)?;
+        archive.write_all(content.as_bytes())?;
+    }
+    Ok(archive.finish()?.into_inner())
+}
+
+/// Plain text of each paragraph of a Word document, in order; tabs and line
+/// breaks inside a paragraph come through as `\t` and `\n`.
+pub fn read_docx_paragraphs(bytes: &[u8]) -> Result<Vec<String>> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut document = String::new();
+    archive
+        .by_name("word/document.xml")
+        .map_err(|_| anyhow!("Not a Word document: word/document.xml is missing"))?
+        .read_to_string(&mut document)?;
+
+    let tag = Regex::new(r"<[^>]*>").unwrap();
+    let mut paragraphs = Vec::new();
+    let mut current = String::new();
+    let mut in_text = false;
+    let mut last = 0;
+    for element in tag.find_iter(&document) {
+        if in_text {
+            current.push_str(&unescape_xml(&document[last..element.start()]));
+        }
+        last = element.end();
+
+        let element = element.as_str();
+        let self_closing = element.ends_with("/>");
+        let name = element[1..].trim_end_matches(['>', '/']).split_whitespace().next().unwrap_or_default();
+        match name {
+            "w:t" => in_text = !self_closing,
+            "/w:t" => in_text = false,
+            // Tab stops in the paragraph properties are `<w:tabs><w:tab .../></w:tabs>`
+            "w:tab" if !current.is_empty() || in_text => current.push('\t'),
+            "w:br" | "w:cr" => current.push('\n'),
+            "/w:p" => paragraphs.push(std::mem::take(&mut current)),
+            _ => {}
+        }
+    }
+    Ok(paragraphs)
+}
+
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn paragraph(style: Option<&str>, runs: &[String]) -> String {
+    let properties = style
+        .map(|style| format!("<w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>", style))
+        .unwrap_or_default();
+    format!("<w:p>{}{}</w:p>", properties, runs.concat())
+}
+
+/// A text run; line breaks inside the text become `<w:br/>`.
+fn run(style: Option<&str>, text: &str) -> String {
+    let properties = style
+        .map(|style| format!("<w:rPr><w:rStyle w:val=\"{}\"/></w:rPr>", style))
+        .unwrap_or_default();
+    let content = text
+        .split('\n')
+        .map(|line| format!("<w:t xml:space=\"preserve\">{}</w:t>", escape_xml(line)))
+        .collect::<Vec<_>>()
+        .join("<w:br/>");
+    format!("<w:r>{}{}</w:r>", properties, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_docx_package() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, None, "Анна: Начнём <сейчас> & быстро").file("part1.txt").speaker("Анна"),
+            TranscriptionSegment::test(65.0, None, "Продолжаем").file("part2.txt"),
+        ];
+        let options = DocxOptions {
+            title: "Встреча".to_string(),
+            show_timestamps: true,
+            file_headings: true,
+            reproducible: false,
+        };
+
+        let bytes = render_docx(&segments, &ProjectMetadata::default(), &options).unwrap();
+
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        assert!(archive.by_name("[Content_Types].xml").is_ok());
+        let mut document = String::new();
+        archive.by_name("word/document.xml").unwrap().read_to_string(&mut document).unwrap();
+
+        assert!(document.contains(r#"<w:p><w:pPr><w:pStyle w:val="Heading1"/></w:pPr><w:r><w:t xml:space="preserve">part2.txt</w:t></w:r></w:p>"#));
+        assert!(document.contains(r#"<w:r><w:rPr><w:rStyle w:val="Timestamp"/></w:rPr><w:t xml:space="preserve">[01:05] </w:t></w:r>"#));
+        assert!(document.contains(r#"<w:rStyle w:val="Speaker"/></w:rPr><w:t xml:space="preserve">Анна:</w:t></w:r><w:r><w:t xml:space="preserve"> Начнём &lt;сейчас&gt; &amp; быстро</w:t>"#));
+    }
+
+    #[test]
+    fn test_read_docx_paragraphs() {
+        let segments = vec![TranscriptionSegment {
+            text: "Q&A <итоги>\nвторая строка".to_string(),
+            ..Default::default()
+        }];
+        let options = DocxOptions { title: "Встреча".to_string(), show_timestamps: false, file_headings: false, reproducible: true };
+        let bytes = render_docx(&segments, &ProjectMetadata::default(), &options).unwrap();
+        assert_eq!(bytes, render_docx(&segments, &ProjectMetadata::default(), &options).unwrap());
+
+        let paragraphs = read_docx_paragraphs(&bytes).unwrap();
+        assert_eq!(paragraphs, vec!["Встреча", "Q&A <итоги>\nвторая строка"]);
+        assert!(read_docx_paragraphs(b"not a zip").is_err());
+    }
+}
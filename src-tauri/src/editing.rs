@@ -0,0 +1,155 @@
+use anyhow::{anyhow, Result};
+
+use crate::merger::TranscriptionSegment;
+
+/// Changes the text and/or times of one segment and keeps the timeline sorted.
+/// Word confidences are dropped with a text change, as they no longer match.
+pub fn update_segment(
+    segments: &mut [TranscriptionSegment],
+    index: usize,
+    text: Option<String>,
+    start_time: Option<f64>,
+    end_time: Option<f64>,
+) -> Result<()> {
+    let segment = segment_mut(segments, index)?;
+    let start = start_time.unwrap_or(segment.start_time);
+    let end = end_time.or(segment.end_time);
+    if start < 0.0 {
+        return Err(anyhow!("Start time must not be negative"));
+    }
+    if end.is_some_and(|end| end <= start) {
+        return Err(anyhow!("End time must be after the start time"));
+    }
+
+    if let Some(text) = text {
+        if text != segment.text {
+            segment.text = text;
+            segment.words.clear();
+            segment.original_text = None;
+        }
+    }
+    segment.start_time = start;
+    if end_time.is_some() {
+        segment.end_time = end;
+        segment.end_time_estimated = false;
+    }
+
+    sort_by_start(segments);
+    Ok(())
+}
+
+/// Splits a segment at a character position of its text. The second part
+/// starts at `split_time`, or at the time proportional to the position.
+pub fn split_segment(
+    segments: &mut Vec<TranscriptionSegment>,
+    index: usize,
+    position: usize,
+    split_time: Option<f64>,
+    default_duration: f64,
+) -> Result<()> {
+    let segment = segment_mut(segments, index)?;
+    let byte_position = segment
+        .text
+        .char_indices()
+        .nth(position)
+        .map(|(byte, _)| byte)
+        .ok_or_else(|| anyhow!("Split position {} is outside the segment text", position))?;
+    let (before, after) = segment.text.split_at(byte_position);
+    let (before, after) = (before.trim().to_string(), after.trim().to_string());
+    if before.is_empty() || after.is_empty() {
+        return Err(anyhow!("Both parts of a split segment need text"));
+    }
+
+    let start = segment.start_time;
+    let end = segment.end_time.unwrap_or(start + default_duration);
+    let split_time = split_time.unwrap_or_else(|| {
+        let share = position as f64 / segment.text.chars().count() as f64;
+        start + (end - start) * share
+    });
+    if split_time <= start || split_time >= end {
+        return Err(anyhow!("Split time must lie inside the segment"));
+    }
+
+    let mut second = segment.clone();
+    segment.text = before;
+    segment.end_time = Some(split_time);
+    segment.words.clear();
+    second.text = after;
+    second.start_time = split_time;
+    second.words.clear();
+    second.speaker_change_hint = false;
+
+    segments.insert(index + 1, second);
+    Ok(())
+}
+
+/// Joins segments `first..=last` into one spanning their time range.
+pub fn merge_segments(segments: &mut Vec<TranscriptionSegment>, first: usize, last: usize) -> Result<()> {
+    if first >= last {
+        return Err(anyhow!("Select at least two segments to merge"));
+    }
+    segment_mut(segments, last)?;
+
+    let merged_away: Vec<TranscriptionSegment> = segments.drain(first + 1..=last).collect();
+    let segment = &mut segments[first];
+    for other in merged_away {
+        segment.text = format!("{} {}", segment.text.trim_end(), other.text.trim_start());
+        segment.end_time = match (segment.end_time, other.end_time) {
+            (Some(end), Some(other_end)) => Some(end.max(other_end)),
+            (end, other_end) => other_end.or(end),
+        };
+        segment.end_time_estimated = other.end_time_estimated;
+        segment.words.extend(other.words);
+        segment.original_text = None;
+        // A mean would hide the weak part, so the merged segment keeps the lowest confidence
+        segment.confidence = match (segment.confidence, other.confidence) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+    }
+    Ok(())
+}
+
+pub fn delete_segment(segments: &mut Vec<TranscriptionSegment>, index: usize) -> Result<TranscriptionSegment> {
+    segment_mut(segments, index)?;
+    Ok(segments.remove(index))
+}
+
+fn segment_mut(segments: &mut [TranscriptionSegment], index: usize) -> Result<&mut TranscriptionSegment> {
+    let count = segments.len();
+    segments
+        .get_mut(index)
+        .ok_or_else(|| anyhow!("Segment {} does not exist ({} segments)", index, count))
+}
+
+fn sort_by_start(segments: &mut [TranscriptionSegment]) {
+    segments.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit_segments() {
+        let mut segments = vec![
+            TranscriptionSegment::test(0.0, 4.0, "Привет всем. Начнём").file("part1.srt"),
+            TranscriptionSegment::test(5.0, 8.0, "с бюджета").file("part1.srt"),
+        ];
+
+        split_segment(&mut segments, 0, 13, None, 5.0).unwrap();
+        assert_eq!(segments[0].text, "Привет всем.");
+        assert_eq!((segments[1].text.as_str(), segments[1].start_time), ("Начнём", 13.0 * 4.0 / 19.0));
+
+        merge_segments(&mut segments, 1, 2).unwrap();
+        assert_eq!((segments[1].text.as_str(), segments[1].end_time), ("Начнём с бюджета", Some(8.0)));
+
+        update_segment(&mut segments, 0, None, Some(9.0), Some(10.0)).unwrap();
+        assert_eq!(segments[1].text, "Привет всем.");
+        assert!(update_segment(&mut segments, 0, None, None, Some(1.0)).is_err());
+
+        assert_eq!(delete_segment(&mut segments, 0).unwrap().text, "Начнём с бюджета");
+        assert!(delete_segment(&mut segments, 3).is_err());
+        assert!(split_segment(&mut segments, 0, 0, None, 5.0).is_err());
+    }
+}
@@ -0,0 +1,917 @@
+use anyhow::Result;
+use encoding_rs::WINDOWS_1251;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::casing::{CaseTransform, TextCase};
+use crate::cjk::{break_units, display_width};
+use crate::linebreak::{wrap_balanced, LineBreakRules};
+use crate::merger::{escape_xml, FileFormat, TranscriptionSegment};
+use crate::project::ProjectMetadata;
+use crate::translit::{transliterate, TransliterationScheme};
+
+/// Line length used by the legacy player profile when none is given
+const LEGACY_MAX_LINE_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Cp1251,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    #[default]
+    None,
+    Gzip,
+    Zip,
+}
+
+/// What to do with the written file once an export finishes
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAfterExport {
+    /// Open with the default application for its type
+    Open,
+    /// Show it selected in the file manager
+    Reveal,
+}
+
+/// Writes `<export>.options.json` next to a reproducible export: a hash of
+/// everything that shaped the output, so pipelines can tell whether a cached
+/// export is still current.
+pub fn write_options_sidecar(export_path: &Path, options: &serde_json::Value) -> Result<PathBuf> {
+    let options = sorted_keys(options);
+    let hash = format!("{:x}", Sha256::digest(serde_json::to_string(&options)?.as_bytes()));
+    let file_name = export_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let sidecar_path = export_path.with_file_name(format!("{}.options.json", file_name));
+    let sidecar = serde_json::json!({
+        "app_version": env!("CARGO_PKG_VERSION"),
+        "options_hash": hash,
+        "options": options,
+    });
+    std::fs::write(&sidecar_path, serde_json::to_string_pretty(&sidecar)?)?;
+    Ok(sidecar_path)
+}
+
+/// The value with every object's keys in sorted order; maps serialize in
+/// insertion order, and hash maps in no fixed order at all.
+fn sorted_keys(value: &serde_json::Value) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(object) => {
+            let mut keys: Vec<&String> = object.keys().collect();
+            keys.sort();
+            keys.into_iter().map(|key| (key.clone(), sorted_keys(&object[key]))).collect()
+        }
+        serde_json::Value::Array(items) => items.iter().map(sorted_keys).collect(),
+        other => other.clone(),
+    }
+}
+
+/// Files produced by [`ExportOptions::write_output`]
+#[derive(Debug, Clone, Serialize)]
+pub struct WrittenExport {
+    pub path: PathBuf,
+    pub size: u64,
+    pub checksum_path: Option<PathBuf>,
+}
+
+/// One-click presets that expand into the individual export options
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportProfile {
+    /// Old hardware/standalone players: CP1251, CRLF, no tags, short lines
+    LegacyPlayer,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportOptions {
+    pub profile: Option<ExportProfile>,
+    pub encoding: TextEncoding,
+    pub line_ending: LineEnding,
+    /// Remove HTML-style (<i>) and ASS override ({\an8}) tags from segment text
+    pub strip_tags: bool,
+    /// SRT/WebVTT: wrap cue text so that no line exceeds this many characters;
+    /// the one-line-per-segment formats are left unwrapped
+    pub max_line_length: Option<usize>,
+    /// SRT/WebVTT: split cues whose wrapped text has more lines into
+    /// consecutive cues, sharing the time by text length
+    pub max_lines_per_cue: Option<usize>,
+    /// SRT/WebVTT: cues shorter than this (in seconds) are extended
+    pub min_cue_duration: Option<f64>,
+    /// SRT/WebVTT: cues longer than this are split between words
+    pub max_cue_duration: Option<f64>,
+    /// SRT/WebVTT: cues read faster than this are extended where the next cue
+    /// leaves room
+    pub max_chars_per_second: Option<f64>,
+    /// SRT/WebVTT: wrap with this language's line-break rules and balanced line
+    /// lengths instead of filling each line greedily
+    pub line_break_language: Option<String>,
+    /// Deliver the file as `.gz` or inside a `.zip` archive
+    pub compression: Compression,
+    /// Write a `sha256sum`-compatible `<file>.sha256` next to the delivered file
+    pub checksums: bool,
+    /// TTML frame rate metadata and the FCPXML caption frame grid; replaces
+    /// the one the transcription was merged with
+    pub frame_rate: Option<f64>,
+    /// TXT line layout such as `{start_hms} | {speaker} | {text}`; replaces
+    /// the one the transcription was merged with
+    pub txt_template: Option<String>,
+    /// Media played by the HTML export; linked relative to the page when possible
+    pub media_path: Option<String>,
+    /// Put the media into the HTML page as a data URI so it works on its own
+    pub embed_media: bool,
+    /// Casing applied to segment text; speaker labels keep theirs
+    pub text_case: TextCase,
+    /// Terms whose spelling survives the casing transform, loaded from the settings
+    #[serde(skip)]
+    pub glossary: Vec<String>,
+    /// Fixed archive timestamps; follows the merge's `reproducible` option
+    #[serde(skip)]
+    pub reproducible: bool,
+    /// Write Cyrillic text in Latin letters; JSON keeps the source text in `original_text`
+    pub transliteration: Option<TransliterationScheme>,
+    /// Add TXT/MD exports to the end of an existing file under a session
+    /// header instead of overwriting it
+    pub append: bool,
+    pub open_after_export: Option<OpenAfterExport>,
+}
+
+impl ExportOptions {
+    /// Returns the options with the selected profile expanded.
+    pub fn resolved(&self) -> ExportOptions {
+        match self.profile {
+            Some(ExportProfile::LegacyPlayer) => ExportOptions {
+                profile: None,
+                encoding: TextEncoding::Cp1251,
+                line_ending: LineEnding::Crlf,
+                strip_tags: true,
+                max_line_length: Some(self.max_line_length.unwrap_or(LEGACY_MAX_LINE_LENGTH)),
+                ..self.clone()
+            },
+            None => self.clone(),
+        }
+    }
+
+    pub fn apply_to_segments(&self, segments: &mut [TranscriptionSegment]) {
+        let tag_pattern = Regex::new(r"<[^>]*>|\{\\[^}]*\}").unwrap();
+        let casing = CaseTransform::new(self.text_case, &self.glossary);
+
+        for segment in segments.iter_mut() {
+            if self.strip_tags {
+                segment.text = tag_pattern.replace_all(&segment.text, "").trim().to_string();
+            }
+            if self.text_case != TextCase::Original {
+                let label_length = segment
+                    .speaker
+                    .as_deref()
+                    .filter(|speaker| segment.text.starts_with(&format!("{}:", speaker)))
+                    .map_or(0, |speaker| speaker.len() + 1);
+                let (label, spoken) = segment.text.split_at(label_length);
+                segment.text = format!("{}{}", label, casing.apply(spoken));
+            }
+            if let Some(scheme) = self.transliteration {
+                let latin = transliterate(&segment.text, scheme);
+                if latin != segment.text {
+                    segment.original_text = Some(std::mem::replace(&mut segment.text, latin));
+                }
+                segment.speaker = segment.speaker.as_deref().map(|speaker| transliterate(speaker, scheme));
+            }
+        }
+    }
+
+    /// Applies `max_line_length` to cue text. Only the caption formats take
+    /// the line breaks; elsewhere they would start what reads as a new segment.
+    pub fn wrap_lines(&self, segments: &mut [TranscriptionSegment]) {
+        let Some(max_length) = self.max_line_length else {
+            return;
+        };
+        let line_breaks = self.line_break_language.as_deref().map(LineBreakRules::for_language);
+        for segment in segments.iter_mut() {
+            segment.text = match &line_breaks {
+                Some(rules) => wrap_balanced(&segment.text, max_length, rules),
+                None => wrap_text(&segment.text, max_length),
+            };
+        }
+    }
+
+    /// Everything these options change about the segments of an export to
+    /// `format`: the text transforms, then for the caption formats the line
+    /// length, line count and timing limits of the cues.
+    pub fn prepare_segments(&self, mut segments: Vec<TranscriptionSegment>, format: &FileFormat, default_duration: f64) -> Vec<TranscriptionSegment> {
+        self.apply_to_segments(&mut segments);
+        if matches!(format, FileFormat::Srt | FileFormat::Vtt | FileFormat::Sbv | FileFormat::Fcpxml) {
+            self.wrap_lines(&mut segments);
+            segments = self.split_long_cues(segments, default_duration);
+            segments = self.enforce_cue_timing(segments, default_duration);
+        }
+        segments
+    }
+
+    /// Applies the cue duration and reading speed limits to sorted segments.
+    /// Overlong cues are split between words (and wrapped again); short or
+    /// fast cues are extended up to the next cue's start, but not beyond
+    /// `max_cue_duration`. Cues without room to grow stay as they are.
+    pub fn enforce_cue_timing(&self, segments: Vec<TranscriptionSegment>, default_duration: f64) -> Vec<TranscriptionSegment> {
+        let max_duration = self.max_cue_duration.filter(|max| *max > 0.0);
+        let max_cps = self.max_chars_per_second.filter(|cps| *cps > 0.0);
+        if max_duration.is_none() && max_cps.is_none() && self.min_cue_duration.is_none() {
+            return segments;
+        }
+        let line_breaks = self.line_break_language.as_deref().map(LineBreakRules::for_language);
+
+        let mut cues = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let end = segment.end_time.unwrap_or(segment.start_time + default_duration);
+            match max_duration {
+                Some(max) if end - segment.start_time > max => {
+                    let count = ((end - segment.start_time) / max).ceil() as usize;
+                    let parts: Vec<String> = split_words_evenly(&segment.text, count)
+                        .into_iter()
+                        .map(|part| match (self.max_line_length, &line_breaks) {
+                            (Some(max_length), Some(rules)) => wrap_balanced(&part, max_length, rules),
+                            (Some(max_length), None) => wrap_text(&part, max_length),
+                            (None, _) => part,
+                        })
+                        .collect();
+                    cues.extend(share_time(&segment, parts, end));
+                }
+                _ => cues.push(TranscriptionSegment { end_time: Some(end), ..segment }),
+            }
+        }
+
+        for index in 0..cues.len() {
+            let start = cues[index].start_time;
+            let end = cues[index].end_time.unwrap_or(start + default_duration);
+            let mut required = self.min_cue_duration.unwrap_or(0.0);
+            if let Some(cps) = max_cps {
+                required = required.max(reading_length(&cues[index].text) as f64 / cps);
+            }
+            if let Some(max) = max_duration {
+                required = required.min(max);
+            }
+            if end - start < required {
+                let next_start = cues.get(index + 1).map_or(f64::INFINITY, |next| next.start_time);
+                cues[index].end_time = Some((start + required).min(next_start).max(end));
+            }
+        }
+        cues
+    }
+
+    /// Applies `max_lines_per_cue` to segments already wrapped by
+    /// [`wrap_lines`](Self::wrap_lines).
+    pub fn split_long_cues(&self, segments: Vec<TranscriptionSegment>, default_duration: f64) -> Vec<TranscriptionSegment> {
+        let Some(max_lines) = self.max_lines_per_cue.filter(|max_lines| *max_lines > 0) else {
+            return segments;
+        };
+
+        let mut cues = Vec::with_capacity(segments.len());
+        for segment in segments {
+            let lines: Vec<&str> = segment.text.lines().collect();
+            if lines.len() <= max_lines {
+                cues.push(segment);
+                continue;
+            }
+
+            let parts: Vec<String> = lines.chunks(max_lines).map(|part| part.join("\n")).collect();
+            let end = segment.end_time.unwrap_or(segment.start_time + default_duration);
+            cues.extend(share_time(&segment, parts, end));
+        }
+        cues
+    }
+
+    /// Converts the final text to bytes with the requested line endings and encoding.
+    pub fn encode(&self, content: &str) -> Result<Vec<u8>> {
+        let normalized = content.replace("\r\n", "\n");
+        let text = match self.line_ending {
+            LineEnding::Lf => normalized,
+            LineEnding::Crlf => normalized.replace('\n', "\r\n"),
+        };
+
+        match self.encoding {
+            TextEncoding::Utf8 => Ok(text.into_bytes()),
+            TextEncoding::Utf8Bom => {
+                let mut bytes = vec![0xEF, 0xBB, 0xBF];
+                bytes.extend_from_slice(text.as_bytes());
+                Ok(bytes)
+            }
+            TextEncoding::Cp1251 => {
+                let mut bytes = Vec::with_capacity(text.len());
+                let mut replaced = 0;
+                let mut buffer = [0u8; 4];
+
+                // Characters missing from CP1251 become '?' instead of HTML entities
+                for ch in text.chars() {
+                    let (encoded, _, had_errors) = WINDOWS_1251.encode(ch.encode_utf8(&mut buffer));
+                    if had_errors {
+                        bytes.push(b'?');
+                        replaced += 1;
+                    } else {
+                        bytes.extend_from_slice(&encoded);
+                    }
+                }
+
+                if replaced > 0 {
+                    println!("Replaced {} characters not representable in CP1251", replaced);
+                }
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Writes the encoded export to `path`, compressed if requested, plus the
+    /// checksum file. Returns where the deliverable actually ended up.
+    pub fn write_output(&self, path: &Path, bytes: &[u8]) -> Result<WrittenExport> {
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy().to_string();
+
+        let (final_path, data) = match self.compression {
+            Compression::None => (path.to_path_buf(), bytes.to_vec()),
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+                encoder.write_all(bytes)?;
+                (path.with_file_name(format!("{}.gz", file_name)), encoder.finish()?)
+            }
+            Compression::Zip => {
+                let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+                let mut options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+                if self.reproducible {
+                    options = options.last_modified_time(zip::DateTime::default());
+                }
+                archive.start_file(file_name.as_str(), options)?;
+                archive.write_all(bytes)?;
+                (path.with_extension("zip"), archive.finish()?.into_inner())
+            }
+        };
+
+        std::fs::write(&final_path, &data)?;
+
+        let checksum_path = if self.checksums {
+            let final_name = final_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            let checksum_path = final_path.with_file_name(format!("{}.sha256", final_name));
+            let hash = format!("{:x}", Sha256::digest(&data));
+            std::fs::write(&checksum_path, format!("{}  {}\n", hash, final_name))?;
+            Some(checksum_path)
+        } else {
+            None
+        };
+
+        Ok(WrittenExport {
+            path: final_path,
+            size: data.len() as u64,
+            checksum_path,
+        })
+    }
+}
+
+/// Describes the export parameters that make sense for `format` as a JSON schema,
+/// so the frontend can render option controls without hardcoding them per exporter.
+/// Word and HTML documents are not a `FileFormat` and have no schema, so their
+/// options (`media_path` and `embed_media` for HTML) are left out.
+pub fn options_schema(format: &FileFormat) -> serde_json::Value {
+    use serde_json::json;
+
+    let mut properties = serde_json::Map::new();
+
+    // Timecodes are only rewritten in bracketed text lines
+    if matches!(format, FileFormat::Txt | FileFormat::Markdown) {
+        properties.insert("timecode_format".into(), json!({
+            "type": "string",
+            "enum": ["hms", "hms_ms", "seconds", "seconds_ms", "custom"],
+            "default": "hms",
+        }));
+        properties.insert("custom_timecode_format".into(), json!({
+            "type": "string",
+            "description": "Pattern with HH, MM, SS and MS placeholders, used when timecode_format is \"custom\"",
+        }));
+    }
+    if *format == FileFormat::Txt {
+        properties.insert("include_extended_info".into(), json!({ "type": "boolean", "default": false }));
+    }
+
+    properties.insert("replace_rules".into(), json!({
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": {
+                "find": { "type": "string" },
+                "replace": { "type": "string" },
+                "regex": { "type": "boolean", "default": false },
+                "case_sensitive": { "type": "boolean", "default": true },
+            },
+            "required": ["find", "replace"],
+        },
+    }));
+
+    let mut export_properties = serde_json::Map::new();
+    export_properties.insert("encoding".into(), json!({
+        "type": "string",
+        "enum": ["utf8", "utf8bom", "cp1251"],
+        "default": "utf8",
+    }));
+    export_properties.insert("line_ending".into(), json!({
+        "type": "string",
+        "enum": ["lf", "crlf"],
+        "default": "lf",
+    }));
+    export_properties.insert("compression".into(), json!({
+        "type": "string",
+        "enum": ["none", "gzip", "zip"],
+        "default": "none",
+    }));
+    export_properties.insert("checksums".into(), json!({ "type": "boolean", "default": false }));
+    export_properties.insert("open_after_export".into(), json!({
+        "type": ["string", "null"],
+        "enum": ["open", "reveal", null],
+        "default": null,
+    }));
+    export_properties.insert("transliteration".into(), json!({
+        "type": ["string", "null"],
+        "enum": ["icao", null],
+        "default": null,
+    }));
+    if *format == FileFormat::Txt {
+        export_properties.insert("txt_template".into(), json!({
+            "type": ["string", "null"],
+            "description": "Per-line layout with {token} placeholders; get_template_tokens lists them",
+            "examples": ["{start_hms} | {speaker} | {text}"],
+            "default": null,
+        }));
+    }
+    if matches!(format, FileFormat::Ttml | FileFormat::Fcpxml) {
+        export_properties.insert("frame_rate".into(), json!({
+            "type": ["number", "null"],
+            "examples": [23.976, 24, 25, 29.97, 30],
+            "default": null,
+        }));
+    }
+    // JSON keeps segment text untouched
+    if *format != FileFormat::Json {
+        export_properties.insert("profile".into(), json!({
+            "type": ["string", "null"],
+            "enum": ["legacy_player", null],
+            "default": null,
+        }));
+        export_properties.insert("strip_tags".into(), json!({ "type": "boolean", "default": false }));
+        export_properties.insert("text_case".into(), json!({
+            "type": "string",
+            "enum": ["original", "sentence", "upper", "lower"],
+            "default": "original",
+        }));
+        if matches!(format, FileFormat::Srt | FileFormat::Vtt | FileFormat::Sbv | FileFormat::Fcpxml) {
+            export_properties.insert("max_line_length".into(), json!({
+                "type": ["integer", "null"],
+                "minimum": 1,
+                "default": null,
+            }));
+            export_properties.insert("max_lines_per_cue".into(), json!({
+                "type": ["integer", "null"],
+                "minimum": 1,
+                "examples": [2],
+                "default": null,
+            }));
+            export_properties.insert("min_cue_duration".into(), json!({
+                "type": ["number", "null"],
+                "description": "Seconds",
+                "examples": [0.833, 1.0],
+                "default": null,
+            }));
+            export_properties.insert("max_cue_duration".into(), json!({
+                "type": ["number", "null"],
+                "description": "Seconds",
+                "examples": [7.0],
+                "default": null,
+            }));
+            export_properties.insert("max_chars_per_second".into(), json!({
+                "type": ["number", "null"],
+                "examples": [17, 20],
+                "default": null,
+            }));
+            export_properties.insert("line_break_language".into(), json!({
+                "type": ["string", "null"],
+                "description": "Language whose line-break rules apply when wrapping; \"ru\" and \"en\" have rule sets, others only balance lines. Defaults to the transcript language",
+                "examples": ["ru", "en"],
+                "default": null,
+            }));
+        }
+    }
+    if matches!(format, FileFormat::Txt | FileFormat::Markdown) {
+        export_properties.insert("append".into(), json!({
+            "type": "boolean",
+            "description": "Add to the end of an existing file under a session header",
+            "default": false,
+        }));
+    }
+    properties.insert("export_options".into(), json!({
+        "type": "object",
+        "properties": export_properties,
+    }));
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": format!("Export options for .{}", format.extension()),
+        "type": "object",
+        "properties": properties,
+    })
+}
+
+/// An existing TXT/MD document with `content` added as a new session headed by
+/// `label`. The Markdown title of `content` gives way to the session heading.
+pub fn append_session(existing: &str, content: &str, format: &FileFormat, label: &str) -> String {
+    let existing = existing.trim_end();
+    let session = match format {
+        FileFormat::Markdown => {
+            let body = match content.split_once('\n') {
+                Some((title, rest)) if title.starts_with("# ") => rest.trim_start_matches('\n'),
+                _ => content,
+            };
+            format!("---\n\n## {}\n\n{}", label, body)
+        }
+        _ => format!("===== {} =====\n\n{}", label, content),
+    };
+
+    if existing.is_empty() {
+        session
+    } else {
+        format!("{}\n\n{}", existing, session)
+    }
+}
+
+/// Cues for the parts of `segment`'s text, dividing its time up to `end` by
+/// the length of each part.
+fn share_time(segment: &TranscriptionSegment, parts: Vec<String>, end: f64) -> Vec<TranscriptionSegment> {
+    let total_length: usize = parts.iter().map(|part| reading_length(part)).sum();
+    let duration = end - segment.start_time;
+
+    let mut start = segment.start_time;
+    let mut length_so_far = 0;
+    let mut cues = Vec::with_capacity(parts.len());
+    for part in parts {
+        length_so_far += reading_length(&part);
+        let part_end = segment.start_time + duration * length_so_far as f64 / total_length.max(1) as f64;
+        cues.push(TranscriptionSegment {
+            start_time: start,
+            end_time: Some(part_end),
+            text: part,
+            words: Vec::new(),
+            ..segment.clone()
+        });
+        start = part_end;
+    }
+    cues
+}
+
+/// Characters a viewer reads: line breaks do not count, spaces do.
+fn reading_length(text: &str) -> usize {
+    text.chars().filter(|ch| *ch != '\n' && *ch != '\r').count()
+}
+
+/// Up to `count` runs of whole words of about equal length.
+fn split_words_evenly(text: &str, count: usize) -> Vec<String> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let total_length: usize = words.iter().map(|word| word.chars().count() + 1).sum();
+    let target = total_length as f64 / count.max(1) as f64;
+
+    let mut parts: Vec<String> = Vec::new();
+    let mut current: Vec<&str> = Vec::new();
+    let mut length_so_far = 0;
+    for word in words {
+        let length = word.chars().count() + 1;
+        // Break before the word when that lands closer to the boundary than after it
+        let boundary = target * (parts.len() + 1) as f64;
+        let (before, after) = (length_so_far as f64, (length_so_far + length) as f64);
+        if !current.is_empty() && parts.len() + 1 < count && after > boundary && boundary - before < after - boundary {
+            parts.push(current.join(" "));
+            current.clear();
+        }
+        current.push(word);
+        length_so_far += length;
+    }
+    if !current.is_empty() {
+        parts.push(current.join(" "));
+    }
+    parts
+}
+
+/// Greedy word wrap by display width (CJK characters count twice); existing
+/// line breaks are kept, Chinese/Japanese text may break between characters
+/// and other over-long words are left intact.
+pub(crate) fn wrap_text(text: &str, max_length: usize) -> String {
+    let max_length = max_length.max(1);
+    let mut lines = Vec::new();
+
+    for source_line in text.lines() {
+        let mut current = String::new();
+        for word in source_line.split_whitespace() {
+            for (index, unit) in break_units(word).into_iter().enumerate() {
+                let separator = if index == 0 && !current.is_empty() { " " } else { "" };
+                if !current.is_empty() && display_width(&current) + separator.len() + display_width(unit) > max_length {
+                    lines.push(std::mem::take(&mut current));
+                } else {
+                    current.push_str(separator);
+                }
+                current.push_str(unit);
+            }
+        }
+        lines.push(current);
+    }
+
+    lines.join("\n")
+}
+
+/// Adds the project's meeting details to the head of an exported document:
+/// lines above a TXT transcript, a block under the Markdown title, a NOTE in
+/// WebVTT, `;` comments in ASS, `<metadata>` in the TTML head and a
+/// `metadata` object in JSON and the `[ti:]` tag of LRC. SRT, SBV, FCPXML
+/// captions and the fixed Podcasting 2.0 schema have no place for comments
+/// and are returned unchanged.
+pub fn add_metadata_header(content: &str, format: &FileFormat, metadata: &ProjectMetadata) -> Result<String> {
+    if metadata.is_empty() {
+        return Ok(content.to_string());
+    }
+    let fields = metadata.fields();
+
+    let header = match format {
+        FileFormat::Srt
+        | FileFormat::Sbv
+        | FileFormat::Docx
+        | FileFormat::Fcpxml
+        | FileFormat::PodcastJson
+        | FileFormat::SubViewer
+        | FileFormat::MicroDvd => {
+            return Ok(content.to_string())
+        }
+        // Players show the title tag; the other fields have no LRC tag
+        FileFormat::Lrc => {
+            return Ok(match &metadata.meeting_title {
+                Some(title) => format!("[ti:{}]\n{}", title.replace(['[', ']'], ""), content),
+                None => content.to_string(),
+            });
+        }
+        FileFormat::Json => {
+            let mut document: serde_json::Value = serde_json::from_str(content)?;
+            if let Some(object) = document.as_object_mut() {
+                object.insert("metadata".to_string(), serde_json::to_value(metadata)?);
+            }
+            return Ok(serde_json::to_string_pretty(&document)?);
+        }
+        FileFormat::Ttml => {
+            let metadata_block = format!(
+                "    <metadata>\n{}    </metadata>\n",
+                fields
+                    .iter()
+                    .map(|(label, value)| match *label {
+                        "Meeting" => format!("      <ttm:title>{}</ttm:title>\n", escape_xml(value)),
+                        _ => format!("      <ttm:desc>{}: {}</ttm:desc>\n", label, escape_xml(value)),
+                    })
+                    .collect::<String>()
+            );
+            return Ok(content.replacen("<head>\n", &format!("<head>\n{}", metadata_block), 1));
+        }
+        // Comment lines right under the [Script Info] section title
+        FileFormat::Ass => {
+            let comments: String = fields
+                .iter()
+                .map(|(label, value)| format!("; {}: {}\n", label, value))
+                .collect();
+            return Ok(match content.split_once('\n') {
+                Some((title, rest)) => format!("{}\n{}{}", title, comments, rest),
+                None => format!("{}{}", comments, content),
+            });
+        }
+        FileFormat::Txt => fields
+            .iter()
+            .map(|(label, value)| format!("{}: {}\n", label, value))
+            .collect::<String>(),
+        FileFormat::Markdown => fields
+            .iter()
+            .map(|(label, value)| format!("**{}:** {}  \n", label, value))
+            .collect::<String>(),
+        FileFormat::Vtt => {
+            let lines: Vec<String> = fields
+                .iter()
+                .map(|(label, value)| format!("{}: {}", label, value.replace("-->", "->")))
+                .collect();
+            format!("NOTE\n{}\n", lines.join("\n"))
+        }
+    };
+
+    // Markdown and WebVTT keep their first line ("# Title", "WEBVTT") on top
+    let (head, body) = match format {
+        FileFormat::Markdown | FileFormat::Vtt => match content.split_once("\n\n") {
+            Some((head, body)) => (format!("{}\n\n", head), body),
+            None => (String::new(), content),
+        },
+        _ => (String::new(), content),
+    };
+    Ok(format!("{}{}\n{}", head, header, body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_profile_strips_and_wraps() {
+        let options = ExportOptions {
+            profile: Some(ExportProfile::LegacyPlayer),
+            max_line_length: Some(12),
+            ..ExportOptions::default()
+        }
+        .resolved();
+        let segments = vec![TranscriptionSegment {
+            text: "{\\an8}<i>Привет всем, это тест</i>".to_string(),
+            ..Default::default()
+        }];
+
+        let srt = options.prepare_segments(segments.clone(), &FileFormat::Srt, 5.0);
+        assert_eq!(srt[0].text, "Привет всем,\nэто тест");
+        // One line per segment in text exports, so only the tags go
+        let txt = options.prepare_segments(segments, &FileFormat::Txt, 5.0);
+        assert_eq!(txt[0].text, "Привет всем, это тест");
+    }
+
+    #[test]
+    fn test_split_long_cues() {
+        let options = ExportOptions { max_line_length: Some(16), max_lines_per_cue: Some(2), ..ExportOptions::default() };
+        let mut segments = vec![TranscriptionSegment {
+            start_time: 10.0,
+            end_time: Some(16.0),
+            text: "Сегодня мы обсудим бюджет на следующий год и планы отдела".to_string(),
+            ..Default::default()
+        }];
+
+        options.wrap_lines(&mut segments);
+        let cues = options.split_long_cues(segments, 5.0);
+
+        let texts: Vec<&str> = cues.iter().map(|cue| cue.text.as_str()).collect();
+        assert_eq!(texts, vec!["Сегодня мы\nобсудим бюджет", "на следующий год\nи планы отдела"]);
+        assert_eq!((cues[0].start_time, cues[1].end_time), (10.0, Some(16.0)));
+        assert_eq!(cues[0].end_time, Some(cues[1].start_time));
+    }
+
+    #[test]
+    fn test_enforce_cue_timing() {
+        let options = ExportOptions {
+            min_cue_duration: Some(1.0),
+            max_cue_duration: Some(6.0),
+            max_chars_per_second: Some(10.0),
+            ..ExportOptions::default()
+        };
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 0.4, "Да."),
+            TranscriptionSegment::test(1.0, 2.0, "Это слишком быстро для чтения"),
+            TranscriptionSegment::test(3.5, 15.5, "Очень длинная реплика без пауз, которую нужно разделить на части"),
+        ];
+
+        let cues = options.enforce_cue_timing(segments, 5.0);
+
+        let timing: Vec<(f64, Option<f64>)> = cues.iter().map(|cue| (cue.start_time, cue.end_time)).collect();
+        assert_eq!(timing[0], (0.0, Some(1.0)));
+        // 29 characters need 2.9 s, the next cue starts after 2.5 s
+        assert_eq!(timing[1], (1.0, Some(3.5)));
+        assert_eq!(cues.len(), 4);
+        assert_eq!(cues[2].text, "Очень длинная реплика без пауз,");
+        assert_eq!((cues[2].start_time, cues[3].end_time), (3.5, Some(15.5)));
+    }
+
+    #[test]
+    fn test_transliteration_keeps_original_text() {
+        let options = ExportOptions { transliteration: Some(TransliterationScheme::Icao), ..ExportOptions::default() };
+        let mut segments = vec![
+            TranscriptionSegment { text: "Анна: Привет".to_string(), speaker: Some("Анна".to_string()), ..Default::default() },
+            TranscriptionSegment { text: "Hello".to_string(), ..Default::default() },
+        ];
+
+        options.apply_to_segments(&mut segments);
+
+        assert_eq!((segments[0].text.as_str(), segments[0].speaker.as_deref()), ("Anna: Privet", Some("Anna")));
+        assert_eq!(segments[0].original_text.as_deref(), Some("Анна: Привет"));
+        assert_eq!(segments[1].original_text, None);
+    }
+
+    #[test]
+    fn test_append_session() {
+        let markdown = "# Merged Transcription\n\n*Generated on: today*\n\n**[00:05]** Привет\n\n";
+        assert_eq!(
+            append_session("# Лекции\n\nПервая лекция\n\n", markdown, &FileFormat::Markdown, "Лекция 2"),
+            "# Лекции\n\nПервая лекция\n\n---\n\n## Лекция 2\n\n*Generated on: today*\n\n**[00:05]** Привет\n\n"
+        );
+        assert_eq!(append_session("", "[00:05] Hi\n", &FileFormat::Txt, "Lecture 1"), "===== Lecture 1 =====\n\n[00:05] Hi\n");
+        assert_eq!(
+            append_session("[00:01] Old\n", "[00:05] Hi\n", &FileFormat::Txt, "Lecture 2"),
+            "[00:01] Old\n\n===== Lecture 2 =====\n\n[00:05] Hi\n"
+        );
+    }
+
+    #[test]
+    fn test_zip_output_with_checksum() {
+        let directory = std::env::temp_dir().join(format!("export_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let options = ExportOptions {
+            compression: Compression::Zip,
+            checksums: true,
+            ..ExportOptions::default()
+        };
+
+        let written = options.write_output(&directory.join("merged.srt"), b"1\n00:00:00,000 --> 00:00:01,000\nHi\n").unwrap();
+
+        assert_eq!(written.path, directory.join("merged.zip"));
+        let mut archive = zip::ZipArchive::new(std::fs::File::open(&written.path).unwrap()).unwrap();
+        assert_eq!(archive.by_index(0).unwrap().name(), "merged.srt");
+        let checksum = std::fs::read_to_string(written.checksum_path.unwrap()).unwrap();
+        assert!(checksum.ends_with("  merged.zip\n"));
+        assert_eq!(checksum.split_whitespace().next().unwrap().len(), 64);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_options_sidecar() {
+        let directory = std::env::temp_dir().join(format!("sidecar_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let export = directory.join("merged.srt");
+        let hash_of = |options: serde_json::Value| {
+            let path = write_options_sidecar(&export, &options).unwrap();
+            let sidecar: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+            sidecar["options_hash"].as_str().unwrap().to_string()
+        };
+
+        let first = hash_of(serde_json::json!({ "output_format": "srt", "merge": { "a": 1, "b": 2 } }));
+        assert_eq!(first.len(), 64);
+        assert_eq!(first, hash_of(serde_json::json!({ "merge": { "b": 2, "a": 1 }, "output_format": "srt" })));
+        assert_ne!(first, hash_of(serde_json::json!({ "output_format": "vtt", "merge": { "a": 1, "b": 2 } })));
+        assert!(directory.join("merged.srt.options.json").exists());
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_options_schema_depends_on_format() {
+        let txt = options_schema(&FileFormat::Txt);
+        assert!(txt["properties"]["timecode_format"].is_object());
+        assert!(txt["properties"]["export_options"]["properties"].get("max_line_length").is_none());
+        assert!(txt["properties"]["export_options"]["properties"]["append"].is_object());
+
+        let srt = options_schema(&FileFormat::Srt);
+        assert!(srt["properties"]["export_options"]["properties"]["max_line_length"].is_object());
+
+        let json = options_schema(&FileFormat::Json);
+        assert!(json["properties"].get("timecode_format").is_none());
+        assert!(json["properties"]["export_options"]["properties"].get("strip_tags").is_none());
+        assert!(json["properties"]["export_options"]["properties"].get("append").is_none());
+    }
+
+    #[test]
+    fn test_cp1251_crlf_encoding() {
+        let options = ExportOptions {
+            profile: Some(ExportProfile::LegacyPlayer),
+            ..ExportOptions::default()
+        }
+        .resolved();
+
+        let bytes = options.encode("Да\n🙂\n").unwrap();
+
+        assert_eq!(bytes, vec![0xC4, 0xE0, b'\r', b'\n', b'?', b'\r', b'\n']);
+    }
+
+    #[test]
+    fn test_metadata_header() {
+        let metadata = ProjectMetadata {
+            meeting_title: Some("Планёрка".to_string()),
+            attendees: vec!["Анна".to_string(), "Борис".to_string()],
+            ..ProjectMetadata::default()
+        };
+
+        let txt = add_metadata_header("[00:01] Привет\n", &FileFormat::Txt, &metadata).unwrap();
+        assert_eq!(txt, "Meeting: Планёрка\nAttendees: Анна, Борис\n\n[00:01] Привет\n");
+
+        let vtt = add_metadata_header("WEBVTT\n\n00:01.000 --> 00:02.000\nПривет\n\n", &FileFormat::Vtt, &metadata).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\nNOTE\nMeeting: Планёрка\nAttendees: Анна, Борис\n\n00:01.000"));
+
+        let json = add_metadata_header(r#"{"segments": []}"#, &FileFormat::Json, &metadata).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["metadata"]["attendees"][1], "Борис");
+
+        let srt = "1\n00:00:01,000 --> 00:00:02,000\nПривет\n\n";
+        assert_eq!(add_metadata_header(srt, &FileFormat::Srt, &metadata).unwrap(), srt);
+
+        let lrc = add_metadata_header("[00:01.00]Привет\n", &FileFormat::Lrc, &metadata).unwrap();
+        assert_eq!(lrc, "[ti:Планёрка]\n[00:01.00]Привет\n");
+    }
+}
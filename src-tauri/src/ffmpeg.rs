@@ -1,5 +1,5 @@
 use anyhow::{anyhow, Result};
-use reqwest;
+use serde::{Deserialize, Serialize};
 use sha2::{Sha256, Digest};
 use std::path::{Path, PathBuf};
 use std::process::Command;
@@ -9,6 +9,8 @@ use zip::ZipArchive;
 use futures_util::StreamExt;
 use tauri::Window;
 
+use crate::settings::AppSettings;
+
 pub struct FFmpegManager {
     ffmpeg_path: PathBuf,
 }
@@ -126,13 +128,7 @@ impl FFmpegManager {
             PathBuf::from("/usr/local/bin/ffmpeg"),
         ];
 
-        for path in common_paths {
-            if path.exists() {
-                return Some(path);
-            }
-        }
-
-        None
+        common_paths.into_iter().find(|path| path.exists())
     }
 
     async fn test_ffmpeg(&self, path: &Path) -> bool {
@@ -501,6 +497,23 @@ impl FFmpegManager {
         Ok((format_duration(duration), duration))
     }
 
+    /// Title, artist and recording date stored in the media file's container.
+    pub async fn get_media_tags(&self, file_path: &str) -> Result<MediaTags> {
+        let ffmpeg_path = self.get_ffmpeg_path()?;
+        let mut cmd = Command::new(&ffmpeg_path);
+        // Without an output ffmpeg exits with an error after printing the input details
+        cmd.args(["-hide_banner", "-i", file_path]);
+
+        #[cfg(target_os = "windows")]
+        {
+            use std::os::windows::process::CommandExt;
+            cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+        }
+
+        let output = cmd.output()?;
+        Ok(parse_media_tags(&String::from_utf8_lossy(&output.stderr)))
+    }
+
     async fn get_file_info_alternative(&self, file_path: &str) -> Result<f64> {
         let ffmpeg_path = self.get_ffmpeg_path()?;
         
@@ -537,6 +550,56 @@ impl FFmpegManager {
     }
 }
 
+/// Tags read from a media file, usable as `{media_title}`, `{media_artist}`
+/// and `{media_date}` in naming templates and export headers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MediaTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    /// As stored in the file, usually an ISO 8601 date-time
+    pub creation_time: Option<String>,
+}
+
+impl MediaTags {
+    /// Replaces the media placeholders; a tag the file does not have leaves
+    /// its placeholder empty.
+    pub fn fill(&self, template: &str) -> String {
+        let date: Option<String> = self.creation_time.as_ref().map(|time| time.chars().take(10).collect());
+        template
+            .replace("{media_title}", self.title.as_deref().unwrap_or_default())
+            .replace("{media_artist}", self.artist.as_deref().unwrap_or_default())
+            .replace("{media_date}", date.as_deref().unwrap_or_default())
+    }
+}
+
+/// Reads the `key : value` lines of the `Metadata:` blocks ffmpeg prints for
+/// an input. The first value wins, so container tags beat stream tags; MP3's
+/// `date` stands in for a missing `creation_time`.
+pub fn parse_media_tags(ffmpeg_output: &str) -> MediaTags {
+    let mut tags = MediaTags::default();
+    let mut date = None;
+    for line in ffmpeg_output.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            continue;
+        }
+        let slot = match key.trim().to_lowercase().as_str() {
+            "title" => &mut tags.title,
+            "artist" => &mut tags.artist,
+            "creation_time" => &mut tags.creation_time,
+            "date" => &mut date,
+            _ => continue,
+        };
+        slot.get_or_insert_with(|| value.to_string());
+    }
+    tags.creation_time = tags.creation_time.or(date);
+    tags
+}
+
 fn parse_duration_string(duration_str: &str) -> Result<f64> {
     // Parse duration in format HH:MM:SS.sss
     let parts: Vec<&str> = duration_str.split(':').collect();
@@ -551,7 +614,19 @@ fn parse_duration_string(duration_str: &str) -> Result<f64> {
     Ok(hours * 3600.0 + minutes * 60.0 + seconds)
 }
 
-fn get_app_data_dir() -> Result<PathBuf> {
+/// Where FFmpeg, projects and caches live: the folder chosen in the settings,
+/// otherwise the platform default.
+pub(crate) fn get_app_data_dir() -> Result<PathBuf> {
+    let default_dir = default_app_data_dir()?;
+    let relocated = AppSettings::load_from(&AppSettings::settings_path()?)
+        .ok()
+        .and_then(|settings| settings.data_dir);
+    Ok(relocated.unwrap_or(default_dir))
+}
+
+/// The platform's app data folder; the settings always stay here so that a
+/// relocated data folder can be found.
+pub(crate) fn default_app_data_dir() -> Result<PathBuf> {
     #[cfg(target_os = "windows")]
     {
         if let Some(appdata) = std::env::var_os("APPDATA") {
@@ -0,0 +1,185 @@
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::merger::{MergeOptions, ParseWarning, TranscriptionFile, TranscriptionMerger};
+
+/// Parser fixtures are real-world transcription files, each next to a
+/// `<file>.golden.json` holding what the parser made of it when it was last
+/// checked by hand.
+pub fn golden_path(input: &Path) -> PathBuf {
+    let mut name = input.file_name().unwrap_or_default().to_os_string();
+    name.push(".golden.json");
+    input.with_file_name(name)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FixtureReport {
+    pub input: String,
+    pub golden: String,
+    /// Format the file was detected as
+    pub format: String,
+    pub segments: usize,
+    pub warnings: Vec<ParseWarning>,
+    pub passed: bool,
+    /// How the parser output departs from the golden file, one line each
+    pub differences: Vec<String>,
+    /// The golden file was written from the current parser output
+    pub updated: bool,
+}
+
+/// Parses `input` with the default merge options and compares the segments
+/// and warnings with its golden file. With `update` the golden file is
+/// written from the current output instead, to accept a deliberate change.
+pub async fn validate_fixture(input: &Path, update: bool) -> Result<FixtureReport> {
+    let merger = TranscriptionMerger::new(MergeOptions::default());
+    let file = merger.parse_transcription_file(input).await?;
+    let actual = snapshot(&file);
+    let golden = golden_path(input);
+
+    let differences = if update {
+        std::fs::write(&golden, serde_json::to_string_pretty(&actual)? + "\n")?;
+        Vec::new()
+    } else {
+        let content = std::fs::read_to_string(&golden).map_err(|_| {
+            anyhow!("No golden file {}; validate with update to create it", golden.display())
+        })?;
+        let expected: Value =
+            serde_json::from_str(&content).map_err(|e| anyhow!("Invalid golden file {}: {}", golden.display(), e))?;
+        compare(&expected, &actual)
+    };
+
+    Ok(FixtureReport {
+        input: input.to_string_lossy().to_string(),
+        golden: golden.to_string_lossy().to_string(),
+        format: file.format.extension().to_string(),
+        segments: file.segments.len(),
+        warnings: file.warnings,
+        passed: differences.is_empty(),
+        differences,
+        updated: update,
+    })
+}
+
+/// Validates (or with `update` rewrites the golden files of) every fixture in
+/// `dir`, in name order.
+pub async fn validate_fixture_dir(dir: &Path, update: bool) -> Result<Vec<FixtureReport>> {
+    let mut inputs: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            !name.starts_with('.') && !name.ends_with(".golden.json")
+        })
+        .collect();
+    inputs.sort();
+
+    let mut reports = Vec::new();
+    for input in inputs {
+        reports.push(validate_fixture(&input, update).await?);
+    }
+    Ok(reports)
+}
+
+fn snapshot(file: &TranscriptionFile) -> Value {
+    json!({
+        "format": file.format.extension(),
+        "segments": file.segments,
+        "warnings": file.warnings,
+    })
+}
+
+fn compare(expected: &Value, actual: &Value) -> Vec<String> {
+    let mut differences = Vec::new();
+    if expected["format"] != actual["format"] {
+        differences.push(format!("format: expected {}, got {}", expected["format"], actual["format"]));
+    }
+    for key in ["segments", "warnings"] {
+        let empty = Vec::new();
+        let expected_items = expected[key].as_array().unwrap_or(&empty);
+        let actual_items = actual[key].as_array().unwrap_or(&empty);
+        if expected_items.len() != actual_items.len() {
+            differences.push(format!("{}: expected {}, got {}", key, expected_items.len(), actual_items.len()));
+        }
+        for (index, (expected_item, actual_item)) in expected_items.iter().zip(actual_items).enumerate() {
+            diff_value(&format!("{}[{}]", key, index), expected_item, actual_item, &mut differences);
+        }
+    }
+    differences
+}
+
+/// Times are compared to the microsecond, so float formatting of estimated
+/// end times does not count as a change.
+fn diff_value(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            let mut keys: Vec<&String> = expected_map.keys().chain(actual_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let missing = Value::Null;
+                diff_value(
+                    &format!("{}.{}", path, key),
+                    expected_map.get(key).unwrap_or(&missing),
+                    actual_map.get(key).unwrap_or(&missing),
+                    differences,
+                );
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) if expected_items.len() == actual_items.len() => {
+            for (index, (expected_item, actual_item)) in expected_items.iter().zip(actual_items).enumerate() {
+                diff_value(&format!("{}[{}]", path, index), expected_item, actual_item, differences);
+            }
+        }
+        (Value::Number(a), Value::Number(b))
+            if (a.as_f64().unwrap_or_default() - b.as_f64().unwrap_or_default()).abs() < 1e-6 => {}
+        _ if expected != actual => differences.push(format!("{}: expected {}, got {}", path, expected, actual)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_parser_fixtures() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests").join("fixtures").join("parsers");
+        // UPDATE_PARSER_FIXTURES=1 accepts the current parser output as golden
+        let update = std::env::var_os("UPDATE_PARSER_FIXTURES").is_some();
+        let reports = validate_fixture_dir(&dir, update).await.unwrap();
+
+        let formats: Vec<&str> = reports.iter().map(|report| report.format.as_str()).collect();
+        for format in ["srt", "vtt", "sbv", "sub", "txt", "md", "json", "docx"] {
+            assert!(formats.contains(&format), "no parser fixture for {}", format);
+        }
+        let failures: Vec<String> = reports
+            .iter()
+            .flat_map(|report| report.differences.iter().map(move |difference| format!("{}: {}", report.input, difference)))
+            .collect();
+        assert!(failures.is_empty(), "parser output differs from the golden files:\n{}", failures.join("\n"));
+    }
+
+    #[tokio::test]
+    async fn test_fixture_reports_changes() {
+        let dir = std::env::temp_dir().join(format!("fixtures_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let input = dir.join("talk.srt");
+        std::fs::write(&input, "1\n00:00:01,000 --> 00:00:02,500\nHello.\n").unwrap();
+
+        assert!(validate_fixture(&input, false).await.is_err());
+        assert!(validate_fixture(&input, true).await.unwrap().updated);
+        assert!(validate_fixture(&input, false).await.unwrap().passed);
+
+        std::fs::write(&input, "1\n00:00:01,000 --> 00:00:03,000\nHello!\n").unwrap();
+        let report = validate_fixture(&input, false).await.unwrap();
+        assert_eq!(
+            report.differences,
+            vec!["segments[0].end_time: expected 2.5, got 3.0", "segments[0].text: expected \"Hello.\", got \"Hello!\""]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
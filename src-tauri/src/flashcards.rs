@@ -0,0 +1,168 @@
+use serde::{Deserialize, Serialize};
+
+use crate::merger::{split_sentences, TranscriptionSegment};
+use crate::translit::{transliterate, TransliterationScheme};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CardUnit {
+    #[default]
+    Segment,
+    Sentence,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TableFormat {
+    #[default]
+    Tsv,
+    Csv,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlashcardOptions {
+    pub unit: CardUnit,
+    pub format: TableFormat,
+    /// Adds a translation column; filled from `translations` (by card order) or left empty
+    pub include_translation: bool,
+    pub translations: Vec<String>,
+    /// Source audio to cut a clip per card from; without it the sound column is omitted
+    pub source_audio: Option<String>,
+    /// Write the text in Latin letters, with the source text in a column after it
+    pub transliteration: Option<TransliterationScheme>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Card {
+    pub start_time: f64,
+    pub end_time: f64,
+    pub text: String,
+}
+
+/// Builds one card per segment, or per sentence with the segment's time range
+/// split proportionally to sentence length.
+pub fn build_cards(segments: &[TranscriptionSegment], unit: CardUnit, default_duration: f64) -> Vec<Card> {
+    let mut cards = Vec::new();
+
+    for segment in segments {
+        let end_time = segment.end_time.unwrap_or(segment.start_time + default_duration);
+
+        match unit {
+            CardUnit::Segment => cards.push(Card {
+                start_time: segment.start_time,
+                end_time,
+                text: segment.text.clone(),
+            }),
+            CardUnit::Sentence => {
+                let sentences = split_sentences(&segment.text);
+                let total_chars: usize = sentences.iter().map(|s| s.chars().count()).sum();
+                let duration = end_time - segment.start_time;
+                let mut cursor = segment.start_time;
+
+                for sentence in sentences {
+                    let share = sentence.chars().count() as f64 / total_chars.max(1) as f64;
+                    let sentence_end = cursor + duration * share;
+                    cards.push(Card {
+                        start_time: cursor,
+                        end_time: sentence_end,
+                        text: sentence,
+                    });
+                    cursor = sentence_end;
+                }
+            }
+        }
+    }
+
+    cards
+}
+
+/// Renders the Anki import table: timestamp, text, [original text], [translation], [sound].
+/// `clips` holds the clip file name per card when audio was cut.
+pub fn render_table(cards: &[Card], clips: Option<&[String]>, options: &FlashcardOptions) -> String {
+    let mut output = String::new();
+
+    for (index, card) in cards.iter().enumerate() {
+        let total_seconds = card.start_time as u64;
+        let mut fields = vec![
+            format!("{:02}:{:02}:{:02}", total_seconds / 3600, (total_seconds % 3600) / 60, total_seconds % 60),
+        ];
+        match options.transliteration {
+            Some(scheme) => fields.extend([transliterate(&card.text, scheme), card.text.clone()]),
+            None => fields.push(card.text.clone()),
+        }
+        if options.include_translation {
+            fields.push(options.translations.get(index).cloned().unwrap_or_default());
+        }
+        if let Some(clips) = clips {
+            fields.push(format!("[sound:{}]", clips[index]));
+        }
+
+        let row: Vec<String> = fields.iter().map(|field| escape_field(field, options.format)).collect();
+        let separator = match options.format {
+            TableFormat::Tsv => "\t",
+            TableFormat::Csv => ",",
+        };
+        output.push_str(&row.join(separator));
+        output.push('\n');
+    }
+
+    output
+}
+
+/// Anki fields are HTML, so line breaks become <br>; CSV fields are quoted when needed.
+fn escape_field(field: &str, format: TableFormat) -> String {
+    let field = field.replace("\r\n", "\n").replace('\n', "<br>");
+    match format {
+        TableFormat::Tsv => field.replace('\t', " "),
+        TableFormat::Csv if field.contains([',', '"']) => format!("\"{}\"", field.replace('"', "\"\"")),
+        TableFormat::Csv => field,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::clip_file_name;
+
+    #[test]
+    fn test_sentence_cards_and_csv() {
+        let segments = vec![TranscriptionSegment {
+            start_time: 60.0,
+            end_time: Some(70.0),
+            text: "Hola. Me llamo \"Ana\", gracias.".to_string(),
+            ..Default::default()
+        }];
+
+        let cards = build_cards(&segments, CardUnit::Sentence, 5.0);
+        assert_eq!(cards.len(), 2);
+        assert!((cards[0].end_time - cards[1].start_time).abs() < 0.001);
+        assert!((cards[1].end_time - 70.0).abs() < 0.001);
+
+        let clips: Vec<String> = cards.iter().enumerate().map(|(index, card)| clip_file_name(index + 1, card.start_time)).collect();
+        assert_eq!(clips[0], "clip_1_00-01-00_000.mp3");
+
+        let options = FlashcardOptions {
+            format: TableFormat::Csv,
+            include_translation: true,
+            translations: vec!["Hello.".to_string()],
+            ..FlashcardOptions::default()
+        };
+        let table = render_table(&cards, Some(&clips), &options);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines[0], "00:01:00,Hola.,Hello.,[sound:clip_1_00-01-00_000.mp3]");
+        assert!(lines[1].starts_with("00:01:01,\"Me llamo \"\"Ana\"\", gracias.\",,[sound:"));
+    }
+
+    #[test]
+    fn test_transliterated_table_keeps_original() {
+        let cards = vec![Card { start_time: 5.0, end_time: 8.0, text: "Привет, мир".to_string() }];
+        let options = FlashcardOptions {
+            format: TableFormat::Csv,
+            transliteration: Some(TransliterationScheme::Icao),
+            ..FlashcardOptions::default()
+        };
+
+        assert_eq!(render_table(&cards, None, &options), "00:00:05,\"Privet, mir\",\"Привет, мир\"\n");
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::SilenceRange;
+use crate::merger::TranscriptionSegment;
+use crate::stats::{spoken_text, tokenize};
+
+/// Phrases Whisper tends to invent on silence or music, written as
+/// lowercase words without punctuation
+const KNOWN_PHRASES: &[&str] = &[
+    "thank you for watching",
+    "thanks for watching",
+    "please subscribe",
+    "like and subscribe",
+    "subtitles by the amara org community",
+    "спасибо за просмотр",
+    "продолжение следует",
+    "подписывайтесь на канал",
+    "ставьте лайки",
+    "субтитры сделал",
+    "субтитры создавал",
+    "редактор субтитров",
+];
+
+/// Identical segments in a row from which the repeats count as a loop
+const MIN_REPEATS: usize = 3;
+/// Share of a segment lying in detected silence above which it cannot be speech
+const SILENCE_OVERLAP: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HallucinationFilter {
+    #[default]
+    Off,
+    /// Keep suspected segments but mark them (`hallucination` in JSON)
+    Flag,
+    Remove,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HallucinationKind {
+    /// Mostly a stock phrase such as "Thank you for watching"
+    KnownPhrase,
+    /// Repeats the previous segments word for word
+    Repetition,
+    /// Lies in a stretch the audio analysis found silent
+    DuringSilence,
+}
+
+/// Suspected hallucination per segment, in the order of `segments` (sorted by
+/// time). Merger-inserted markers are never suspected.
+pub fn detect_hallucinations(
+    segments: &[TranscriptionSegment],
+    silences: &[SilenceRange],
+    default_duration: f64,
+) -> Vec<Option<HallucinationKind>> {
+    let phrases: Vec<String> = KNOWN_PHRASES.iter().map(|phrase| format!(" {} ", phrase)).collect();
+    let normalized: Vec<String> = segments
+        .iter()
+        .map(|segment| tokenize(spoken_text(segment)).join(" "))
+        .collect();
+
+    let mut result = vec![None; segments.len()];
+    let mut run_start = 0;
+    for (index, segment) in segments.iter().enumerate() {
+        if segment.original_filename.is_empty() || normalized[index].is_empty() {
+            continue;
+        }
+
+        // Repeats of the previous segment extend the current run; `run_start` is its first segment
+        let repeats_previous = index > 0 && normalized[index] == normalized[index - 1];
+        if !repeats_previous {
+            run_start = index;
+        }
+
+        let padded = format!(" {} ", normalized[index]);
+        let known_phrase = phrases
+            .iter()
+            .any(|phrase| padded.contains(phrase.as_str()) && phrase.len() * 2 >= padded.len());
+
+        result[index] = if known_phrase {
+            Some(HallucinationKind::KnownPhrase)
+        } else if repeats_previous && index + 1 - run_start >= MIN_REPEATS {
+            // The run just became long enough: everything after its first segment is a loop
+            for earlier in result.iter_mut().take(index).skip(run_start + 1) {
+                earlier.get_or_insert(HallucinationKind::Repetition);
+            }
+            Some(HallucinationKind::Repetition)
+        } else if silence_share(segment, silences, default_duration) >= SILENCE_OVERLAP {
+            Some(HallucinationKind::DuringSilence)
+        } else {
+            None
+        };
+    }
+
+    result
+}
+
+fn silence_share(segment: &TranscriptionSegment, silences: &[SilenceRange], default_duration: f64) -> f64 {
+    let start = segment.start_time;
+    let end = segment.end_time.unwrap_or(start + default_duration);
+    if end <= start {
+        return 0.0;
+    }
+    let silent: f64 = silences
+        .iter()
+        .map(|silence| (silence.end.min(end) - silence.start.max(start)).max(0.0))
+        .sum();
+    silent / (end - start)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_hallucinations() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 4.0, "Да.").file("chunk_001.srt"),
+            TranscriptionSegment::test(4.0, 8.0, "Да.").file("chunk_001.srt"),
+            TranscriptionSegment::test(8.0, 12.0, "Начнём с бюджета").file("chunk_001.srt"),
+            TranscriptionSegment::test(12.0, 16.0, "И снова.").file("chunk_001.srt"),
+            TranscriptionSegment::test(16.0, 20.0, "И снова.").file("chunk_001.srt"),
+            TranscriptionSegment::test(20.0, 24.0, "и снова").file("chunk_001.srt"),
+            TranscriptionSegment::test(24.0, 28.0, "Продолжение следует...").file("chunk_001.srt"),
+            TranscriptionSegment::test(30.0, 34.0, "Тишина").file("chunk_001.srt"),
+            TranscriptionSegment::test(40.0, 44.0, "Thank you for watching, and see you in the next episode of our long show")
+                .file("chunk_001.srt"),
+        ];
+        let silences = vec![SilenceRange { start: 29.0, end: 33.5 }];
+
+        let flags = detect_hallucinations(&segments, &silences, 5.0);
+
+        use HallucinationKind::*;
+        assert_eq!(flags, vec![
+            None, None, None,
+            None, Some(Repetition), Some(Repetition),
+            Some(KnownPhrase), Some(DuringSilence), None,
+        ]);
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use crate::audio::ChunkManifest;
+use crate::merger::{format_txt_timestamp, split_sentences, TranscriptionSegment};
+
+/// Table formats of the sheets handed to people rather than programs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SheetFormat {
+    #[default]
+    Csv,
+    Markdown,
+}
+
+impl SheetFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            SheetFormat::Csv => "csv",
+            SheetFormat::Markdown => "md",
+        }
+    }
+}
+
+/// What a transcriber needs to pick up one chunk where the previous one ended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HandoffRow {
+    pub chunk_number: usize,
+    pub file_name: String,
+    pub start_time: f64,
+    pub end_time: f64,
+    /// Last sentence transcribed in the previous chunk, once there is one
+    pub previous_sentence: Option<String>,
+}
+
+/// One row per chunk of the manifest. The previous sentence comes from the
+/// segments starting inside the previous chunk, so it is only filled in for
+/// chunks whose predecessor has been transcribed and merged.
+pub fn handoff_rows(manifest: &ChunkManifest, segments: &[TranscriptionSegment]) -> Vec<HandoffRow> {
+    let mut chunks: Vec<_> = manifest.chunks.iter().collect();
+    chunks.sort_by_key(|chunk| chunk.chunk_number);
+
+    let mut rows = Vec::with_capacity(chunks.len());
+    let mut previous_sentence = None;
+    for chunk in chunks {
+        let end_time = chunk.start_time + chunk.duration;
+        rows.push(HandoffRow {
+            chunk_number: chunk.chunk_number,
+            file_name: chunk.file_name.clone(),
+            start_time: chunk.start_time,
+            end_time,
+            previous_sentence: previous_sentence.take(),
+        });
+
+        let text: Vec<&str> = segments
+            .iter()
+            .filter(|segment| segment.start_time >= chunk.start_time && segment.start_time < end_time)
+            .map(|segment| segment.text.trim())
+            .collect();
+        previous_sentence = split_sentences(&text.join(" ")).pop();
+    }
+    rows
+}
+
+pub fn render_handoff(rows: &[HandoffRow], format: SheetFormat) -> String {
+    let header = ["Chunk", "File", "Start", "End", "Previous chunk ends with"];
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            vec![
+                row.chunk_number.to_string(),
+                row.file_name.clone(),
+                format_txt_timestamp(row.start_time),
+                format_txt_timestamp(row.end_time),
+                row.previous_sentence.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    render_sheet(&header, &rows, format)
+}
+
+/// A table with a header row as CSV or as a Markdown table.
+pub fn render_sheet(header: &[&str], rows: &[Vec<String>], format: SheetFormat) -> String {
+    let mut output = match format {
+        SheetFormat::Csv => format!("{}\n", header.join(",")),
+        SheetFormat::Markdown => format!("| {} |\n|{}\n", header.join(" | "), "---|".repeat(header.len())),
+    };
+
+    for fields in rows {
+        match format {
+            SheetFormat::Csv => {
+                let fields: Vec<String> = fields.iter().map(|field| escape_csv(field)).collect();
+                output.push_str(&fields.join(","));
+            }
+            SheetFormat::Markdown => {
+                let fields: Vec<String> = fields.iter().map(|field| field.replace('|', "\\|")).collect();
+                output.push_str(&format!("| {} |", fields.join(" | ")));
+            }
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ManifestChunk;
+
+    #[test]
+    fn test_handoff_sheet() {
+        let chunk = |chunk_number: usize, start_time: f64| ManifestChunk {
+            file_name: format!("chunk_{:03}.mp3", chunk_number),
+            chunk_number,
+            start_time,
+            duration: 600.0,
+        };
+        let manifest = ChunkManifest {
+            source: "meeting.mp3".to_string(),
+            chunks: vec![chunk(2, 600.0), chunk(1, 0.0), chunk(3, 1200.0)],
+            silences: Vec::new(),
+            assignments: Vec::new(),
+        };
+        let segments = vec![TranscriptionSegment {
+            start_time: 590.0,
+            text: "Начнём. Бюджет, как вы знаете, \"урезали\".".to_string(),
+            ..Default::default()
+        }];
+
+        let rows = handoff_rows(&manifest, &segments);
+        let previous: Vec<Option<&str>> = rows.iter().map(|row| row.previous_sentence.as_deref()).collect();
+        assert_eq!(previous, vec![None, Some("Бюджет, как вы знаете, \"урезали\"."), None]);
+
+        let csv = render_handoff(&rows, SheetFormat::Csv);
+        assert_eq!(
+            csv.lines().nth(2),
+            Some("2,chunk_002.mp3,10:00,20:00,\"Бюджет, как вы знаете, \"\"урезали\"\".\"")
+        );
+        let markdown = render_handoff(&rows, SheetFormat::Markdown);
+        assert!(markdown.starts_with("| Chunk | File | Start | End | Previous chunk ends with |\n|---|---|---|---|---|\n| 1 |"));
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::Result;
+use base64::Engine;
+use std::path::Path;
+use tokio::process::Command;
+
+use crate::merger::{escape_xml, format_txt_timestamp, TranscriptionSegment};
+use crate::project::ProjectMetadata;
+use crate::supervisor::{run_supervised, Supervision};
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Roboto, sans-serif; max-width: 48rem; margin: 0 auto; padding: 0 1rem 4rem; line-height: 1.5; color: #1f2328; }
+header.player { position: sticky; top: 0; background: #fff; padding: 1rem 0; border-bottom: 1px solid #d0d7de; }
+header.player audio, header.player video { width: 100%; max-height: 40vh; }
+dl.metadata { color: #59636e; display: grid; grid-template-columns: max-content 1fr; gap: 0 1rem; }
+dl.metadata dd { margin: 0; }
+p.segment { cursor: pointer; margin: 0.25rem 0; padding: 0.25rem 0.5rem; border-radius: 4px; }
+p.segment:hover { background: #f6f8fa; }
+p.segment.active { background: #fff8c5; }
+.time { color: #808080; font-weight: bold; font-variant-numeric: tabular-nums; }
+.speaker { font-weight: bold; }
+header.player input[type=search] { width: 100%; box-sizing: border-box; margin-top: 0.5rem; padding: 0.375rem 0.5rem; font: inherit; }
+p.segment.hidden { display: none; }
+";
+
+/// Clicking a line seeks the player; the line being played is highlighted and
+/// kept in view. Typing in the search box hides the lines without the query.
+const SCRIPT: &str = "
+(function () {
+  var player = document.getElementById('player');
+  var lines = Array.prototype.slice.call(document.querySelectorAll('p.segment'));
+  var active = null;
+  var search = document.getElementById('search');
+  if (search) {
+    var status = document.getElementById('search-status');
+    search.addEventListener('input', function () {
+      var query = search.value.trim().toLowerCase();
+      var shown = 0;
+      lines.forEach(function (line) {
+        var match = !query || line.textContent.toLowerCase().indexOf(query) !== -1;
+        line.classList.toggle('hidden', !match);
+        if (match) shown++;
+      });
+      status.textContent = query ? 'Найдено: ' + shown : '';
+    });
+  }
+  lines.forEach(function (line) {
+    line.addEventListener('click', function () {
+      if (!player) return;
+      player.currentTime = parseFloat(line.dataset.start);
+      player.play();
+    });
+  });
+  if (!player) return;
+  player.addEventListener('timeupdate', function () {
+    var time = player.currentTime;
+    var current = null;
+    for (var i = 0; i < lines.length && parseFloat(lines[i].dataset.start) <= time; i++) {
+      current = lines[i];
+    }
+    if (current === active) return;
+    if (active) active.classList.remove('active');
+    active = current;
+    if (active) {
+      active.classList.add('active');
+      active.scrollIntoView({ block: 'nearest', behavior: 'smooth' });
+    }
+  });
+})();
+";
+
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    pub title: String,
+    pub show_timestamps: bool,
+    /// Start a heading whenever the source file changes
+    pub file_headings: bool,
+    /// `src` of the player: a relative path, a file URL or a data URI
+    pub media_src: Option<String>,
+    /// Use a `<video>` element instead of `<audio>`
+    pub video: bool,
+    /// Add a search box that filters the lines
+    pub search: bool,
+    /// `lang` of the page; Russian when not known
+    pub language: Option<String>,
+}
+
+/// Builds a single HTML page with the transcript and a player; every line
+/// carries its start time in `data-start`.
+pub fn render_html(segments: &[TranscriptionSegment], metadata: &ProjectMetadata, options: &HtmlOptions) -> String {
+    let mut body = String::new();
+
+    body.push_str("<header class=\"player\">\n");
+    body.push_str(&format!("<h1>{}</h1>\n", escape_xml(&options.title)));
+    if let Some(src) = &options.media_src {
+        let element = if options.video { "video" } else { "audio" };
+        body.push_str(&format!(
+            "<{element} id=\"player\" controls preload=\"metadata\" src=\"{}\"></{element}>\n",
+            escape_xml(src)
+        ));
+    }
+    if options.search {
+        body.push_str("<input type=\"search\" id=\"search\" placeholder=\"Поиск по тексту\">\n");
+        body.push_str("<div id=\"search-status\" class=\"time\"></div>\n");
+    }
+    body.push_str("</header>\n");
+
+    let fields = metadata.fields();
+    if !fields.is_empty() {
+        body.push_str("<dl class=\"metadata\">\n");
+        for (label, value) in fields {
+            body.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", label, escape_xml(&value)));
+        }
+        body.push_str("</dl>\n");
+    }
+
+    let mut current_file: Option<&str> = None;
+    for segment in segments {
+        let from_file = !segment.original_filename.is_empty();
+        if options.file_headings && from_file && current_file != Some(segment.original_filename.as_str()) {
+            current_file = Some(segment.original_filename.as_str());
+            body.push_str(&format!("<h2>{}</h2>\n", escape_xml(&segment.original_filename)));
+        }
+
+        let mut line = format!("<p class=\"segment\" data-start=\"{:.3}\">", segment.start_time);
+        if options.show_timestamps {
+            line.push_str(&format!("<span class=\"time\">[{}]</span> ", format_txt_timestamp(segment.start_time)));
+        }
+        let text = segment.text.trim();
+        let label = segment
+            .speaker
+            .as_deref()
+            .filter(|speaker| text.starts_with(&format!("{}:", speaker)));
+        match label {
+            Some(speaker) => line.push_str(&format!(
+                "<span class=\"speaker\">{}:</span>{}",
+                escape_xml(speaker),
+                escape_text(&text[speaker.len() + 1..])
+            )),
+            None => line.push_str(&escape_text(text)),
+        }
+        line.push_str("</p>\n");
+        body.push_str(&line);
+    }
+
+    format!(
+        "<!DOCTYPE html>\n<html lang=\"{}\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>{}</style>\n</head>\n<body>\n{}<script>{}</script>\n</body>\n</html>\n",
+        escape_xml(options.language.as_deref().unwrap_or("ru")),
+        escape_xml(&options.title),
+        STYLE,
+        body,
+        SCRIPT
+    )
+}
+
+/// Escaped segment text with line breaks kept.
+fn escape_text(text: &str) -> String {
+    escape_xml(text).replace('\n', "<br>")
+}
+
+/// Player source for media next to the exported page: a path relative to the
+/// page when the media is in the same folder or below it, a file URL otherwise.
+pub fn media_link(media_path: &Path, output_dir: &Path) -> String {
+    match media_path.strip_prefix(output_dir) {
+        Ok(relative) => relative
+            .components()
+            .map(|part| urlencoding::encode(&part.as_os_str().to_string_lossy()).into_owned())
+            .collect::<Vec<_>>()
+            .join("/"),
+        Err(_) => crate::nle::file_url(media_path),
+    }
+}
+
+/// The media as a `data:` URI, which makes the page self-contained at the cost
+/// of its size.
+pub fn media_data_uri(media_path: &Path) -> std::io::Result<String> {
+    let bytes = std::fs::read(media_path)?;
+    Ok(format!(
+        "data:{};base64,{}",
+        media_mime_type(media_path),
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    ))
+}
+
+/// Re-encodes the media as low-bitrate mono MP3 for embedding into a viewer
+/// page; speech stays intelligible at 32-48 kbps and an hour takes 15-20 MB.
+pub async fn compress_audio(ffmpeg_path: &Path, input_path: &Path, output_path: &Path, bitrate_kbps: u32) -> Result<()> {
+    let bitrate = format!("{}k", bitrate_kbps);
+    // The whole recording is re-encoded, so only a stalled run is cut short
+    let run = run_supervised(
+        || {
+            let mut cmd = Command::new(ffmpeg_path);
+            cmd.arg("-i").arg(input_path);
+            cmd.args([
+                "-vn",
+                "-ac", "1",
+                "-acodec", "libmp3lame",
+                "-b:a", &bitrate,
+                "-y",
+            ]);
+            cmd.arg(output_path);
+            cmd
+        },
+        Supervision { timeout: None, ..Supervision::default() },
+    )
+    .await;
+
+    if let Err(e) = &run {
+        println!("FFmpeg audio compression failed: {}", e);
+    }
+    run.map(|_| ())
+}
+
+pub fn is_video(media_path: &Path) -> bool {
+    media_mime_type(media_path).starts_with("video/")
+}
+
+fn media_mime_type(media_path: &Path) -> &'static str {
+    let extension = media_path
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" | "opus" => "audio/ogg",
+        "flac" => "audio/flac",
+        "m4a" | "aac" => "audio/mp4",
+        "mp4" | "mov" => "video/mp4",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_html_lines() {
+        let segments = vec![
+            TranscriptionSegment {
+                start_time: 65.5,
+                text: "Анна: Первая <строка>\nвторая".to_string(),
+                original_filename: "part1.txt".to_string(),
+                speaker: Some("Анна".to_string()),
+                ..Default::default()
+            },
+            TranscriptionSegment {
+                start_time: 600.0,
+                text: "[ad break]".to_string(),
+                ..Default::default()
+            },
+        ];
+        let options = HtmlOptions {
+            title: "Эпизод 12".to_string(),
+            show_timestamps: true,
+            file_headings: true,
+            media_src: Some("show & tell.mp3".to_string()),
+            video: false,
+            search: false,
+            language: None,
+        };
+
+        let html = render_html(&segments, &ProjectMetadata::default(), &options);
+
+        assert!(html.contains(r#"<audio id="player" controls preload="metadata" src="show &amp; tell.mp3"></audio>"#));
+        assert!(html.contains("<h2>part1.txt</h2>"));
+        assert!(html.contains(
+            "<p class=\"segment\" data-start=\"65.500\"><span class=\"time\">[01:05]</span> \
+             <span class=\"speaker\">Анна:</span> Первая &lt;строка&gt;<br>вторая</p>\n"
+        ));
+        assert!(html.contains("<p class=\"segment\" data-start=\"600.000\"><span class=\"time\">[10:00]</span> [ad break]</p>\n"));
+        assert_eq!(html.matches("<h2>").count(), 1);
+        assert!(!html.contains("id=\"search\""));
+
+        let viewer = render_html(&segments, &ProjectMetadata::default(), &HtmlOptions { search: true, ..options });
+        assert!(viewer.contains("<input type=\"search\" id=\"search\" placeholder=\"Поиск по тексту\">\n"));
+    }
+
+    #[test]
+    fn test_media_link() {
+        assert_eq!(media_link(Path::new("/exports/media/My Show.mp3"), Path::new("/exports")), "media/My%20Show.mp3");
+        assert_eq!(media_link(Path::new("/media/show.wav"), Path::new("/exports")), "file:///media/show.wav");
+        assert!(is_video(Path::new("/media/show.MP4")));
+    }
+}
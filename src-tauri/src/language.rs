@@ -0,0 +1,202 @@
+use std::collections::HashMap;
+
+use crate::merger::TranscriptionSegment;
+use crate::stats::{spoken_text, tokenize};
+
+/// Only the opening of a recording is looked at; it is enough to tell the
+/// language and keeps long merges fast.
+const SAMPLE_SECONDS: f64 = 300.0;
+/// Fewer letters than this say nothing reliable about the language
+const MIN_LETTERS: usize = 20;
+
+/// Frequent function words of the Latin-script languages told apart by their
+/// vocabulary; the first language with the most hits wins.
+const FUNCTION_WORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "is", "are", "of", "to", "that", "it", "you", "this", "with", "was", "for", "have"]),
+    ("de", &["der", "die", "das", "und", "ist", "nicht", "ich", "mit", "sie", "ein", "eine", "auch", "wir"]),
+    ("fr", &["le", "la", "les", "et", "est", "une", "des", "je", "pas", "que", "pour", "nous", "vous", "c'est"]),
+    ("es", &["el", "los", "las", "es", "y", "una", "que", "por", "para", "pero", "con", "muy", "está", "yo"]),
+];
+
+/// Typical conversational speaking rates in words per minute; CJK text is
+/// counted in words by `cjk::word_count`, so the same scale applies.
+const SPEAKING_RATES: &[(&str, f64)] = &[
+    ("en", 150.0),
+    ("ru", 120.0),
+    ("uk", 120.0),
+    ("de", 125.0),
+    ("fr", 160.0),
+    ("es", 170.0),
+    ("ja", 150.0),
+    ("zh", 150.0),
+    ("ko", 140.0),
+];
+
+/// Letters found in Ukrainian but not in Russian
+const UKRAINIAN_LETTERS: &str = "іїєґІЇЄҐ";
+
+#[derive(Default)]
+struct ScriptCounts {
+    latin: usize,
+    cyrillic: usize,
+    ukrainian: usize,
+    han: usize,
+    kana: usize,
+    hangul: usize,
+}
+
+impl ScriptCounts {
+    fn add(&mut self, text: &str) {
+        for ch in text.chars().filter(|ch| ch.is_alphabetic()) {
+            match ch as u32 {
+                0x3040..=0x30FF | 0x31F0..=0x31FF => self.kana += 1,
+                0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x3FFFD => self.han += 1,
+                0x1100..=0x11FF | 0x3130..=0x318F | 0xAC00..=0xD7A3 => self.hangul += 1,
+                0x0400..=0x04FF => {
+                    self.cyrillic += 1;
+                    if UKRAINIAN_LETTERS.contains(ch) {
+                        self.ukrainian += 1;
+                    }
+                }
+                _ if ch.is_ascii_alphabetic() || ('\u{00C0}'..='\u{024F}').contains(&ch) => self.latin += 1,
+                _ => {}
+            }
+        }
+    }
+
+    fn total(&self) -> usize {
+        self.latin + self.cyrillic + self.han + self.kana + self.hangul
+    }
+}
+
+/// Typical speaking rate (words per minute) of a language, e.g. "ru" or "pt-BR".
+pub fn speaking_rate(language: &str) -> Option<f64> {
+    let code = language.split(['-', '_']).next().unwrap_or_default();
+    SPEAKING_RATES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(code))
+        .map(|(_, rate)| *rate)
+}
+
+/// Guesses the language (ISO 639-1 code) of the first minutes of a transcript
+/// from its script and, for Latin text, its function words. `None` when the
+/// sample is too short or the language is not one of those recognised.
+pub fn detect_language(segments: &[TranscriptionSegment]) -> Option<String> {
+    let start = segments.first()?.start_time;
+    let mut sample: Vec<&str> = segments
+        .iter()
+        .filter(|segment| segment.start_time < start + SAMPLE_SECONDS)
+        .map(spoken_text)
+        .collect();
+    let mut counts = ScriptCounts::default();
+    sample.iter().for_each(|text| counts.add(text));
+    if counts.total() < MIN_LETTERS {
+        // A slow start: fall back to the whole transcript
+        sample = segments.iter().map(spoken_text).collect();
+        counts = ScriptCounts::default();
+        sample.iter().for_each(|text| counts.add(text));
+    }
+    let total = counts.total();
+    if total < MIN_LETTERS {
+        return None;
+    }
+
+    // Japanese mixes kana into Han text; a few percent of kana already tells it from Chinese
+    let language = if counts.kana * 20 >= total {
+        "ja"
+    } else if counts.hangul * 2 >= total {
+        "ko"
+    } else if counts.han * 2 >= total {
+        "zh"
+    } else if counts.cyrillic * 2 >= total {
+        if counts.ukrainian * 100 >= counts.cyrillic {
+            "uk"
+        } else {
+            "ru"
+        }
+    } else if counts.latin * 2 >= total {
+        return latin_language(&sample);
+    } else {
+        return None;
+    };
+    Some(language.to_string())
+}
+
+fn latin_language(sample: &[&str]) -> Option<String> {
+    let mut word_counts: HashMap<String, usize> = HashMap::new();
+    for word in sample.iter().flat_map(|text| tokenize(text)) {
+        *word_counts.entry(word).or_default() += 1;
+    }
+    let hits = |words: &[&str]| words.iter().filter_map(|word| word_counts.get(*word)).sum::<usize>();
+
+    let mut best: Option<(&str, usize)> = None;
+    for (language, words) in FUNCTION_WORDS {
+        let count = hits(words);
+        if count > 0 && best.is_none_or(|(_, best_count)| count > best_count) {
+            best = Some((language, count));
+        }
+    }
+    best.map(|(language, _)| language.to_string())
+}
+
+/// Warnings for files whose language differs from that of most files, as
+/// when a recording of another project slipped into the merge. Files whose
+/// language is unknown are not compared.
+pub fn mixed_language_warnings(files: &[(String, Option<String>)]) -> Vec<String> {
+    let mut counts: Vec<(&str, usize)> = Vec::new();
+    for language in files.iter().filter_map(|(_, language)| language.as_deref()) {
+        match counts.iter_mut().find(|(known, _)| *known == language) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((language, 1)),
+        }
+    }
+    // On a tie the language of the earlier file wins
+    let Some(majority) = counts.iter().rev().max_by_key(|(_, count)| *count).map(|(language, _)| *language) else {
+        return Vec::new();
+    };
+
+    files
+        .iter()
+        .filter_map(|(file, language)| {
+            let language = language.as_deref().filter(|language| *language != majority)?;
+            Some(format!("{} looks like {} while the other files look like {}", file, language, majority))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(texts: &[&str]) -> Vec<TranscriptionSegment> {
+        texts
+            .iter()
+            .enumerate()
+            .map(|(index, text)| TranscriptionSegment::test(index as f64 * 5.0, None, text))
+            .collect()
+    }
+
+    #[test]
+    fn test_detect_language() {
+        let detect = |texts: &[&str]| detect_language(&segments(texts));
+
+        assert_eq!(detect(&["Анна: Добрый день, коллеги.", "Сегодня обсуждаем бюджет на следующий год."]).as_deref(), Some("ru"));
+        assert_eq!(detect(&["Добрий день, колеги.", "Сьогодні ми обговорюємо бюджет і плани."]).as_deref(), Some("uk"));
+        assert_eq!(detect(&["Good morning, everyone.", "Today we discuss the budget and the plans for this year."]).as_deref(), Some("en"));
+        assert_eq!(detect(&["Guten Morgen, wir sprechen heute über das Budget und die Pläne."]).as_deref(), Some("de"));
+        assert_eq!(detect(&["皆さん、こんにちは。", "今日は来年の予算について話します。"]).as_deref(), Some("ja"));
+        assert_eq!(detect(&["大家好。", "今天我们讨论明年的预算和项目计划安排。"]).as_deref(), Some("zh"));
+        assert_eq!(detect(&["OK", "Да"]), None);
+    }
+
+    #[test]
+    fn test_mixed_language_warnings() {
+        let file = |name: &str, language: Option<&str>| (name.to_string(), language.map(str::to_string));
+        let files = vec![file("part1.srt", Some("ru")), file("part2.srt", Some("ru")), file("other.srt", Some("en")), file("music.srt", None)];
+        assert_eq!(mixed_language_warnings(&files), vec!["other.srt looks like en while the other files look like ru"]);
+
+        let tie = vec![file("a.srt", Some("de")), file("b.srt", Some("en"))];
+        assert_eq!(mixed_language_warnings(&tie), vec!["b.srt looks like en while the other files look like de"]);
+        assert!(mixed_language_warnings(&files[..2]).is_empty());
+    }
+}
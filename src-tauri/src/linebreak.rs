@@ -0,0 +1,195 @@
+use std::collections::HashSet;
+
+use crate::cjk::{break_units, display_width, is_unspaced};
+
+/// Words a subtitle line should not end with: prepositions, articles and
+/// conjunctions belong with the word that follows them.
+const RUSSIAN_NO_BREAK_AFTER: &[&str] = &[
+    "в", "во", "на", "с", "со", "к", "ко", "о", "об", "обо", "у", "по", "за", "из", "изо", "от", "ото", "до",
+    "для", "без", "при", "про", "над", "под", "перед", "через", "между", "около", "и", "а", "но", "или",
+    "что", "чтобы", "как", "не", "ни", "же", "ли",
+];
+
+const ENGLISH_NO_BREAK_AFTER: &[&str] = &[
+    "a", "an", "the", "of", "to", "in", "on", "at", "for", "with", "from", "by", "about", "into", "over",
+    "under", "as", "and", "or", "but", "that", "if", "my", "your", "his", "her", "its", "our", "their",
+    "this", "these", "those", "not",
+];
+
+/// Every extra line costs more than any arrangement of a smaller number of lines
+const LINE_COST: f64 = 100_000.0;
+/// Ending a line with a word from the language's no-break list
+const BAD_BREAK_COST: f64 = 5_000.0;
+/// Breaking inside a phrase rather than after punctuation
+const MID_PHRASE_COST: f64 = 50.0;
+/// Splitting a hyphenated compound after its hyphen
+const HYPHEN_BREAK_COST: f64 = 200.0;
+
+/// Line-break preferences of one language.
+#[derive(Debug, Clone, Default)]
+pub struct LineBreakRules {
+    no_break_after: HashSet<String>,
+}
+
+impl LineBreakRules {
+    /// Built-in rules for "ru" and "en" (region suffixes such as "en-GB" are
+    /// ignored); other languages only get balanced lines.
+    pub fn for_language(language: &str) -> Self {
+        let base = language.split(['-', '_']).next().unwrap_or_default().to_lowercase();
+        let words: &[&str] = match base.as_str() {
+            "ru" => RUSSIAN_NO_BREAK_AFTER,
+            "en" => ENGLISH_NO_BREAK_AFTER,
+            _ => &[],
+        };
+        Self { no_break_after: words.iter().map(|word| word.to_string()).collect() }
+    }
+
+    fn allows_break_after(&self, word: &str) -> bool {
+        let bare = word.trim_matches(|ch: char| !ch.is_alphanumeric()).to_lowercase();
+        !self.no_break_after.contains(&bare)
+    }
+}
+
+/// A word, the part of a hyphenated compound up to and including its hyphen,
+/// or a Han/kana character with the punctuation attached to it.
+struct Piece<'a> {
+    text: &'a str,
+    /// Follows the previous piece without a space ("северо-" + "западный", "今" + "日")
+    joined: bool,
+}
+
+/// Wraps subtitle text into as few lines as the greedy wrap would, but picks
+/// the break points so that lines are of similar length, phrases stay
+/// together after punctuation and no line ends with a preposition, article
+/// or conjunction. Hyphenated compounds may be split after the hyphen and
+/// Chinese/Japanese text between characters; other over-long words are left
+/// intact. Lengths are display widths, so CJK characters count twice.
+pub fn wrap_balanced(text: &str, max_length: usize, rules: &LineBreakRules) -> String {
+    let max_length = max_length.max(1);
+    text.lines()
+        .map(|line| wrap_line(line, max_length, rules))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn wrap_line(line: &str, max_length: usize, rules: &LineBreakRules) -> String {
+    let pieces = split_pieces(line);
+    if pieces.is_empty() {
+        return String::new();
+    }
+
+    // best[i]: cheapest cost of laying out pieces[..i]; from[i]: where its last line starts
+    let count = pieces.len();
+    let mut best = vec![f64::INFINITY; count + 1];
+    let mut from = vec![0; count + 1];
+    best[0] = 0.0;
+
+    for end in 1..=count {
+        let mut length = 0;
+        for start in (0..end).rev() {
+            length += display_width(pieces[start].text);
+            if start + 1 < end && !pieces[start + 1].joined {
+                length += 1;
+            }
+            // A single over-long piece still has to go somewhere
+            if length > max_length && start + 1 < end {
+                break;
+            }
+            let slack = max_length.saturating_sub(length) as f64;
+            let cost = best[start] + LINE_COST + slack * slack + break_cost(&pieces, end, rules);
+            if cost < best[end] {
+                best[end] = cost;
+                from[end] = start;
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut end = count;
+    while end > 0 {
+        let start = from[end];
+        let mut line = String::new();
+        for (index, piece) in pieces[start..end].iter().enumerate() {
+            if index > 0 && !piece.joined {
+                line.push(' ');
+            }
+            line.push_str(piece.text);
+        }
+        lines.push(line);
+        end = start;
+    }
+    lines.reverse();
+    lines.join("\n")
+}
+
+/// Cost of ending a line after `pieces[end - 1]`; nothing for the last line.
+fn break_cost(pieces: &[Piece], end: usize, rules: &LineBreakRules) -> f64 {
+    if end == pieces.len() {
+        return 0.0;
+    }
+    let last = pieces[end - 1].text;
+    if last.ends_with([',', '.', '!', '?', ';', ':', '…', '—', '–', '、', '。', '，', '！', '？', '；', '：']) {
+        0.0
+    } else if pieces[end].joined && !last.chars().last().is_some_and(is_unspaced) && !pieces[end].text.starts_with(is_unspaced) {
+        HYPHEN_BREAK_COST
+    } else if !pieces[end].joined && !rules.allows_break_after(last) {
+        BAD_BREAK_COST
+    } else {
+        MID_PHRASE_COST
+    }
+}
+
+fn split_pieces(line: &str) -> Vec<Piece<'_>> {
+    let mut pieces = Vec::new();
+    for word in line.split_whitespace() {
+        let mut joined = false;
+        for unit in break_units(word) {
+            let mut rest = unit;
+            // Only hyphens between letters, so "—" and "-5" stay whole
+            while let Some(position) = rest.char_indices().skip(1).find_map(|(index, ch)| {
+                let next = rest[index + ch.len_utf8()..].chars().next();
+                (ch == '-' && next.is_some_and(char::is_alphabetic)).then_some(index + 1)
+            }) {
+                pieces.push(Piece { text: &rest[..position], joined });
+                joined = true;
+                rest = &rest[position..];
+            }
+            pieces.push(Piece { text: rest, joined });
+            joined = true;
+        }
+    }
+    pieces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_balanced_avoids_bad_breaks() {
+        let russian = LineBreakRules::for_language("ru-RU");
+
+        // Greedy wrapping would leave "в" at the end of the first line
+        assert_eq!(
+            wrap_balanced("Мы поехали в командировку в Москву", 20, &russian),
+            "Мы поехали\nв командировку\nв Москву"
+        );
+        assert_eq!(
+            wrap_balanced("Привет, это очень длинная строка", 20, &russian),
+            "Привет, это очень\nдлинная строка"
+        );
+        assert_eq!(
+            wrap_balanced("We have seen the results of the test", 24, &LineBreakRules::for_language("en")),
+            "We have seen\nthe results of the test"
+        );
+        assert_eq!(
+            wrap_balanced("северо-западный ветер", 10, &LineBreakRules::default()),
+            "северо-\nзападный\nветер"
+        );
+        // 16 columns are 8 characters; the break goes after the comma
+        assert_eq!(
+            wrap_balanced("我们今天开会，讨论预算。", 16, &LineBreakRules::for_language("zh")),
+            "我们今天开会，\n讨论预算。"
+        );
+    }
+}
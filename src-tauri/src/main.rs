@@ -5,10 +5,53 @@
 
 mod commands;
 mod audio;
+mod analysis;
 mod merger;
 mod ffmpeg;
+mod replace;
+mod export;
+mod settings;
+mod stats;
+mod diagnostics;
+mod project;
+mod calendar;
+mod minutes;
+mod flashcards;
+mod quotecard;
+mod stems;
+mod nle;
+mod profanity;
+mod casing;
+mod linebreak;
+mod cjk;
+mod translit;
+mod hallucination;
+mod corrections;
+mod language;
+mod qc;
+mod handoff;
+mod assignments;
+mod chapters;
+mod workreport;
+mod bilingual;
+mod versions;
+mod translation;
+mod batch;
+mod supervisor;
+mod power;
+mod schedule;
+mod storage;
+mod editing;
+mod docx;
+mod fixtures;
+mod html;
+mod captions;
+mod upload;
+mod cloud;
+mod retranscription;
 
 use commands::*;
+use tauri::Manager;
 
 fn main() {
     tauri::Builder::default()
@@ -16,9 +59,101 @@ fn main() {
             get_file_info,
             start_audio_processing,
             merge_transcriptions,
+            merge_bilingual,
+            set_translation_api_key,
+            save_merge_version,
+            list_merge_versions,
+            diff_merge_versions,
+            restore_merge_version,
+            mark_version_delivered,
+            batch_export_projects,
+            list_transcript_speakers,
+            get_merged_segments,
+            validate_subtitles,
+            get_template_tokens,
+            update_segment,
+            split_segment,
+            merge_segments,
+            delete_segment,
+            replace_in_transcription,
+            export_segments_for_drag,
             export_merged_transcription,
+            export_minutes,
+            export_flashcards,
+            export_handoff_sheet,
+            export_youtube_chapters,
+            update_chunk_assignment,
+            get_assignment_dashboard,
+            export_viewer_bundle,
+            get_transcript_stats,
+            get_merge_stats,
+            get_keyword_timeline,
+            check_transcript_coverage,
+            list_retranscription_candidates,
+            retranscribe_flagged_chunks,
+            set_transcription_api_key,
+            extract_segment_clips,
+            render_quote_cards,
+            export_speaker_stems,
+            export_transcript_markers,
+            list_caption_styles,
+            save_caption_style,
+            delete_caption_style,
+            get_glossary,
+            save_glossary,
+            get_power_status,
+            set_battery_mode,
+            enqueue_audio_processing,
+            list_processing_queue,
+            remove_queued_job,
+            get_processing_window,
+            set_processing_window,
+            get_data_dir,
+            relocate_data_dir,
+            list_profanity_lists,
+            save_profanity_list,
+            delete_profanity_list,
+            burn_captions,
+            get_export_options,
+            list_presets,
+            load_preset,
+            save_preset,
+            delete_preset,
+            list_destinations,
+            save_destination,
+            delete_destination,
+            upload_export,
+            connect_cloud,
+            disconnect_cloud,
+            get_cloud_status,
+            run_diagnostics,
+            validate_parser_fixture,
+            get_storage_usage,
+            clean_storage,
+            create_project,
+            open_project,
+            get_project_history,
+            set_project_metadata,
+            set_project_deadline,
+            export_work_report,
+            get_upcoming_deadlines,
+            set_transcription_prompt,
+            get_chunk_prompt,
+            import_project_metadata_ics,
+            assign_project_episode,
+            get_naming_scheme,
+            save_naming_scheme,
+            convert_transcription,
+            reformat_timestamps,
+            open_file,
             open_folder
         ])
+        .setup(|app| {
+            if let Some(window) = app.get_window("main") {
+                tauri::async_runtime::spawn(run_job_scheduler(window));
+            }
+            Ok(())
+        })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
\ No newline at end of file
@@ -1,18 +1,143 @@
 use anyhow::{anyhow, Result};
 use chrono::{DateTime, Utc};
-use encoding_rs::WINDOWS_1251;
-use regex::Regex;
+use encoding_rs::{Encoding, KOI8_R, UTF_16BE, UTF_16LE, WINDOWS_1251, WINDOWS_1252};
+use regex::{Captures, Regex};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use tokio::fs;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::analysis::ExcludedRange;
+use crate::audio::ChunkManifest;
+use crate::captions::CaptionStyle;
+use crate::cjk;
+use crate::corrections::{CorrectionDictionary, TermCorrection};
+use crate::docx::read_docx_paragraphs;
+use crate::hallucination::{detect_hallucinations, HallucinationFilter, HallucinationKind};
+use crate::language::{detect_language, speaking_rate};
+use crate::nle::{render_fcpxml_captions, Marker};
+use crate::profanity::{ProfanityFilter, ProfanityList};
+use crate::stats::spoken_text;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TranscriptionSegment {
     pub start_time: f64,
     pub end_time: Option<f64>,
     pub text: String,
     pub file_index: usize,
     pub original_filename: String,
+    /// The end time is a word-count estimate rather than taken from the source
+    #[serde(default)]
+    pub end_time_estimated: bool,
+    /// Speaker name, from the source (JSON) or a leading "Name:" in the text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub speaker: Option<String>,
+    /// A pause or a change of voice suggests someone else speaks from here on
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub speaker_change_hint: bool,
+    /// Recognition confidence from 0.0 to 1.0, when the source provides it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+    /// Per-word timing and confidence, in the order the words appear in the
+    /// text, for sources that provide them (Whisper JSON, WebVTT with inline
+    /// timestamps)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub words: Vec<WordTiming>,
+    /// The text in its source script when an export transliterated `text`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_text: Option<String>,
+    /// Why the merge suspects the recogniser invented this segment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hallucination: Option<HallucinationKind>,
+    /// Markdown heading directly above this segment in its source file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+}
+
+/// Shorthand for building segments in tests, e.g.
+/// `TranscriptionSegment::test(0.0, 2.5, "Привет").speaker("Анна")`.
+#[cfg(test)]
+impl TranscriptionSegment {
+    /// `end_time` takes seconds or `None` for an open end.
+    pub fn test(start_time: f64, end_time: impl Into<Option<f64>>, text: &str) -> Self {
+        Self {
+            start_time,
+            end_time: end_time.into(),
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn speaker(mut self, speaker: &str) -> Self {
+        self.speaker = Some(speaker.to_string());
+        self
+    }
+
+    pub fn file(mut self, file: &str) -> Self {
+        self.original_filename = file.to_string();
+        self
+    }
+
+    pub fn heading(mut self, heading: &str) -> Self {
+        self.heading = Some(heading.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WordTiming {
+    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f64>,
+}
+
+/// A non-fatal problem found while parsing an input file (malformed block,
+/// unreadable timestamp, ...). The affected part is skipped and reported here
+/// instead of silently disappearing from the merge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParseWarning {
+    pub file: String,
+    pub line: usize,
+    pub reason: String,
+}
+
+impl ParseWarning {
+    fn new(file: &str, line: usize, reason: impl Into<String>) -> Self {
+        Self {
+            file: file.to_string(),
+            line,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.file, self.line, self.reason)
+    }
+}
+
+/// How low-confidence words are shown in ASS and VTT output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LowConfidenceStyle {
+    #[default]
+    Italic,
+    Color,
+}
+
+/// How malformed input is handled. `Strict` aborts on the first problem
+/// (for automated pipelines), `Lenient` skips it and reports a warning.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParseMode {
+    Strict,
+    #[default]
+    Lenient,
 }
 
 #[derive(Debug, Clone)]
@@ -23,21 +148,184 @@ pub struct TranscriptionFile {
     pub sequence_number: Option<usize>,
     pub format: FileFormat,
     pub segments: Vec<TranscriptionSegment>,
+    pub warnings: Vec<ParseWarning>,
+    /// Language detected from the file's own text, see [`detect_language`]
+    pub language: Option<String>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
 pub enum FileFormat {
+    #[default]
     Txt,
     Srt,
     Markdown,
+    Vtt,
+    Json,
+    /// Advanced SubStation Alpha; output only
+    Ass,
+    /// TTML (DFXP) for broadcast delivery; output only
+    Ttml,
+    /// YouTube's legacy SubViewer captions (`0:00:01.000,0:00:04.000`)
+    Sbv,
+    /// Word transcript as downloaded from Microsoft Teams; input only
+    Docx,
+    /// Final Cut Pro captions; output only
+    Fcpxml,
+    /// LRC lyrics (`[mm:ss.xx]` lines); output only
+    Lrc,
+    /// Podcasting 2.0 transcript JSON for `<podcast:transcript>`; read back
+    /// by the JSON parser
+    PodcastJson,
+    /// SubViewer 2.0 `.sub` subtitles; input only
+    SubViewer,
+    /// MicroDVD `.sub` subtitles timed in frames; input only
+    MicroDvd,
 }
 
-#[derive(Debug, Clone)]
+impl FileFormat {
+    /// Parses a user-facing format name as sent by the frontend ("srt", "md", ...).
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "txt" | "text" => Some(Self::Txt),
+            "srt" => Some(Self::Srt),
+            "md" | "markdown" => Some(Self::Markdown),
+            "vtt" | "webvtt" => Some(Self::Vtt),
+            "json" => Some(Self::Json),
+            "ass" | "ssa" => Some(Self::Ass),
+            "ttml" | "dfxp" => Some(Self::Ttml),
+            "sbv" => Some(Self::Sbv),
+            "fcpxml" => Some(Self::Fcpxml),
+            "lrc" => Some(Self::Lrc),
+            "podcast_json" => Some(Self::PodcastJson),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Txt => "txt",
+            Self::Srt => "srt",
+            Self::Markdown => "md",
+            Self::Vtt => "vtt",
+            Self::Json => "json",
+            Self::Ass => "ass",
+            Self::Ttml => "ttml",
+            Self::Sbv => "sbv",
+            Self::Docx => "docx",
+            Self::Fcpxml => "fcpxml",
+            Self::Lrc => "lrc",
+            Self::PodcastJson => "json",
+            Self::SubViewer | Self::MicroDvd => "sub",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MergeOptions {
+    /// Chosen per call from the format name, never deserialized
+    #[serde(skip)]
     pub output_format: FileFormat,
     pub time_offset_seconds: f64,
     pub remove_timestamps: bool,
     pub add_file_markers: bool,
+    pub parse_mode: ParseMode,
+    /// Cue length used when a segment has no end time
+    pub default_duration_seconds: f64,
+    /// Speaking rate used to estimate durations of untimed TXT/Markdown lines;
+    /// the typical rate of `language` when not set
+    pub words_per_minute: Option<f64>,
+    /// End open-ended segments where the next one starts instead of after the default duration
+    pub extend_to_next_segment: bool,
+    /// Upper bound for extending an open-ended segment up to the next one,
+    /// and for inferred end times
+    pub max_extend_seconds: f64,
+    /// Post-pass replacing missing/estimated end times with
+    /// min(next segment start, start + max_extend_seconds)
+    pub infer_end_times: bool,
+    /// Frame rate written to TTML (`ttp:frameRate`); times become `HH:MM:SS:FF`.
+    /// Fractional NTSC rates (23.976, 29.97) get the 1000/1001 multiplier.
+    /// FCPXML captions are snapped to this frame grid (25 fps when unset).
+    pub frame_rate: Option<f64>,
+    /// Frame rate MicroDVD frame numbers are converted to time with; without
+    /// it the rate the file states is used, else 23.976
+    pub microdvd_frame_rate: Option<f64>,
+    /// Ranges of the source recording left out of chunking (ads, breaks).
+    /// Merged times are shifted past them so they match the original recording.
+    pub excluded_ranges: Vec<ExcludedRange>,
+    /// Insert a marker segment where each excluded range was
+    pub ad_break_markers: bool,
+    /// Mark segments of unlabelled transcripts that likely start a new speaker
+    pub speaker_change_markers: bool,
+    /// Voice changes found in the source audio during processing, in seconds
+    pub speaker_changes: Vec<f64>,
+    /// A gap between segments at least this long also counts as a possible change
+    pub speaker_change_pause_seconds: f64,
+    /// Style words (or whole segments without word data) below this confidence
+    /// in ASS and VTT output
+    pub low_confidence_threshold: Option<f64>,
+    pub low_confidence_style: LowConfidenceStyle,
+    /// TXT/Markdown timestamp style, see `format_timecode`; MM:SS when not set
+    pub timecode_format: Option<String>,
+    /// Pattern for the `custom` timecode style, e.g. `HH:MM:SS,MS`
+    pub custom_timecode_format: Option<String>,
+    /// Languages whose profanity lists are masked ("f***") in the output;
+    /// `auto` stands for the transcript language
+    pub profanity_languages: Vec<String>,
+    /// The lists for `profanity_languages`, loaded from the settings per call
+    #[serde(skip)]
+    pub profanity_lists: Vec<ProfanityList>,
+    /// Wrong → right term pairs applied to the merged text, loaded from the
+    /// dictionary file given to the merge
+    #[serde(skip)]
+    pub term_corrections: Vec<TermCorrection>,
+    /// Per-file speaker renames, keyed by file name: `{"part3.txt": {"Speaker 1": "Анна"}}`.
+    /// Chunk transcribers number speakers per chunk, so the same label can mean
+    /// different people in different files.
+    pub speaker_mapping: HashMap<String, HashMap<String, String>>,
+    /// Chunk start times from audio processing. Transcripts of listed chunks
+    /// start exactly where their chunk does; the times are those of the source
+    /// recording, so excluded ranges need no shifting.
+    pub chunk_manifest: Option<ChunkManifest>,
+    /// Per-file nudges in seconds (negative moves earlier), keyed by file name:
+    /// `{"part2.srt": -3.5}`. Added on top of the computed offset of that file
+    /// only; the files after it keep their place.
+    pub file_offsets: HashMap<String, f64>,
+    /// Merge files in the order they were passed instead of sorting them by
+    /// the number guessed from their names
+    pub preserve_input_order: bool,
+    /// Words that introduce a file's sequence number ("interview_part02"),
+    /// tried in this order before falling back to the last number in the name
+    pub sequence_keywords: Vec<String>,
+    /// Regex matched against file names whose first capture group is the
+    /// sequence number (`episode-(\d+)-take`); tried before the keywords
+    pub sequence_pattern: Option<String>,
+    /// Flag or drop segments that look like recogniser hallucinations (stock
+    /// phrases, loops, text over silence from the chunk manifest)
+    pub hallucination_filter: HallucinationFilter,
+    /// Remove `<i>`, `<font>`, `{\an8}` and similar tags from TXT, Markdown,
+    /// JSON and TTML output. SRT and ASS keep them (ASS as override codes),
+    /// WebVTT keeps the `<i>`, `<b>` and `<u>` it supports.
+    pub strip_formatting_tags: bool,
+    /// Transcript language (ISO 639-1, "ru"); detected from the merged text when
+    /// not given. Written to TTML `xml:lang` and the default line-break
+    /// language of exports; picks the speaking rate and `auto` profanity list.
+    pub language: Option<String>,
+    /// TXT line layout, e.g. `{start_hms} | {speaker} | {text}`; replaces the
+    /// `[timestamp] [file] text` lines. See `TEMPLATE_TOKENS`.
+    pub txt_template: Option<String>,
+    /// TXT/Markdown: join consecutive segments into one paragraph while the
+    /// pause between them is shorter than this many seconds. A new file or
+    /// speaker always starts a new paragraph.
+    pub paragraph_pause_seconds: Option<f64>,
+    /// Re-cut the merged segments so each holds one complete sentence, with
+    /// times interpolated by character position. Segments of different files
+    /// or speakers are never joined.
+    pub sentence_segmentation: bool,
+    /// Leave wall-clock content out of the output and order files sharing a
+    /// sequence number by name, so the same inputs and options always give
+    /// byte-identical exports
+    pub reproducible: bool,
 }
 
 impl Default for MergeOptions {
@@ -47,10 +335,69 @@ impl Default for MergeOptions {
             time_offset_seconds: 0.0,
             remove_timestamps: false,
             add_file_markers: true,
+            parse_mode: ParseMode::Lenient,
+            default_duration_seconds: 5.0,
+            words_per_minute: None,
+            extend_to_next_segment: false,
+            max_extend_seconds: 10.0,
+            infer_end_times: false,
+            frame_rate: None,
+            microdvd_frame_rate: None,
+            excluded_ranges: Vec::new(),
+            ad_break_markers: false,
+            speaker_change_markers: false,
+            speaker_changes: Vec::new(),
+            speaker_change_pause_seconds: 2.0,
+            low_confidence_threshold: None,
+            low_confidence_style: LowConfidenceStyle::Italic,
+            timecode_format: None,
+            custom_timecode_format: None,
+            profanity_languages: Vec::new(),
+            profanity_lists: Vec::new(),
+            term_corrections: Vec::new(),
+            speaker_mapping: HashMap::new(),
+            chunk_manifest: None,
+            file_offsets: HashMap::new(),
+            preserve_input_order: false,
+            sequence_keywords: vec!["part".to_string(), "chunk".to_string(), "segment".to_string()],
+            sequence_pattern: None,
+            strip_formatting_tags: false,
+            language: None,
+            txt_template: None,
+            paragraph_pause_seconds: None,
+            sentence_segmentation: false,
+            hallucination_filter: HallucinationFilter::Off,
+            reproducible: false,
         }
     }
 }
 
+impl MergeOptions {
+    /// Words per minute for duration estimates: `words_per_minute`, else the
+    /// typical rate of `language`.
+    pub fn speaking_rate(&self) -> f64 {
+        self.words_per_minute
+            .or_else(|| self.language.as_deref().and_then(speaking_rate))
+            .unwrap_or(DEFAULT_WORDS_PER_MINUTE)
+            .max(1.0)
+    }
+
+    /// `profanity_languages` with `auto` replaced by the transcript language,
+    /// or dropped while the language is unknown.
+    pub fn resolved_profanity_languages(&self) -> Vec<String> {
+        self.profanity_languages
+            .iter()
+            .filter_map(|language| {
+                if language.eq_ignore_ascii_case("auto") {
+                    self.language.clone()
+                } else {
+                    Some(language.clone())
+                }
+            })
+            .collect()
+    }
+}
+
 pub struct TranscriptionMerger {
     files: Vec<TranscriptionFile>,
     merge_options: MergeOptions,
@@ -65,6 +412,9 @@ impl TranscriptionMerger {
     }
 
     pub async fn add_files(&mut self, file_paths: Vec<String>) -> Result<()> {
+        // A broken custom pattern is reported instead of silently ordering by fallbacks
+        self.sequence_regex()?;
+
         for path_str in file_paths {
             let path = PathBuf::from(&path_str);
             let file = self.parse_transcription_file(&path).await?;
@@ -72,12 +422,22 @@ impl TranscriptionMerger {
         }
 
         // Sort files by sequence number
-        self.files.sort_by_key(|f| f.sequence_number.unwrap_or(999999));
+        if self.merge_options.preserve_input_order {
+            // Keep the given order
+        } else if self.merge_options.reproducible {
+            // Ties no longer depend on the order the files were picked in
+            self.files.sort_by(|a, b| {
+                (a.sequence_number.unwrap_or(999999), &a.filename, &a.path)
+                    .cmp(&(b.sequence_number.unwrap_or(999999), &b.filename, &b.path))
+            });
+        } else {
+            self.files.sort_by_key(|f| f.sequence_number.unwrap_or(999999));
+        }
         
         Ok(())
     }
 
-    async fn parse_transcription_file(&self, path: &Path) -> Result<TranscriptionFile> {
+    pub async fn parse_transcription_file(&self, path: &Path) -> Result<TranscriptionFile> {
         let raw_bytes = fs::read(path).await?;
         let content = read_text_with_encoding(&raw_bytes);
         let filename = path.file_name()
@@ -88,18 +448,40 @@ impl TranscriptionMerger {
         let format = self.detect_format(path, &content)?;
         let sequence_number = self.extract_sequence_number(&filename);
 
+        let mut warnings = Vec::new();
         let segments = match format {
-            FileFormat::Srt => self.parse_srt(&content, &filename)?,
-            FileFormat::Txt => self.parse_txt(&content, &filename)?,
+            FileFormat::Srt => self.parse_srt(&content, &filename, &mut warnings)?,
+            FileFormat::Txt => self.parse_txt(&content, &filename, &mut warnings)?,
             FileFormat::Markdown => self.parse_markdown(&content, &filename)?,
+            FileFormat::Vtt => self.parse_vtt(&content, &filename, &mut warnings)?,
+            FileFormat::Json | FileFormat::PodcastJson => self.parse_json(&content, &filename, &mut warnings)?,
+            FileFormat::Sbv => self.parse_sbv(&content, &filename, &mut warnings)?,
+            FileFormat::SubViewer => self.parse_subviewer(&content, &filename, &mut warnings)?,
+            FileFormat::MicroDvd => self.parse_microdvd(&content, &filename, &mut warnings)?,
+            FileFormat::Docx => {
+                let paragraphs = read_docx_paragraphs(&raw_bytes)
+                    .map_err(|e| anyhow!("Failed to read Word transcript {}: {}", filename, e))?;
+                self.parse_teams_transcript(&paragraphs, &filename, &mut warnings)
+            }
+            FileFormat::Ass | FileFormat::Ttml | FileFormat::Fcpxml | FileFormat::Lrc => {
+                return Err(anyhow!("{} files are not supported as input", format.extension().to_uppercase()))
+            }
         };
 
+        if self.merge_options.parse_mode == ParseMode::Strict {
+            if let Some(warning) = warnings.first() {
+                return Err(anyhow!("Malformed input (strict mode): {}", warning));
+            }
+        }
+
         Ok(TranscriptionFile {
             path: path.to_path_buf(),
             filename,
             sequence_number,
             format,
+            language: detect_language(&segments),
             segments,
+            warnings,
         })
     }
 
@@ -108,11 +490,37 @@ impl TranscriptionMerger {
             match ext.to_string_lossy().to_lowercase().as_str() {
                 "srt" => return Ok(FileFormat::Srt),
                 "md" => return Ok(FileFormat::Markdown),
+                "vtt" => return Ok(FileFormat::Vtt),
+                "json" => return Ok(FileFormat::Json),
+                "sbv" => return Ok(FileFormat::Sbv),
+                "docx" => return Ok(FileFormat::Docx),
+                "sub" => {
+                    if self.looks_like_microdvd(content) {
+                        return Ok(FileFormat::MicroDvd);
+                    }
+                    if self.looks_like_subviewer(content) {
+                        return Ok(FileFormat::SubViewer);
+                    }
+                    // VobSub keeps images in .sub next to an .idx
+                    return Err(anyhow!(
+                        "{} is neither SubViewer nor MicroDVD subtitles",
+                        path.file_name().unwrap_or_default().to_string_lossy()
+                    ));
+                }
                 "txt" => {
-                    // Check if it's actually SRT format
+                    // Check if it's actually SRT, SBV or .sub subtitles renamed
                     if self.looks_like_srt(content) {
                         return Ok(FileFormat::Srt);
                     }
+                    if self.looks_like_sbv(content) {
+                        return Ok(FileFormat::Sbv);
+                    }
+                    if self.looks_like_microdvd(content) {
+                        return Ok(FileFormat::MicroDvd);
+                    }
+                    if self.looks_like_subviewer(content) {
+                        return Ok(FileFormat::SubViewer);
+                    }
                     return Ok(FileFormat::Txt);
                 }
                 _ => {}
@@ -120,8 +528,16 @@ impl TranscriptionMerger {
         }
 
         // Fallback to content-based detection
-        if self.looks_like_srt(content) {
+        if content.trim_start().starts_with("WEBVTT") {
+            Ok(FileFormat::Vtt)
+        } else if self.looks_like_srt(content) {
             Ok(FileFormat::Srt)
+        } else if self.looks_like_sbv(content) {
+            Ok(FileFormat::Sbv)
+        } else if self.looks_like_microdvd(content) {
+            Ok(FileFormat::MicroDvd)
+        } else if self.looks_like_subviewer(content) {
+            Ok(FileFormat::SubViewer)
         } else if content.contains("# ") || content.contains("## ") {
             Ok(FileFormat::Markdown)
         } else {
@@ -135,60 +551,125 @@ impl TranscriptionMerger {
         srt_pattern.is_match(content)
     }
 
+    fn looks_like_sbv(&self, content: &str) -> bool {
+        // A cue starts with a line like 0:00:01.000,0:00:04.000
+        let sbv_pattern = Regex::new(r"(?m)^\d+:\d{2}:\d{2}\.\d{3},\d+:\d{2}:\d{2}\.\d{3}\r?$").unwrap();
+        sbv_pattern.is_match(content)
+    }
+
+    fn looks_like_microdvd(&self, content: &str) -> bool {
+        // Every caption line starts with {start frame}{end frame}
+        let microdvd_pattern = Regex::new(r"^\{\d+\}\{\d*\}").unwrap();
+        content.lines().find(|line| !line.trim().is_empty()).is_some_and(|line| microdvd_pattern.is_match(line.trim_start_matches('\u{feff}')))
+    }
+
+    fn looks_like_subviewer(&self, content: &str) -> bool {
+        // Timing lines carry hundredths: 00:00:41.00,00:00:44.40
+        let subviewer_pattern = Regex::new(r"(?m)^\d{2}:\d{2}:\d{2}\.\d{2},\d{2}:\d{2}:\d{2}\.\d{2}\r?$").unwrap();
+        subviewer_pattern.is_match(content)
+    }
+
+    /// The number after the first matching sequence keyword ("part02"), or else
+    /// the last number in the name, so dates in front ("2024-03-01_…") are skipped.
     fn extract_sequence_number(&self, filename: &str) -> Option<usize> {
-        let patterns = [
-            r"(\d+)", // Any number
-            r"chunk[_-]?(\d+)",
-            r"part[_-]?(\d+)",
-            r"segment[_-]?(\d+)",
-        ];
+        if let Ok(Some(regex)) = self.sequence_regex() {
+            let number = regex
+                .captures(filename)
+                .and_then(|captures| captures.iter().skip(1).flatten().next())
+                .and_then(|group| group.as_str().parse().ok());
+            if number.is_some() {
+                return number;
+            }
+        }
 
-        for pattern in patterns {
-            if let Ok(regex) = Regex::new(pattern) {
-                if let Some(captures) = regex.captures(filename) {
-                    if let Some(num_str) = captures.get(1) {
-                        if let Ok(num) = num_str.as_str().parse::<usize>() {
-                            return Some(num);
-                        }
-                    }
+        let stem = Path::new(filename).file_stem().unwrap_or_default().to_string_lossy();
+
+        for keyword in self.merge_options.sequence_keywords.iter().filter(|keyword| !keyword.trim().is_empty()) {
+            let pattern = format!(r"(?i){}[ _-]?(\d+)", regex::escape(keyword.trim()));
+            if let Some(captures) = Regex::new(&pattern).ok().and_then(|regex| regex.captures(&stem)) {
+                if let Ok(num) = captures[1].parse::<usize>() {
+                    return Some(num);
                 }
             }
         }
 
-        None
+        let last_number = Regex::new(r"\d+").unwrap().find_iter(&stem).last()?;
+        last_number.as_str().parse().ok()
+    }
+
+    fn sequence_regex(&self) -> Result<Option<Regex>> {
+        let Some(pattern) = self.merge_options.sequence_pattern.as_deref().filter(|pattern| !pattern.trim().is_empty()) else {
+            return Ok(None);
+        };
+        let regex = Regex::new(pattern).map_err(|e| anyhow!("Invalid sequence pattern {}: {}", pattern, e))?;
+        if regex.captures_len() < 2 {
+            return Err(anyhow!("Sequence pattern {} needs a capture group for the number", pattern));
+        }
+        Ok(Some(regex))
     }
 
-    fn parse_srt(&self, content: &str, filename: &str) -> Result<Vec<TranscriptionSegment>> {
+    fn parse_srt(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
         let mut segments = Vec::new();
         // Normalize CRLF to LF before splitting to handle Windows-encoded SRT files
         let normalized = content.replace("\r\n", "\n");
-        let blocks: Vec<&str> = normalized.split("\n\n").collect();
 
-        for (index, block) in blocks.iter().enumerate() {
-            let lines: Vec<&str> = block.trim().lines().collect();
+        for (index, (line_number, lines)) in text_blocks(&normalized).into_iter().enumerate() {
             if lines.len() < 3 {
+                warnings.push(ParseWarning::new(
+                    filename,
+                    line_number,
+                    "Incomplete subtitle block (expected index, timestamp and text lines)",
+                ));
                 continue;
             }
 
             // Parse timestamp line (format: 00:00:00,000 --> 00:00:01,000)
             let timestamp_line = lines[1];
-            if let Some((start_str, end_str)) = timestamp_line.split_once(" --> ") {
-                let start_time = self.parse_srt_timestamp(start_str)?;
-                let end_time = Some(self.parse_srt_timestamp(end_str)?);
-                
-                // Join remaining lines as text
-                let text = lines[2..].join(" ").trim().to_string();
-                
-                if !text.is_empty() {
-                    segments.push(TranscriptionSegment {
-                        start_time,
-                        end_time,
-                        text,
-                        file_index: index,
-                        original_filename: filename.to_string(),
-                    });
+            let Some((start_str, end_str)) = timestamp_line.split_once(" --> ") else {
+                warnings.push(ParseWarning::new(
+                    filename,
+                    line_number + 1,
+                    format!("Missing timestamp line: {}", timestamp_line),
+                ));
+                continue;
+            };
+
+            let (start_time, end_time) = match (
+                self.parse_srt_timestamp(start_str.trim()),
+                self.parse_srt_timestamp(end_str.trim()),
+            ) {
+                (Ok(start), Ok(end)) => (start, Some(end)),
+                (Err(e), _) | (_, Err(e)) => {
+                    warnings.push(ParseWarning::new(
+                        filename,
+                        line_number + 1,
+                        format!("Invalid timestamp line '{}': {}", timestamp_line, e),
+                    ));
+                    continue;
                 }
+            };
+
+            // Join remaining lines as text
+            let text = lines[2..].join(" ").trim().to_string();
+
+            if text.is_empty() {
+                warnings.push(ParseWarning::new(filename, line_number, "Subtitle block has no text"));
+                continue;
             }
+
+            segments.push(TranscriptionSegment {
+                start_time,
+                end_time,
+                text,
+                file_index: index,
+                original_filename: filename.to_string(),
+                ..Default::default()
+            });
         }
 
         Ok(segments)
@@ -210,32 +691,486 @@ impl TranscriptionMerger {
         Ok(hours * 3600.0 + minutes * 60.0 + seconds)
     }
 
-    fn parse_txt(&self, content: &str, filename: &str) -> Result<Vec<TranscriptionSegment>> {
+    /// YouTube SBV: blocks of a `start,end` line followed by the text lines,
+    /// without cue numbers.
+    fn parse_sbv(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
+        let mut segments = Vec::new();
+        let normalized = content.replace("\r\n", "\n");
+
+        for (index, (line_number, lines)) in text_blocks(&normalized).into_iter().enumerate() {
+            let timing_line = lines[0];
+            let Some((start_str, end_str)) = timing_line.split_once(',') else {
+                warnings.push(ParseWarning::new(
+                    filename,
+                    line_number,
+                    format!("Missing timing line: {}", timing_line),
+                ));
+                continue;
+            };
+
+            let (start_time, end_time) = match (
+                self.parse_srt_timestamp(start_str.trim()),
+                self.parse_srt_timestamp(end_str.trim()),
+            ) {
+                (Ok(start), Ok(end)) => (start, Some(end)),
+                (Err(e), _) | (_, Err(e)) => {
+                    warnings.push(ParseWarning::new(
+                        filename,
+                        line_number,
+                        format!("Invalid timing line '{}': {}", timing_line, e),
+                    ));
+                    continue;
+                }
+            };
+
+            let text = lines[1..].join(" ").trim().to_string();
+            if text.is_empty() {
+                warnings.push(ParseWarning::new(filename, line_number, "Caption has no text"));
+                continue;
+            }
+
+            segments.push(TranscriptionSegment {
+                start_time,
+                end_time,
+                text,
+                file_index: index,
+                original_filename: filename.to_string(),
+                ..Default::default()
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// SubViewer 2.0: an optional `[INFORMATION]` header and `[COLF]…` style
+    /// line, then blocks of a `00:00:41.00,00:00:44.40` timing line and text
+    /// lines, with `[br]` for line breaks.
+    fn parse_subviewer(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
+        let mut segments = Vec::new();
+        let normalized = content.replace("\r\n", "\n");
+
+        for (index, (line_number, lines)) in text_blocks(&normalized).into_iter().enumerate() {
+            // The header sits in front of the first caption in the same block
+            let header_lines = lines.iter().take_while(|line| line.starts_with('[')).count();
+            let Some((timing_line, text_lines)) = lines[header_lines..].split_first() else {
+                continue;
+            };
+            let line_number = line_number + header_lines;
+
+            let Some((start_str, end_str)) = timing_line.split_once(',') else {
+                warnings.push(ParseWarning::new(
+                    filename,
+                    line_number,
+                    format!("Missing timing line: {}", timing_line),
+                ));
+                continue;
+            };
+
+            let (start_time, end_time) = match (
+                self.parse_srt_timestamp(start_str.trim()),
+                self.parse_srt_timestamp(end_str.trim()),
+            ) {
+                (Ok(start), Ok(end)) => (start, Some(end)),
+                (Err(e), _) | (_, Err(e)) => {
+                    warnings.push(ParseWarning::new(
+                        filename,
+                        line_number,
+                        format!("Invalid timing line '{}': {}", timing_line, e),
+                    ));
+                    continue;
+                }
+            };
+
+            let joined = text_lines.join(" ");
+            let text = joined.split("[br]").map(str::trim).filter(|part| !part.is_empty()).collect::<Vec<_>>().join(" ");
+            if text.is_empty() {
+                warnings.push(ParseWarning::new(filename, line_number, "Caption has no text"));
+                continue;
+            }
+
+            segments.push(TranscriptionSegment {
+                start_time,
+                end_time,
+                text,
+                file_index: index,
+                original_filename: filename.to_string(),
+                ..Default::default()
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// MicroDVD: a `{start}{end}text` line per caption in frame numbers, `|`
+    /// between text lines and `{y:i}`-style control codes. A leading
+    /// `{1}{1}23.976` line states the frame rate, which `microdvd_frame_rate`
+    /// overrides.
+    fn parse_microdvd(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
+        let line_pattern = Regex::new(r"^\{(\d+)\}\{(\d*)\}(.*)$").unwrap();
+        let control_code = Regex::new(r"\{[A-Za-z]:[^}]*\}").unwrap();
+        let mut frame_rate = self.merge_options.microdvd_frame_rate.filter(|rate| *rate > 0.0);
+        let mut segments = Vec::new();
+
+        for (index, line) in content.lines().enumerate() {
+            let line = line.trim().trim_start_matches('\u{feff}');
+            if line.is_empty() {
+                continue;
+            }
+            let Some(captures) = line_pattern.captures(line) else {
+                warnings.push(ParseWarning::new(filename, index + 1, format!("Not a MicroDVD caption: {}", line)));
+                continue;
+            };
+            let text = captures[3].trim();
+
+            if segments.is_empty() && captures[1] == captures[2] && matches!(&captures[1], "0" | "1") {
+                if let Some(rate) = parse_decimal(text).filter(|rate| *rate > 0.0) {
+                    frame_rate = frame_rate.or(Some(rate));
+                    continue;
+                }
+            }
+
+            let rate = frame_rate.unwrap_or(23.976);
+            let frame = |digits: &str| digits.parse::<f64>().unwrap_or_default() / rate;
+            let text = control_code
+                .replace_all(text, "")
+                .split('|')
+                .map(str::trim)
+                .filter(|part| !part.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if text.is_empty() {
+                warnings.push(ParseWarning::new(filename, index + 1, "Caption has no text"));
+                continue;
+            }
+
+            segments.push(TranscriptionSegment {
+                start_time: frame(&captures[1]),
+                end_time: (!captures[2].is_empty()).then(|| frame(&captures[2])),
+                text,
+                file_index: index,
+                original_filename: filename.to_string(),
+                ..Default::default()
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// Microsoft Teams Word transcripts. Current exports have a
+    /// "Name   0:03" paragraph before each entry's text; older ones a
+    /// "0:0:0.0 --> 0:0:3.740" paragraph followed by the speaker's name.
+    /// Title and date paragraphs before the first entry are skipped.
+    fn parse_teams_transcript(
+        &self,
+        paragraphs: &[String],
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Vec<TranscriptionSegment> {
+        lazy_static::lazy_static! {
+            static ref SPEAKER_LINE: Regex = Regex::new(r"^([^\d\t]*[^\d\s,])\s+(\d{1,2}(?::\d{2}){1,2})$").unwrap();
+            static ref TIMING_LINE: Regex = Regex::new(r"^(\d+:\d+:\d+(?:\.\d+)?)\s*-->\s*(\d+:\d+:\d+(?:\.\d+)?)$").unwrap();
+        }
+
+        struct Entry {
+            paragraph: usize,
+            start_time: f64,
+            end_time: Option<f64>,
+            speaker: Option<String>,
+            text: Vec<String>,
+        }
+
+        let mut entries: Vec<Entry> = Vec::new();
+        let mut awaiting_speaker = false;
+        for (index, paragraph) in paragraphs.iter().enumerate() {
+            let line = paragraph.trim();
+            if line.is_empty() || line.ends_with("started transcription") || line.ends_with("stopped transcription") {
+                continue;
+            }
+
+            if let Some(captures) = TIMING_LINE.captures(line) {
+                match (self.parse_srt_timestamp(&captures[1]), self.parse_srt_timestamp(&captures[2])) {
+                    (Ok(start_time), Ok(end_time)) => {
+                        entries.push(Entry { paragraph: index, start_time, end_time: Some(end_time), speaker: None, text: Vec::new() });
+                        awaiting_speaker = true;
+                    }
+                    (Err(e), _) | (_, Err(e)) => {
+                        warnings.push(ParseWarning::new(filename, index + 1, format!("Invalid timing '{}': {}", line, e)))
+                    }
+                }
+            } else if let Some(captures) = SPEAKER_LINE.captures(line) {
+                match self.parse_vtt_timestamp(&captures[2]) {
+                    Ok(start_time) => {
+                        let speaker = Some(captures[1].trim().to_string());
+                        entries.push(Entry { paragraph: index, start_time, end_time: None, speaker, text: Vec::new() });
+                        awaiting_speaker = false;
+                    }
+                    Err(e) => warnings.push(ParseWarning::new(filename, index + 1, format!("Invalid time '{}': {}", line, e))),
+                }
+            } else if let Some(entry) = entries.last_mut() {
+                if awaiting_speaker {
+                    entry.speaker = Some(line.to_string());
+                    awaiting_speaker = false;
+                } else {
+                    entry.text.push(line.replace(['\n', '\t'], " "));
+                }
+            }
+        }
+
+        let mut segments = Vec::new();
+        for (index, entry) in entries.into_iter().enumerate() {
+            let text = entry.text.join(" ");
+            if text.is_empty() {
+                warnings.push(ParseWarning::new(filename, entry.paragraph + 1, "Transcript entry has no text"));
+                continue;
+            }
+            let text = match &entry.speaker {
+                Some(speaker) => format!("{}: {}", speaker, text),
+                None => text,
+            };
+            segments.push(TranscriptionSegment {
+                start_time: entry.start_time,
+                end_time: entry.end_time,
+                text,
+                file_index: index,
+                original_filename: filename.to_string(),
+                speaker: entry.speaker,
+                ..Default::default()
+            });
+        }
+        segments
+    }
+
+    fn parse_vtt(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
+        let mut segments = Vec::new();
+        let normalized = content.replace("\r\n", "\n");
+
+        for (index, (line_number, lines)) in text_blocks(&normalized).into_iter().enumerate() {
+            // Header, comments and style/region definitions carry no cues
+            let first = lines[0];
+            if first.starts_with("WEBVTT") || first.starts_with("NOTE") || first == "STYLE" || first == "REGION" {
+                continue;
+            }
+
+            // The cue identifier line is optional
+            let Some(timing_index) = lines.iter().take(2).position(|line| line.contains("-->")) else {
+                warnings.push(ParseWarning::new(filename, line_number, "Cue without a timing line"));
+                continue;
+            };
+            let timing_line = lines[timing_index];
+            let (start_str, rest) = timing_line.split_once("-->").unwrap();
+            // Cue settings (position, align, ...) may follow the end time
+            let end_str = rest.split_whitespace().next().unwrap_or("");
+
+            let (start_time, end_time) = match (
+                self.parse_vtt_timestamp(start_str.trim()),
+                self.parse_vtt_timestamp(end_str),
+            ) {
+                (Ok(start), Ok(end)) => (start, Some(end)),
+                (Err(e), _) | (_, Err(e)) => {
+                    warnings.push(ParseWarning::new(
+                        filename,
+                        line_number + timing_index,
+                        format!("Invalid timing line '{}': {}", timing_line, e),
+                    ));
+                    continue;
+                }
+            };
+
+            let text = lines[timing_index + 1..].join(" ").trim().to_string();
+            let (text, speaker) = strip_voice_tags(&text);
+            let (text, words) = match self.parse_vtt_words(&text, start_time, end_time.unwrap_or(start_time)) {
+                Some((text, words)) => (text, words),
+                None => (text, Vec::new()),
+            };
+            if text.is_empty() {
+                warnings.push(ParseWarning::new(filename, line_number, "Cue has no text"));
+                continue;
+            }
+            // Like "Name:" lines in other transcripts, so renaming and output treat them alike
+            let text = match &speaker {
+                Some(speaker) => format!("{}: {}", speaker, text),
+                None => text,
+            };
+
+            segments.push(TranscriptionSegment {
+                start_time,
+                end_time,
+                text,
+                file_index: index,
+                original_filename: filename.to_string(),
+                speaker,
+                words,
+                ..Default::default()
+            });
+        }
+
+        Ok(segments)
+    }
+
+    /// Word timing from the inline timestamps of karaoke-style cues
+    /// (`word<00:00:01.500><c> next</c>`), with the timestamps and `<c>` tags
+    /// taken out of the text. A timestamp starts the words after it; words
+    /// between two timestamps share that span evenly. `None` when the cue
+    /// has no inline timestamps.
+    fn parse_vtt_words(&self, text: &str, cue_start: f64, cue_end: f64) -> Option<(String, Vec<WordTiming>)> {
+        let timestamp = Regex::new(r"<((?:\d+:)?\d{2}:\d{2}\.\d{3})>").unwrap();
+        let class_tag = Regex::new(r"</?c(?:\.[^>]*)?>").unwrap();
+        if !timestamp.is_match(text) {
+            return None;
+        }
+
+        let mut starts = vec![cue_start];
+        for captures in timestamp.captures_iter(text) {
+            starts.push(self.parse_vtt_timestamp(&captures[1]).ok()?);
+        }
+        let mut words = Vec::new();
+        for (piece_index, piece) in timestamp.split(text).enumerate() {
+            let start = starts[piece_index];
+            let end = starts.get(piece_index + 1).copied().unwrap_or(cue_end).max(start);
+            let piece = class_tag.replace_all(piece, "");
+            let piece_words: Vec<&str> = piece.split_whitespace().collect();
+            let step = (end - start) / piece_words.len().max(1) as f64;
+            words.extend(piece_words.iter().enumerate().map(|(position, word)| WordTiming {
+                text: word.to_string(),
+                start: Some(start + step * position as f64),
+                end: Some(start + step * (position + 1) as f64),
+                confidence: None,
+            }));
+        }
+
+        let text = words.iter().map(|word| word.text.as_str()).collect::<Vec<_>>().join(" ");
+        Some((text, words))
+    }
+
+    fn parse_vtt_timestamp(&self, timestamp_str: &str) -> Result<f64> {
+        // WebVTT allows omitting the hours: 00:01.000
+        if timestamp_str.matches(':').count() == 1 {
+            self.parse_srt_timestamp(&format!("00:{}", timestamp_str))
+        } else {
+            self.parse_srt_timestamp(timestamp_str)
+        }
+    }
+
+    /// Reads either our own JSON export (`{"segments": [...]}`) or a bare array of
+    /// segments; Whisper-style `start`/`end` keys and Podcasting 2.0
+    /// `startTime`/`endTime`/`body` are accepted as well.
+    fn parse_json(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
+        let value: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| anyhow!("Invalid JSON transcription {}: {}", filename, e))?;
+
+        let items = match &value {
+            serde_json::Value::Array(items) => items,
+            serde_json::Value::Object(object) => object
+                .get("segments")
+                .and_then(|segments| segments.as_array())
+                .ok_or_else(|| anyhow!("JSON transcription {} has no \"segments\" array", filename))?,
+            _ => return Err(anyhow!("JSON transcription {} must be an object or an array", filename)),
+        };
+
+        let mut segments = Vec::new();
+        for (index, item) in items.iter().enumerate() {
+            // Numbers, or strings with a comma decimal separator ("1,5") from European tooling
+            let number = |keys: &[&str]| {
+                keys.iter().find_map(|key| {
+                    item.get(*key)
+                        .and_then(|v| v.as_f64().or_else(|| v.as_str().and_then(parse_decimal)))
+                })
+            };
+            let start_time = number(&["start_time", "start", "startTime"]);
+            let end_time = number(&["end_time", "end", "endTime"]);
+            let text = ["text", "body"]
+                .iter()
+                .find_map(|key| item.get(*key).and_then(|v| v.as_str()))
+                .map(|t| t.trim().to_string());
+            let speaker = item.get("speaker").and_then(|v| v.as_str()).map(|s| s.trim().to_string());
+            // Whisper reports the mean log probability rather than a confidence
+            let confidence = number(&["confidence", "probability"])
+                .or_else(|| item.get("avg_logprob").and_then(|v| v.as_f64()).map(f64::exp));
+            let words = item
+                .get("words")
+                .and_then(|words| words.as_array())
+                .map(|words| words.iter().filter_map(parse_json_word).collect())
+                .unwrap_or_default();
+
+            match (start_time, text) {
+                (Some(start_time), Some(text)) if !text.is_empty() => segments.push(TranscriptionSegment {
+                    start_time,
+                    end_time,
+                    text,
+                    file_index: index,
+                    original_filename: filename.to_string(),
+                    speaker,
+                    confidence,
+                    words,
+                    ..Default::default()
+                }),
+                _ => warnings.push(ParseWarning::new(
+                    filename,
+                    index + 1,
+                    format!("Segment #{} has no start time or text", index + 1),
+                )),
+            }
+        }
+
+        Ok(segments)
+    }
+
+    fn parse_txt(
+        &self,
+        content: &str,
+        filename: &str,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<Vec<TranscriptionSegment>> {
         let mut segments = Vec::new();
 
         // Range timestamp patterns (e.g., [00:00-01:06] or [01:30:00-01:31:25])
         // These MUST be checked first, before single-timestamp patterns
         let range_hh_mm_ss = Regex::new(
-            r"\[(\d{1,2}):(\d{2}):(\d{2})-(\d{1,2}):(\d{2}):(\d{2})\]"
+            r"\[(\d{1,2}):(\d{2}):(\d{2}(?:[\.,]\d{1,3})?)-(\d{1,2}):(\d{2}):(\d{2}(?:[\.,]\d{1,3})?)\]"
         ).unwrap();
         let range_mm_ss = Regex::new(
-            r"\[(\d{1,2}):(\d{2})-(\d{1,2}):(\d{2})\]"
+            r"\[(\d{1,2}):(\d{2}(?:[\.,]\d{1,3})?)-(\d{1,2}):(\d{2}(?:[\.,]\d{1,3})?)\]"
         ).unwrap();
 
         // Multiple regex patterns for different single-timecode formats
         let patterns = [
             // [HH:MM:SS.mmm] format - full precision with brackets
             r"\[(\d{1,2}):(\d{2}):(\d{2})(?:[\.,](\d{1,3}))?\]",
-            // [MM:SS] format - minutes:seconds with brackets
-            r"\[(\d{1,2}):(\d{2})\]",
+            // [MM:SS.mmm] format - minutes:seconds with brackets, milliseconds optional
+            r"\[(\d{1,2}):(\d{2}(?:[\.,]\d{1,3})?)\]",
             // HH:MM:SS.mmm format - full precision without brackets
             r"^(\d{1,2}):(\d{2}):(\d{2})(?:[\.,](\d{1,3}))?(?:\s|$)",
-            // MM:SS format - minutes:seconds without brackets
-            r"^(\d{1,2}):(\d{2})(?:\s|$)",
+            // MM:SS.mmm format - minutes:seconds without brackets
+            r"^(\d{1,2}):(\d{2}(?:[\.,]\d{1,3})?)(?:\s|$)",
             // Whisper format: [HH:MM:SS.mmm --> HH:MM:SS.mmm] (extract start time)
             r"\[(\d{1,2}):(\d{2}):(\d{2})(?:[\.,](\d{1,3}))?\s*-->\s*\d{1,2}:\d{2}:\d{2}(?:[\.,]\d{1,3})?\]",
-            // Simple seconds format: [123] (only bracketed, to avoid catching plain numbers)
-            r"\[(\d+)\]",
+            // Simple seconds format: [123] or [123,5] (only bracketed, to avoid catching plain numbers)
+            r"\[(\d+(?:[\.,]\d{1,3})?)\]",
         ];
 
         let regexes: Vec<Regex> = patterns.iter()
@@ -244,7 +1179,7 @@ impl TranscriptionMerger {
 
         let lines: Vec<&str> = content.lines().collect();
         let mut current_time = 0.0;
-        let average_read_speed = 150.0; // words per minute
+        let average_read_speed = self.merge_options.speaking_rate();
 
         for (index, line) in lines.iter().enumerate() {
             let line = line.trim();
@@ -262,10 +1197,10 @@ impl TranscriptionMerger {
                 // [HH:MM:SS-HH:MM:SS] format
                 let sh: f64 = captures[1].parse().unwrap_or(0.0);
                 let sm: f64 = captures[2].parse().unwrap_or(0.0);
-                let ss: f64 = captures[3].parse().unwrap_or(0.0);
+                let ss = parse_decimal(&captures[3]).unwrap_or(0.0);
                 let eh: f64 = captures[4].parse().unwrap_or(0.0);
                 let em: f64 = captures[5].parse().unwrap_or(0.0);
-                let es: f64 = captures[6].parse().unwrap_or(0.0);
+                let es = parse_decimal(&captures[6]).unwrap_or(0.0);
 
                 segment_start_time = sh * 3600.0 + sm * 60.0 + ss;
                 segment_end_time = Some(eh * 3600.0 + em * 60.0 + es);
@@ -275,9 +1210,9 @@ impl TranscriptionMerger {
             } else if let Some(captures) = range_mm_ss.captures(line) {
                 // [MM:SS-MM:SS] format
                 let sm: f64 = captures[1].parse().unwrap_or(0.0);
-                let ss: f64 = captures[2].parse().unwrap_or(0.0);
+                let ss = parse_decimal(&captures[2]).unwrap_or(0.0);
                 let em: f64 = captures[3].parse().unwrap_or(0.0);
-                let es: f64 = captures[4].parse().unwrap_or(0.0);
+                let es = parse_decimal(&captures[4]).unwrap_or(0.0);
 
                 segment_start_time = sm * 60.0 + ss;
                 segment_end_time = Some(em * 60.0 + es);
@@ -293,10 +1228,15 @@ impl TranscriptionMerger {
                         let parsed_time = match captures.len() {
                             2 => {
                                 // Single number (seconds or MM:SS without hours)
-                                if let Ok(seconds) = captures.get(1).unwrap().as_str().parse::<f64>() {
+                                if let Some(seconds) = parse_decimal(captures.get(1).unwrap().as_str()) {
                                     if seconds < 3600.0 {
                                         seconds
                                     } else {
+                                        warnings.push(ParseWarning::new(
+                                            filename,
+                                            index + 1,
+                                            format!("Bracketed seconds value {} is out of range, keeping previous time", seconds),
+                                        ));
                                         current_time
                                     }
                                 } else {
@@ -304,9 +1244,9 @@ impl TranscriptionMerger {
                                 }
                             },
                             3 => {
-                                // MM:SS format
+                                // MM:SS format, seconds possibly fractional
                                 let minutes: f64 = captures.get(1).unwrap().as_str().parse().unwrap_or(0.0);
-                                let seconds: f64 = captures.get(2).unwrap().as_str().parse().unwrap_or(0.0);
+                                let seconds = parse_decimal(captures.get(2).unwrap().as_str()).unwrap_or(0.0);
                                 minutes * 60.0 + seconds
                             },
                             4 => {
@@ -321,10 +1261,11 @@ impl TranscriptionMerger {
                                 let hours: f64 = captures.get(1).unwrap().as_str().parse().unwrap_or(0.0);
                                 let minutes: f64 = captures.get(2).unwrap().as_str().parse().unwrap_or(0.0);
                                 let seconds: f64 = captures.get(3).unwrap().as_str().parse().unwrap_or(0.0);
-                                let millis: f64 = captures.get(4)
-                                    .map(|m| m.as_str().parse().unwrap_or(0.0))
-                                    .unwrap_or(0.0) / 1000.0;
-                                hours * 3600.0 + minutes * 60.0 + seconds + millis
+                                // Fraction of a second: ",5" is half a second, not 5 ms
+                                let fraction = captures.get(4)
+                                    .and_then(|m| parse_decimal(&format!("0.{}", m.as_str())))
+                                    .unwrap_or(0.0);
+                                hours * 3600.0 + minutes * 60.0 + seconds + fraction
                             },
                             _ => current_time
                         };
@@ -349,8 +1290,8 @@ impl TranscriptionMerger {
 
             if !text.is_empty() {
                 // Use actual end_time from range format, or estimate from word count
-                let word_count = text.split_whitespace().count();
-                let estimated_duration = (word_count as f64 / average_read_speed) * 60.0;
+                let word_count = cjk::word_count(&text);
+                let estimated_duration = (word_count / average_read_speed) * 60.0;
 
                 let end_time = if let Some(et) = segment_end_time {
                     // Range format provided an explicit end time
@@ -368,6 +1309,8 @@ impl TranscriptionMerger {
                     text,
                     file_index: index,
                     original_filename: filename.to_string(),
+                    end_time_estimated: segment_end_time.is_none(),
+                    ..Default::default()
                 });
             }
         }
@@ -380,6 +1323,7 @@ impl TranscriptionMerger {
                 text: content.trim().to_string(),
                 file_index: 0,
                 original_filename: filename.to_string(),
+                ..Default::default()
             });
         }
 
@@ -390,16 +1334,23 @@ impl TranscriptionMerger {
         let mut segments = Vec::new();
         let lines: Vec<&str> = content.lines().collect();
         let mut current_time = 0.0;
+        let mut heading = None;
 
         for (index, line) in lines.iter().enumerate() {
             let line = line.trim();
-            if line.is_empty() || line.starts_with('#') {
+            if line.starts_with('#') {
+                // Kept on the next segment, where it can start a chapter
+                let title = line.trim_start_matches('#').trim();
+                heading = (!title.is_empty()).then(|| title.to_string());
+                continue;
+            }
+            if line.is_empty() {
                 continue;
             }
 
             // Estimate timing based on content
-            let word_count = line.split_whitespace().count();
-            let estimated_duration = (word_count as f64 / 150.0) * 60.0; // 150 WPM
+            let word_count = cjk::word_count(line);
+            let estimated_duration = (word_count / self.merge_options.speaking_rate()) * 60.0;
             
             segments.push(TranscriptionSegment {
                 start_time: current_time,
@@ -407,6 +1358,9 @@ impl TranscriptionMerger {
                 text: line.to_string(),
                 file_index: index,
                 original_filename: filename.to_string(),
+                end_time_estimated: true,
+                heading: heading.take(),
+                ..Default::default()
             });
 
             current_time += estimated_duration.max(1.0);
@@ -416,55 +1370,431 @@ impl TranscriptionMerger {
     }
 
     pub async fn merge(&self) -> Result<String> {
+        let segments = self.merged_segments();
+        self.format_segments(&segments)
+    }
+
+    /// All segments of all files on one timeline, before formatting.
+    pub fn merged_segments(&self) -> Vec<TranscriptionSegment> {
+        self.merged_segments_with_hallucinations().0
+    }
+
+    /// The merged segments and, separately, those suspected to be
+    /// hallucinations: still among the segments when flagged, removed otherwise.
+    pub fn merged_segments_with_hallucinations(&self) -> (Vec<TranscriptionSegment>, Vec<TranscriptionSegment>) {
         let mut all_segments = Vec::new();
         let mut cumulative_offset = self.merge_options.time_offset_seconds;
 
         for (file_index, file) in self.files.iter().enumerate() {
+            let chunk = self
+                .merge_options
+                .chunk_manifest
+                .as_ref()
+                .and_then(|manifest| manifest.chunk_for(&file.filename, file.sequence_number));
+            if let Some(chunk) = chunk {
+                cumulative_offset = self.merge_options.time_offset_seconds + chunk.start_time;
+            }
+
+            let file_offset = cumulative_offset + self.merge_options.file_offsets.get(&file.filename).copied().unwrap_or(0.0);
             for mut segment in file.segments.clone() {
                 // Apply time offset
-                segment.start_time += cumulative_offset;
+                segment.start_time = (segment.start_time + file_offset).max(0.0);
                 if let Some(end_time) = segment.end_time {
-                    segment.end_time = Some(end_time + cumulative_offset);
+                    segment.end_time = Some((end_time + file_offset).max(0.0));
+                }
+                for word in segment.words.iter_mut() {
+                    word.start = word.start.map(|start| (start + file_offset).max(0.0));
+                    word.end = word.end.map(|end| (end + file_offset).max(0.0));
                 }
                 
                 all_segments.push(segment);
             }
 
-            // Add gap between files (estimated based on last segment)
+            // Add gap between files (exact for manifest chunks, otherwise estimated based on last segment)
             if file_index < self.files.len() - 1 {
-                if let Some(last_segment) = file.segments.last() {
+                if let Some(chunk) = chunk {
+                    cumulative_offset += chunk.duration;
+                } else if let Some(last_segment) = file.segments.last() {
                     let file_duration = last_segment.end_time.unwrap_or(last_segment.start_time + 30.0);
                     cumulative_offset += file_duration;
                 }
             }
         }
 
+        if !self.merge_options.excluded_ranges.is_empty() {
+            self.restore_excluded_ranges(&mut all_segments);
+        }
+
         // Sort by start time
         all_segments.sort_by(|a, b| a.start_time.partial_cmp(&b.start_time).unwrap());
 
-        match self.merge_options.output_format {
-            FileFormat::Srt => self.format_as_srt(&all_segments),
-            FileFormat::Txt => self.format_as_txt(&all_segments),
-            FileFormat::Markdown => self.format_as_markdown(&all_segments),
+        if !self.merge_options.term_corrections.is_empty() {
+            let dictionary = CorrectionDictionary::new(&self.merge_options.term_corrections);
+            for segment in all_segments.iter_mut() {
+                let corrected = dictionary.apply(&segment.text).into_owned();
+                if corrected != segment.text {
+                    segment.text = corrected;
+                    segment.words.clear();
+                }
+            }
         }
-    }
 
-    fn format_as_srt(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let mut hallucinations = Vec::new();
+        if self.merge_options.hallucination_filter != HallucinationFilter::Off {
+            let silences = self
+                .merge_options
+                .chunk_manifest
+                .as_ref()
+                .map(|manifest| manifest.silences.as_slice())
+                .unwrap_or_default();
+            let flags = detect_hallucinations(&all_segments, silences, self.merge_options.default_duration_seconds);
+            for (segment, flag) in all_segments.iter_mut().zip(flags) {
+                segment.hallucination = flag;
+            }
+            hallucinations = all_segments.iter().filter(|s| s.hallucination.is_some()).cloned().collect();
+            if self.merge_options.hallucination_filter == HallucinationFilter::Remove {
+                all_segments.retain(|s| s.hallucination.is_none());
+            }
+        }
+
+        for segment in all_segments.iter_mut().filter(|s| s.speaker.is_none()) {
+            segment.speaker = detect_speaker(&segment.text);
+        }
+
+        if !self.merge_options.speaker_mapping.is_empty() {
+            self.map_speakers(&mut all_segments);
+        }
+
+        if self.merge_options.speaker_change_markers {
+            self.mark_speaker_changes(&mut all_segments);
+        }
+
+        if self.merge_options.infer_end_times {
+            self.infer_end_times(&mut all_segments);
+        }
+
+        if self.merge_options.sentence_segmentation {
+            all_segments = resegment_by_sentences(&all_segments, self.merge_options.default_duration_seconds);
+        }
+
+        (all_segments, hallucinations)
+    }
+
+    /// Maps times from the transcribed audio (with excluded ranges cut out) back
+    /// to the original recording and adds break markers when enabled.
+    fn restore_excluded_ranges(&self, segments: &mut Vec<TranscriptionSegment>) {
+        let mut ranges = self.merge_options.excluded_ranges.clone();
+        ranges.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap_or(std::cmp::Ordering::Equal));
+
+        // Overlapping or touching ranges were cut out once, so they shift the
+        // timeline once and get a single marker (labelled by the first range)
+        let mut merged: Vec<ExcludedRange> = Vec::new();
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        let ranges = merged;
+
+        let to_source_time = |time: f64| {
+            let mut time = time;
+            for range in &ranges {
+                if time < range.start {
+                    break;
+                }
+                time += range.duration();
+            }
+            time
+        };
+
+        // Manifest chunk times already count the excluded ranges
+        if self.merge_options.chunk_manifest.is_none() {
+            for segment in segments.iter_mut() {
+                segment.start_time = to_source_time(segment.start_time);
+                segment.end_time = segment.end_time.map(to_source_time);
+                for word in segment.words.iter_mut() {
+                    word.start = word.start.map(to_source_time);
+                    word.end = word.end.map(to_source_time);
+                }
+            }
+        }
+
+        if self.merge_options.ad_break_markers {
+            segments.extend(ranges.iter().map(|range| TranscriptionSegment {
+                start_time: range.start,
+                end_time: Some(range.end),
+                text: range.label.clone().unwrap_or_else(|| DEFAULT_AD_BREAK_LABEL.to_string()),
+                file_index: usize::MAX,
+                ..Default::default()
+            }));
+        }
+    }
+
+    /// Renames speakers per source file, including the "Name:" label in the text.
+    fn map_speakers(&self, segments: &mut [TranscriptionSegment]) {
+        for segment in segments.iter_mut() {
+            let Some(mapping) = self.merge_options.speaker_mapping.get(&segment.original_filename) else {
+                continue;
+            };
+            let Some(renamed) = segment.speaker.as_ref().and_then(|speaker| mapping.get(speaker)).cloned() else {
+                continue;
+            };
+
+            let old = segment.speaker.replace(renamed.clone()).unwrap_or_default();
+            if let Some(rest) = segment.text.strip_prefix(&format!("{}:", old)) {
+                segment.text = format!("{}:{}", renamed, rest);
+            }
+        }
+    }
+
+    /// Speakers of each loaded file in order of appearance, for building a
+    /// `speaker_mapping` table.
+    pub fn speakers_by_file(&self) -> Vec<(String, Vec<String>)> {
+        self.files
+            .iter()
+            .map(|file| {
+                let mut speakers: Vec<String> = Vec::new();
+                for segment in &file.segments {
+                    let speaker = segment.speaker.clone().or_else(|| detect_speaker(&segment.text));
+                    if let Some(speaker) = speaker.filter(|speaker| !speakers.contains(speaker)) {
+                        speakers.push(speaker);
+                    }
+                }
+                (file.filename.clone(), speakers)
+            })
+            .collect()
+    }
+
+    /// Prefixes segments that follow a long pause or an audio change point with
+    /// a dialogue dash. Segments with a speaker label, and the one after them,
+    /// are left alone: the label already tells who speaks.
+    fn mark_speaker_changes(&self, segments: &mut [TranscriptionSegment]) {
+        let mut changes = self.merge_options.speaker_changes.clone();
+        changes.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+        let mut changes = changes.into_iter().peekable();
+
+        for index in 1..segments.len() {
+            let (previous, current) = (&segments[index - 1], &segments[index]);
+            // Segment starts are coarse; a change just after the start still belongs to it
+            let mut changed = false;
+            while changes.peek().is_some_and(|&time| time <= current.start_time + SPEAKER_CHANGE_TOLERANCE_SECONDS) {
+                changed |= changes.next().is_some_and(|time| time > previous.start_time + SPEAKER_CHANGE_TOLERANCE_SECONDS);
+            }
+
+            let unlabelled = previous.speaker.is_none() && current.speaker.is_none();
+            let from_files = !previous.original_filename.is_empty() && !current.original_filename.is_empty();
+            let gap = current.start_time - previous.end_time.unwrap_or(previous.start_time);
+            changed |= previous.end_time.is_some() && gap >= self.merge_options.speaker_change_pause_seconds;
+
+            if changed && unlabelled && from_files && !current.speaker_change_hint {
+                let segment = &mut segments[index];
+                segment.speaker_change_hint = true;
+                segment.text = format!("{}{}", SPEAKER_CHANGE_MARK, segment.text);
+            }
+        }
+    }
+
+    /// Copies of the segments with words from the profanity lists masked.
+    pub fn mask_profanity(&self, segments: &[TranscriptionSegment]) -> Vec<TranscriptionSegment> {
+        let filter = ProfanityFilter::new(&self.merge_options.profanity_lists);
+        segments
+            .iter()
+            .map(|segment| {
+                let mut segment = segment.clone();
+                segment.text = filter.mask(&segment.text).into_owned();
+                for word in segment.words.iter_mut() {
+                    word.text = filter.mask(&word.text).into_owned();
+                }
+                segment
+            })
+            .collect()
+    }
+
+    /// Timestamp of TXT and Markdown lines in the configured timecode style.
+    fn text_timestamp(&self, seconds: f64) -> String {
+        self.merge_options
+            .timecode_format
+            .as_deref()
+            .and_then(|format| format_timecode(seconds, format, self.merge_options.custom_timecode_format.as_deref()))
+            .unwrap_or_else(|| format_txt_timestamp(seconds))
+    }
+
+    /// Escaped segment text with low-confidence words wrapped in `open`/`close`.
+    /// Without word data the whole segment is wrapped when its own confidence
+    /// is low.
+    fn confidence_styled_text(
+        &self,
+        segment: &TranscriptionSegment,
+        escape: impl Fn(&str) -> String,
+        open: &str,
+        close: &str,
+    ) -> String {
+        let Some(threshold) = self.merge_options.low_confidence_threshold else {
+            return escape(&segment.text);
+        };
+        if segment.words.is_empty() {
+            return match segment.confidence {
+                Some(confidence) if confidence < threshold => format!("{}{}{}", open, escape(&segment.text), close),
+                _ => escape(&segment.text),
+            };
+        }
+
+        // Words are found in order, so a short word never matches inside an earlier one
+        let mut output = String::new();
+        let mut rest = segment.text.as_str();
+        for word in &segment.words {
+            let needle = word.text.trim();
+            let Some(position) = rest.find(needle).filter(|_| !needle.is_empty()) else {
+                continue;
+            };
+            output.push_str(&escape(&rest[..position]));
+            if word.confidence.is_some_and(|confidence| confidence < threshold) {
+                output.push_str(&format!("{}{}{}", open, escape(needle), close));
+            } else {
+                output.push_str(&escape(needle));
+            }
+            rest = &rest[position + needle.len()..];
+        }
+        output.push_str(&escape(rest));
+        output
+    }
+
+    /// Segments inserted by the merger (break markers) have no source file.
+    fn shows_file_marker(&self, segment: &TranscriptionSegment) -> bool {
+        self.merge_options.add_file_markers && !segment.original_filename.is_empty()
+    }
+
+    /// Replaces missing and word-count estimated end times (which frequently
+    /// overlap the next cue) with the next segment's start, capped at
+    /// `max_extend_seconds`. Expects segments sorted by start time.
+    fn infer_end_times(&self, segments: &mut [TranscriptionSegment]) {
+        let max_duration = self.merge_options.max_extend_seconds;
+
+        for index in 0..segments.len() {
+            let segment = &segments[index];
+            if segment.end_time.is_some() && !segment.end_time_estimated {
+                continue;
+            }
+
+            let start = segment.start_time;
+            let next_start = segments[index + 1..]
+                .iter()
+                .map(|s| s.start_time)
+                .find(|&next| next > start);
+
+            let inferred = match (next_start, segment.end_time) {
+                (Some(next_start), _) => next_start.min(start + max_duration),
+                // Last segment: keep the estimate within bounds
+                (None, Some(estimate)) => estimate.min(start + max_duration),
+                (None, None) => start + self.merge_options.default_duration_seconds,
+            };
+
+            segments[index].end_time = Some(inferred);
+            segments[index].end_time_estimated = true;
+        }
+    }
+
+    /// Renders segments in the configured output format.
+    pub fn format_segments(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let masked;
+        let segments = if self.merge_options.profanity_lists.is_empty() {
+            segments
+        } else {
+            masked = self.mask_profanity(segments);
+            &masked
+        };
+
+        let stripped: Vec<TranscriptionSegment>;
+        let format = &self.merge_options.output_format;
+        let segments = if self.merge_options.strip_formatting_tags && !matches!(format, FileFormat::Srt | FileFormat::Ass) {
+            stripped = segments
+                .iter()
+                .map(|segment| TranscriptionSegment {
+                    text: strip_formatting_tags(&segment.text, *format == FileFormat::Vtt),
+                    ..segment.clone()
+                })
+                .collect();
+            &stripped
+        } else {
+            segments
+        };
+
+        let paragraphs: Vec<TranscriptionSegment>;
+        let segments = match self.merge_options.paragraph_pause_seconds {
+            Some(pause) if matches!(format, FileFormat::Txt | FileFormat::Markdown) => {
+                paragraphs = self.group_paragraphs(segments, pause);
+                &paragraphs
+            }
+            _ => segments,
+        };
+
+        match self.merge_options.output_format {
+            FileFormat::Srt => self.format_as_srt(segments),
+            FileFormat::Txt => self.format_as_txt(segments),
+            FileFormat::Markdown => self.format_as_markdown(segments),
+            FileFormat::Vtt => self.format_as_vtt(segments),
+            FileFormat::Json => self.format_as_json(segments),
+            FileFormat::Ass => self.format_as_ass(segments),
+            FileFormat::Ttml => self.format_as_ttml(segments),
+            FileFormat::Sbv => self.format_as_sbv(segments),
+            FileFormat::Docx => Err(anyhow!("Word documents are written by the DOCX export")),
+            FileFormat::Fcpxml => self.format_as_fcpxml(segments),
+            FileFormat::Lrc => self.format_as_lrc(segments),
+            FileFormat::PodcastJson => self.format_as_podcast_json(segments),
+            FileFormat::SubViewer | FileFormat::MicroDvd => Err(anyhow!("SubViewer and MicroDVD files are supported as input only")),
+        }
+    }
+
+    /// Joins runs of segments separated by pauses shorter than `pause` into
+    /// one segment each, spanning their time range.
+    fn group_paragraphs(&self, segments: &[TranscriptionSegment], pause: f64) -> Vec<TranscriptionSegment> {
+        let mut paragraphs: Vec<TranscriptionSegment> = Vec::new();
+        let mut previous_end = f64::NEG_INFINITY;
+        for (index, segment) in segments.iter().enumerate() {
+            let end = self.resolved_end_time(segments, index);
+            let joins = paragraphs.last().is_some_and(|paragraph| {
+                segment.start_time - previous_end < pause
+                    && paragraph.original_filename == segment.original_filename
+                    && paragraph.speaker == segment.speaker
+                    && !segment.speaker_change_hint
+            });
+            previous_end = end;
+
+            match paragraphs.last_mut() {
+                Some(paragraph) if joins => {
+                    // A speaker label is only needed at the start of the paragraph
+                    let text = match &segment.speaker {
+                        Some(_) => crate::stats::spoken_text(segment),
+                        None => segment.text.trim(),
+                    };
+                    paragraph.text = format!("{} {}", paragraph.text.trim_end(), text);
+                    paragraph.end_time = Some(paragraph.end_time.unwrap_or(end).max(end));
+                    paragraph.end_time_estimated = segment.end_time_estimated;
+                    paragraph.words.extend(segment.words.iter().cloned());
+                    paragraph.confidence = match (paragraph.confidence, segment.confidence) {
+                        (Some(a), Some(b)) => Some(a.min(b)),
+                        (a, b) => a.or(b),
+                    };
+                }
+                _ => paragraphs.push(TranscriptionSegment { end_time: Some(end), ..segment.clone() }),
+            }
+        }
+        paragraphs
+    }
+
+    fn format_as_srt(&self, segments: &[TranscriptionSegment]) -> Result<String> {
         let mut output = String::new();
 
         for (index, segment) in segments.iter().enumerate() {
             output.push_str(&format!("{}\n", index + 1));
             
             let start = self.format_srt_timestamp(segment.start_time);
-            let end = if let Some(end_time) = segment.end_time {
-                self.format_srt_timestamp(end_time)
-            } else {
-                self.format_srt_timestamp(segment.start_time + 5.0) // Default 5 second duration
-            };
+            let end = self.format_srt_timestamp(self.resolved_end_time(segments, index));
             
             output.push_str(&format!("{} --> {}\n", start, end));
             
-            if self.merge_options.add_file_markers {
+            if self.shows_file_marker(segment) {
                 output.push_str(&format!("[{}] {}\n\n", segment.original_filename, segment.text));
             } else {
                 output.push_str(&format!("{}\n\n", segment.text));
@@ -477,13 +1807,22 @@ impl TranscriptionMerger {
     fn format_as_txt(&self, segments: &[TranscriptionSegment]) -> Result<String> {
         let mut output = String::new();
 
+        if let Some(template) = self.merge_options.txt_template.as_deref().filter(|template| !template.trim().is_empty()) {
+            let template = LineTemplate::parse(template)?;
+            for index in 0..segments.len() {
+                output.push_str(&template.render(|token| self.template_value(token, segments, index)));
+                output.push('\n');
+            }
+            return Ok(output);
+        }
+
         for segment in segments {
             if !self.merge_options.remove_timestamps {
-                let timestamp = self.format_txt_timestamp(segment.start_time);
+                let timestamp = self.text_timestamp(segment.start_time);
                 output.push_str(&format!("[{}] ", timestamp));
             }
             
-            if self.merge_options.add_file_markers {
+            if self.shows_file_marker(segment) {
                 output.push_str(&format!("[{}] ", segment.original_filename));
             }
             
@@ -493,23 +1832,51 @@ impl TranscriptionMerger {
         Ok(output)
     }
 
+    /// Value of one `TEMPLATE_TOKENS` entry for the segment at `index`.
+    fn template_value(&self, token: &str, segments: &[TranscriptionSegment], index: usize) -> String {
+        let segment = &segments[index];
+        let end = || self.resolved_end_time(segments, index);
+        let hms = |seconds: f64, format: &str| format_timecode(seconds, format, None).unwrap_or_default();
+        match token {
+            "start" => self.text_timestamp(segment.start_time),
+            "start_hms" => hms(segment.start_time, "hms"),
+            "start_hms_ms" => hms(segment.start_time, "hms_ms"),
+            "start_seconds" => hms(segment.start_time, "seconds_ms"),
+            "end" => self.text_timestamp(end()),
+            "end_hms" => hms(end(), "hms"),
+            "duration" => hms(end() - segment.start_time, "seconds_ms"),
+            "speaker" => segment.speaker.clone().unwrap_or_default(),
+            "text" => crate::stats::spoken_text(segment).to_string(),
+            "full_text" => segment.text.clone(),
+            "file" => segment.original_filename.clone(),
+            "index" => (index + 1).to_string(),
+            "confidence" => segment
+                .confidence
+                .map(|confidence| format!("{:.0}%", confidence * 100.0))
+                .unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
     fn format_as_markdown(&self, segments: &[TranscriptionSegment]) -> Result<String> {
         let mut output = String::new();
         output.push_str("# Merged Transcription\n\n");
         
-        let now: DateTime<Utc> = Utc::now();
-        output.push_str(&format!("*Generated on: {}*\n\n", now.format("%Y-%m-%d %H:%M:%S UTC")));
+        if !self.merge_options.reproducible {
+            let now: DateTime<Utc> = Utc::now();
+            output.push_str(&format!("*Generated on: {}*\n\n", now.format("%Y-%m-%d %H:%M:%S UTC")));
+        }
 
         let mut current_file = String::new();
         
         for segment in segments {
-            if self.merge_options.add_file_markers && segment.original_filename != current_file {
+            if self.shows_file_marker(segment) && segment.original_filename != current_file {
                 current_file = segment.original_filename.clone();
                 output.push_str(&format!("## {}\n\n", current_file));
             }
             
             if !self.merge_options.remove_timestamps {
-                let timestamp = self.format_txt_timestamp(segment.start_time);
+                let timestamp = self.text_timestamp(segment.start_time);
                 output.push_str(&format!("**[{}]** ", timestamp));
             }
             
@@ -519,27 +1886,254 @@ impl TranscriptionMerger {
         Ok(output)
     }
 
+    fn format_as_vtt(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let mut output = String::from("WEBVTT\n\n");
+        let (open, close) = match self.merge_options.low_confidence_style {
+            LowConfidenceStyle::Italic => ("<i>", "</i>"),
+            LowConfidenceStyle::Color => ("<c.low-confidence>", "</c>"),
+        };
+        if self.merge_options.low_confidence_threshold.is_some()
+            && self.merge_options.low_confidence_style == LowConfidenceStyle::Color
+        {
+            output.push_str(&format!("STYLE\n::cue(.low-confidence) {{\n  color: {};\n}}\n\n", LOW_CONFIDENCE_COLOR));
+        }
+
+        for (index, segment) in segments.iter().enumerate() {
+            let start = self.format_vtt_timestamp(segment.start_time);
+            let end = self.format_vtt_timestamp(self.resolved_end_time(segments, index));
+            output.push_str(&format!("{} --> {}\n", start, end));
+
+            let text = self.confidence_styled_text(segment, str::to_string, open, close);
+            if self.shows_file_marker(segment) {
+                output.push_str(&format!("[{}] {}\n\n", segment.original_filename, text));
+            } else {
+                output.push_str(&format!("{}\n\n", text));
+            }
+        }
+
+        Ok(output)
+    }
+
+    fn format_as_sbv(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let mut output = String::new();
+
+        for (index, segment) in segments.iter().enumerate() {
+            let start = format_sbv_timestamp(segment.start_time);
+            let end = format_sbv_timestamp(self.resolved_end_time(segments, index));
+            output.push_str(&format!("{},{}\n", start, end));
+
+            if self.shows_file_marker(segment) {
+                output.push_str(&format!("[{}] {}\n\n", segment.original_filename, segment.text));
+            } else {
+                output.push_str(&format!("{}\n\n", segment.text));
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// One `[mm:ss.xx]` line per segment, its lines joined. Where a segment
+    /// ends before the next one starts, an empty timed line clears it.
+    fn format_as_lrc(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let mut output = String::new();
+
+        for (index, segment) in segments.iter().enumerate() {
+            let text = segment.text.split_whitespace().collect::<Vec<_>>().join(" ");
+            output.push_str(&format!("[{}]", format_lrc_timestamp(segment.start_time)));
+            if self.shows_file_marker(segment) {
+                output.push_str(&format!("[{}] {}\n", segment.original_filename, text));
+            } else {
+                output.push_str(&format!("{}\n", text));
+            }
+
+            let end = self.resolved_end_time(segments, index);
+            let next_start = segments.get(index + 1).map_or(f64::INFINITY, |next| next.start_time);
+            if end < next_start && format_lrc_timestamp(end) != format_lrc_timestamp(next_start) {
+                output.push_str(&format!("[{}]\n", format_lrc_timestamp(end)));
+            }
+        }
+
+        Ok(output)
+    }
+
+    /// The Podcast Namespace transcript schema: `startTime`, `endTime`,
+    /// `speaker` and `body`, with the speaker label taken out of the body and
+    /// times rounded to milliseconds.
+    fn format_as_podcast_json(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let millis = |seconds: f64| (seconds * 1000.0).round() / 1000.0;
+        let items: Vec<serde_json::Value> = segments
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| {
+                let mut item = serde_json::Map::new();
+                if let Some(speaker) = &segment.speaker {
+                    item.insert("speaker".to_string(), speaker.as_str().into());
+                }
+                item.insert("startTime".to_string(), millis(segment.start_time).into());
+                item.insert("endTime".to_string(), millis(self.resolved_end_time(segments, index)).into());
+                item.insert("body".to_string(), spoken_text(segment).into());
+                serde_json::Value::Object(item)
+            })
+            .collect();
+        let document = serde_json::json!({ "version": "1.0.0", "segments": items });
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+
+    fn format_as_json(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let document = serde_json::json!({ "segments": segments });
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+
+    fn format_as_ass(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        Ok(self.format_as_ass_with_style(segments, &CaptionStyle::default()))
+    }
+
+    /// ASS script whose `Default` style comes from a caption preset.
+    pub fn format_as_ass_with_style(&self, segments: &[TranscriptionSegment], style: &CaptionStyle) -> String {
+        let mut output = String::from(ASS_SCRIPT_INFO);
+        output.push_str(ASS_STYLES_FORMAT);
+        output.push_str(&style.ass_style_line());
+        output.push_str("\n\n");
+        output.push_str(ASS_EVENTS_FORMAT);
+
+        let low_confidence_color = format!("{{\\c{}&}}", style_color_bgr(LOW_CONFIDENCE_COLOR));
+        let (open, close) = match self.merge_options.low_confidence_style {
+            LowConfidenceStyle::Italic => ("{\\i1}", "{\\i0}"),
+            // A bare \c resets to the style's primary colour
+            LowConfidenceStyle::Color => (low_confidence_color.as_str(), "{\\c}"),
+        };
+
+        for (index, segment) in segments.iter().enumerate() {
+            let start = format_ass_timestamp(segment.start_time);
+            let end = format_ass_timestamp(self.resolved_end_time(segments, index));
+            let speaker = segment.speaker.as_deref().unwrap_or_default().replace(',', " ");
+
+            let mut text = self.confidence_styled_text(segment, escape_ass_keeping_tags, open, close);
+            if self.shows_file_marker(segment) {
+                text = format!("[{}] {}", escape_ass_text(&segment.original_filename), text);
+            }
+
+            output.push_str(&format!(
+                "Dialogue: 0,{},{},Default,{},0,0,0,,{}\n",
+                start, end, speaker, text
+            ));
+        }
+
+        output
+    }
+
+    fn format_as_ttml(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let frame_rate = self.merge_options.frame_rate.filter(|rate| *rate > 0.0);
+
+        let mut timing = String::from(r#"ttp:timeBase="media""#);
+        if let Some(rate) = frame_rate {
+            let (nominal, ntsc) = ttml_frame_rate(rate);
+            timing.push_str(&format!(r#" ttp:frameRate="{}""#, nominal));
+            if ntsc {
+                timing.push_str(r#" ttp:frameRateMultiplier="1000 1001""#);
+            }
+        }
+
+        let mut output = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        output.push_str(&format!(
+            "<tt xmlns=\"http://www.w3.org/ns/ttml\" \
+             xmlns:tts=\"http://www.w3.org/ns/ttml#styling\" \
+             xmlns:ttm=\"http://www.w3.org/ns/ttml#metadata\" \
+             xmlns:ttp=\"http://www.w3.org/ns/ttml#parameter\" \
+             {} xml:lang=\"{}\">\n",
+            timing,
+            escape_xml(self.merge_options.language.as_deref().unwrap_or_default())
+        ));
+        output.push_str(TTML_HEAD);
+        output.push_str("  <body style=\"default\" region=\"bottom\">\n    <div>\n");
+
+        for (index, segment) in segments.iter().enumerate() {
+            let begin = format_ttml_time(segment.start_time, frame_rate);
+            let end = format_ttml_time(self.resolved_end_time(segments, index), frame_rate);
+
+            let mut text = escape_xml(&segment.text).replace('\n', "<br/>");
+            if self.shows_file_marker(segment) {
+                text = format!("[{}] {}", escape_xml(&segment.original_filename), text);
+            }
+            let agent = match &segment.speaker {
+                Some(speaker) => format!(" ttm:role=\"x-speaker\" ttm:agent=\"{}\"", escape_xml(speaker)),
+                None => String::new(),
+            };
+
+            output.push_str(&format!(
+                "      <p xml:id=\"s{}\" begin=\"{}\" end=\"{}\"{}>{}</p>\n",
+                index + 1, begin, end, agent, text
+            ));
+        }
+
+        output.push_str("    </div>\n  </body>\n</tt>\n");
+        Ok(output)
+    }
+
+    /// Captions for Final Cut Pro at the merge frame rate (25 fps when none
+    /// is set), in the caption role of the transcript language.
+    fn format_as_fcpxml(&self, segments: &[TranscriptionSegment]) -> Result<String> {
+        let frame_rate = self.merge_options.frame_rate.filter(|rate| *rate > 0.0).unwrap_or(25.0);
+        let labelled: Vec<TranscriptionSegment> = segments
+            .iter()
+            .map(|segment| match self.shows_file_marker(segment) {
+                true => TranscriptionSegment { text: format!("[{}] {}", segment.original_filename, segment.text), ..segment.clone() },
+                false => segment.clone(),
+            })
+            .collect();
+        let captions: Vec<Marker> = labelled
+            .iter()
+            .enumerate()
+            .map(|(index, segment)| Marker { segment, end: self.resolved_end_time(segments, index) })
+            .collect();
+        let language = self.merge_options.language.as_deref().unwrap_or("en");
+        Ok(render_fcpxml_captions(&captions, "Merged Transcription", frame_rate, language))
+    }
+
+    /// End time of a segment for timed output formats. Open-ended segments get
+    /// the default duration, or run up to the next segment when configured.
+    fn resolved_end_time(&self, segments: &[TranscriptionSegment], index: usize) -> f64 {
+        let segment = &segments[index];
+        if let Some(end_time) = segment.end_time {
+            return end_time;
+        }
+
+        if self.merge_options.extend_to_next_segment {
+            let next_start = segments[index + 1..]
+                .iter()
+                .map(|s| s.start_time)
+                .find(|&start| start > segment.start_time);
+            if let Some(next_start) = next_start {
+                return next_start.min(segment.start_time + self.merge_options.max_extend_seconds);
+            }
+        }
+
+        segment.start_time + self.merge_options.default_duration_seconds
+    }
+
     fn format_srt_timestamp(&self, seconds: f64) -> String {
-        let total_seconds = seconds as u64;
-        let hours = total_seconds / 3600;
-        let minutes = (total_seconds % 3600) / 60;
-        let secs = total_seconds % 60;
-        let millis = ((seconds - total_seconds as f64) * 1000.0) as u32;
-        
+        // Rounded rather than truncated: 6371.003 is 6371.00299... in binary
+        let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+        let hours = total_millis / 3_600_000;
+        let minutes = total_millis / 60_000 % 60;
+        let secs = total_millis / 1000 % 60;
+        let millis = total_millis % 1000;
+
         format!("{:02}:{:02}:{:02},{:03}", hours, minutes, secs, millis)
     }
 
-    fn format_txt_timestamp(&self, seconds: f64) -> String {
-        let total_seconds = seconds as u64;
-        let hours = total_seconds / 3600;
-        let minutes = (total_seconds % 3600) / 60;
-        let secs = total_seconds % 60;
-        
-        if hours > 0 {
-            format!("{:02}:{:02}:{:02}", hours, minutes, secs)
-        } else {
-            format!("{:02}:{:02}", minutes, secs)
-        }
+    fn format_vtt_timestamp(&self, seconds: f64) -> String {
+        self.format_srt_timestamp(seconds).replace(',', ".")
+    }
+
+    /// Paths of the loaded files in merge order.
+    pub fn file_order(&self) -> Vec<String> {
+        self.files.iter().map(|file| file.path.to_string_lossy().to_string()).collect()
+    }
+
+    /// File names with the language detected in each, in merge order.
+    pub fn file_languages(&self) -> Vec<(String, Option<String>)> {
+        self.files.iter().map(|file| (file.filename.clone(), file.language.clone())).collect()
     }
 
     pub fn get_file_count(&self) -> usize {
@@ -549,24 +2143,575 @@ impl TranscriptionMerger {
     pub fn get_total_segments(&self) -> usize {
         self.files.iter().map(|f| f.segments.len()).sum()
     }
+
+    pub fn get_warnings(&self) -> Vec<ParseWarning> {
+        self.files.iter().flat_map(|f| f.warnings.clone()).collect()
+    }
+}
+
+/// LRC timestamp: minutes (not wrapped at the hour), seconds and hundredths.
+fn format_lrc_timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!("{:02}:{:02}.{:02}", centis / 6000, centis / 100 % 60, centis % 100)
+}
+
+/// SBV timestamp: unpadded hours, then `MM:SS.mmm` (`0:01:02.500`).
+fn format_sbv_timestamp(seconds: f64) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
 }
 
-/// Try UTF-8 first; if invalid, fall back to Windows-1251 (common for Russian text files).
-fn read_text_with_encoding(bytes: &[u8]) -> String {
-    // Strip UTF-8 BOM if present
-    let bytes = bytes.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(bytes);
+/// Splits LF-normalized text into blank-line separated blocks, returning each
+/// block's trimmed lines together with the 1-based line number where it starts.
+fn text_blocks(normalized: &str) -> Vec<(usize, Vec<&str>)> {
+    let mut blocks = Vec::new();
+    let mut block_start_line = 1;
 
-    match std::str::from_utf8(bytes) {
-        Ok(s) => s.to_string(),
-        Err(_) => {
-            let (decoded, _, had_errors) = WINDOWS_1251.decode(bytes);
-            if had_errors {
-                // Last resort: lossy UTF-8
-                String::from_utf8_lossy(bytes).to_string()
-            } else {
-                decoded.to_string()
+    for block in normalized.split("\n\n") {
+        let line_number = block_start_line + block.lines().take_while(|l| l.trim().is_empty()).count();
+        block_start_line += block.split('\n').count() + 1;
+
+        let lines: Vec<&str> = block.trim().lines().collect();
+        if !lines.is_empty() {
+            blocks.push((line_number, lines));
+        }
+    }
+
+    blocks
+}
+
+/// Amber, readable on dark video and on white reviewer backgrounds
+const LOW_CONFIDENCE_COLOR: &str = "#ffb000";
+
+/// Prefix of segments where the speaker possibly changes, as in subtitle dialogue
+const SPEAKER_CHANGE_MARK: &str = "— ";
+/// How far an audio change point may lie after a segment start and still mark it
+const SPEAKER_CHANGE_TOLERANCE_SECONDS: f64 = 1.0;
+
+/// Speaking rate for estimates when neither it nor a known language is given
+const DEFAULT_WORDS_PER_MINUTE: f64 = 150.0;
+
+/// Marker text for excluded ranges without their own label
+const DEFAULT_AD_BREAK_LABEL: &str = "[ad break]";
+
+/// PlayRes matches 1080p so font sizes and margins are in video pixels.
+const ASS_SCRIPT_INFO: &str = "[Script Info]
+ScriptType: v4.00+
+WrapStyle: 0
+ScaledBorderAndShadow: yes
+PlayResX: 1920
+PlayResY: 1080
+
+";
+
+const ASS_STYLES_FORMAT: &str = "[V4+ Styles]
+Format: Name, Fontname, Fontsize, PrimaryColour, SecondaryColour, OutlineColour, BackColour, Bold, Italic, Underline, StrikeOut, ScaleX, ScaleY, Spacing, Angle, BorderStyle, Outline, Shadow, Alignment, MarginL, MarginR, MarginV, Encoding
+";
+
+const ASS_EVENTS_FORMAT: &str = "[Events]
+Format: Layer, Start, End, Style, Name, MarginL, MarginR, MarginV, Effect, Text
+";
+
+const TTML_HEAD: &str = r#"  <head>
+    <styling>
+      <style xml:id="default" tts:fontFamily="proportionalSansSerif" tts:textAlign="center"/>
+    </styling>
+    <layout>
+      <region xml:id="bottom" tts:origin="10% 80%" tts:extent="80% 15%" tts:displayAlign="after"/>
+    </layout>
+  </head>
+"#;
+
+/// Integer `ttp:frameRate` for a rate, and whether it needs the 1000/1001
+/// multiplier (29.97 → 30 × 1000/1001).
+fn ttml_frame_rate(rate: f64) -> (u32, bool) {
+    if (rate - rate.round()).abs() < 0.001 {
+        (rate.round() as u32, false)
+    } else {
+        ((rate * 1.001).round() as u32, true)
+    }
+}
+
+/// `HH:MM:SS.mmm`, or `HH:MM:SS:FF` when a frame rate is set.
+fn format_ttml_time(seconds: f64, frame_rate: Option<f64>) -> String {
+    let seconds = seconds.max(0.0);
+    match frame_rate {
+        Some(rate) => {
+            let (nominal, _) = ttml_frame_rate(rate);
+            let whole = seconds.floor() as u64;
+            let frames = (((seconds - whole as f64) * rate).round() as u32).min(nominal - 1);
+            format!("{:02}:{:02}:{:02}:{:02}", whole / 3600, (whole / 60) % 60, whole % 60, frames)
+        }
+        None => {
+            let millis = (seconds * 1000.0).round() as u64;
+            format!(
+                "{:02}:{:02}:{:02}.{:03}",
+                millis / 3_600_000,
+                (millis / 60_000) % 60,
+                (millis / 1000) % 60,
+                millis % 1000
+            )
+        }
+    }
+}
+
+pub(crate) fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// `H:MM:SS.cc` with centiseconds, as ASS expects.
+fn format_ass_timestamp(seconds: f64) -> String {
+    let centis = (seconds.max(0.0) * 100.0).round() as u64;
+    format!(
+        "{}:{:02}:{:02}.{:02}",
+        centis / 360_000,
+        (centis / 6000) % 60,
+        (centis / 100) % 60,
+        centis % 100
+    )
+}
+
+/// Keeps text literal: braces would start override blocks, newlines become `\N`.
+/// `#rrggbb` as the `&HBBGGRR` colour of ASS override tags.
+fn style_color_bgr(color: &str) -> String {
+    let hex = color.trim_start_matches('#');
+    let channel = |range: std::ops::Range<usize>| hex.get(range).unwrap_or("00").to_uppercase();
+    format!("&H{}{}{}", channel(4..6), channel(2..4), channel(0..2))
+}
+
+/// `{"word": " hello", "start": 1.2, "end": 1.5, "probability": 0.93}` as
+/// produced by Whisper, or `text`/`confidence`. Words with neither timing nor
+/// confidence carry nothing the text does not, and are skipped.
+fn parse_json_word(word: &serde_json::Value) -> Option<WordTiming> {
+    let text = word.get("word").or_else(|| word.get("text"))?.as_str()?.trim().to_string();
+    let number = |keys: [&str; 2]| keys.iter().find_map(|key| word.get(*key).and_then(|value| value.as_f64()));
+    let word = WordTiming {
+        text,
+        start: number(["start", "start_time"]),
+        end: number(["end", "end_time"]),
+        confidence: number(["probability", "confidence"]),
+    };
+    (word.start.is_some() || word.confidence.is_some()).then_some(word)
+}
+
+/// SRT formatting tags (`<i>`, `<b>`, `<u>`, `<s>`, `<font ...>`) and ASS
+/// override blocks (`{\an8}`)
+const FORMATTING_TAG: &str = r"(?i)</?(?:[ibus]|font)(?:\s[^>]*)?>|\{\\[^}]*\}";
+/// The formatting tags WebVTT renders
+const VTT_TAGS: &[&str] = &["<i>", "</i>", "<b>", "</b>", "<u>", "</u>"];
+
+fn strip_formatting_tags(text: &str, keep_vtt_tags: bool) -> String {
+    let tag_pattern = Regex::new(FORMATTING_TAG).unwrap();
+    let stripped = tag_pattern.replace_all(text, |captures: &Captures| {
+        let tag = captures[0].to_lowercase();
+        if keep_vtt_tags && VTT_TAGS.contains(&tag.as_str()) {
+            tag
+        } else {
+            String::new()
+        }
+    });
+    stripped.trim().to_string()
+}
+
+/// ASS dialogue text where SRT tags become the matching override codes and
+/// override blocks pass through; other braces are escaped.
+fn escape_ass_keeping_tags(text: &str) -> String {
+    let tag_pattern = Regex::new(FORMATTING_TAG).unwrap();
+    let mut output = String::new();
+    let mut last = 0;
+    for tag in tag_pattern.find_iter(text) {
+        output.push_str(&escape_ass_text(&text[last..tag.start()]));
+        output.push_str(&ass_override(tag.as_str()));
+        last = tag.end();
+    }
+    output.push_str(&escape_ass_text(&text[last..]));
+    output
+}
+
+fn ass_override(tag: &str) -> String {
+    if tag.starts_with('{') {
+        return tag.to_string();
+    }
+    let lower = tag.to_lowercase();
+    let closing = lower.starts_with("</");
+    let inner = lower.trim_start_matches("</").trim_start_matches('<').trim_end_matches('>');
+    match inner.split_whitespace().next().unwrap_or_default() {
+        name @ ("i" | "b" | "u" | "s") => format!("{{\\{}{}}}", name, if closing { 0 } else { 1 }),
+        "font" if closing => "{\\c}".to_string(),
+        "font" => Regex::new(r#"color\s*=\s*"?#([0-9a-f]{6})"#)
+            .unwrap()
+            .captures(inner)
+            .map(|captures| format!("{{\\c{}&}}", style_color_bgr(&captures[1])))
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+fn escape_ass_text(text: &str) -> String {
+    text.replace('{', "\\{")
+        .replace('}', "\\}")
+        .replace("\r\n", "\\N")
+        .replace('\n', "\\N")
+}
+
+/// Tokens of a TXT line template (`{name}`) and what they are replaced with.
+/// `{{` and `}}` write literal braces.
+pub const TEMPLATE_TOKENS: &[(&str, &str)] = &[
+    ("start", "Start time in the chosen timecode style"),
+    ("start_hms", "Start time as HH:MM:SS"),
+    ("start_hms_ms", "Start time as HH:MM:SS.mmm"),
+    ("start_seconds", "Start time in seconds (12.5)"),
+    ("end", "End time in the chosen timecode style"),
+    ("end_hms", "End time as HH:MM:SS"),
+    ("duration", "Length in seconds"),
+    ("speaker", "Speaker name, empty when unknown"),
+    ("text", "Text without a leading speaker label"),
+    ("full_text", "Text as merged, speaker label included"),
+    ("file", "Source file name"),
+    ("index", "Line number, counted from 1"),
+    ("confidence", "Recognition confidence in percent, empty when unknown"),
+];
+
+enum TemplatePart {
+    Literal(String),
+    Token(String),
+}
+
+/// A parsed TXT line template.
+struct LineTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+impl LineTemplate {
+    fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(ch) => name.push(ch),
+                            None => return Err(anyhow!("Unclosed '{{{}' in template", name)),
+                        }
+                    }
+                    let name = name.trim().to_string();
+                    if !TEMPLATE_TOKENS.iter().any(|(token, _)| *token == name) {
+                        return Err(anyhow!("Unknown template token {{{}}}", name));
+                    }
+                    if !literal.is_empty() {
+                        parts.push(TemplatePart::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(TemplatePart::Token(name));
+                }
+                '}' => return Err(anyhow!("Unmatched '}}' in template; write '}}}}' for a literal brace")),
+                _ => literal.push(ch),
             }
         }
+        if !literal.is_empty() {
+            parts.push(TemplatePart::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    fn render(&self, value: impl Fn(&str) -> String) -> String {
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Literal(text) => text.clone(),
+                TemplatePart::Token(name) => value(name),
+            })
+            .collect()
+    }
+}
+
+/// Timestamp in one of the export timecode styles: `hms`, `hms_ms`, `seconds`,
+/// `seconds_ms` or `custom` (a pattern with HH, MM, SS and MS placeholders).
+/// Returns `None` for other names and for `custom` without a pattern.
+pub(crate) fn format_timecode(seconds: f64, format: &str, custom_format: Option<&str>) -> Option<String> {
+    // Work in whole milliseconds so rounding never produces "60" seconds
+    let total_millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    let hours = total_millis / 3_600_000;
+    let minutes = (total_millis % 3_600_000) / 60_000;
+    let secs = (total_millis % 60_000) / 1000;
+    let millis = total_millis % 1000;
+
+    match format {
+        "hms" => Some(format!("{:02}:{:02}:{:02}", hours, minutes, secs)),
+        "hms_ms" => Some(format!("{:02}:{:02}:{:02}.{:03}", hours, minutes, secs, millis)),
+        "seconds" => Some((total_millis / 1000).to_string()),
+        "seconds_ms" => {
+            // Seconds with a fractional part (330.5, 330.0)
+            let formatted = format!("{}.{:03}", total_millis / 1000, millis);
+            let trimmed = formatted.trim_end_matches('0');
+            Some(if trimmed.ends_with('.') { format!("{}0", trimmed) } else { trimmed.to_string() })
+        }
+        "custom" => custom_format.map(|pattern| {
+            pattern
+                .replace("HH", &format!("{:02}", hours))
+                .replace("MM", &format!("{:02}", minutes))
+                .replace("SS", &format!("{:02}", secs))
+                .replace("MS", &format!("{:03}", millis))
+        }),
+        _ => None,
+    }
+}
+
+/// MM:SS, or HH:MM:SS from the first hour on, as used in TXT and Markdown output.
+pub(crate) fn format_txt_timestamp(seconds: f64) -> String {
+    let total_seconds = seconds as u64;
+    let hours = total_seconds / 3600;
+    let minutes = (total_seconds % 3600) / 60;
+    let secs = total_seconds % 60;
+
+    if hours > 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}", minutes, secs)
+    }
+}
+
+/// Extracts the speaker from a leading "Name:" or "Name (role):" label. The text
+/// itself is left untouched; the label must be short and start with a letter.
+pub(crate) fn detect_speaker(text: &str) -> Option<String> {
+    lazy_static::lazy_static! {
+        static ref SPEAKER_LABEL: Regex =
+            Regex::new(r"^(\p{L}[\p{L}\p{N} .'\-]{0,30}(?:\([^)]{1,30}\))?):\s").unwrap();
+    }
+
+    let name = SPEAKER_LABEL.captures(text)?.get(1)?.as_str().trim();
+    // Sentences that merely contain a colon ("Note that the plan is:") are not labels
+    if name.split_whitespace().count() > 4 {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+/// Takes WebVTT voice spans (`<v John Doe>Hi</v>`, `<v.loud Esme>`), as in
+/// Zoom and Teams exports, out of cue text. The first voice becomes the
+/// speaker; further voices in the same cue stay as "Name:" labels.
+fn strip_voice_tags(text: &str) -> (String, Option<String>) {
+    lazy_static::lazy_static! {
+        static ref VOICE_TAG: Regex = Regex::new(r"<v(?:\.[^\s>]*)?\s+([^>]+)>").unwrap();
+    }
+
+    let mut speaker = None;
+    let text = VOICE_TAG.replace_all(text, |captures: &Captures| {
+        let name = captures[1].trim().to_string();
+        if speaker.is_none() {
+            speaker = Some(name);
+            String::new()
+        } else {
+            format!(" {}: ", name)
+        }
+    });
+    let text = text.replace("</v>", "").split_whitespace().collect::<Vec<_>>().join(" ");
+    (text, speaker)
+}
+
+/// Splits text after sentence-ending punctuation (including the CJK full
+/// stop and fullwidth marks), dropping empty pieces.
+/// Splits or joins segments so that each holds one sentence. Runs of segments
+/// from the same file and speaker are cut at sentence punctuation; a cue's
+/// times come from the character positions of its first and last letters
+/// within the segments they were spoken in. Runs without any sentence
+/// punctuation are left as they are rather than joined into one long cue.
+pub(crate) fn resegment_by_sentences(segments: &[TranscriptionSegment], default_duration: f64) -> Vec<TranscriptionSegment> {
+    let mut result = Vec::with_capacity(segments.len());
+    let mut run_start = 0;
+    for index in 1..=segments.len() {
+        let ends_run = index == segments.len() || {
+            let (previous, current) = (&segments[index - 1], &segments[index]);
+            current.original_filename != previous.original_filename
+                || current.original_filename.is_empty()
+                || current.speaker != previous.speaker
+                || current.speaker_change_hint
+        };
+        if ends_run {
+            result.extend(resegment_run(&segments[run_start..index], default_duration));
+            run_start = index;
+        }
+    }
+    result
+}
+
+fn resegment_run(run: &[TranscriptionSegment], default_duration: f64) -> Vec<TranscriptionSegment> {
+    const TERMINATORS: &[char] = &['.', '!', '?', '…', '。', '！', '？'];
+    const CLOSERS: &[char] = &['"', '\'', ')', '»', '”', '」', '』'];
+
+    // Markers inserted by the merge have no file and stay as they are
+    if run.iter().any(|segment| segment.original_filename.is_empty()) {
+        return run.to_vec();
+    }
+
+    // Every character of the joined text with the time span it is spoken in
+    // and the segment it came from
+    struct Spoken {
+        ch: char,
+        start: f64,
+        end: f64,
+        segment: usize,
+    }
+    let mut chars: Vec<Spoken> = Vec::new();
+    for (segment_index, segment) in run.iter().enumerate() {
+        let text = crate::stats::spoken_text(segment);
+        let start = segment.start_time;
+        let end = segment.end_time.unwrap_or(start + default_duration).max(start);
+        let step = (end - start) / text.chars().count().max(1) as f64;
+        if !chars.is_empty() {
+            chars.push(Spoken { ch: ' ', start, end: start, segment: segment_index });
+        }
+        chars.extend(text.chars().enumerate().map(|(position, ch)| Spoken {
+            ch,
+            start: start + step * position as f64,
+            end: start + step * (position + 1) as f64,
+            segment: segment_index,
+        }));
+    }
+    if !chars.iter().any(|spoken| TERMINATORS.contains(&spoken.ch)) {
+        return run.to_vec();
+    }
+
+    let mut sentences: Vec<(usize, usize)> = Vec::new();
+    let mut sentence_start = 0;
+    let mut position = 0;
+    while position < chars.len() {
+        let mut end = position;
+        if TERMINATORS.contains(&chars[position].ch) {
+            while end + 1 < chars.len() && (TERMINATORS.contains(&chars[end + 1].ch) || CLOSERS.contains(&chars[end + 1].ch)) {
+                end += 1;
+            }
+            // "3.5" and "т.е." are not sentence ends
+            if end + 1 == chars.len() || chars[end + 1].ch.is_whitespace() {
+                sentences.push((sentence_start, end));
+                sentence_start = end + 1;
+            }
+        }
+        position = end + 1;
+    }
+    if sentence_start < chars.len() {
+        sentences.push((sentence_start, chars.len() - 1));
+    }
+
+    let label = run[0]
+        .speaker
+        .as_deref()
+        .filter(|_| crate::stats::spoken_text(&run[0]) != run[0].text.trim());
+    let mut cues = Vec::with_capacity(sentences.len());
+    for (first, last) in sentences {
+        let Some(first) = (first..=last).find(|&index| !chars[index].ch.is_whitespace()) else {
+            continue;
+        };
+        let text: String = chars[first..=last].iter().map(|spoken| spoken.ch).collect();
+        let touched = &run[chars[first].segment..=chars[last].segment];
+        let source = &run[chars[first].segment];
+
+        cues.push(TranscriptionSegment {
+            start_time: chars[first].start,
+            end_time: Some(chars[last].end),
+            text: match label {
+                Some(speaker) => format!("{}: {}", speaker, text.trim()),
+                None => text.trim().to_string(),
+            },
+            end_time_estimated: touched.iter().any(|segment| segment.end_time_estimated || segment.end_time.is_none()),
+            speaker_change_hint: cues.is_empty() && run[0].speaker_change_hint,
+            confidence: touched.iter().filter_map(|segment| segment.confidence).reduce(f64::min),
+            words: Vec::new(),
+            original_text: None,
+            heading: source.heading.clone().filter(|_| first == 0 || chars[first - 1].segment != chars[first].segment),
+            ..source.clone()
+        });
+    }
+    cues
+}
+
+pub(crate) fn split_sentences(text: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for ch in text.chars() {
+        current.push(ch);
+        if matches!(ch, '.' | '!' | '?' | '…' | '。' | '！' | '？') {
+            sentences.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+    sentences.push(current.trim().to_string());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+/// Parses a decimal number accepting either '.' or ',' as the separator.
+pub(crate) fn parse_decimal(value: &str) -> Option<f64> {
+    value.trim().replace(',', ".").parse().ok()
+}
+
+/// Letters making up most of Russian text and the accented letters common in
+/// Western European languages; text decoded with the wrong code page has few
+const FREQUENT_LETTERS: &str = "оеаинтсрвлкмдпуяéèàùâêîôûçäöüßñáíóú";
+
+/// Decodes a text file from whatever older tools wrote: a byte order mark
+/// (UTF-8, UTF-16) decides first, then UTF-16 without one is recognised by
+/// its zero bytes, then UTF-8. Anything else is read with the single-byte
+/// code page (Windows-1251, KOI8-R, Windows-1252) giving the most plausible
+/// letters.
+pub(crate) fn read_text_with_encoding(bytes: &[u8]) -> String {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        return encoding.decode_without_bom_handling(&bytes[bom_length..]).0.into_owned();
+    }
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return encoding.decode_without_bom_handling(bytes).0.into_owned();
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return text.to_string();
+    }
+
+    let plausibility = |text: &str| text.chars().filter(|ch| FREQUENT_LETTERS.contains(*ch)).count();
+    // On a tie the last candidate wins, so Windows-1251 goes last
+    [WINDOWS_1252, KOI8_R, WINDOWS_1251]
+        .iter()
+        .map(|encoding| encoding.decode_without_bom_handling(bytes).0.into_owned())
+        .max_by_key(|text| plausibility(text))
+        .unwrap_or_default()
+}
+
+/// UTF-16 without a byte order mark: the ASCII digits, spaces and line breaks
+/// of subtitle files leave a zero in every other byte.
+fn sniff_utf16(bytes: &[u8]) -> Option<&'static Encoding> {
+    let sample = &bytes[..bytes.len().min(4096) & !1];
+    let pairs = sample.len() / 2;
+    if pairs < 2 {
+        return None;
+    }
+    let zeros = |offset: usize| sample.iter().skip(offset).step_by(2).filter(|byte| **byte == 0).count();
+    let (even_zeros, odd_zeros) = (zeros(0), zeros(1));
+
+    if odd_zeros * 5 >= pairs && even_zeros * 20 < pairs {
+        Some(UTF_16LE)
+    } else if even_zeros * 5 >= pairs && odd_zeros * 20 < pairs {
+        Some(UTF_16BE)
+    } else {
+        None
     }
 }
 
@@ -574,13 +2719,46 @@ fn read_text_with_encoding(bytes: &[u8]) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_read_text_with_encoding() {
+        let text = "1\n00:00:01,000 --> 00:00:02,000\nПривет, как дела?\n";
+        let utf16le: Vec<u8> = text.encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let utf16be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+
+        assert_eq!(read_text_with_encoding(&[&[0xFF, 0xFE][..], &utf16le].concat()), text);
+        assert_eq!(read_text_with_encoding(&utf16le), text);
+        assert_eq!(read_text_with_encoding(&utf16be), text);
+        assert_eq!(read_text_with_encoding(&[&[0xEF, 0xBB, 0xBF][..], text.as_bytes()].concat()), text);
+        assert_eq!(read_text_with_encoding(&WINDOWS_1251.encode(text).0), text);
+        assert_eq!(read_text_with_encoding(&KOI8_R.encode(text).0), text);
+        assert_eq!(read_text_with_encoding(&WINDOWS_1252.encode("Très bien, ça va").0), "Très bien, ça va");
+    }
+
+    #[test]
+    fn test_txt_milliseconds_survive_to_export() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            timecode_format: Some("hms_ms".to_string()),
+            add_file_markers: false,
+            ..MergeOptions::default()
+        });
+        let content = "[05:30.250] Первая\n[05:31,5] Вторая\n[01:02:03.040] Третья\n";
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
+
+        let starts: Vec<f64> = segments.iter().map(|segment| segment.start_time).collect();
+        assert_eq!(starts, vec![330.25, 331.5, 3723.04]);
+        let output = merger.format_segments(&segments).unwrap();
+        assert!(output.contains("[00:05:30.250] Первая"));
+        assert!(output.contains("[00:05:31.500] Вторая"));
+        assert!(output.contains("[01:02:03.040] Третья"));
+    }
+
     #[test]
     fn test_parse_range_mm_ss_format() {
         let merger = TranscriptionMerger::new(MergeOptions::default());
         let content = "[00:00-01:06] Спикер: Привет, это тестовая строка.\n\
                         [01:06-02:27] Спикер: Вторая строка текста.\n\
                         [02:27-03:55] Спикер: Третья строка.\n";
-        let segments = merger.parse_txt(content, "test.txt").unwrap();
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 3);
 
@@ -606,7 +2784,7 @@ mod tests {
         let merger = TranscriptionMerger::new(MergeOptions::default());
         let content = "[0:00:00-0:01:06] First line.\n\
                         [0:01:06-0:02:27] Second line.\n";
-        let segments = merger.parse_txt(content, "test.txt").unwrap();
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 2);
         assert!((segments[0].start_time - 0.0).abs() < 0.01);
@@ -620,7 +2798,7 @@ mod tests {
         let merger = TranscriptionMerger::new(MergeOptions::default());
         let content = "[01:30] Some text here.\n\
                         [02:45] More text here.\n";
-        let segments = merger.parse_txt(content, "test.txt").unwrap();
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 2);
         assert!((segments[0].start_time - 90.0).abs() < 0.01);
@@ -631,9 +2809,8 @@ mod tests {
     async fn test_merge_two_files_with_range_timestamps() {
         let options = MergeOptions {
             output_format: FileFormat::Txt,
-            time_offset_seconds: 0.0,
-            remove_timestamps: false,
             add_file_markers: false,
+            ..MergeOptions::default()
         };
         let merger = TranscriptionMerger::new(options);
 
@@ -643,8 +2820,8 @@ mod tests {
         let content2 = "[00:00-01:30] File two, segment one.\n\
                          [01:30-03:00] File two, segment two.\n";
 
-        let segments1 = merger.parse_txt(content1, "file1.txt").unwrap();
-        let segments2 = merger.parse_txt(content2, "file2.txt").unwrap();
+        let segments1 = merger.parse_txt(content1, "file1.txt", &mut Vec::new()).unwrap();
+        let segments2 = merger.parse_txt(content2, "file2.txt", &mut Vec::new()).unwrap();
 
         // File 1: last segment ends at 120s (2:00)
         assert!((segments1.last().unwrap().end_time.unwrap() - 120.0).abs() < 0.01);
@@ -662,7 +2839,7 @@ mod tests {
 [01:06-02:27] Алён: Привет. Я, наверное, я скажу те вещи.\n\
 [02:27-03:55] Алён: Что стоит объяснить, вопрос к декану.\n";
 
-        let segments = merger.parse_txt(content, "Транскрипция 1.txt").unwrap();
+        let segments = merger.parse_txt(content, "Транскрипция 1.txt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 3);
 
@@ -689,7 +2866,7 @@ mod tests {
         // Real files have empty lines at the end
         let merger = TranscriptionMerger::new(MergeOptions::default());
         let content = "[00:00-01:00] First line.\n[01:00-02:00] Second line.\n\n\n\n";
-        let segments = merger.parse_txt(content, "test.txt").unwrap();
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 2);
         assert!((segments[1].end_time.unwrap() - 120.0).abs() < 0.01);
@@ -700,7 +2877,7 @@ mod tests {
         let merger = TranscriptionMerger::new(MergeOptions::default());
         // Simulate Windows-style CRLF line endings
         let content = "1\r\n00:00:00,000 --> 00:00:05,000\r\nFirst subtitle.\r\n\r\n2\r\n00:00:05,000 --> 00:00:10,000\r\nSecond subtitle.\r\n\r\n";
-        let segments = merger.parse_srt(content, "test.srt").unwrap();
+        let segments = merger.parse_srt(content, "test.srt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 2);
         assert!((segments[0].start_time - 0.0).abs() < 0.01);
@@ -711,6 +2888,827 @@ mod tests {
         assert_eq!(segments[1].text, "Second subtitle.");
     }
 
+    #[test]
+    fn test_parse_srt_collects_warnings_for_malformed_blocks() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        let content = "1\n00:00:00,000 --> 00:00:05,000\nFirst subtitle.\n\n\
+                       2\n00:00:05,000 00:00:10,000\nNo arrow here.\n\n\
+                       3\n00:00:xx,000 --> 00:00:15,000\nBroken time.\n\n\
+                       4\n00:00:15,000 --> 00:00:20,000\n";
+        let mut warnings = Vec::new();
+        let segments = merger.parse_srt(content, "test.srt", &mut warnings).unwrap();
+
+        assert_eq!(segments.len(), 1);
+        assert_eq!(warnings.len(), 3);
+        assert!(warnings.iter().all(|w| w.file == "test.srt"));
+        assert_eq!(warnings[0].line, 6);
+        assert!(warnings[0].reason.contains("Missing timestamp"));
+        assert_eq!(warnings[1].line, 10);
+        assert_eq!(warnings[2].line, 13);
+        assert!(warnings[2].reason.contains("Incomplete"));
+    }
+
+    #[tokio::test]
+    async fn test_strict_mode_rejects_malformed_file() {
+        let path = std::env::temp_dir().join(format!("strict_{}.srt", uuid::Uuid::new_v4()));
+        std::fs::write(&path, "1\n00:00:00,000 --> 00:00:05,000\nOk.\n\n2\nnot a timestamp\nBroken.\n").unwrap();
+        let files = vec![path.to_string_lossy().to_string()];
+
+        let mut lenient = TranscriptionMerger::new(MergeOptions::default());
+        lenient.add_files(files.clone()).await.expect("Lenient mode should skip the broken block");
+        assert_eq!(lenient.get_total_segments(), 1);
+        assert_eq!(lenient.get_warnings().len(), 1);
+
+        let mut strict = TranscriptionMerger::new(MergeOptions {
+            parse_mode: ParseMode::Strict,
+            ..MergeOptions::default()
+        });
+        let error = strict.add_files(files).await.unwrap_err();
+        assert!(error.to_string().contains("strict mode"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_parse_vtt_with_identifiers_and_settings() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        let content = "WEBVTT - Lecture\n\n\
+                       NOTE exported by a tool\n\n\
+                       intro\n00:01.500 --> 00:04.000 align:start\nHello there.\n\n\
+                       01:00:00.000 --> 01:00:02.250\nSecond cue\nspans lines.\n";
+        let mut warnings = Vec::new();
+        let segments = merger.parse_vtt(content, "test.vtt", &mut warnings).unwrap();
+
+        assert!(warnings.is_empty());
+        assert_eq!(segments.len(), 2);
+        assert!((segments[0].start_time - 1.5).abs() < 0.001);
+        assert!((segments[0].end_time.unwrap() - 4.0).abs() < 0.001);
+        assert_eq!(segments[0].text, "Hello there.");
+        assert!((segments[1].start_time - 3600.0).abs() < 0.001);
+        assert_eq!(segments[1].text, "Second cue spans lines.");
+    }
+
+    #[test]
+    fn test_sbv_round_trip() {
+        let merger = TranscriptionMerger::new(MergeOptions { add_file_markers: false, ..MergeOptions::default() });
+        let content = "0:00:01.000,0:00:04.000\r\nHello there.\r\n\r\n1:02:03.250,1:02:05.500\r\nSecond caption\r\nspans lines.\r\n";
+        assert_eq!(merger.detect_format(Path::new("captions.sbv"), content).unwrap(), FileFormat::Sbv);
+        // Downloads without the extension used to fall through to TXT
+        assert_eq!(merger.detect_format(Path::new("captions"), content).unwrap(), FileFormat::Sbv);
+        assert_eq!(merger.detect_format(Path::new("captions.txt"), content).unwrap(), FileFormat::Sbv);
+
+        let mut warnings = Vec::new();
+        let segments = merger.parse_sbv(content, "captions.sbv", &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(segments.len(), 2);
+        assert!((segments[1].start_time - 3723.25).abs() < 0.001);
+        assert_eq!(segments[1].text, "Second caption spans lines.");
+
+        assert_eq!(
+            merger.format_as_sbv(&segments).unwrap(),
+            "0:00:01.000,0:00:04.000\nHello there.\n\n1:02:03.250,1:02:05.500\nSecond caption spans lines.\n\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_sub_formats() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        let subviewer = "[INFORMATION]\n[TITLE]Demo\n[END INFORMATION]\n[SUBTITLE]\n00:00:01.50,00:00:03.00\nOne[br]two\n";
+        let microdvd = "{1}{1}23.976\n{48}{96}{y:b}Bold|line\n";
+        assert_eq!(merger.detect_format(Path::new("old.sub"), subviewer).unwrap(), FileFormat::SubViewer);
+        assert_eq!(merger.detect_format(Path::new("old.sub"), microdvd).unwrap(), FileFormat::MicroDvd);
+        // MicroDVD often came as .txt and used to be read as plain text
+        assert_eq!(merger.detect_format(Path::new("old.txt"), microdvd).unwrap(), FileFormat::MicroDvd);
+        assert!(merger.detect_format(Path::new("movie.sub"), "\u{0}\u{1}binary").is_err());
+
+        let segments = merger.parse_subviewer(subviewer, "old.sub", &mut Vec::new()).unwrap();
+        assert_eq!((segments[0].start_time, segments[0].end_time), (1.5, Some(3.0)));
+        assert_eq!(segments[0].text, "One two");
+
+        let segments = merger.parse_microdvd(microdvd, "old.sub", &mut Vec::new()).unwrap();
+        assert_eq!(segments.len(), 1);
+        assert!((segments[0].start_time - 48.0 / 23.976).abs() < 1e-9);
+        assert_eq!(segments[0].text, "Bold line");
+
+        let pal = TranscriptionMerger::new(MergeOptions { microdvd_frame_rate: Some(25.0), ..MergeOptions::default() });
+        let segments = pal.parse_microdvd(microdvd, "old.sub", &mut Vec::new()).unwrap();
+        assert_eq!((segments[0].start_time, segments[0].end_time), (1.92, Some(3.84)));
+    }
+
+    #[test]
+    fn test_format_as_lrc() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            output_format: FileFormat::Lrc,
+            add_file_markers: false,
+            ..MergeOptions::default()
+        });
+        let segments = vec![
+            TranscriptionSegment::test(12.345, 15.0, "First verse,\nsecond line"),
+            TranscriptionSegment::test(15.004, 18.5, "Chorus"),
+            TranscriptionSegment::test(3725.5, 3728.0, "Long outro"),
+        ];
+
+        assert_eq!(
+            merger.format_segments(&segments).unwrap(),
+            "[00:12.35]First verse, second line\n[00:15.00]Chorus\n[00:18.50]\n[62:05.50]Long outro\n[62:08.00]\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_teams_transcript() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        let paragraphs = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect::<Vec<_>>();
+
+        let current = paragraphs(&[
+            "Weekly sync",
+            "3 October 2024, 14:00",
+            "John Doe started transcription",
+            "John Doe   0:03",
+            "Good afternoon, everyone.",
+            "Мария Иванова\t1:02:15",
+            "Спасибо.",
+            "Ещё одна мысль.",
+            "John Doe   1:02:20",
+        ]);
+        let mut warnings = Vec::new();
+        let segments = merger.parse_teams_transcript(&current, "sync.docx", &mut warnings);
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "John Doe: Good afternoon, everyone.");
+        assert_eq!(segments[0].start_time, 3.0);
+        assert_eq!(segments[1].speaker.as_deref(), Some("Мария Иванова"));
+        assert_eq!(segments[1].text, "Мария Иванова: Спасибо. Ещё одна мысль.");
+        assert_eq!(segments[1].start_time, 3735.0);
+        assert_eq!((warnings.len(), warnings[0].line), (1, 9));
+
+        let legacy = paragraphs(&["0:0:0.0 --> 0:0:3.740", "Kate Smith", "Hi everyone."]);
+        let segments = merger.parse_teams_transcript(&legacy, "old.docx", &mut Vec::new());
+        assert_eq!(segments[0].speaker.as_deref(), Some("Kate Smith"));
+        assert_eq!((segments[0].start_time, segments[0].end_time), (0.0, Some(3.74)));
+        assert_eq!(segments[0].text, "Kate Smith: Hi everyone.");
+    }
+
+    #[test]
+    fn test_parse_vtt_voice_tags() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        // Teams: GUID cue identifiers and closed voice spans
+        let content = "WEBVTT\n\n\
+                       2b2f8c1e-7d3a-4c1e-9a57-3f4f0c6c2d11/15-0\n00:00:01.000 --> 00:00:03.500\n<v John Doe>Good morning, everyone.</v>\n\n\
+                       2b2f8c1e-7d3a-4c1e-9a57-3f4f0c6c2d11/16-0\n00:00:03.500 --> 00:00:05.000\n<v.loud Мария Иванова>Доброе утро!</v> <v John Doe>Let's start.</v>\n\n\
+                       00:00:05.000 --> 00:00:06.000\nNo voice here.\n";
+        let segments = merger.parse_vtt(content, "meeting.vtt", &mut Vec::new()).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert_eq!(segments[0].speaker.as_deref(), Some("John Doe"));
+        assert_eq!(segments[0].text, "John Doe: Good morning, everyone.");
+        assert_eq!(crate::stats::spoken_text(&segments[0]), "Good morning, everyone.");
+        assert_eq!(segments[1].speaker.as_deref(), Some("Мария Иванова"));
+        assert_eq!(segments[1].text, "Мария Иванова: Доброе утро! John Doe: Let's start.");
+        assert_eq!((segments[2].speaker.as_deref(), segments[2].text.as_str()), (None, "No voice here."));
+    }
+
+    #[tokio::test]
+    async fn test_json_round_trip() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            output_format: FileFormat::Json,
+            ..MergeOptions::default()
+        });
+        let segments = merger
+            .parse_srt("1\n00:00:01,250 --> 00:00:03,000\nHello.\n", "a.srt", &mut Vec::new())
+            .unwrap();
+        let json = merger.format_as_json(&segments).unwrap();
+
+        let mut warnings = Vec::new();
+        let parsed = merger.parse_json(&json, "a.json", &mut warnings).unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(parsed.len(), 1);
+        assert!((parsed[0].start_time - 1.25).abs() < 0.001);
+        assert_eq!(parsed[0].end_time, Some(3.0));
+        assert_eq!(parsed[0].text, "Hello.");
+
+        // Whisper-style keys
+        let whisper = r#"{"segments": [{"start": 0.0, "end": 2.0, "text": " Hi"}, {"start": 2.0}]}"#;
+        let parsed = merger.parse_json(whisper, "w.json", &mut warnings).unwrap();
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(parsed[0].text, "Hi");
+        assert_eq!(warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_podcast_json_round_trip() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            output_format: FileFormat::PodcastJson,
+            ..MergeOptions::default()
+        });
+        let segments = vec![
+            TranscriptionSegment {
+                start_time: 0.5,
+                end_time: Some(2.3000000000000003),
+                text: "Anna: Welcome to the show.".to_string(),
+                speaker: Some("Anna".to_string()),
+                ..Default::default()
+            },
+            TranscriptionSegment { start_time: 2.5, end_time: None, text: "Thanks.".to_string(), ..Default::default() },
+        ];
+        let json = merger.format_segments(&segments).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(document["version"], "1.0.0");
+        assert_eq!(
+            document["segments"][0],
+            serde_json::json!({ "speaker": "Anna", "startTime": 0.5, "endTime": 2.3, "body": "Welcome to the show." })
+        );
+        assert!(document["segments"][1].get("speaker").is_none());
+        assert_eq!(document["segments"][1]["endTime"], 7.5);
+
+        let parsed = merger.parse_json(&json, "episode.json", &mut Vec::new()).unwrap();
+        assert_eq!(parsed[0].speaker.as_deref(), Some("Anna"));
+        assert_eq!((parsed[0].start_time, parsed[0].end_time), (0.5, Some(2.3)));
+        assert_eq!(parsed[1].text, "Thanks.");
+    }
+
+    #[test]
+    fn test_open_ended_segments_use_configured_durations() {
+        let open = |start_time: f64| TranscriptionSegment::test(start_time, None, "text").file("a.json");
+        let segments = vec![open(0.0), open(2.0), open(30.0)];
+
+        let merger = TranscriptionMerger::new(MergeOptions {
+            default_duration_seconds: 3.0,
+            ..MergeOptions::default()
+        });
+        assert!((merger.resolved_end_time(&segments, 0) - 3.0).abs() < 0.001);
+
+        let merger = TranscriptionMerger::new(MergeOptions {
+            default_duration_seconds: 3.0,
+            extend_to_next_segment: true,
+            max_extend_seconds: 8.0,
+            ..MergeOptions::default()
+        });
+        // Extended up to the next start
+        assert!((merger.resolved_end_time(&segments, 0) - 2.0).abs() < 0.001);
+        // Capped by max_extend_seconds
+        assert!((merger.resolved_end_time(&segments, 1) - 10.0).abs() < 0.001);
+        // Last segment falls back to the default duration
+        assert!((merger.resolved_end_time(&segments, 2) - 33.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_format_as_ass() {
+        let segments = vec![
+            TranscriptionSegment {
+                start_time: 3723.456,
+                end_time: Some(3725.0),
+                text: "Анна: Привет, {мир}\nвторая строка".to_string(),
+                speaker: Some("Анна".to_string()),
+                ..Default::default()
+            },
+        ];
+        let merger = TranscriptionMerger::new(MergeOptions {
+            output_format: FileFormat::Ass,
+            add_file_markers: false,
+            ..MergeOptions::default()
+        });
+
+        let output = merger.format_segments(&segments).unwrap();
+
+        assert!(output.starts_with("[Script Info]\n"));
+        assert!(output.contains("[V4+ Styles]\n"));
+        assert!(output.ends_with(
+            "Dialogue: 0,1:02:03.46,1:02:05.00,Default,Анна,0,0,0,,Анна: Привет, \\{мир\\}\\Nвторая строка\n"
+        ));
+    }
+
+    #[test]
+    fn test_formatting_tags_per_output() {
+        let segments = vec![TranscriptionSegment {
+            start_time: 1.0,
+            end_time: Some(2.0),
+            text: "{\\an8}<i>Тихо</i>, <font color=\"#FF8000\">громко</font> {мир}".to_string(),
+            ..Default::default()
+        }];
+        let format = |output_format: FileFormat| {
+            TranscriptionMerger::new(MergeOptions {
+                output_format,
+                add_file_markers: false,
+                remove_timestamps: true,
+                strip_formatting_tags: true,
+                ..MergeOptions::default()
+            })
+            .format_segments(&segments)
+            .unwrap()
+        };
+
+        assert_eq!(format(FileFormat::Txt).trim(), "Тихо, громко {мир}");
+        assert!(format(FileFormat::Vtt).contains("\n<i>Тихо</i>, громко {мир}\n"));
+        assert!(format(FileFormat::Srt).contains("\n{\\an8}<i>Тихо</i>, <font color=\"#FF8000\">громко</font> {мир}\n"));
+        assert!(format(FileFormat::Ass).ends_with(",,{\\an8}{\\i1}Тихо{\\i0}, {\\c&H0080FF&}громко{\\c} \\{мир\\}\n"));
+    }
+
+    #[test]
+    fn test_format_as_ttml() {
+        let segments = vec![TranscriptionSegment {
+            start_time: 61.5,
+            end_time: Some(63.0),
+            text: "Q&A: <важно>\nвторая".to_string(),
+            speaker: Some("Анна".to_string()),
+            ..Default::default()
+        }];
+        let merger = |frame_rate| {
+            TranscriptionMerger::new(MergeOptions {
+                output_format: FileFormat::Ttml,
+                add_file_markers: false,
+                frame_rate,
+                ..MergeOptions::default()
+            })
+        };
+
+        let plain = merger(None).format_segments(&segments).unwrap();
+        assert!(plain.contains(r#"<p xml:id="s1" begin="00:01:01.500" end="00:01:03.000" ttm:role="x-speaker" ttm:agent="Анна">Q&amp;A: &lt;важно&gt;<br/>вторая</p>"#));
+        assert!(!plain.contains("ttp:frameRate"));
+
+        let ntsc = merger(Some(29.97)).format_segments(&segments).unwrap();
+        assert!(ntsc.contains(r#"ttp:frameRate="30" ttp:frameRateMultiplier="1000 1001""#));
+        assert!(ntsc.contains(r#"begin="00:01:01:15""#));
+    }
+
+    #[tokio::test]
+    async fn test_excluded_ranges_shift_timeline() {
+        let directory = std::env::temp_dir().join(format!("merger_ads_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("show.srt");
+        std::fs::write(
+            &path,
+            "1\n00:00:05,000 --> 00:00:08,000\nДо рекламы\n\n2\n00:00:12,000 --> 00:00:15,000\nПосле рекламы\n\n",
+        )
+        .unwrap();
+
+        let mut merger = TranscriptionMerger::new(MergeOptions {
+            excluded_ranges: vec![ExcludedRange { start: 10.0, end: 70.0, label: None }],
+            ad_break_markers: true,
+            ..MergeOptions::default()
+        });
+        merger.add_files(vec![path.to_string_lossy().to_string()]).await.unwrap();
+
+        let segments = merger.merged_segments();
+        let timeline: Vec<(f64, &str)> = segments.iter().map(|s| (s.start_time, s.text.as_str())).collect();
+        assert_eq!(timeline, vec![(5.0, "До рекламы"), (10.0, "[ad break]"), (72.0, "После рекламы")]);
+        assert_eq!(segments[2].end_time, Some(75.0));
+
+        // Word timings move with their segment
+        let path = directory.join("show.json");
+        std::fs::write(
+            &path,
+            r#"[{"start": 12.0, "end": 15.0, "text": "После рекламы",
+                 "words": [{"word": "После", "start": 12.0, "end": 12.6}, {"word": "рекламы", "start": 12.6, "end": 15.0}]}]"#,
+        )
+        .unwrap();
+        let mut merger = TranscriptionMerger::new(MergeOptions {
+            excluded_ranges: vec![ExcludedRange { start: 10.0, end: 70.0, label: None }],
+            ..MergeOptions::default()
+        });
+        merger.add_files(vec![path.to_string_lossy().to_string()]).await.unwrap();
+        let words = &merger.merged_segments()[0].words;
+        assert_eq!((words[0].start, words[1].end), (Some(72.0), Some(75.0)));
+
+        // Overlapping ranges shift once and share one marker
+        let mut merger = TranscriptionMerger::new(MergeOptions {
+            excluded_ranges: vec![
+                ExcludedRange { start: 60.0, end: 80.0, label: None },
+                ExcludedRange { start: 10.0, end: 70.0, label: None },
+            ],
+            ad_break_markers: true,
+            ..MergeOptions::default()
+        });
+        merger.add_files(vec![directory.join("show.srt").to_string_lossy().to_string()]).await.unwrap();
+        let segments = merger.merged_segments();
+        let timeline: Vec<(f64, Option<f64>)> = segments.iter().map(|s| (s.start_time, s.end_time)).collect();
+        assert_eq!(timeline, vec![(5.0, Some(8.0)), (10.0, Some(80.0)), (82.0, Some(85.0))]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_preserve_input_order() {
+        let directory = std::env::temp_dir().join(format!("merger_order_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let files: Vec<String> = ["part 2.txt", "intro.txt", "part 1.txt"]
+            .iter()
+            .map(|name| {
+                std::fs::write(directory.join(name), "[00:01] Текст\n").unwrap();
+                directory.join(name).to_string_lossy().to_string()
+            })
+            .collect();
+        let order = |preserve_input_order: bool| {
+            let files = files.clone();
+            async move {
+                let mut merger = TranscriptionMerger::new(MergeOptions { preserve_input_order, ..MergeOptions::default() });
+                merger.add_files(files).await.unwrap();
+                merger
+                    .file_order()
+                    .iter()
+                    .map(|path| Path::new(path).file_name().unwrap().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+            }
+        };
+
+        assert_eq!(order(false).await, vec!["part 1.txt", "part 2.txt", "intro.txt"]);
+        assert_eq!(order(true).await, vec!["part 2.txt", "intro.txt", "part 1.txt"]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_chunk_manifest_places_files_exactly() {
+        use crate::audio::ManifestChunk;
+
+        let directory = std::env::temp_dir().join(format!("merger_manifest_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let files: Vec<String> = [
+            ("chunk_001.srt", "1\n00:00:01,000 --> 00:00:04,000\nПервый\n\n2\n00:20:00,000 --> 00:20:03,000\nКонец первого\n\n"),
+            ("chunk_002.srt", "1\n00:00:02,000 --> 00:00:05,000\nВторой\n\n"),
+            ("part 3.srt", "1\n00:00:00,500 --> 00:00:02,000\nТретий\n\n"),
+        ]
+        .iter()
+        .map(|(name, content)| {
+            std::fs::write(directory.join(name), content).unwrap();
+            directory.join(name).to_string_lossy().to_string()
+        })
+        .collect();
+        let chunk = |number: usize, start_time: f64, duration: f64| ManifestChunk {
+            file_name: format!("chunk_{:03}.mp3", number),
+            chunk_number: number,
+            start_time,
+            duration,
+        };
+
+        let mut merger = TranscriptionMerger::new(MergeOptions {
+            chunk_manifest: Some(ChunkManifest {
+                source: "show.mp3".to_string(),
+                chunks: vec![chunk(1, 0.0, 1795.5), chunk(2, 1795.5, 1200.0)],
+                ..ChunkManifest::default()
+            }),
+            excluded_ranges: vec![ExcludedRange { start: 10.0, end: 70.0, label: None }],
+            ..MergeOptions::default()
+        });
+        merger.add_files(files).await.unwrap();
+
+        // The last file is not in the manifest and follows the second chunk
+        let starts: Vec<f64> = merger.merged_segments().iter().map(|s| s.start_time).collect();
+        assert_eq!(starts, vec![1.0, 1200.0, 1797.5, 2996.0]);
+
+        // Nudges move one file without shifting the ones after it
+        merger.merge_options.file_offsets = HashMap::from([
+            ("chunk_001.srt".to_string(), -2.5),
+            ("chunk_002.srt".to_string(), 3.0),
+        ]);
+        let starts: Vec<f64> = merger.merged_segments().iter().map(|s| s.start_time).collect();
+        assert_eq!(starts, vec![0.0, 1197.5, 1800.5, 2996.0]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_txt_timecode_format_keeps_milliseconds() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            add_file_markers: false,
+            timecode_format: Some("custom".to_string()),
+            custom_timecode_format: Some("HH:MM:SS,MS".to_string()),
+            ..MergeOptions::default()
+        });
+        let segments = vec![TranscriptionSegment {
+            start_time: 3725.25,
+            end_time: Some(3727.0),
+            text: "Привет".to_string(),
+            ..Default::default()
+        }];
+
+        assert_eq!(merger.format_as_txt(&segments).unwrap(), "[01:02:05,250] Привет\n");
+        assert_eq!(format_timecode(59.9996, "hms_ms", None).unwrap(), "00:01:00.000");
+        assert_eq!(format_timecode(5.0, "custom", None), None);
+    }
+
+    #[test]
+    fn test_word_timings() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        let whisper = r#"[{"start": 0.0, "end": 1.0, "text": " Добрый день", "words": [
+            {"word": " Добрый", "start": 0.0, "end": 0.42, "probability": 0.9},
+            {"word": " день", "start": 0.42, "end": 1.0}
+        ]}]"#;
+        let segments = merger.parse_json(whisper, "w.json", &mut Vec::new()).unwrap();
+        assert_eq!(
+            segments[0].words[1],
+            WordTiming { text: "день".to_string(), start: Some(0.42), end: Some(1.0), confidence: None }
+        );
+
+        let vtt = "WEBVTT\n\n00:00:01.000 --> 00:00:03.000\nДобрый<00:00:01.500><c> день</c><00:00:02.000><c> всем вам</c>\n";
+        let segments = merger.parse_vtt(vtt, "a.vtt", &mut Vec::new()).unwrap();
+        assert_eq!(segments[0].text, "Добрый день всем вам");
+        let timings: Vec<(&str, f64, f64)> = segments[0]
+            .words
+            .iter()
+            .map(|word| (word.text.as_str(), word.start.unwrap(), word.end.unwrap()))
+            .collect();
+        assert_eq!(timings, vec![("Добрый", 1.0, 1.5), ("день", 1.5, 2.0), ("всем", 2.0, 2.5), ("вам", 2.5, 3.0)]);
+
+        // Word times move with the file offset
+        let mut merger = TranscriptionMerger::new(MergeOptions { time_offset_seconds: 10.0, ..MergeOptions::default() });
+        merger.files.push(TranscriptionFile {
+            path: PathBuf::from("a.vtt"),
+            filename: "a.vtt".to_string(),
+            sequence_number: None,
+            format: FileFormat::Vtt,
+            segments,
+            warnings: Vec::new(),
+            language: None,
+        });
+        let merged = merger.merged_segments();
+        assert_eq!(merged[0].words[3].end, Some(13.0));
+    }
+
+    #[test]
+    fn test_low_confidence_styling() {
+        let whisper = r#"{"segments": [
+            {"start": 0.0, "end": 2.0, "text": " Сегодня {пробуем} сегодня", "words": [
+                {"word": " Сегодня", "probability": 0.95},
+                {"word": " {пробуем}", "probability": 0.41},
+                {"word": " сегодня", "probability": 0.38}
+            ]},
+            {"start": 2.0, "end": 4.0, "text": "Неразборчиво", "avg_logprob": -1.2}
+        ]}"#;
+        let mut merger = TranscriptionMerger::new(MergeOptions {
+            add_file_markers: false,
+            low_confidence_threshold: Some(0.5),
+            ..MergeOptions::default()
+        });
+        let segments = merger.parse_json(whisper, "w.json", &mut Vec::new()).unwrap();
+        assert_eq!(segments[0].words.len(), 3);
+        assert!((segments[1].confidence.unwrap() - 0.301).abs() < 0.001);
+
+        let ass = merger.format_as_ass(&segments).unwrap();
+        assert!(ass.contains(",,Сегодня {\\i1}\\{пробуем\\}{\\i0} {\\i1}сегодня{\\i0}\n"));
+        assert!(ass.contains(",,{\\i1}Неразборчиво{\\i0}\n"));
+
+        merger.merge_options.low_confidence_style = LowConfidenceStyle::Color;
+        let vtt = merger.format_as_vtt(&segments).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\nSTYLE\n::cue(.low-confidence) {\n  color: #ffb000;\n}\n\n"));
+        assert!(vtt.contains("Сегодня <c.low-confidence>{пробуем}</c> <c.low-confidence>сегодня</c>\n"));
+        let ass = merger.format_as_ass(&segments).unwrap();
+        assert!(ass.contains(",,{\\c&H00B0FF&}Неразборчиво{\\c}\n"));
+    }
+
+    #[tokio::test]
+    async fn test_speaker_mapping_per_file() {
+        let directory = std::env::temp_dir().join(format!("merger_speakers_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let first = directory.join("part1.txt");
+        let second = directory.join("part2.txt");
+        std::fs::write(&first, "[00:00-00:05] Speaker 1: Добрый день.\n[00:05-00:10] Speaker 2: Здравствуйте.\n").unwrap();
+        std::fs::write(&second, "[00:00-00:05] Speaker 1: Продолжим.\n").unwrap();
+
+        let mapping = serde_json::json!({
+            "part1.txt": { "Speaker 1": "Ведущий", "Speaker 2": "Гость" },
+            "part2.txt": { "Speaker 1": "Гость" },
+        });
+        let mut merger = TranscriptionMerger::new(MergeOptions {
+            speaker_mapping: serde_json::from_value(mapping).unwrap(),
+            ..MergeOptions::default()
+        });
+        merger
+            .add_files(vec![first.to_string_lossy().to_string(), second.to_string_lossy().to_string()])
+            .await
+            .unwrap();
+
+        let speakers = merger.speakers_by_file();
+        assert_eq!(speakers[0], ("part1.txt".to_string(), vec!["Speaker 1".to_string(), "Speaker 2".to_string()]));
+
+        let segments = merger.merged_segments();
+        let labelled: Vec<(Option<&str>, &str)> = segments.iter().map(|s| (s.speaker.as_deref(), s.text.as_str())).collect();
+        assert_eq!(labelled, vec![
+            (Some("Ведущий"), "Ведущий: Добрый день."),
+            (Some("Гость"), "Гость: Здравствуйте."),
+            (Some("Гость"), "Гость: Продолжим."),
+        ]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_speaker_change_markers() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            speaker_change_markers: true,
+            speaker_changes: vec![8.4, 30.0],
+            ..MergeOptions::default()
+        });
+        let mut segments = vec![
+            TranscriptionSegment::test(0.0, 4.0, "Добрый вечер.").file("show.srt"),
+            TranscriptionSegment::test(4.0, 8.0, "Сегодня у нас гость.").file("show.srt"),
+            // Voice change in the audio just after the segment start
+            TranscriptionSegment::test(8.0, 12.0, "Спасибо за приглашение.").file("show.srt"),
+            // Long pause before
+            TranscriptionSegment::test(15.0, 18.0, "Начнём с главного.").file("show.srt"),
+            TranscriptionSegment::test(30.0, 34.0, "Анна: Я отвечу.").file("show.srt").speaker("Анна"),
+        ];
+
+        merger.mark_speaker_changes(&mut segments);
+
+        let hints: Vec<bool> = segments.iter().map(|s| s.speaker_change_hint).collect();
+        assert_eq!(hints, vec![false, false, true, true, false]);
+        assert_eq!(segments[2].text, "— Спасибо за приглашение.");
+        assert_eq!(segments[4].text, "Анна: Я отвечу.");
+    }
+
+    #[test]
+    fn test_infer_end_times_removes_overlaps() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            infer_end_times: true,
+            max_extend_seconds: 6.0,
+            ..MergeOptions::default()
+        });
+        // Long lines right after each other: word-count estimates overlap the next cue
+        let content = "[00:00] one two three four five six seven eight nine ten eleven twelve\n\
+                       [00:02] short\n\
+                       [00:20] final words here\n\
+                       [00:30-00:40] explicit range keeps its end\n";
+        let mut segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
+        assert!(segments[0].end_time.unwrap() > segments[1].start_time);
+
+        merger.infer_end_times(&mut segments);
+
+        assert!((segments[0].end_time.unwrap() - 2.0).abs() < 0.001);
+        // Capped at start + max_extend_seconds
+        assert!((segments[1].end_time.unwrap() - 8.0).abs() < 0.001);
+        assert!((segments[2].end_time.unwrap() - 26.0).abs() < 0.001);
+        assert!((segments[3].end_time.unwrap() - 40.0).abs() < 0.001);
+        assert!(!segments[3].end_time_estimated);
+    }
+
+    #[test]
+    fn test_detect_speaker() {
+        assert_eq!(detect_speaker("Алён: Привет.").as_deref(), Some("Алён"));
+        assert_eq!(detect_speaker("Ведущий (модератор): Да.").as_deref(), Some("Ведущий (модератор)"));
+        assert_eq!(detect_speaker("So what we want to do now is this: nothing"), None);
+        assert_eq!(detect_speaker("No label here."), None);
+    }
+
+    #[test]
+    fn test_comma_decimal_timestamps() {
+        let merger = TranscriptionMerger::new(MergeOptions::default());
+        let content = "[0:01:02,5] Half a second past.\n\
+                       [01:10,25-01:12,5] Range with commas.\n\
+                       [90,5] Bracketed seconds.\n";
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
+
+        assert_eq!(segments.len(), 3);
+        assert!((segments[0].start_time - 62.5).abs() < 0.001);
+        assert!((segments[1].start_time - 70.25).abs() < 0.001);
+        assert!((segments[1].end_time.unwrap() - 72.5).abs() < 0.001);
+        assert_eq!(segments[1].text, "Range with commas.");
+        assert!((segments[2].start_time - 90.5).abs() < 0.001);
+
+        let json = r#"[{"start": "1,5", "end": "3,25", "text": "Strings"}]"#;
+        let segments = merger.parse_json(json, "a.json", &mut Vec::new()).unwrap();
+        assert!((segments[0].start_time - 1.5).abs() < 0.001);
+        assert!((segments[0].end_time.unwrap() - 3.25).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_words_per_minute_controls_estimates() {
+        let merger = TranscriptionMerger::new(MergeOptions {
+            words_per_minute: Some(60.0),
+            language: Some("ru".to_string()),
+            ..MergeOptions::default()
+        });
+        let segments = merger.parse_txt("[00:10] one two three four five\n", "test.txt", &mut Vec::new()).unwrap();
+        assert!((segments[0].end_time.unwrap() - 15.0).abs() < 0.001);
+
+        // Without a rate the language's typical one applies: 120 wpm for Russian
+        let merger = TranscriptionMerger::new(MergeOptions { language: Some("ru".to_string()), ..MergeOptions::default() });
+        assert_eq!(merger.merge_options.speaking_rate(), 120.0);
+        assert_eq!(MergeOptions::default().speaking_rate(), 150.0);
+
+        let options = MergeOptions {
+            profanity_languages: vec!["auto".to_string(), "en".to_string()],
+            language: Some("ru".to_string()),
+            ..MergeOptions::default()
+        };
+        assert_eq!(options.resolved_profanity_languages(), vec!["ru", "en"]);
+    }
+
+    #[test]
+    fn test_txt_line_template() {
+        let segments = vec![
+            TranscriptionSegment {
+                start_time: 65.5,
+                end_time: Some(70.0),
+                text: "Анна: Добрый день".to_string(),
+                speaker: Some("Анна".to_string()),
+                original_filename: "part1.srt".to_string(),
+                ..Default::default()
+            },
+            TranscriptionSegment {
+                start_time: 3600.0,
+                text: "Тишина".to_string(),
+                confidence: Some(0.42),
+                ..Default::default()
+            },
+        ];
+        let format = |template: &str| {
+            TranscriptionMerger::new(MergeOptions { txt_template: Some(template.to_string()), ..MergeOptions::default() })
+                .format_segments(&segments)
+        };
+
+        assert_eq!(
+            format("{start_hms} | {speaker} | {text}").unwrap(),
+            "00:01:05 | Анна | Добрый день\n01:00:00 |  | Тишина\n"
+        );
+        assert_eq!(
+            format("{{{index}}} {start_seconds}-{end_hms} {confidence}").unwrap(),
+            "{1} 65.5-00:01:10 \n{2} 3600.0-01:00:05 42%\n"
+        );
+        assert!(format("{start} {speeker}").is_err());
+        assert!(format("{text").is_err());
+        assert!(format("text}").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reproducible_merge() {
+        let directory = std::env::temp_dir().join(format!("reproducible_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = |name: &str, text: &str| {
+            let path = directory.join(name);
+            std::fs::write(&path, text).unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let (intro, outro) = (path("intro.txt", "[00:00] Intro\n"), path("outro.txt", "[00:00] Outro\n"));
+
+        let merge = |files: Vec<String>| async move {
+            let mut merger = TranscriptionMerger::new(MergeOptions {
+                output_format: FileFormat::Markdown,
+                reproducible: true,
+                ..MergeOptions::default()
+            });
+            merger.add_files(files).await.unwrap();
+            merger.merge().await.unwrap()
+        };
+        let markdown = merge(vec![outro.clone(), intro.clone()]).await;
+        assert!(!markdown.contains("Generated on"));
+        assert!(markdown.find("Intro").unwrap() < markdown.find("Outro").unwrap());
+        assert_eq!(markdown, merge(vec![intro, outro]).await);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_resegment_by_sentences() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 2.0, "Анна: Добрый день. Сегодня").file("part1.srt").speaker("Анна"),
+            TranscriptionSegment::test(2.0, 4.0, "Анна: обсудим бюджет на 3.5 млн.").file("part1.srt").speaker("Анна"),
+            TranscriptionSegment::test(4.0, 6.0, "Борис: хорошо").file("part1.srt").speaker("Борис"),
+            TranscriptionSegment::test(6.0, 8.0, "Борис: без точки").file("part1.srt").speaker("Борис"),
+        ];
+
+        let cues = resegment_by_sentences(&segments, 5.0);
+        let summary: Vec<(&str, f64, f64)> = cues
+            .iter()
+            .map(|cue| (cue.text.as_str(), (cue.start_time * 1000.0).round() / 1000.0, (cue.end_time.unwrap() * 1000.0).round() / 1000.0))
+            .collect();
+        // "Добрый день. Сегодня" has 20 characters over 2 s
+        assert_eq!(
+            summary,
+            vec![
+                ("Анна: Добрый день.", 0.0, 1.2),
+                ("Анна: Сегодня обсудим бюджет на 3.5 млн.", 1.3, 4.0),
+                ("Борис: хорошо", 4.0, 6.0),
+                ("Борис: без точки", 6.0, 8.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paragraph_grouping() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 2.0, "Анна: Добрый день.").file("part1.srt").speaker("Анна"),
+            TranscriptionSegment::test(2.5, 4.0, "Анна: Начнём с бюджета.").file("part1.srt").speaker("Анна"),
+            TranscriptionSegment::test(7.0, 9.0, "Анна: После паузы.").file("part1.srt").speaker("Анна"),
+            TranscriptionSegment::test(9.5, 11.0, "Борис: Согласен.").file("part1.srt").speaker("Борис"),
+        ];
+        let format = |output_format: FileFormat| {
+            TranscriptionMerger::new(MergeOptions {
+                output_format,
+                add_file_markers: false,
+                paragraph_pause_seconds: Some(2.0),
+                ..MergeOptions::default()
+            })
+            .format_segments(&segments)
+            .unwrap()
+        };
+
+        assert_eq!(
+            format(FileFormat::Txt),
+            "[00:00] Анна: Добрый день. Начнём с бюджета.\n[00:07] Анна: После паузы.\n[00:09] Борис: Согласен.\n"
+        );
+        assert!(format(FileFormat::Markdown).contains("**[00:00]** Анна: Добрый день. Начнём с бюджета.\n\n**[00:07]**"));
+        assert_eq!(format(FileFormat::Srt).matches(" --> ").count(), 4);
+    }
+
     #[test]
     fn test_looks_like_srt_with_crlf() {
         let merger = TranscriptionMerger::new(MergeOptions::default());
@@ -725,7 +3723,7 @@ mod tests {
         let content = "В 2024 году произошло много событий.\n\
                         Сумма составила 15000 рублей.\n\
                         3 основных пункта были рассмотрены.\n";
-        let segments = merger.parse_txt(content, "test.txt").unwrap();
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
 
         // All numbers should be preserved in the text
         assert!(segments.iter().any(|s| s.text.contains("2024")));
@@ -739,7 +3737,7 @@ mod tests {
         // Bracketed numbers should still be treated as timestamps
         let content = "[120] Some text at two minutes.\n\
                         [300] Some text at five minutes.\n";
-        let segments = merger.parse_txt(content, "test.txt").unwrap();
+        let segments = merger.parse_txt(content, "test.txt", &mut Vec::new()).unwrap();
 
         assert_eq!(segments.len(), 2);
         assert!((segments[0].start_time - 120.0).abs() < 0.01);
@@ -767,9 +3765,8 @@ mod tests {
 
         let options = MergeOptions {
             output_format: FileFormat::Txt,
-            time_offset_seconds: 0.0,
-            remove_timestamps: false,
             add_file_markers: true,
+            ..MergeOptions::default()
         };
         let mut merger = TranscriptionMerger::new(options);
         merger.add_files(files.clone()).await.expect("Failed to add files");
@@ -803,9 +3800,8 @@ mod tests {
 
         let options = MergeOptions {
             output_format: FileFormat::Srt,
-            time_offset_seconds: 0.0,
-            remove_timestamps: false,
             add_file_markers: false,
+            ..MergeOptions::default()
         };
         let mut merger = TranscriptionMerger::new(options);
         merger.add_files(files).await.expect("Failed to add files");
@@ -835,9 +3831,8 @@ mod tests {
 
         let options = MergeOptions {
             output_format: FileFormat::Markdown,
-            time_offset_seconds: 0.0,
-            remove_timestamps: false,
             add_file_markers: true,
+            ..MergeOptions::default()
         };
         let mut merger = TranscriptionMerger::new(options);
         merger.add_files(files).await.expect("Failed to add files");
@@ -858,5 +3853,68 @@ mod tests {
         assert_eq!(merger.extract_sequence_number("Транскрипция 2.txt"), Some(2));
         assert_eq!(merger.extract_sequence_number("chunk_3.txt"), Some(3));
         assert_eq!(merger.extract_sequence_number("part-10.txt"), Some(10));
+        // Keywords win over other numbers, otherwise the last number counts
+        assert_eq!(merger.extract_sequence_number("2024-03-01_interview_part02.srt"), Some(2));
+        assert_eq!(merger.extract_sequence_number("2024-03-01 интервью 5.srt"), Some(5));
+        assert_eq!(merger.extract_sequence_number("Part 4 take 2.txt"), Some(4));
+
+        let merger = TranscriptionMerger::new(MergeOptions {
+            sequence_keywords: vec!["take".to_string(), "part".to_string()],
+            ..MergeOptions::default()
+        });
+        assert_eq!(merger.extract_sequence_number("Part 4 take 2.txt"), Some(2));
+
+        let merger = TranscriptionMerger::new(MergeOptions {
+            sequence_pattern: Some(r"episode-(\d+)-take".to_string()),
+            ..MergeOptions::default()
+        });
+        assert_eq!(merger.extract_sequence_number("show_episode-12-take3.srt"), Some(12));
+        assert_eq!(merger.extract_sequence_number("bonus_part7.srt"), Some(7));
+
+        let merger = TranscriptionMerger::new(MergeOptions {
+            sequence_pattern: Some(r"episode-\d+".to_string()),
+            ..MergeOptions::default()
+        });
+        assert!(merger.sequence_regex().is_err());
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn prop_timestamps_round_trip(millis in 0u64..360_000_000) {
+            let merger = TranscriptionMerger::new(MergeOptions::default());
+            let seconds = millis as f64 / 1000.0;
+            let srt = merger.format_srt_timestamp(seconds);
+            proptest::prop_assert!((merger.parse_srt_timestamp(&srt).unwrap() - seconds).abs() < 0.0005, "{} -> {}", seconds, srt);
+            let vtt = merger.format_vtt_timestamp(seconds);
+            proptest::prop_assert!((merger.parse_vtt_timestamp(&vtt).unwrap() - seconds).abs() < 0.0005, "{} -> {}", seconds, vtt);
+            let sbv = format_sbv_timestamp(seconds);
+            proptest::prop_assert!((merger.parse_srt_timestamp(&sbv).unwrap() - seconds).abs() < 0.0005, "{} -> {}", seconds, sbv);
+        }
+
+        #[test]
+        fn prop_timestamp_parsing_never_panics(timestamp in "\\PC{0,24}|[0-9:,.-]{0,16}") {
+            let merger = TranscriptionMerger::new(MergeOptions::default());
+            let _ = merger.parse_srt_timestamp(&timestamp);
+            let _ = merger.parse_vtt_timestamp(&timestamp);
+        }
+    }
+
+    proptest::proptest! {
+        // Every case compiles the parsers' patterns anew, so fewer of them
+        #![proptest_config(proptest::prelude::ProptestConfig::with_cases(32))]
+
+        /// Timing lines with stray digits, separators and text mixed in; every
+        /// parser either reads them or reports a warning, none panics.
+        #[test]
+        fn prop_cue_parsers_never_panic(
+            content in "(WEBVTT\n)?(([0-9]{1,3}:){0,2}[0-9]{1,2}([,.][0-9]{0,4})?( --> |,| )?([0-9]{1,2}:){0,2}[0-9]{0,2}([,.][0-9]{0,3})?\n|\\[[0-9:.,-]{0,10}\\] ?[a-zа-я ]{0,6}\n|<v [A-Za-z]{0,4}>?[a-z ]{0,6}\n|[0-9]{1,2}\n|\n){0,12}"
+        ) {
+            let merger = TranscriptionMerger::new(MergeOptions::default());
+            let mut warnings = Vec::new();
+            let _ = merger.parse_srt(&content, "fuzz.srt", &mut warnings);
+            let _ = merger.parse_vtt(&content, "fuzz.vtt", &mut warnings);
+            let _ = merger.parse_sbv(&content, "fuzz.sbv", &mut warnings);
+            let _ = merger.parse_txt(&content, "fuzz.txt", &mut warnings);
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,261 @@
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::merger::{format_txt_timestamp, split_sentences, TranscriptionSegment};
+
+/// Used when no template is supplied
+const DEFAULT_TEMPLATE: &str = "# {{title}}\n\n\
+**Дата:** {{date}}  \n\
+**Длительность:** {{duration}}\n\n\
+## Участники\n\n{{attendees}}\n\n\
+## Краткое содержание\n\n{{summary}}\n\n\
+## Решения\n\n{{decisions}}\n\n\
+## Стенограмма\n\n{{transcript}}\n";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MinutesOptions {
+    pub title: String,
+    /// Meeting date for `{{date}}`; today when not set
+    pub date: Option<String>,
+    /// Known attendees (e.g. from the calendar invite), listed before detected speakers
+    pub attendees: Vec<String>,
+    /// Number of sentences picked for the extractive summary
+    pub summary_sentences: usize,
+    /// Segments containing one of these (case-insensitive) are listed as decisions
+    pub decision_keywords: Vec<String>,
+}
+
+impl Default for MinutesOptions {
+    fn default() -> Self {
+        Self {
+            title: "Протокол встречи".to_string(),
+            date: None,
+            attendees: Vec::new(),
+            summary_sentences: 5,
+            decision_keywords: [
+                "решили", "решение", "договорились", "утвердили", "поручить",
+                "decided", "decision", "agreed", "action item",
+            ]
+            .iter()
+            .map(|keyword| keyword.to_string())
+            .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TemplateKind {
+    Markdown,
+    Html,
+}
+
+/// Fills `{{title}}`, `{{date}}`, `{{duration}}`, `{{attendees}}`, `{{summary}}`,
+/// `{{decisions}}` and `{{transcript}}` in the template. Unknown placeholders are
+/// left as they are so typos stay visible in the output. Substituted values are
+/// not scanned again, so a `{{...}}` spoken in the transcript stays as it is.
+pub fn render_minutes(
+    template: Option<&str>,
+    kind: TemplateKind,
+    segments: &[TranscriptionSegment],
+    options: &MinutesOptions,
+) -> String {
+    let template = template.unwrap_or(DEFAULT_TEMPLATE);
+
+    let duration = segments
+        .iter()
+        .map(|segment| segment.end_time.unwrap_or(segment.start_time))
+        .fold(0.0, f64::max);
+
+    let mut attendees: Vec<&str> = Vec::new();
+    let known = options.attendees.iter().map(String::as_str);
+    for speaker in known.chain(segments.iter().filter_map(|segment| segment.speaker.as_deref())) {
+        if !attendees.contains(&speaker) {
+            attendees.push(speaker);
+        }
+    }
+
+    let decisions: Vec<String> = segments
+        .iter()
+        .filter(|segment| {
+            let text = segment.text.to_lowercase();
+            options.decision_keywords.iter().any(|keyword| text.contains(&keyword.to_lowercase()))
+        })
+        .map(|segment| format!("[{}] {}", format_txt_timestamp(segment.start_time), segment.text))
+        .collect();
+
+    let transcript: Vec<String> = segments
+        .iter()
+        .map(|segment| format!("[{}] {}", format_txt_timestamp(segment.start_time), segment.text))
+        .collect();
+
+    let summary = extractive_summary(segments, options.summary_sentences);
+
+    let values = [
+        ("title", escape(&options.title, kind)),
+        (
+            "date",
+            match &options.date {
+                Some(date) => escape(date, kind),
+                None => chrono::Local::now().format("%Y-%m-%d").to_string(),
+            },
+        ),
+        ("duration", format_txt_timestamp(duration)),
+        ("attendees", render_list(&attendees, kind)),
+        ("summary", render_paragraphs(&summary, kind)),
+        ("decisions", render_list(&decisions, kind)),
+        ("transcript", render_paragraphs(&transcript, kind)),
+    ];
+
+    let placeholder = Regex::new(r"\{\{(\w+)\}\}").unwrap();
+    placeholder
+        .replace_all(template, |captures: &Captures| {
+            values
+                .iter()
+                .find(|(name, _)| *name == &captures[1])
+                .map(|(_, value)| value.clone())
+                .unwrap_or_else(|| captures[0].to_string())
+        })
+        .into_owned()
+}
+
+/// Picks the highest-scoring sentences (sum of word frequencies, normalized by
+/// length) and returns them in their original order.
+fn extractive_summary(segments: &[TranscriptionSegment], count: usize) -> Vec<String> {
+    let sentences: Vec<String> = segments
+        .iter()
+        .flat_map(|segment| split_sentences(&segment.text))
+        .collect();
+
+    let words_of = |sentence: &str| -> Vec<String> {
+        sentence
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.chars().count() > 3)
+            .map(|word| word.to_lowercase())
+            .collect()
+    };
+
+    let mut frequencies: HashMap<String, usize> = HashMap::new();
+    for sentence in &sentences {
+        for word in words_of(sentence) {
+            *frequencies.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    let mut scored: Vec<(usize, f64)> = sentences
+        .iter()
+        .enumerate()
+        .map(|(index, sentence)| {
+            let words = words_of(sentence);
+            let total: usize = words.iter().map(|word| frequencies[word]).sum();
+            (index, total as f64 / (words.len().max(1) as f64).sqrt())
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
+
+    let mut picked: Vec<usize> = scored.iter().take(count).map(|(index, _)| *index).collect();
+    picked.sort_unstable();
+    picked.into_iter().map(|index| sentences[index].clone()).collect()
+}
+
+fn render_list<S: AsRef<str>>(items: &[S], kind: TemplateKind) -> String {
+    if items.is_empty() {
+        return "—".to_string();
+    }
+    match kind {
+        TemplateKind::Markdown => items
+            .iter()
+            .map(|item| format!("- {}", item.as_ref()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        TemplateKind::Html => format!(
+            "<ul>\n{}\n</ul>",
+            items
+                .iter()
+                .map(|item| format!("<li>{}</li>", escape(item.as_ref(), kind)))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ),
+    }
+}
+
+fn render_paragraphs(items: &[String], kind: TemplateKind) -> String {
+    match kind {
+        TemplateKind::Markdown => items.join("\n\n"),
+        TemplateKind::Html => items
+            .iter()
+            .map(|item| format!("<p>{}</p>", escape(item, kind)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+fn escape(text: &str, kind: TemplateKind) -> String {
+    match kind {
+        TemplateKind::Markdown => text.to_string(),
+        TemplateKind::Html => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;")
+            .replace('"', "&quot;"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_html_template() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 10.0, "Анна: Обсудим бюджет на квартал.").speaker("Анна"),
+            TranscriptionSegment::test(10.0, 20.0, "Борис: Решили увеличить бюджет <на 10%>.").speaker("Борис"),
+            TranscriptionSegment::test(20.0, 30.0, "Анна: Спасибо всем.").speaker("Анна"),
+        ];
+        let template = "<h1>{{title}}</h1>{{attendees}}{{decisions}}{{unknown}}";
+
+        let html = render_minutes(Some(template), TemplateKind::Html, &segments, &MinutesOptions::default());
+
+        assert!(html.starts_with("<h1>Протокол встречи</h1>"));
+        assert!(html.contains("<li>Анна</li>\n<li>Борис</li>"));
+        assert!(html.contains("<li>[00:10] Борис: Решили увеличить бюджет &lt;на 10%&gt;.</li>"));
+        assert!(html.ends_with("{{unknown}}"));
+    }
+
+    #[test]
+    fn test_default_markdown_template() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 10.0, "Анна: Первое. Второе предложение.").speaker("Анна"),
+            TranscriptionSegment::test(65.0, 75.0, "Анна: Третье.").speaker("Анна"),
+        ];
+        let options = MinutesOptions {
+            summary_sentences: 2,
+            date: Some("2024-01-15".to_string()),
+            attendees: vec!["Борис".to_string(), "Анна".to_string()],
+            ..MinutesOptions::default()
+        };
+
+        let markdown = render_minutes(None, TemplateKind::Markdown, &segments, &options);
+
+        assert!(markdown.contains("**Дата:** 2024-01-15"));
+        assert!(markdown.contains("**Длительность:** 01:15"));
+        assert!(markdown.contains("## Участники\n\n- Борис\n- Анна\n\n"));
+        assert!(markdown.contains("## Решения\n\n—"));
+        assert!(!markdown.contains("{{"));
+    }
+
+    #[test]
+    fn test_values_are_not_substituted_again() {
+        let segments = vec![TranscriptionSegment::test(0.0, 10.0, "Анна: Поле {{date}} в шаблоне.").speaker("Анна")];
+        let options = MinutesOptions {
+            title: "{{transcript}}".to_string(),
+            date: Some("2024-01-15".to_string()),
+            ..MinutesOptions::default()
+        };
+
+        let markdown = render_minutes(Some("{{title}} {{date}}\n{{transcript}}"), TemplateKind::Markdown, &segments, &options);
+
+        assert_eq!(markdown, "{{transcript}} 2024-01-15\n[00:00] Анна: Поле {{date}} в шаблоне.");
+    }
+}
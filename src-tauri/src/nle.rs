@@ -0,0 +1,550 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::path::Path;
+
+use crate::handoff::{render_sheet, SheetFormat};
+use crate::merger::{escape_xml, TranscriptionSegment};
+
+/// Editing application interchange formats for transcript markers.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NleFormat {
+    /// OpenTimelineIO (`.otio`), readable by DaVinci Resolve, Premiere (via
+    /// plugins) and OTIO adapters
+    Otio,
+    /// Final Cut Pro X XML (`.fcpxml`)
+    Fcpxml,
+    /// Adobe Premiere Pro marker list (`.csv`)
+    #[serde(rename = "premiere_csv")]
+    PremiereCsv,
+    /// CMX 3600 edit decision list with DaVinci Resolve marker comments (`.edl`)
+    Edl,
+}
+
+impl NleFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Otio => "otio",
+            Self::Fcpxml => "fcpxml",
+            Self::PremiereCsv => "csv",
+            Self::Edl => "edl",
+        }
+    }
+}
+
+/// What the markers are placed on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerSource {
+    /// One marker per merged segment
+    #[default]
+    Segments,
+    /// One marker where each source file of the merge starts
+    FileBoundaries,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarkerExportOptions {
+    pub format: NleFormat,
+    /// Timeline frame rate; marker times are snapped to frames
+    pub frame_rate: f64,
+    /// Marker names are cut to this many characters; the full text goes to the note
+    pub name_length: usize,
+    pub source: MarkerSource,
+    /// Timecode of the first timeline frame, `HH:MM:SS:FF`; EDL markers are
+    /// offset by it (Resolve timelines start at 01:00:00:00)
+    pub start_timecode: String,
+}
+
+impl Default for MarkerExportOptions {
+    fn default() -> Self {
+        Self {
+            format: NleFormat::Otio,
+            frame_rate: 25.0,
+            name_length: 40,
+            source: MarkerSource::Segments,
+            start_timecode: "01:00:00:00".to_string(),
+        }
+    }
+}
+
+/// One marker per segment; `end` is already resolved for open-ended segments.
+pub struct Marker<'a> {
+    pub segment: &'a TranscriptionSegment,
+    pub end: f64,
+}
+
+/// Renders a timeline holding a single clip of the source media with the
+/// transcript as markers on it.
+pub fn render_markers(
+    markers: &[Marker],
+    media_path: &Path,
+    media_duration: f64,
+    name: &str,
+    options: &MarkerExportOptions,
+) -> Result<String> {
+    match options.format {
+        NleFormat::Otio => render_otio(markers, media_path, media_duration, name, options),
+        NleFormat::Fcpxml => Ok(render_fcpxml(markers, media_path, media_duration, name, options)),
+        NleFormat::PremiereCsv => Ok(render_premiere_csv(markers, options)),
+        NleFormat::Edl => render_edl(markers, name, options),
+    }
+}
+
+/// One segment per source file, spanning it and named after it, for
+/// [`MarkerSource::FileBoundaries`].
+pub fn file_markers(segments: &[TranscriptionSegment], default_duration: f64) -> Vec<TranscriptionSegment> {
+    let mut files: Vec<TranscriptionSegment> = Vec::new();
+    for segment in segments.iter().filter(|segment| !segment.original_filename.is_empty()) {
+        let end = segment.end_time.unwrap_or(segment.start_time + default_duration);
+        match files.iter_mut().find(|file| file.original_filename == segment.original_filename) {
+            Some(file) => {
+                file.start_time = file.start_time.min(segment.start_time);
+                file.end_time = file.end_time.map(|file_end| file_end.max(end));
+            }
+            None => files.push(TranscriptionSegment {
+                start_time: segment.start_time,
+                end_time: Some(end),
+                text: segment.original_filename.clone(),
+                original_filename: segment.original_filename.clone(),
+                ..Default::default()
+            }),
+        }
+    }
+    files
+}
+
+fn render_otio(
+    markers: &[Marker],
+    media_path: &Path,
+    media_duration: f64,
+    name: &str,
+    options: &MarkerExportOptions,
+) -> Result<String> {
+    let rate = options.frame_rate;
+    let time = |seconds: f64| json!({ "OTIO_SCHEMA": "RationalTime.1", "rate": rate, "value": (seconds * rate).round() });
+    let range = |start: f64, duration: f64| json!({
+        "OTIO_SCHEMA": "TimeRange.1",
+        "start_time": time(start),
+        "duration": time(duration),
+    });
+
+    let otio_markers: Vec<serde_json::Value> = markers
+        .iter()
+        .map(|marker| json!({
+            "OTIO_SCHEMA": "Marker.2",
+            "name": marker_name(marker.segment, options.name_length),
+            "marked_range": range(marker.segment.start_time, (marker.end - marker.segment.start_time).max(0.0)),
+            "color": "RED",
+            "comment": marker.segment.text,
+            "metadata": { "speaker": marker.segment.speaker },
+        }))
+        .collect();
+
+    let media_name = media_path.file_name().unwrap_or_default().to_string_lossy();
+    let document = json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": name,
+        "global_start_time": null,
+        "metadata": {},
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "source_range": null,
+            "effects": [],
+            "markers": [],
+            "metadata": {},
+            "children": [{
+                "OTIO_SCHEMA": "Track.1",
+                "name": "Transcript",
+                "kind": "Audio",
+                "source_range": null,
+                "effects": [],
+                "markers": [],
+                "metadata": {},
+                "children": [{
+                    "OTIO_SCHEMA": "Clip.1",
+                    "name": media_name,
+                    "source_range": range(0.0, media_duration),
+                    "media_reference": {
+                        "OTIO_SCHEMA": "ExternalReference.1",
+                        "name": media_name,
+                        "target_url": file_url(media_path),
+                        "available_range": range(0.0, media_duration),
+                        "metadata": {},
+                    },
+                    "effects": [],
+                    "markers": otio_markers,
+                    "metadata": {},
+                }],
+            }],
+        },
+    });
+
+    Ok(serde_json::to_string_pretty(&document)?)
+}
+
+fn render_fcpxml(
+    markers: &[Marker],
+    media_path: &Path,
+    media_duration: f64,
+    name: &str,
+    options: &MarkerExportOptions,
+) -> String {
+    let time = |seconds: f64| fcpxml_time(seconds, options.frame_rate);
+    let media_name = escape_xml(&media_path.file_name().unwrap_or_default().to_string_lossy());
+    let duration = time(media_duration);
+
+    let mut marker_lines = String::new();
+    for marker in markers {
+        let start = marker.segment.start_time;
+        // FCP markers need a duration of at least one frame
+        let length = (marker.end - start).max(1.0 / options.frame_rate);
+        marker_lines.push_str(&format!(
+            "                            <marker start=\"{}\" duration=\"{}\" value=\"{}\" note=\"{}\"/>\n",
+            time(start),
+            time(length),
+            escape_xml(&marker_name(marker.segment, options.name_length)),
+            escape_xml(&marker.segment.text),
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE fcpxml>
+<fcpxml version="1.9">
+    <resources>
+        <format id="r1" frameDuration="{frame}"/>
+        <asset id="r2" name="{media}" start="0s" duration="{duration}" hasAudio="1" audioSources="1">
+            <media-rep kind="original-media" src="{url}"/>
+        </asset>
+    </resources>
+    <library>
+        <event name="{name}">
+            <project name="{name}">
+                <sequence format="r1" duration="{duration}" tcStart="0s">
+                    <spine>
+                        <asset-clip ref="r2" name="{media}" offset="0s" start="0s" duration="{duration}">
+{markers}                        </asset-clip>
+                    </spine>
+                </sequence>
+            </project>
+        </event>
+    </library>
+</fcpxml>
+"#,
+        frame = fcpxml_time(1.0 / options.frame_rate, options.frame_rate),
+        media = media_name,
+        duration = duration,
+        url = escape_xml(&file_url(media_path)),
+        name = escape_xml(name),
+        markers = marker_lines,
+    )
+}
+
+/// An FCPXML project whose timeline holds the transcript as captions in the
+/// iTT caption role for `language`, with times snapped to frames. Captions
+/// of one role may not overlap in Final Cut, so each ends at the latest where
+/// the next one starts.
+pub fn render_fcpxml_captions(captions: &[Marker], name: &str, frame_rate: f64, language: &str) -> String {
+    let time = |seconds: f64| fcpxml_time(seconds, frame_rate);
+    let frame = 1.0 / frame_rate;
+    let role = format!("iTT?captionFormat=ITT.{}", escape_xml(language));
+
+    let mut caption_lines = String::new();
+    let mut timeline_end: f64 = 0.0;
+    for (index, caption) in captions.iter().enumerate() {
+        let start = caption.segment.start_time.max(0.0);
+        let next_start = captions.get(index + 1).map(|next| next.segment.start_time).filter(|next| *next > start);
+        let end = next_start.map_or(caption.end, |next| caption.end.min(next)).max(start + frame);
+        timeline_end = timeline_end.max(end);
+
+        let text = escape_xml(caption.segment.text.trim());
+        caption_lines.push_str(&format!(
+            "                            <caption lane=\"1\" offset=\"{start}\" name=\"{name}\" start=\"{start}\" duration=\"{duration}\" role=\"{role}\">\n\
+             \x20                               <text placement=\"bottom\"><text-style ref=\"ts{id}\">{text}</text-style></text>\n\
+             \x20                               <text-style-def id=\"ts{id}\"><text-style font=\"Helvetica\" fontSize=\"13\" fontColor=\"1 1 1 1\" backgroundColor=\"0 0 0 1\"/></text-style-def>\n\
+             \x20                           </caption>\n",
+            start = time(start),
+            name = escape_xml(&marker_name(caption.segment, 40)),
+            duration = time(end - start),
+            role = role,
+            id = index + 1,
+            text = text,
+        ));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE fcpxml>
+<fcpxml version="1.9">
+    <resources>
+        <format id="r1" frameDuration="{frame}"/>
+    </resources>
+    <library>
+        <event name="{name}">
+            <project name="{name}">
+                <sequence format="r1" duration="{duration}" tcStart="0s" tcFormat="NDF">
+                    <spine>
+                        <gap name="Gap" offset="0s" start="0s" duration="{duration}">
+{captions}                        </gap>
+                    </spine>
+                </sequence>
+            </project>
+        </event>
+    </library>
+</fcpxml>
+"#,
+        frame = fcpxml_time(frame, frame_rate),
+        duration = time(timeline_end),
+        name = escape_xml(name),
+        captions = caption_lines,
+    )
+}
+
+/// The columns Premiere Pro writes when exporting markers, so the list
+/// round-trips through its marker panel and marker import tools.
+fn render_premiere_csv(markers: &[Marker], options: &MarkerExportOptions) -> String {
+    let timecode = |seconds: f64| premiere_timecode(seconds, options.frame_rate);
+    let rows: Vec<Vec<String>> = markers
+        .iter()
+        .map(|marker| {
+            let start = marker.segment.start_time;
+            let end = marker.end.max(start);
+            vec![
+                marker_name(marker.segment, options.name_length),
+                marker.segment.text.trim().to_string(),
+                timecode(start),
+                timecode(end),
+                timecode(end - start),
+                "Comment".to_string(),
+            ]
+        })
+        .collect();
+    render_sheet(&["Marker Name", "Description", "In", "Out", "Duration", "Marker Type"], &rows, SheetFormat::Csv)
+}
+
+/// DaVinci Resolve's marker EDL: one one-frame event per marker, followed by
+/// a comment line `note |C:color |M:name |D:frames` that "Import Timeline
+/// Markers from EDL" turns back into a marker.
+fn render_edl(markers: &[Marker], name: &str, options: &MarkerExportOptions) -> Result<String> {
+    let rate = options.frame_rate;
+    let start_frame = parse_timecode(&options.start_timecode, rate)?;
+    let nominal = rate.round().max(1.0) as u64;
+    let timecode = |frames: u64| premiere_timecode(frames as f64 / nominal as f64, nominal as f64);
+    // `|` separates the fields of the comment and a new line ends it
+    let field = |text: &str| text.split_whitespace().collect::<Vec<_>>().join(" ").replace('|', "/");
+
+    let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", field(name));
+    for (index, marker) in markers.iter().enumerate() {
+        let start = start_frame + (marker.segment.start_time.max(0.0) * rate).round() as u64;
+        let duration = (((marker.end - marker.segment.start_time) * rate).round() as u64).max(1);
+        edl.push_str(&format!(
+            "{:03}  001      V     C        {in_} {out} {in_} {out}  \n{} |C:ResolveColorBlue |M:{} |D:{}\n\n",
+            index + 1,
+            field(&marker.segment.text),
+            field(&marker_name(marker.segment, options.name_length)),
+            duration,
+            in_ = timecode(start),
+            out = timecode(start + 1),
+        ));
+    }
+    Ok(edl)
+}
+
+/// Frame count of an `HH:MM:SS:FF` timecode (`;` before the frames of
+/// drop-frame timecodes is accepted and counted as non-drop).
+fn parse_timecode(timecode: &str, frame_rate: f64) -> Result<u64> {
+    let nominal = frame_rate.round().max(1.0) as u64;
+    let parts: Vec<u64> = timecode
+        .trim()
+        .split([':', ';'])
+        .map(|part| part.parse().map_err(|_| anyhow!("Invalid timecode {}, expected HH:MM:SS:FF", timecode)))
+        .collect::<Result<_>>()?;
+    match parts[..] {
+        [hours, minutes, seconds, frames] if minutes < 60 && seconds < 60 && frames < nominal => {
+            Ok(((hours * 60 + minutes) * 60 + seconds) * nominal + frames)
+        }
+        _ => Err(anyhow!("Invalid timecode {}, expected HH:MM:SS:FF", timecode)),
+    }
+}
+
+/// Non-drop-frame `HH:MM:SS:FF`; NTSC rates count frames at 29.97 and
+/// number them as 30 per second, as Premiere does.
+fn premiere_timecode(seconds: f64, frame_rate: f64) -> String {
+    let nominal = frame_rate.round().max(1.0) as u64;
+    let frames = (seconds.max(0.0) * frame_rate).round() as u64;
+    let whole_seconds = frames / nominal;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        whole_seconds / 3600,
+        whole_seconds / 60 % 60,
+        whole_seconds % 60,
+        frames % nominal
+    )
+}
+
+/// Frame-aligned rational time: `N/25s`, or `N*1001/30000s` for NTSC rates.
+fn fcpxml_time(seconds: f64, frame_rate: f64) -> String {
+    let frames = (seconds.max(0.0) * frame_rate).round() as u64;
+    if (frame_rate - frame_rate.round()).abs() < 0.001 {
+        format!("{}/{}s", frames, frame_rate.round() as u64)
+    } else {
+        let nominal = (frame_rate * 1.001).round() as u64;
+        format!("{}/{}s", frames * 1001, nominal * 1000)
+    }
+}
+
+/// "Speaker: first characters of the text", with the label not repeated.
+fn marker_name(segment: &TranscriptionSegment, length: usize) -> String {
+    let text = segment.text.trim();
+    let text = segment
+        .speaker
+        .as_deref()
+        .and_then(|speaker| text.strip_prefix(speaker))
+        .and_then(|rest| rest.strip_prefix(':'))
+        .map(str::trim)
+        .unwrap_or(text);
+    let name: String = text.chars().take(length).collect();
+    match &segment.speaker {
+        Some(speaker) => format!("{}: {}", speaker, name),
+        None => name,
+    }
+}
+
+/// `file:///C:/Media/My%20Show.wav` or `file:///home/me/show.wav`
+pub(crate) fn file_url(path: &Path) -> String {
+    let path = path.to_string_lossy().replace('\\', "/");
+    let encoded: Vec<String> = path
+        .split('/')
+        .map(|part| {
+            // Keep drive letters such as `C:` readable
+            if part.len() == 2 && part.ends_with(':') {
+                part.to_string()
+            } else {
+                urlencoding::encode(part).into_owned()
+            }
+        })
+        .collect();
+    let joined = encoded.join("/");
+    if joined.starts_with('/') {
+        format!("file://{}", joined)
+    } else {
+        format!("file:///{}", joined)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fcpxml_markers() {
+        let segment = TranscriptionSegment {
+            start_time: 2.0,
+            end_time: Some(4.5),
+            text: "Анна: Начнём с <итогов> & планов".to_string(),
+            speaker: Some("Анна".to_string()),
+            ..Default::default()
+        };
+        let markers = [Marker { segment: &segment, end: 4.5 }];
+        let options = MarkerExportOptions {
+            format: NleFormat::Fcpxml,
+            frame_rate: 29.97,
+            name_length: 10,
+            ..MarkerExportOptions::default()
+        };
+
+        let xml = render_markers(&markers, Path::new("C:\\Media\\My Show.wav"), 60.0, "Эпизод", &options).unwrap();
+
+        assert!(xml.contains(r#"<format id="r1" frameDuration="1001/30000s"/>"#));
+        assert!(xml.contains(r#"src="file:///C:/Media/My%20Show.wav""#));
+        assert!(xml.contains(r#"<marker start="60060/30000s" duration="75075/30000s" value="Анна: Начнём с &lt;""#));
+        assert!(xml.contains(r#"note="Анна: Начнём с &lt;итогов&gt; &amp; планов""#));
+    }
+
+    #[test]
+    fn test_otio_markers() {
+        let segment = TranscriptionSegment {
+            start_time: 1.0,
+            end_time: Some(3.0),
+            text: "Привет".to_string(),
+            ..Default::default()
+        };
+        let markers = [Marker { segment: &segment, end: 3.0 }];
+
+        let otio = render_markers(&markers, Path::new("/media/show.wav"), 10.0, "Episode", &MarkerExportOptions::default()).unwrap();
+        let document: serde_json::Value = serde_json::from_str(&otio).unwrap();
+
+        let clip = &document["tracks"]["children"][0]["children"][0];
+        assert_eq!(clip["media_reference"]["target_url"], "file:///media/show.wav");
+        assert_eq!(clip["markers"][0]["marked_range"]["start_time"]["value"], 25.0);
+        assert_eq!(clip["markers"][0]["marked_range"]["duration"]["value"], 50.0);
+        assert_eq!(clip["markers"][0]["name"], "Привет");
+    }
+
+    #[test]
+    fn test_premiere_csv_markers() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 4.0, "Добрый день, начнём").file("part1.srt"),
+            TranscriptionSegment::test(3661.5, 3663.0, "Вопрос из зала").file("part2.srt"),
+            TranscriptionSegment::test(3664.0, 3670.0, "Ответ").file("part2.srt"),
+        ];
+        let options = MarkerExportOptions { format: NleFormat::PremiereCsv, ..MarkerExportOptions::default() };
+
+        let markers: Vec<Marker> = segments.iter().map(|segment| Marker { segment, end: segment.end_time.unwrap() }).collect();
+        let csv = render_markers(&markers, Path::new("interview.mp4"), 3700.0, "Interview", &options).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "Marker Name,Description,In,Out,Duration,Marker Type");
+        assert_eq!(lines[1], "\"Добрый день, начнём\",\"Добрый день, начнём\",00:00:00:00,00:00:04:00,00:00:04:00,Comment");
+        assert_eq!(lines[2], "Вопрос из зала,Вопрос из зала,01:01:01:13,01:01:03:00,00:00:01:13,Comment");
+
+        let files = file_markers(&segments, 5.0);
+        let markers: Vec<Marker> = files.iter().map(|segment| Marker { segment, end: segment.end_time.unwrap() }).collect();
+        let csv = render_markers(&markers, Path::new("interview.mp4"), 3700.0, "Interview", &options).unwrap();
+        assert_eq!(csv.lines().nth(2), Some("part2.srt,part2.srt,01:01:01:13,01:01:10:00,00:00:08:13,Comment"));
+    }
+
+    #[test]
+    fn test_fcpxml_captions() {
+        let first = TranscriptionSegment { start_time: 1.0, text: "Hello & welcome".to_string(), ..Default::default() };
+        let second = TranscriptionSegment { start_time: 2.5, text: "Second line".to_string(), ..Default::default() };
+        let captions = vec![Marker { segment: &first, end: 3.0 }, Marker { segment: &second, end: 4.0 }];
+
+        let xml = render_fcpxml_captions(&captions, "Talk", 29.97, "ru");
+        assert!(xml.contains(r#"<format id="r1" frameDuration="1001/30000s"/>"#));
+        // The first caption ends where the second begins
+        assert!(xml.contains(r#"offset="30030/30000s" name="Hello &amp; welcome" start="30030/30000s" duration="45045/30000s" role="iTT?captionFormat=ITT.ru""#));
+        assert!(xml.contains(r#"<text-style ref="ts2">Second line</text-style>"#));
+        assert!(xml.contains(r#"<gap name="Gap" offset="0s" start="0s" duration="120120/30000s">"#));
+    }
+
+    #[test]
+    fn test_edl_markers() {
+        let first = TranscriptionSegment { start_time: 0.0, text: "part1.srt".to_string(), ..Default::default() };
+        let second = TranscriptionSegment {
+            start_time: 61.5,
+            text: "Борис: Вопрос | ответ\nдальше".to_string(),
+            speaker: Some("Борис".to_string()),
+            ..Default::default()
+        };
+        let markers = [Marker { segment: &first, end: 60.0 }, Marker { segment: &second, end: 62.0 }];
+        let options = MarkerExportOptions { format: NleFormat::Edl, frame_rate: 24.0, ..MarkerExportOptions::default() };
+
+        let edl = render_markers(&markers, Path::new("show.wav"), 120.0, "Show", &options).unwrap();
+        let lines: Vec<&str> = edl.lines().collect();
+        assert_eq!(lines[..2], ["TITLE: Show", "FCM: NON-DROP FRAME"]);
+        assert_eq!(lines[3], "001  001      V     C        01:00:00:00 01:00:00:01 01:00:00:00 01:00:00:01  ");
+        assert_eq!(lines[4], "part1.srt |C:ResolveColorBlue |M:part1.srt |D:1440");
+        assert_eq!(lines[6], "002  001      V     C        01:01:01:12 01:01:01:13 01:01:01:12 01:01:01:13  ");
+        assert_eq!(lines[7], "Борис: Вопрос / ответ дальше |C:ResolveColorBlue |M:Борис: Вопрос / ответ дальше |D:12");
+
+        let options = MarkerExportOptions { start_timecode: "00:59:58:00".to_string(), ..options };
+        let edl = render_markers(&markers, Path::new("show.wav"), 120.0, "Show", &options).unwrap();
+        assert!(edl.contains("002  001      V     C        01:00:59:12 "));
+        let options = MarkerExportOptions { start_timecode: "01:00:00:30".to_string(), ..options };
+        assert!(render_markers(&markers, Path::new("show.wav"), 120.0, "Show", &options).is_err());
+    }
+}
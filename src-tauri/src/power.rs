@@ -0,0 +1,131 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+
+/// How chunking behaves while a laptop runs on battery.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatteryMode {
+    /// Process at full speed regardless of the power source
+    #[default]
+    Off,
+    /// Encode on a single thread with the fastest encoder preset
+    Throttle,
+    /// Hold the remaining chunks until AC power returns
+    Pause,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerSource {
+    Ac,
+    Battery,
+    /// The platform gave no answer; treated like AC power
+    Unknown,
+}
+
+/// What the machine currently draws power from. Machines without a battery
+/// report AC.
+pub fn power_source() -> PowerSource {
+    if cfg!(target_os = "macos") {
+        command_output("pmset", &["-g", "batt"]).map_or(PowerSource::Unknown, |output| parse_pmset(&output))
+    } else if cfg!(target_os = "windows") {
+        command_output(
+            "powershell",
+            &["-NoProfile", "-Command", "(Get-CimInstance Win32_Battery).BatteryStatus"],
+        )
+        .map_or(PowerSource::Unknown, |output| parse_battery_status(&output))
+    } else {
+        sysfs_power_source(Path::new("/sys/class/power_supply"))
+    }
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x08000000); // CREATE_NO_WINDOW
+    }
+
+    let output = cmd.output().ok()?;
+    output.status.success().then(|| String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// `pmset -g batt` starts with "Now drawing from 'Battery Power'" or "'AC Power'".
+fn parse_pmset(output: &str) -> PowerSource {
+    if output.contains("'Battery Power'") {
+        PowerSource::Battery
+    } else if output.contains("'AC Power'") || output.contains("'UPS Power'") {
+        PowerSource::Ac
+    } else {
+        PowerSource::Unknown
+    }
+}
+
+/// `Win32_Battery.BatteryStatus` per battery: 1 discharging, 4 low and
+/// 5 critical mean no charger; an empty answer means there is no battery.
+fn parse_battery_status(output: &str) -> PowerSource {
+    let statuses: Vec<u32> = output.lines().filter_map(|line| line.trim().parse().ok()).collect();
+    if statuses.is_empty() {
+        PowerSource::Ac
+    } else if statuses.iter().all(|status| matches!(status, 1 | 4 | 5)) {
+        PowerSource::Battery
+    } else {
+        PowerSource::Ac
+    }
+}
+
+/// Linux lists each supply under `/sys/class/power_supply` with a `type`; a
+/// battery whose `status` is "Discharging" means the charger is unplugged.
+fn sysfs_power_source(dir: &Path) -> PowerSource {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return PowerSource::Unknown;
+    };
+    let read = |supply: &Path, name: &str| std::fs::read_to_string(supply.join(name)).unwrap_or_default().trim().to_string();
+
+    let discharging = entries.flatten().any(|entry| {
+        let supply = entry.path();
+        read(&supply, "type") == "Battery" && read(&supply, "status") == "Discharging"
+    });
+    if discharging {
+        PowerSource::Battery
+    } else {
+        PowerSource::Ac
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_source_detection() {
+        assert_eq!(
+            parse_pmset("Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t87%; discharging"),
+            PowerSource::Battery
+        );
+        assert_eq!(parse_pmset("Now drawing from 'AC Power'\n"), PowerSource::Ac);
+        assert_eq!(parse_battery_status("1\r\n"), PowerSource::Battery);
+        assert_eq!(parse_battery_status("2\r\n"), PowerSource::Ac);
+        assert_eq!(parse_battery_status(""), PowerSource::Ac);
+
+        let dir = std::env::temp_dir().join(format!("power_{}", uuid::Uuid::new_v4()));
+        let supply = |name: &str, kind: &str, status: &str| {
+            let path = dir.join(name);
+            std::fs::create_dir_all(&path).unwrap();
+            std::fs::write(path.join("type"), format!("{}\n", kind)).unwrap();
+            std::fs::write(path.join("status"), format!("{}\n", status)).unwrap();
+        };
+        supply("AC", "Mains", "");
+        supply("BAT0", "Battery", "Charging");
+        assert_eq!(sysfs_power_source(&dir), PowerSource::Ac);
+        supply("BAT0", "Battery", "Discharging");
+        assert_eq!(sysfs_power_source(&dir), PowerSource::Battery);
+        assert_eq!(sysfs_power_source(&dir.join("missing")), PowerSource::Unknown);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,73 @@
+use regex::{Captures, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+
+/// Words to mask for one language, kept in the settings. A trailing `*` makes
+/// an entry match any ending, which covers inflected forms ("бля*").
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfanityList {
+    pub language: String,
+    pub words: Vec<String>,
+}
+
+/// Masks listed words as their first letter followed by asterisks ("f***").
+/// Unlike redaction the word stays recognisable to readers.
+pub struct ProfanityFilter {
+    pattern: Option<Regex>,
+}
+
+impl ProfanityFilter {
+    pub fn new(lists: &[ProfanityList]) -> Self {
+        let alternatives: Vec<String> = lists
+            .iter()
+            .flat_map(|list| &list.words)
+            .map(|word| word.trim())
+            .filter(|word| !word.trim_end_matches('*').is_empty())
+            .map(|word| match word.strip_suffix('*') {
+                Some(stem) => format!(r"{}\w*", regex::escape(stem)),
+                None => regex::escape(word),
+            })
+            .collect();
+
+        let pattern = if alternatives.is_empty() {
+            None
+        } else {
+            RegexBuilder::new(&format!(r"\b(?:{})\b", alternatives.join("|")))
+                .case_insensitive(true)
+                .build()
+                .ok()
+        };
+        Self { pattern }
+    }
+
+    pub fn mask<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        match &self.pattern {
+            Some(pattern) => pattern.replace_all(text, |captures: &Captures| mask_word(&captures[0])),
+            None => Cow::Borrowed(text),
+        }
+    }
+}
+
+fn mask_word(word: &str) -> String {
+    word.chars()
+        .enumerate()
+        .map(|(index, ch)| if index == 0 { ch } else { '*' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_profanity() {
+        let filter = ProfanityFilter::new(&[
+            ProfanityList { language: "en".to_string(), words: vec!["fuck*".to_string(), "damn".to_string()] },
+            ProfanityList { language: "ru".to_string(), words: vec!["блин".to_string(), " ".to_string()] },
+        ]);
+
+        assert_eq!(filter.mask("Fucking hell, damn it"), "F****** hell, d*** it");
+        assert_eq!(filter.mask("Блин! Блинчики и damnation"), "Б***! Блинчики и damnation");
+        assert!(matches!(ProfanityFilter::new(&[]).mask("damn"), Cow::Borrowed("damn")));
+    }
+}
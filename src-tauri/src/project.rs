@@ -0,0 +1,419 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use crate::ffmpeg::{get_app_data_dir, MediaTags};
+use crate::versions::MergeVersion;
+
+/// Extension of project files written by the app
+pub const PROJECT_EXTENSION: &str = "taproj";
+
+/// Characters of the previous chunk's transcript carried into the next
+/// prompt; Whisper reads only the last 224 tokens of a prompt anyway
+const PROMPT_TAIL_CHARS: usize = 300;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationKind {
+    Processing,
+    Merge,
+    Export,
+    Conversion,
+    Reformat,
+    /// Manual change of the merged segments: text, timing, split, merge,
+    /// delete or find-and-replace
+    Edit,
+    /// A translation added to the merged segments
+    Translation,
+    /// A saved version made the merged result again
+    Restore,
+}
+
+/// One significant operation, recorded with the options it ran with so the
+/// result can be reproduced later.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: OperationKind,
+    pub summary: String,
+    #[serde(default)]
+    pub details: serde_json::Value,
+}
+
+/// Meeting details attached to a project, entered manually or imported from an
+/// .ics invite. They are written into export headers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProjectMetadata {
+    pub meeting_title: Option<String>,
+    /// ISO 8601 date or date-time
+    pub meeting_date: Option<String>,
+    pub location: Option<String>,
+    pub attendees: Vec<String>,
+}
+
+impl ProjectMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Label/value pairs in display order, skipping fields that are not set.
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = Vec::new();
+        if let Some(title) = &self.meeting_title {
+            fields.push(("Meeting", title.clone()));
+        }
+        if let Some(date) = &self.meeting_date {
+            fields.push(("Date", date.clone()));
+        }
+        if let Some(location) = &self.location {
+            fields.push(("Location", location.clone()));
+        }
+        if !self.attendees.is_empty() {
+            fields.push(("Attendees", self.attendees.join(", ")));
+        }
+        fields
+    }
+}
+
+/// Episode numbering of a project. The name it renders is used for the chunk
+/// folder, export files and archives, so every output of an episode matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EpisodeNaming {
+    /// Placeholders: `{show}`, `{episode}`, `{date}` and `{title}`, plus the
+    /// media tags of [`MediaTags::fill`]
+    pub template: String,
+    pub show: String,
+    pub episode: u32,
+}
+
+impl EpisodeNaming {
+    /// Renders the template and strips characters not allowed in file names.
+    pub fn render(&self, date: &str, title: Option<&str>, media: &MediaTags) -> String {
+        let name = media.fill(&self.template);
+        let name = name
+            .replace("{show}", &self.show)
+            .replace("{episode}", &self.episode.to_string())
+            .replace("{date}", date)
+            .replace("{title}", title.unwrap_or_default());
+        sanitize_file_name(&name)
+    }
+}
+
+pub(crate) fn sanitize_file_name(name: &str) -> String {
+    let cleaned: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|') || c.is_control() { '_' } else { c })
+        .collect();
+    // Windows does not allow trailing dots or spaces
+    cleaned.split_whitespace().collect::<Vec<_>>().join(" ").trim_end_matches('.').to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub metadata: ProjectMetadata,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub naming: Option<EpisodeNaming>,
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    /// Names and jargon given to the transcriber as context for every chunk
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcription_prompt: Option<String>,
+    /// When the transcript is due
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deadline: Option<DateTime<Utc>>,
+    /// Tags of the media file last split into chunks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub media: Option<MediaTags>,
+    /// Numbered snapshots of the merged result, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<MergeVersion>,
+    /// Where the project file lives; not part of the file itself
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl Project {
+    pub fn new(name: &str, path: PathBuf) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            created_at: Utc::now(),
+            metadata: ProjectMetadata::default(),
+            naming: None,
+            history: Vec::new(),
+            transcription_prompt: None,
+            deadline: None,
+            media: None,
+            versions: Vec::new(),
+            path,
+        }
+    }
+
+    /// Default location for new projects: `<app data>/projects/<id>.taproj`
+    pub fn default_path(id: &str) -> Result<PathBuf> {
+        Ok(get_app_data_dir()?
+            .join("projects")
+            .join(format!("{}.{}", id, PROJECT_EXTENSION)))
+    }
+
+    /// Projects saved in the default location; unreadable files are skipped.
+    pub fn saved_projects() -> Vec<Project> {
+        let Ok(directory) = get_app_data_dir().map(|dir| dir.join("projects")) else {
+            return Vec::new();
+        };
+        std::fs::read_dir(directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().is_some_and(|extension| extension == PROJECT_EXTENSION))
+                    .filter_map(|path| Project::load(&path).ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("Failed to read project {}: {}", path.display(), e))?;
+        let mut project: Project = serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid project file {}: {}", path.display(), e))?;
+        project.path = path.to_path_buf();
+        Ok(project)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Name from the episode naming scheme, dated by the meeting date when known.
+    pub fn episode_name(&self) -> Option<String> {
+        let naming = self.naming.as_ref()?;
+        let date = match &self.metadata.meeting_date {
+            // Date part of an ISO 8601 date-time
+            Some(date) => date.chars().take(10).collect(),
+            None => self.created_at.format("%Y-%m-%d").to_string(),
+        };
+        Some(naming.render(&date, self.metadata.meeting_title.as_deref(), &self.media.clone().unwrap_or_default()))
+    }
+
+    /// Meeting details for export headers, with the media placeholders in
+    /// the title and location filled in.
+    pub fn header_metadata(&self) -> ProjectMetadata {
+        let media = self.media.clone().unwrap_or_default();
+        let fill = |field: &Option<String>| field.as_deref().map(|value| media.fill(value));
+        ProjectMetadata {
+            meeting_title: fill(&self.metadata.meeting_title),
+            location: fill(&self.metadata.location),
+            ..self.metadata.clone()
+        }
+    }
+
+    /// Prompt for transcribing a chunk: the project's context followed by the
+    /// end of the previous chunk's transcript, so names and sentences carry
+    /// over the chunk boundary.
+    pub fn chunk_prompt(&self, previous_transcript: &str) -> String {
+        let previous = previous_transcript.split_whitespace().collect::<Vec<_>>().join(" ");
+        let char_count = previous.chars().count();
+        let tail = if char_count > PROMPT_TAIL_CHARS {
+            let cut: String = previous.chars().skip(char_count - PROMPT_TAIL_CHARS).collect();
+            // Start at a whole word
+            match cut.split_once(' ') {
+                Some((_, rest)) => rest.to_string(),
+                None => cut,
+            }
+        } else {
+            previous
+        };
+
+        [self.transcription_prompt.as_deref().unwrap_or_default().trim(), tail.as_str()]
+            .iter()
+            .filter(|part| !part.is_empty())
+            .copied()
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    pub fn record(&mut self, kind: OperationKind, summary: impl Into<String>, details: serde_json::Value) {
+        self.history.push(HistoryEntry {
+            timestamp: Utc::now(),
+            kind,
+            summary: summary.into(),
+            details,
+        });
+    }
+}
+
+/// A project due soon or already overdue.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpcomingDeadline {
+    pub project_name: String,
+    pub path: PathBuf,
+    pub deadline: DateTime<Utc>,
+    /// Negative once the deadline has passed
+    pub hours_left: f64,
+    pub overdue: bool,
+}
+
+/// Reads a deadline given as RFC 3339, as a local date-time
+/// (`2024-03-01T18:00`) or as a local date, which means the end of that day.
+pub fn parse_deadline(value: &str) -> Result<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(deadline) = DateTime::parse_from_rfc3339(value) {
+        return Ok(deadline.with_timezone(&Utc));
+    }
+    let local = ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(23, 59, 59))
+        })
+        .ok_or_else(|| anyhow!("Invalid deadline '{}', expected a date like 2024-03-01", value))?;
+    Local
+        .from_local_datetime(&local)
+        .earliest()
+        .map(|deadline| deadline.with_timezone(&Utc))
+        .ok_or_else(|| anyhow!("Deadline '{}' does not exist in the local time zone", value))
+}
+
+/// Projects due within `within` from `now`, overdue ones included, soonest first.
+pub fn upcoming_deadlines(projects: &[Project], now: DateTime<Utc>, within: Duration) -> Vec<UpcomingDeadline> {
+    let mut upcoming: Vec<UpcomingDeadline> = projects
+        .iter()
+        .filter_map(|project| {
+            let deadline = project.deadline.filter(|deadline| *deadline <= now + within)?;
+            let hours_left = (deadline - now).num_seconds() as f64 / 3600.0;
+            Some(UpcomingDeadline {
+                project_name: project.name.clone(),
+                path: project.path.clone(),
+                deadline,
+                hours_left,
+                overdue: hours_left < 0.0,
+            })
+        })
+        .collect();
+    upcoming.sort_by_key(|deadline| deadline.deadline);
+    upcoming
+}
+
+// Project that operations are currently recorded into
+lazy_static::lazy_static! {
+    pub static ref ACTIVE_PROJECT: Arc<Mutex<Option<Project>>> = Arc::new(Mutex::new(None));
+}
+
+/// Episode name of the open project, if it has a naming scheme.
+pub async fn active_episode_name() -> Option<String> {
+    ACTIVE_PROJECT.lock().await.as_ref().and_then(Project::episode_name)
+}
+
+/// Appends an entry to the active project's history and saves it. Without an
+/// open project this does nothing; a failed save is logged, never propagated,
+/// so bookkeeping cannot break the operation itself.
+pub async fn record_operation(kind: OperationKind, summary: impl Into<String>, details: serde_json::Value) {
+    let mut active = ACTIVE_PROJECT.lock().await;
+    if let Some(project) = active.as_mut() {
+        project.record(kind, summary, details);
+        if let Err(e) = project.save() {
+            println!("Failed to save project history: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_round_trip() {
+        let directory = std::env::temp_dir().join(format!("project_{}", uuid::Uuid::new_v4()));
+        let path = directory.join(format!("lecture.{}", PROJECT_EXTENSION));
+
+        let mut project = Project::new("Lecture", path.clone());
+        project.metadata.attendees = vec!["Анна".to_string(), "Борис".to_string()];
+        project.record(OperationKind::Merge, "Merged 2 files", serde_json::json!({ "files": ["a.txt", "b.txt"] }));
+        project.record(OperationKind::Export, "Exported SRT", serde_json::Value::Null);
+        project.save().unwrap();
+
+        let loaded = Project::load(&path).unwrap();
+        assert_eq!(loaded.name, "Lecture");
+        assert_eq!(loaded.path, path);
+        assert_eq!(loaded.history.len(), 2);
+        assert_eq!(loaded.history[0].kind, OperationKind::Merge);
+        assert_eq!(loaded.history[0].details["files"][1], "b.txt");
+        assert_eq!(loaded.metadata.fields(), vec![("Attendees", "Анна, Борис".to_string())]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn test_episode_name() {
+        let mut project = Project::new("Podcast", PathBuf::new());
+        assert_eq!(project.episode_name(), None);
+
+        project.naming = Some(EpisodeNaming {
+            template: "{show} E{episode} – {date}".to_string(),
+            show: "Радио: Т/В".to_string(),
+            episode: 12,
+        });
+        project.metadata.meeting_date = Some("2024-01-15T10:00:00".to_string());
+        assert_eq!(project.episode_name().as_deref(), Some("Радио_ Т_В E12 – 2024-01-15"));
+
+        // Tags probed from the media file fill their placeholders
+        use crate::ffmpeg::parse_media_tags;
+        let output = "Input #0, mp3, from 'ep.mp3':\n  Metadata:\n    title           : Выпуск 12\n    artist          : Радио\n    date            : 2024-01-14\n  Duration: 00:41:07.05, start: 0.000000, bitrate: 128 kb/s\n    Metadata:\n      title           : Stream\n";
+        project.media = Some(parse_media_tags(output));
+        project.naming.as_mut().unwrap().template = "{media_artist} – {media_title} ({media_date})".to_string();
+        assert_eq!(project.episode_name().as_deref(), Some("Радио – Выпуск 12 (2024-01-14)"));
+        project.metadata.meeting_title = Some("{media_title}, запись {media_date}".to_string());
+        assert_eq!(project.header_metadata().meeting_title.as_deref(), Some("Выпуск 12, запись 2024-01-14"));
+    }
+
+    #[test]
+    fn test_upcoming_deadlines() {
+        let now = parse_deadline("2024-03-01T12:00:00Z").unwrap();
+        let mut projects: Vec<Project> = ["Интервью", "Лекция", "Подкаст", "Без срока"]
+            .iter()
+            .map(|name| Project::new(name, PathBuf::new()))
+            .collect();
+        projects[0].deadline = Some(parse_deadline("2024-03-04T09:00:00+03:00").unwrap());
+        projects[1].deadline = Some(parse_deadline("2024-02-29T12:00:00Z").unwrap());
+        projects[2].deadline = Some(parse_deadline("2024-04-01").unwrap());
+
+        let upcoming = upcoming_deadlines(&projects, now, Duration::days(7));
+        let summary: Vec<(&str, f64, bool)> = upcoming
+            .iter()
+            .map(|deadline| (deadline.project_name.as_str(), deadline.hours_left, deadline.overdue))
+            .collect();
+        assert_eq!(summary, vec![("Лекция", -24.0, true), ("Интервью", 66.0, false)]);
+        assert!(parse_deadline("1 марта").is_err());
+    }
+
+    #[test]
+    fn test_chunk_prompt() {
+        let mut project = Project::new("Podcast", PathBuf::new());
+        assert_eq!(project.chunk_prompt("Добрый день,\n коллеги."), "Добрый день, коллеги.");
+
+        project.transcription_prompt = Some(" Гости: Анна Петрова, Kubernetes, SRE. ".to_string());
+        let previous = format!("{} и на этом мы заканчиваем первую часть", "слово ".repeat(100));
+        let prompt = project.chunk_prompt(&previous);
+        let (context, tail) = prompt.split_once('\n').unwrap();
+        assert_eq!(context, "Гости: Анна Петрова, Kubernetes, SRE.");
+        assert!(tail.ends_with("заканчиваем первую часть") && tail.starts_with("слово"));
+        assert!(tail.chars().count() <= PROMPT_TAIL_CHARS);
+        assert_eq!(project.chunk_prompt(""), context);
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::cjk::display_width;
+use crate::merger::TranscriptionSegment;
+
+/// Cue times closer than this count as touching rather than overlapping
+const TIME_TOLERANCE: f64 = 0.001;
+
+/// Limits the subtitle check holds the cues to; a `None` limit is not checked.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QcRules {
+    pub check_overlaps: bool,
+    pub check_order: bool,
+    pub check_durations: bool,
+    /// Columns per line; Han, kana and Hangul take two
+    pub max_line_length: Option<usize>,
+    pub max_chars_per_second: Option<f64>,
+}
+
+impl Default for QcRules {
+    fn default() -> Self {
+        Self {
+            check_overlaps: true,
+            check_order: true,
+            check_durations: true,
+            max_line_length: Some(42),
+            max_chars_per_second: Some(20.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationKind {
+    /// The cue ends after the next one starts
+    Overlap,
+    /// The cue starts before the previous one
+    OutOfOrder,
+    /// The cue ends at or before its start
+    NonPositiveDuration,
+    LineTooLong,
+    ReadingSpeed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    pub kind: ViolationKind,
+    pub segment_index: usize,
+    pub start_time: f64,
+    pub message: String,
+}
+
+/// Checks the cues in timeline order and lists every rule they break.
+/// Segments without an end time are left out of the duration, overlap and
+/// reading speed checks.
+pub fn validate_subtitles(segments: &[TranscriptionSegment], rules: &QcRules) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut report = |kind, index: usize, message: String| {
+        violations.push(Violation { kind, segment_index: index, start_time: segments[index].start_time, message });
+    };
+
+    for (index, segment) in segments.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|previous| &segments[previous]);
+        if rules.check_order {
+            if let Some(previous) = previous.filter(|previous| segment.start_time < previous.start_time - TIME_TOLERANCE) {
+                report(
+                    ViolationKind::OutOfOrder,
+                    index,
+                    format!("Starts at {:.3}s, before the previous cue at {:.3}s", segment.start_time, previous.start_time),
+                );
+            }
+        }
+
+        let duration = segment.end_time.map(|end| end - segment.start_time);
+        if rules.check_durations {
+            if let Some(duration) = duration.filter(|duration| *duration <= 0.0) {
+                report(ViolationKind::NonPositiveDuration, index, format!("Duration is {:.3}s", duration));
+            }
+        }
+
+        if rules.check_overlaps {
+            let overlap = segment
+                .end_time
+                .zip(segments.get(index + 1))
+                .map(|(end, next)| end - next.start_time)
+                .filter(|overlap| *overlap > TIME_TOLERANCE);
+            if let Some(overlap) = overlap {
+                report(ViolationKind::Overlap, index, format!("Overlaps the next cue by {:.3}s", overlap));
+            }
+        }
+
+        if let Some(max_line_length) = rules.max_line_length {
+            for (line_number, line) in segment.text.lines().enumerate() {
+                let width = display_width(line.trim());
+                if width > max_line_length {
+                    report(
+                        ViolationKind::LineTooLong,
+                        index,
+                        format!("Line {} is {} characters long (limit {})", line_number + 1, width, max_line_length),
+                    );
+                }
+            }
+        }
+
+        if let (Some(max_cps), Some(duration)) = (rules.max_chars_per_second, duration.filter(|duration| *duration > 0.0)) {
+            let characters = segment.text.chars().filter(|ch| *ch != '\n' && *ch != '\r').count();
+            let cps = characters as f64 / duration;
+            if cps > max_cps {
+                report(
+                    ViolationKind::ReadingSpeed,
+                    index,
+                    format!("{:.1} characters per second (limit {})", cps, max_cps),
+                );
+            }
+        }
+    }
+    violations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_subtitles() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 3.0, "Добрый день"),
+            TranscriptionSegment::test(2.5, 2.5, "Сегодня обсуждаем бюджет на следующий год и планы"),
+            TranscriptionSegment::test(2.0, 2.5, "Очень быстро произнесённая реплика"),
+            TranscriptionSegment::test(4.0, 6.0, "Спасибо"),
+        ];
+        let violations = validate_subtitles(&segments, &QcRules::default());
+        let found: Vec<(ViolationKind, usize)> = violations.iter().map(|violation| (violation.kind, violation.segment_index)).collect();
+        assert_eq!(
+            found,
+            vec![
+                (ViolationKind::Overlap, 0),
+                (ViolationKind::NonPositiveDuration, 1),
+                (ViolationKind::Overlap, 1),
+                (ViolationKind::LineTooLong, 1),
+                (ViolationKind::OutOfOrder, 2),
+                (ViolationKind::ReadingSpeed, 2),
+            ]
+        );
+
+        let relaxed = QcRules { check_overlaps: false, max_line_length: None, max_chars_per_second: None, ..QcRules::default() };
+        assert_eq!(validate_subtitles(&segments, &relaxed).len(), 2);
+    }
+}
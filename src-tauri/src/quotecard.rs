@@ -0,0 +1,207 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::export::wrap_text;
+use crate::merger::{format_txt_timestamp, TranscriptionSegment};
+use crate::supervisor::{run_supervised, Supervision};
+
+/// Layout of a quote card. Rendering is done by FFmpeg's drawtext filter, so no
+/// image or font libraries are bundled with the app.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuoteCardOptions {
+    pub width: u32,
+    pub height: u32,
+    pub background_color: String,
+    pub text_color: String,
+    pub accent_color: String,
+    pub font_size: u32,
+    /// TrueType/OpenType font; a common system font is used when not set
+    pub font_file: Option<String>,
+    /// Line under the attribution, e.g. the podcast and episode name
+    pub branding: Option<String>,
+}
+
+impl Default for QuoteCardOptions {
+    fn default() -> Self {
+        Self {
+            width: 1080,
+            height: 1080,
+            background_color: "#1e1e2e".to_string(),
+            text_color: "#ffffff".to_string(),
+            accent_color: "#f5a524".to_string(),
+            font_size: 52,
+            font_file: None,
+            branding: None,
+        }
+    }
+}
+
+/// Quote text without the leading "Name:" label, which goes to the attribution line.
+pub fn quote_text(segment: &TranscriptionSegment) -> String {
+    let text = segment.text.trim();
+    match &segment.speaker {
+        Some(speaker) => text
+            .strip_prefix(speaker.as_str())
+            .and_then(|rest| rest.strip_prefix(':'))
+            .map(|rest| rest.trim().to_string())
+            .unwrap_or_else(|| text.to_string()),
+        None => text.to_string(),
+    }
+}
+
+/// "— Speaker · 00:12:34", or just the timestamp when the speaker is unknown.
+pub fn attribution(segment: &TranscriptionSegment) -> String {
+    let timestamp = format_txt_timestamp(segment.start_time);
+    match &segment.speaker {
+        Some(speaker) => format!("— {} · {}", speaker, timestamp),
+        None => format!("— {}", timestamp),
+    }
+}
+
+/// Renders a PNG card for the segment with FFmpeg.
+pub async fn render_quote_card(
+    ffmpeg_path: &Path,
+    segment: &TranscriptionSegment,
+    output_path: &Path,
+    options: &QuoteCardOptions,
+) -> Result<()> {
+    let font_file = match &options.font_file {
+        Some(font) => PathBuf::from(font),
+        None => default_font().ok_or_else(|| anyhow!("No system font found, please choose a font file"))?,
+    };
+
+    // Text goes through files so quotes, colons and percent signs need no escaping
+    let work_dir = std::env::temp_dir().join(format!("quote_card_{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&work_dir)?;
+    let quote_file = work_dir.join("quote.txt");
+    let attribution_file = work_dir.join("attribution.txt");
+
+    let margin = options.width / 10;
+    let chars_per_line = ((options.width - 2 * margin) as f64 / (options.font_size as f64 * 0.55)) as usize;
+    std::fs::write(&quote_file, format!("“{}”", wrap_text(&quote_text(segment), chars_per_line)))?;
+
+    let mut attribution_text = attribution(segment);
+    if let Some(branding) = &options.branding {
+        attribution_text.push('\n');
+        attribution_text.push_str(branding);
+    }
+    std::fs::write(&attribution_file, attribution_text)?;
+
+    let filter = build_filter(&font_file, &quote_file, &attribution_file, options);
+
+    let background = format!("color=c={}:s={}x{}", ffmpeg_color(&options.background_color), options.width, options.height);
+    let run = run_supervised(
+        || {
+            let mut cmd = Command::new(ffmpeg_path);
+            cmd.args([
+                "-f", "lavfi",
+                "-i", &background,
+                "-vf", &filter,
+                "-frames:v", "1",
+                "-y",
+            ]);
+            cmd.arg(output_path);
+            cmd
+        },
+        Supervision::default(),
+    )
+    .await;
+    let _ = std::fs::remove_dir_all(&work_dir);
+
+    if let Err(e) = &run {
+        println!("FFmpeg quote card rendering failed: {}", e);
+    }
+    run.map(|_| ())
+}
+
+fn build_filter(font_file: &Path, quote_file: &Path, attribution_file: &Path, options: &QuoteCardOptions) -> String {
+    let margin = options.width / 10;
+    let attribution_size = options.font_size * 3 / 5;
+
+    format!(
+        "drawbox=x={bar_x}:y={margin}:w=8:h=ih-{double_margin}:color={accent}:t=fill,\
+         drawtext=fontfile={font}:textfile={quote}:expansion=none:fontcolor={text}:fontsize={size}:\
+         line_spacing={spacing}:x={margin}:y=(h-text_h)/2-{shift},\
+         drawtext=fontfile={font}:textfile={attribution}:expansion=none:fontcolor={accent}:fontsize={small}:\
+         line_spacing={small_spacing}:x={margin}:y=h-{margin}-text_h",
+        bar_x = margin / 2,
+        margin = margin,
+        double_margin = margin * 2,
+        accent = ffmpeg_color(&options.accent_color),
+        text = ffmpeg_color(&options.text_color),
+        font = filter_path(font_file),
+        quote = filter_path(quote_file),
+        attribution = filter_path(attribution_file),
+        size = options.font_size,
+        spacing = options.font_size / 3,
+        shift = options.font_size,
+        small = attribution_size,
+        small_spacing = attribution_size / 3,
+    )
+}
+
+/// Quotes a path for use as a filter option value (Windows drive colons included).
+pub(crate) fn filter_path(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "\\'");
+    format!("'{}'", path)
+}
+
+fn ffmpeg_color(color: &str) -> String {
+    match color.strip_prefix('#') {
+        Some(hex) => format!("0x{}", hex),
+        None => color.to_string(),
+    }
+}
+
+fn default_font() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let candidates = ["C:/Windows/Fonts/segoeui.ttf", "C:/Windows/Fonts/arial.ttf"];
+
+    #[cfg(target_os = "macos")]
+    let candidates = [
+        "/System/Library/Fonts/Supplemental/Arial.ttf",
+        "/Library/Fonts/Arial.ttf",
+    ];
+
+    #[cfg(target_os = "linux")]
+    let candidates = [
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    ];
+
+    candidates.iter().map(PathBuf::from).find(|path| path.exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_parts_and_filter() {
+        let segment = TranscriptionSegment {
+            start_time: 754.0,
+            text: "Анна: Слушайте больше подкастов.".to_string(),
+            speaker: Some("Анна".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(quote_text(&segment), "Слушайте больше подкастов.");
+        assert_eq!(attribution(&segment), "— Анна · 12:34");
+
+        let filter = build_filter(
+            Path::new("C:\\Windows\\Fonts\\arial.ttf"),
+            Path::new("/tmp/q.txt"),
+            Path::new("/tmp/a.txt"),
+            &QuoteCardOptions::default(),
+        );
+        assert!(filter.contains("fontfile='C\\:/Windows/Fonts/arial.ttf'"));
+        assert!(filter.contains("fontcolor=0xffffff"));
+        assert!(filter.contains("expansion=none"));
+    }
+}
@@ -0,0 +1,189 @@
+use anyhow::{anyhow, Result};
+use regex::{NoExpand, Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::merger::TranscriptionSegment;
+
+/// A find/replace rule applied to segment text. In literal mode `find` is
+/// matched verbatim and `replace` is inserted as-is; in regex mode `replace`
+/// may reference capture groups (`$1`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceRule {
+    pub find: String,
+    pub replace: String,
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default = "default_case_sensitive")]
+    pub case_sensitive: bool,
+}
+
+fn default_case_sensitive() -> bool {
+    true
+}
+
+/// One segment modified by the replace rules, for auditing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SegmentChange {
+    pub segment_index: usize,
+    pub start_time: f64,
+    pub before: String,
+    pub after: String,
+}
+
+/// Occurrences of a rule's pattern in one segment.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleMatch {
+    pub segment_index: usize,
+    pub start_time: f64,
+    pub count: usize,
+}
+
+impl ReplaceRule {
+    fn compile(&self) -> Result<Regex> {
+        if self.find.is_empty() {
+            return Err(anyhow!("Replace rule has an empty search pattern"));
+        }
+
+        let pattern = if self.regex {
+            self.find.clone()
+        } else {
+            regex::escape(&self.find)
+        };
+
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .map_err(|e| anyhow!("Invalid pattern '{}': {}", self.find, e))
+    }
+
+    fn apply(&self, regex: &Regex, text: &str) -> String {
+        if self.regex {
+            regex.replace_all(text, self.replace.as_str()).into_owned()
+        } else {
+            regex.replace_all(text, NoExpand(&self.replace)).into_owned()
+        }
+    }
+}
+
+/// Applies all rules in order to every segment and returns one change entry
+/// per segment whose text was actually modified.
+pub fn apply_replace_rules(
+    segments: &mut [TranscriptionSegment],
+    rules: &[ReplaceRule],
+) -> Result<Vec<SegmentChange>> {
+    let compiled: Vec<(&ReplaceRule, Regex)> = rules
+        .iter()
+        .map(|rule| rule.compile().map(|regex| (rule, regex)))
+        .collect::<Result<_>>()?;
+
+    let mut changes = Vec::new();
+
+    for (segment_index, segment) in segments.iter_mut().enumerate() {
+        let mut text = segment.text.clone();
+        for (rule, regex) in &compiled {
+            text = rule.apply(regex, &text);
+        }
+
+        if text != segment.text {
+            changes.push(SegmentChange {
+                segment_index,
+                start_time: segment.start_time,
+                before: std::mem::replace(&mut segment.text, text.clone()),
+                after: text,
+            });
+        }
+    }
+
+    Ok(changes)
+}
+
+/// Segments the rule would touch and how often it matches in each, without
+/// modifying anything.
+pub fn find_matches(segments: &[TranscriptionSegment], rule: &ReplaceRule) -> Result<Vec<RuleMatch>> {
+    let regex = rule.compile()?;
+    Ok(segments
+        .iter()
+        .enumerate()
+        .filter_map(|(segment_index, segment)| {
+            let count = regex.find_iter(&segment.text).count();
+            (count > 0).then_some(RuleMatch {
+                segment_index,
+                start_time: segment.start_time,
+                count,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_and_regex_rules_produce_changelog() {
+        let mut segments = vec![
+            TranscriptionSegment::test(0.0, None, "We deploy on cubernetes."),
+            TranscriptionSegment::test(5.0, None, "Nothing to see here."),
+            TranscriptionSegment::test(9.5, None, "Costs $5 per hour, Cubernetes again."),
+        ];
+        let rules = vec![
+            ReplaceRule {
+                find: "cubernetes".to_string(),
+                replace: "Kubernetes".to_string(),
+                regex: false,
+                case_sensitive: false,
+            },
+            ReplaceRule {
+                find: r"\$(\d+)".to_string(),
+                replace: "$1 USD".to_string(),
+                regex: true,
+                case_sensitive: true,
+            },
+        ];
+
+        let changes = apply_replace_rules(&mut segments, &rules).unwrap();
+
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0].segment_index, 0);
+        assert_eq!(changes[0].before, "We deploy on cubernetes.");
+        assert_eq!(changes[0].after, "We deploy on Kubernetes.");
+        assert_eq!(changes[1].segment_index, 2);
+        assert!((changes[1].start_time - 9.5).abs() < 0.001);
+        assert_eq!(segments[2].text, "Costs 5 USD per hour, Kubernetes again.");
+    }
+
+    #[test]
+    fn test_literal_replacement_is_not_expanded() {
+        let mut segments = vec![TranscriptionSegment::test(0.0, None, "price")];
+        let rules = vec![ReplaceRule {
+            find: "price".to_string(),
+            replace: "$1".to_string(),
+            regex: false,
+            case_sensitive: true,
+        }];
+
+        apply_replace_rules(&mut segments, &rules).unwrap();
+        assert_eq!(segments[0].text, "$1");
+    }
+
+    #[test]
+    fn test_find_matches_counts_per_segment() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, None, "Acme Cloud, acme cloud"),
+            TranscriptionSegment::test(4.0, None, "nothing"),
+            TranscriptionSegment::test(8.0, None, "ACME CLOUD"),
+        ];
+        let rule = ReplaceRule {
+            find: "acme cloud".to_string(),
+            replace: "AcmeCloud".to_string(),
+            regex: false,
+            case_sensitive: false,
+        };
+
+        let matches = find_matches(&segments, &rule).unwrap();
+
+        let found: Vec<(usize, f64, usize)> = matches.iter().map(|m| (m.segment_index, m.start_time, m.count)).collect();
+        assert_eq!(found, vec![(0, 0.0, 2), (2, 8.0, 1)]);
+        assert_eq!(segments[0].text, "Acme Cloud, acme cloud");
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::audio::ChunkManifest;
+use crate::cloud::KEYRING_SERVICE;
+use crate::stats::RetranscriptionCandidate;
+
+const KEYRING_USER: &str = "transcription-openai";
+const DEFAULT_MODEL: &str = "whisper-1";
+/// New transcripts go in this folder next to the manifest, named after their chunk
+const OUTPUT_FOLDER: &str = "retranscribed";
+
+/// The second pass over flagged chunks.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetranscriptionOptions {
+    /// OpenAI transcription model; `whisper-1` when not given
+    pub model: Option<String>,
+    /// Language code of the recording, detected by the service when not given
+    pub language: Option<String>,
+    /// Chunks covered less than this are redone (0.5 when not given)
+    pub min_coverage: Option<f64>,
+}
+
+fn keyring_entry() -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, KEYRING_USER).map_err(|e| anyhow!("Keychain is not available: {}", e))
+}
+
+/// Stores the OpenAI API key used for re-transcription in the OS keychain;
+/// an empty key removes it.
+pub fn set_api_key(api_key: &str) -> Result<()> {
+    let entry = keyring_entry()?;
+    if api_key.trim().is_empty() {
+        return match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(anyhow!("Failed to remove the API key: {}", e)),
+        };
+    }
+    entry
+        .set_password(api_key.trim())
+        .map_err(|e| anyhow!("Failed to store the API key: {}", e))
+}
+
+fn api_key() -> Result<String> {
+    keyring_entry()?
+        .get_password()
+        .map_err(|_| anyhow!("No OpenAI API key stored for re-transcription, please add one first"))
+}
+
+/// Transcribes the audio of every candidate chunk again and writes each
+/// transcript as SRT to `retranscribed/<chunk>.srt` next to the manifest.
+/// Returns the written paths in candidate order.
+pub async fn retranscribe_chunks(
+    manifest_path: &Path,
+    candidates: &[RetranscriptionCandidate],
+    options: &RetranscriptionOptions,
+) -> Result<Vec<PathBuf>> {
+    let key = api_key()?;
+    let folder = manifest_path.parent().unwrap_or_else(|| Path::new("."));
+    let output_folder = folder.join(OUTPUT_FOLDER);
+    tokio::fs::create_dir_all(&output_folder).await?;
+
+    let mut outputs = Vec::with_capacity(candidates.len());
+    for candidate in candidates {
+        let audio_path = folder.join(&candidate.file_name);
+        let srt = transcribe_with_openai(&key, &audio_path, options)
+            .await
+            .map_err(|e| anyhow!("Chunk {}: {}", candidate.chunk_number, e))?;
+        let stem = Path::new(&candidate.file_name).file_stem().unwrap_or_default();
+        let output = output_folder.join(stem).with_extension("srt");
+        tokio::fs::write(&output, srt).await?;
+        outputs.push(output);
+    }
+    Ok(outputs)
+}
+
+async fn transcribe_with_openai(key: &str, audio_path: &Path, options: &RetranscriptionOptions) -> Result<String> {
+    let audio = tokio::fs::read(audio_path)
+        .await
+        .map_err(|e| anyhow!("Failed to read {}: {}", audio_path.display(), e))?;
+    let file_name = audio_path.file_name().unwrap_or_default().to_string_lossy();
+
+    let mut fields = vec![
+        ("model", options.model.as_deref().unwrap_or(DEFAULT_MODEL).to_string()),
+        ("response_format", "srt".to_string()),
+    ];
+    if let Some(language) = options.language.as_deref().filter(|language| !language.trim().is_empty()) {
+        fields.push(("language", language.trim().to_string()));
+    }
+    let boundary = format!("----transcription-assistant-{}", uuid::Uuid::new_v4().simple());
+    let body = multipart_body(&boundary, &fields, &file_name, &audio);
+
+    let response = reqwest::Client::new()
+        .post("https://api.openai.com/v1/audio/transcriptions")
+        .bearer_auth(key)
+        .header("Content-Type", format!("multipart/form-data; boundary={}", boundary))
+        .body(body)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("OpenAI returned {}: {}", status, text));
+    }
+    Ok(response.text().await?)
+}
+
+/// A `multipart/form-data` body with text `fields` and the audio as `file`.
+fn multipart_body(boundary: &str, fields: &[(&str, String)], file_name: &str, audio: &[u8]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(audio.len() + 512);
+    for (name, value) in fields {
+        body.extend_from_slice(
+            format!("--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n", boundary, name, value).as_bytes(),
+        );
+    }
+    body.extend_from_slice(
+        format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\nContent-Type: application/octet-stream\r\n\r\n",
+            boundary,
+            file_name.replace('"', "")
+        )
+        .as_bytes(),
+    );
+    body.extend_from_slice(audio);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    body
+}
+
+/// The merge inputs with the transcript of every redone chunk swapped for its
+/// new one; a chunk that had no transcript among `files` gets it added.
+pub fn replace_chunk_files(files: &[String], manifest: &ChunkManifest, new_files: &[PathBuf]) -> Vec<String> {
+    let chunk_of = |path: &str| {
+        let name = Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string();
+        manifest.chunk_for(&name, None).map(|chunk| chunk.chunk_number)
+    };
+
+    let mut files = files.to_vec();
+    for new_file in new_files {
+        let new_file = new_file.to_string_lossy().to_string();
+        let chunk = chunk_of(&new_file);
+        match files.iter().position(|file| chunk.is_some() && chunk_of(file) == chunk) {
+            Some(index) => files[index] = new_file,
+            None => files.push(new_file),
+        }
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::ManifestChunk;
+
+    #[test]
+    fn test_replace_chunk_files_and_body() {
+        let chunk = |chunk_number: usize, file_name: &str| ManifestChunk {
+            file_name: file_name.to_string(),
+            chunk_number,
+            start_time: (chunk_number - 1) as f64 * 600.0,
+            duration: 600.0,
+        };
+        let manifest = ChunkManifest {
+            source: "talk.mp3".to_string(),
+            chunks: vec![chunk(1, "talk_001.mp3"), chunk(2, "talk_002.mp3"), chunk(3, "talk_003.mp3")],
+            silences: Vec::new(),
+            assignments: Vec::new(),
+        };
+        let files = vec!["/t/talk_001.srt".to_string(), "/t/talk_002.txt".to_string()];
+        let new_files = vec![PathBuf::from("/t/retranscribed/talk_002.srt"), PathBuf::from("/t/retranscribed/talk_003.srt")];
+        assert_eq!(
+            replace_chunk_files(&files, &manifest, &new_files),
+            vec!["/t/talk_001.srt", "/t/retranscribed/talk_002.srt", "/t/retranscribed/talk_003.srt"]
+        );
+
+        let fields = vec![("model", "whisper-1".to_string())];
+        let body = String::from_utf8(multipart_body("b", &fields, "talk_002.mp3", b"ID3")).unwrap();
+        assert_eq!(
+            body,
+            "--b\r\nContent-Disposition: form-data; name=\"model\"\r\n\r\nwhisper-1\r\n\
+             --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"talk_002.mp3\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\nID3\r\n--b--\r\n"
+        );
+    }
+}
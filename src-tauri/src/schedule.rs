@@ -0,0 +1,197 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::audio::ProcessingOptions;
+use crate::ffmpeg::get_app_data_dir;
+
+/// Daily hours in which queued jobs may run; a window whose end is before its
+/// start runs overnight (22:00–06:00).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProcessingWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl ProcessingWindow {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    #[default]
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedJob {
+    pub id: String,
+    pub file_path: String,
+    pub options: ProcessingOptions,
+    pub queued_at: DateTime<Utc>,
+    pub status: JobStatus,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Outcome as shown after the processing command: chunk count or the error
+    pub message: Option<String>,
+}
+
+/// Audio processing jobs waiting for the processing window, persisted as
+/// `queue.json` in the data folder so they survive a restart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JobQueue {
+    pub jobs: Vec<QueuedJob>,
+}
+
+/// What a run of the queue got through, for the notification afterwards.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueSummary {
+    pub completed: usize,
+    pub failed: usize,
+    /// One "file: outcome" line per job
+    pub lines: Vec<String>,
+}
+
+impl JobQueue {
+    pub fn queue_path() -> Result<PathBuf> {
+        Ok(get_app_data_dir()?.join("queue.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::queue_path()?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::queue_path()?)
+    }
+
+    /// A job still marked running was cut off by a restart and runs again.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        let mut queue: Self =
+            serde_json::from_str(&content).map_err(|e| anyhow!("Invalid job queue {}: {}", path.display(), e))?;
+        for job in queue.jobs.iter_mut().filter(|job| job.status == JobStatus::Running) {
+            job.status = JobStatus::Pending;
+        }
+        Ok(queue)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn enqueue(&mut self, file_path: &str, options: ProcessingOptions) -> &QueuedJob {
+        self.jobs.push(QueuedJob {
+            id: uuid::Uuid::new_v4().to_string(),
+            file_path: file_path.to_string(),
+            options,
+            queued_at: Utc::now(),
+            status: JobStatus::Pending,
+            finished_at: None,
+            message: None,
+        });
+        self.jobs.last().unwrap()
+    }
+
+    /// Takes the oldest pending job and marks it running.
+    pub fn start_next(&mut self) -> Option<QueuedJob> {
+        let job = self.jobs.iter_mut().find(|job| job.status == JobStatus::Pending)?;
+        job.status = JobStatus::Running;
+        Some(job.clone())
+    }
+
+    pub fn finish(&mut self, id: &str, success: bool, message: String) {
+        if let Some(job) = self.jobs.iter_mut().find(|job| job.id == id) {
+            job.status = if success { JobStatus::Done } else { JobStatus::Failed };
+            job.finished_at = Some(Utc::now());
+            job.message = Some(message);
+        }
+    }
+
+    /// Removes a job that is not running; false when there is no such job.
+    pub fn remove(&mut self, id: &str) -> Result<bool> {
+        match self.jobs.iter().position(|job| job.id == id) {
+            Some(index) if self.jobs[index].status == JobStatus::Running => {
+                Err(anyhow!("Job {} is running and cannot be removed", id))
+            }
+            Some(index) => {
+                self.jobs.remove(index);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn summary(&self, ids: &[String]) -> QueueSummary {
+        let jobs: Vec<&QueuedJob> = self.jobs.iter().filter(|job| ids.contains(&job.id)).collect();
+        let file_name = |path: &str| Path::new(path).file_name().unwrap_or_default().to_string_lossy().to_string();
+        QueueSummary {
+            completed: jobs.iter().filter(|job| job.status == JobStatus::Done).count(),
+            failed: jobs.iter().filter(|job| job.status == JobStatus::Failed).count(),
+            lines: jobs
+                .iter()
+                .map(|job| format!("{}: {}", file_name(&job.file_path), job.message.as_deref().unwrap_or_default()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_processing_window() {
+        let time = |text: &str| NaiveTime::parse_from_str(text, "%H:%M").unwrap();
+        let overnight = ProcessingWindow { start: time("22:00"), end: time("06:00") };
+        assert!(overnight.contains(time("23:30")) && overnight.contains(time("02:00")));
+        assert!(!overnight.contains(time("06:00")) && !overnight.contains(time("12:00")));
+
+        let lunch = ProcessingWindow { start: time("12:00"), end: time("14:00") };
+        assert!(lunch.contains(time("12:00")) && !lunch.contains(time("22:00")));
+    }
+
+    #[test]
+    fn test_queue_survives_restart() {
+        let path = std::env::temp_dir().join(format!("queue_{}", uuid::Uuid::new_v4())).join("queue.json");
+        let mut queue = JobQueue::load_from(&path).unwrap();
+        let first = queue.enqueue("/audio/monday.mp3", ProcessingOptions::default()).id.clone();
+        let second = queue.enqueue("/audio/tuesday.mp3", ProcessingOptions::default()).id.clone();
+
+        assert_eq!(queue.start_next().unwrap().id, first);
+        queue.finish(&first, true, "Successfully created 3 audio chunks".to_string());
+        assert_eq!(queue.start_next().unwrap().id, second);
+        assert!(queue.remove(&second).is_err());
+        queue.save_to(&path).unwrap();
+
+        // The app quit while the second job ran
+        let mut queue = JobQueue::load_from(&path).unwrap();
+        assert_eq!(queue.jobs[1].status, JobStatus::Pending);
+        assert_eq!(queue.start_next().unwrap().id, second);
+        queue.finish(&second, false, "Processing failed: no audio stream".to_string());
+
+        let summary = queue.summary(&[first, second]);
+        assert_eq!((summary.completed, summary.failed), (1, 1));
+        assert_eq!(summary.lines[1], "tuesday.mp3: Processing failed: no audio stream");
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
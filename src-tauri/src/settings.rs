@@ -0,0 +1,335 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::audio::ProcessingOptions;
+use crate::captions::CaptionStyle;
+use crate::export::ExportOptions;
+use crate::ffmpeg::default_app_data_dir;
+use crate::merger::MergeOptions;
+use crate::power::BatteryMode;
+use crate::profanity::ProfanityList;
+use crate::project::EpisodeNaming;
+use crate::schedule::ProcessingWindow;
+use crate::upload::RemoteDestination;
+
+/// A named bundle of processing, merge and export options
+/// ("Podcast 25MB chunks → SRT", "Lecture 30min → MD").
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    #[serde(default)]
+    pub processing: ProcessingOptions,
+    #[serde(default)]
+    pub merge: MergeOptions,
+    /// Output format name as used by the merge/export commands ("srt", "md", ...)
+    #[serde(default = "default_preset_format")]
+    pub output_format: String,
+    #[serde(default)]
+    pub export: ExportOptions,
+}
+
+fn default_preset_format() -> String {
+    "txt".to_string()
+}
+
+/// Defaults for numbering episodes; the counter advances each time a project
+/// gets an episode assigned.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NamingScheme {
+    pub template: String,
+    pub show: String,
+    pub next_episode: u32,
+}
+
+impl Default for NamingScheme {
+    fn default() -> Self {
+        Self {
+            template: "{show} E{episode} – {date}".to_string(),
+            show: String::new(),
+            next_episode: 1,
+        }
+    }
+}
+
+/// User settings persisted as `settings.json` in the app data directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub presets: Vec<Preset>,
+    pub destinations: Vec<RemoteDestination>,
+    pub naming: NamingScheme,
+    pub caption_styles: Vec<CaptionStyle>,
+    pub profanity_lists: Vec<ProfanityList>,
+    /// Acronyms and names whose spelling export casing transforms keep
+    pub glossary: Vec<String>,
+    /// Data folder moved off the default location, see [`move_data_dir`]
+    pub data_dir: Option<PathBuf>,
+    /// Throttle or pause chunking while the laptop runs on battery
+    pub battery_mode: BatteryMode,
+    /// Hours in which queued processing jobs run, e.g. overnight
+    pub processing_window: Option<ProcessingWindow>,
+}
+
+impl AppSettings {
+    pub fn settings_path() -> Result<PathBuf> {
+        Ok(default_app_data_dir()?.join("settings.json"))
+    }
+
+    pub fn load() -> Result<Self> {
+        Self::load_from(&Self::settings_path()?)
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&Self::settings_path()?)
+    }
+
+    /// Missing settings file means defaults; a corrupt one is reported instead of overwritten.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        serde_json::from_str(&content)
+            .map_err(|e| anyhow!("Invalid settings file {}: {}", path.display(), e))
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    /// Adds the preset or replaces the one with the same name.
+    pub fn upsert_preset(&mut self, preset: Preset) {
+        match self.presets.iter_mut().find(|existing| existing.name == preset.name) {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+    }
+
+    pub fn remove_preset(&mut self, name: &str) -> bool {
+        let before = self.presets.len();
+        self.presets.retain(|preset| preset.name != name);
+        self.presets.len() != before
+    }
+
+    pub fn caption_style(&self, name: &str) -> Option<&CaptionStyle> {
+        self.caption_styles.iter().find(|style| style.name == name)
+    }
+
+    /// Adds the caption style or replaces the one with the same name.
+    pub fn upsert_caption_style(&mut self, style: CaptionStyle) {
+        match self.caption_styles.iter_mut().find(|existing| existing.name == style.name) {
+            Some(existing) => *existing = style,
+            None => self.caption_styles.push(style),
+        }
+    }
+
+    pub fn remove_caption_style(&mut self, name: &str) -> bool {
+        let before = self.caption_styles.len();
+        self.caption_styles.retain(|style| style.name != name);
+        self.caption_styles.len() != before
+    }
+
+    /// The profanity lists of the given languages; unknown languages are skipped.
+    pub fn profanity_lists_for(&self, languages: &[String]) -> Vec<ProfanityList> {
+        self.profanity_lists
+            .iter()
+            .filter(|list| languages.iter().any(|language| language.eq_ignore_ascii_case(&list.language)))
+            .cloned()
+            .collect()
+    }
+
+    /// Adds the list or replaces the one for the same language.
+    pub fn upsert_profanity_list(&mut self, list: ProfanityList) {
+        match self.profanity_lists.iter_mut().find(|existing| existing.language.eq_ignore_ascii_case(&list.language)) {
+            Some(existing) => *existing = list,
+            None => self.profanity_lists.push(list),
+        }
+    }
+
+    pub fn remove_profanity_list(&mut self, language: &str) -> bool {
+        let before = self.profanity_lists.len();
+        self.profanity_lists.retain(|list| !list.language.eq_ignore_ascii_case(language));
+        self.profanity_lists.len() != before
+    }
+
+    /// Replaces the glossary; blank entries and case-insensitive duplicates are dropped.
+    pub fn set_glossary(&mut self, terms: Vec<String>) {
+        let mut glossary: Vec<String> = Vec::new();
+        for term in terms {
+            let term = term.trim();
+            if !term.is_empty() && !glossary.iter().any(|existing| existing.to_lowercase() == term.to_lowercase()) {
+                glossary.push(term.to_string());
+            }
+        }
+        self.glossary = glossary;
+    }
+
+    /// Takes the next episode number and advances the counter. The show and
+    /// template fall back to the scheme defaults.
+    pub fn assign_episode(&mut self, show: Option<String>, template: Option<String>) -> EpisodeNaming {
+        let naming = EpisodeNaming {
+            template: template.unwrap_or_else(|| self.naming.template.clone()),
+            show: show.unwrap_or_else(|| self.naming.show.clone()),
+            episode: self.naming.next_episode,
+        };
+        self.naming.next_episode += 1;
+        naming
+    }
+
+    pub fn destination(&self, name: &str) -> Option<&RemoteDestination> {
+        self.destinations.iter().find(|destination| destination.name == name)
+    }
+
+    /// Adds the destination or replaces the one with the same name.
+    pub fn upsert_destination(&mut self, destination: RemoteDestination) {
+        match self.destinations.iter_mut().find(|existing| existing.name == destination.name) {
+            Some(existing) => *existing = destination,
+            None => self.destinations.push(destination),
+        }
+    }
+
+    pub fn remove_destination(&mut self, name: &str) -> bool {
+        let before = self.destinations.len();
+        self.destinations.retain(|destination| destination.name != name);
+        self.destinations.len() != before
+    }
+}
+
+/// Moves the content of the data folder `from` into `to`, except the settings
+/// file, which stays at the default location. Nothing is moved when an entry
+/// already exists in `to`. Returns the names of the moved entries.
+pub fn move_data_dir(from: &Path, to: &Path) -> Result<Vec<String>> {
+    if to.starts_with(from) || from.starts_with(to) {
+        return Err(anyhow!("The new data folder must not contain or lie inside the current one"));
+    }
+    std::fs::create_dir_all(to)?;
+
+    let entries: Vec<std::fs::DirEntry> = match std::fs::read_dir(from) {
+        Ok(entries) => entries
+            .collect::<std::io::Result<Vec<_>>>()?
+            .into_iter()
+            .filter(|entry| entry.file_name() != "settings.json")
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(existing) = entries.iter().find(|entry| to.join(entry.file_name()).exists()) {
+        return Err(anyhow!("{} already exists in {}", existing.file_name().to_string_lossy(), to.display()));
+    }
+
+    let mut moved = Vec::new();
+    for entry in entries {
+        let target = to.join(entry.file_name());
+        // Renaming fails across drives, which is the usual reason for moving
+        if std::fs::rename(entry.path(), &target).is_err() {
+            copy_recursively(&entry.path(), &target)?;
+            if entry.path().is_dir() {
+                std::fs::remove_dir_all(entry.path())?;
+            } else {
+                std::fs::remove_file(entry.path())?;
+            }
+        }
+        moved.push(entry.file_name().to_string_lossy().to_string());
+    }
+    Ok(moved)
+}
+
+fn copy_recursively(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        std::fs::copy(from, to)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presets_round_trip() {
+        let path = std::env::temp_dir()
+            .join(format!("settings_{}", uuid::Uuid::new_v4()))
+            .join("settings.json");
+        let mut settings = AppSettings::load_from(&path).unwrap();
+        assert!(settings.presets.is_empty());
+
+        settings.upsert_preset(serde_json::from_str(r#"{"name": "Lecture 30min → MD", "output_format": "md"}"#).unwrap());
+        settings.upsert_preset(Preset {
+            name: "Lecture 30min → MD".to_string(),
+            processing: ProcessingOptions { max_duration_seconds: 1800, ..ProcessingOptions::default() },
+            merge: MergeOptions::default(),
+            output_format: "md".to_string(),
+            export: ExportOptions::default(),
+        });
+        settings.save_to(&path).unwrap();
+
+        let loaded = AppSettings::load_from(&path).unwrap();
+        assert_eq!(loaded.presets.len(), 1);
+        assert_eq!(loaded.preset("Lecture 30min → MD").unwrap().output_format, "md");
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_move_data_dir() {
+        let root = std::env::temp_dir().join(format!("data_dir_{}", uuid::Uuid::new_v4()));
+        let (from, to) = (root.join("default"), root.join("other drive"));
+        std::fs::create_dir_all(from.join("ffmpeg")).unwrap();
+        std::fs::write(from.join("ffmpeg").join("ffmpeg"), b"binary").unwrap();
+        std::fs::write(from.join("settings.json"), b"{}").unwrap();
+
+        assert!(move_data_dir(&from, &from.join("nested")).is_err());
+        let moved = move_data_dir(&from, &to).unwrap();
+
+        assert_eq!(moved, vec!["ffmpeg"]);
+        assert_eq!(std::fs::read(to.join("ffmpeg").join("ffmpeg")).unwrap(), b"binary");
+        assert!(from.join("settings.json").exists() && !from.join("ffmpeg").exists());
+        std::fs::create_dir_all(from.join("ffmpeg")).unwrap();
+        assert!(move_data_dir(&from, &to).is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_assign_episode_advances_counter() {
+        let mut settings: AppSettings =
+            serde_json::from_str(r#"{"naming": {"show": "Подкаст", "next_episode": 7}}"#).unwrap();
+
+        let first = settings.assign_episode(None, None);
+        let second = settings.assign_episode(Some("Другое шоу".to_string()), None);
+
+        assert_eq!((first.episode, first.show.as_str()), (7, "Подкаст"));
+        assert_eq!(first.template, "{show} E{episode} – {date}");
+        assert_eq!((second.episode, second.show.as_str()), (8, "Другое шоу"));
+        assert_eq!(settings.naming.next_episode, 9);
+    }
+
+    #[test]
+    fn test_profanity_lists_by_language() {
+        let mut settings = AppSettings::default();
+        settings.upsert_profanity_list(ProfanityList { language: "ru".to_string(), words: vec!["блин".to_string()] });
+        settings.upsert_profanity_list(ProfanityList { language: "en".to_string(), words: vec!["damn".to_string()] });
+        settings.upsert_profanity_list(ProfanityList { language: "RU".to_string(), words: vec!["блин*".to_string()] });
+
+        let lists = settings.profanity_lists_for(&["ru".to_string(), "de".to_string()]);
+        assert_eq!(lists, vec![ProfanityList { language: "RU".to_string(), words: vec!["блин*".to_string()] }]);
+        assert!(settings.remove_profanity_list("en"));
+        assert_eq!(settings.profanity_lists.len(), 1);
+    }
+}
@@ -0,0 +1,595 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::audio::ChunkManifest;
+use crate::language::detect_language;
+use crate::merger::{split_sentences, TranscriptionSegment};
+
+/// Window of the moving type-token ratio; plain TTR falls with text length,
+/// so long and short recordings could not be compared
+const MATTR_WINDOW: usize = 100;
+
+/// Frequent function words that would otherwise top every keyword list
+const STOP_WORDS: &[&str] = &[
+    "этот", "этом", "этого", "этой", "эти", "этих", "того", "тоже", "чтобы", "только", "когда", "потому",
+    "которые", "который", "которая", "которое", "очень", "просто", "можно", "нужно", "будет", "было",
+    "были", "была", "есть", "если", "тогда", "там", "тут", "здесь", "вот", "даже", "ещё", "еще",
+    "себя", "свой", "своей", "меня", "тебя", "него", "неё", "нее", "нами", "вами", "сейчас", "какой",
+    "какие", "где", "чем", "через", "после", "перед", "между", "всех", "всем", "весь", "всё", "все",
+    "that", "this", "with", "from", "have", "they", "there", "their", "what", "which", "when", "were",
+    "would", "could", "should", "about", "just", "like", "really", "then", "than", "them", "these",
+    "those", "into", "your", "some", "will", "been", "also", "because", "know", "think", "yeah",
+];
+
+/// Words per minute within one stretch of the recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateBucket {
+    pub start: f64,
+    pub end: f64,
+    pub words: usize,
+    pub words_per_minute: f64,
+}
+
+/// Readability and verbosity figures for the merged transcript.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TranscriptStats {
+    pub segments: usize,
+    pub words: usize,
+    pub sentences: usize,
+    /// From the first segment start to the last segment end, in seconds
+    pub duration_seconds: f64,
+    pub average_sentence_words: f64,
+    pub average_word_length: f64,
+    pub words_per_minute: f64,
+    pub unique_words: usize,
+    /// Unique words / words
+    pub type_token_ratio: f64,
+    /// Mean type-token ratio over sliding windows of 100 words
+    pub moving_type_token_ratio: f64,
+    pub rate_timeline: Vec<RateBucket>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeywordOptions {
+    pub top_terms: usize,
+    pub bucket_minutes: f64,
+    /// Shorter words are ignored, which drops most particles and pronouns
+    pub min_word_length: usize,
+    /// Ignored in addition to the built-in Russian and English stop words
+    pub stop_words: Vec<String>,
+}
+
+impl Default for KeywordOptions {
+    fn default() -> Self {
+        Self {
+            top_terms: 20,
+            bucket_minutes: 5.0,
+            min_word_length: 4,
+            stop_words: Vec::new(),
+        }
+    }
+}
+
+/// Where one frequent term occurs in the recording.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TermTimeline {
+    pub term: String,
+    pub count: usize,
+    /// Start times of the segments using the term, once per occurrence
+    pub occurrences: Vec<f64>,
+    /// Occurrences per bucket, one heatmap row
+    pub buckets: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KeywordTimeline {
+    pub bucket_seconds: f64,
+    pub bucket_count: usize,
+    pub terms: Vec<TermTimeline>,
+}
+
+/// How much of one audio chunk the transcript covers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkCoverage {
+    pub chunk_number: usize,
+    pub file_name: String,
+    pub duration: f64,
+    /// Detected silence inside the chunk, not expected to be transcribed
+    pub silence_seconds: f64,
+    /// Time inside the chunk covered by at least one segment
+    pub covered_seconds: f64,
+    /// Covered share of the non-silent part, from 0.0 to 1.0
+    pub coverage: f64,
+}
+
+/// Totals of a merge for delivery reports.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MergeStats {
+    pub duration_seconds: f64,
+    pub words: usize,
+    pub segments: usize,
+    pub words_per_minute: f64,
+    /// In merge order
+    pub files: Vec<FileStats>,
+    /// Empty when the transcript has no speaker labels
+    pub speakers: Vec<SpeakerTime>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileStats {
+    pub file_name: String,
+    pub segments: usize,
+    pub words: usize,
+    /// Detected from the file's own segments
+    pub language: Option<String>,
+}
+
+/// How long one speaker talks, summed over their segments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpeakerTime {
+    pub speaker: String,
+    pub seconds: f64,
+    pub segments: usize,
+    pub words: usize,
+}
+
+/// Where one merged file ends and the next begins. A negative gap is an
+/// overlap, which usually means a wrong offset or chunk order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileBoundary {
+    pub previous_file: String,
+    pub next_file: String,
+    /// End of the last segment of the previous file
+    pub previous_end: f64,
+    /// Start of the first segment of the next file
+    pub next_start: f64,
+    pub gap_seconds: f64,
+    pub message: String,
+}
+
+/// A chunk whose transcript should be redone, e.g. with a larger model.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetranscriptionCandidate {
+    pub chunk_number: usize,
+    /// Audio file of the chunk, next to the manifest
+    pub file_name: String,
+    pub coverage: f64,
+    /// Suspected hallucinations starting inside the chunk
+    pub hallucinations: usize,
+}
+
+/// Lowercased words of a text; digits count as words, punctuation does not.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|ch: char| !(ch.is_alphanumeric() || ch == '\'' || ch == '’' || ch == '-'))
+        .map(|word| word.trim_matches(|ch: char| !ch.is_alphanumeric()))
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Segment text without a leading "Speaker:" label.
+pub fn spoken_text(segment: &TranscriptionSegment) -> &str {
+    let text = segment.text.trim();
+    segment
+        .speaker
+        .as_deref()
+        .and_then(|speaker| text.strip_prefix(speaker))
+        .and_then(|rest| rest.strip_prefix(':'))
+        .map(str::trim)
+        .unwrap_or(text)
+}
+
+/// Statistics over the transcript segments; merger-inserted markers (ad
+/// breaks) are left out. `bucket_seconds` sets the resolution of the rate timeline.
+pub fn compute_stats(segments: &[TranscriptionSegment], default_duration: f64, bucket_seconds: f64) -> TranscriptStats {
+    let segments: Vec<&TranscriptionSegment> = segments
+        .iter()
+        .filter(|segment| !segment.original_filename.is_empty())
+        .collect();
+    let Some(first) = segments.first() else {
+        return TranscriptStats::default();
+    };
+
+    let start = first.start_time;
+    let end = segments
+        .iter()
+        .map(|segment| segment.end_time.unwrap_or(segment.start_time + default_duration))
+        .fold(start, f64::max);
+    let duration = end - start;
+
+    let mut words = Vec::new();
+    let mut sentences = 0;
+    let bucket_seconds = bucket_seconds.max(1.0);
+    let mut bucket_words = vec![0; (duration / bucket_seconds).ceil().max(1.0) as usize];
+    for segment in &segments {
+        let text = spoken_text(segment);
+        let segment_words = tokenize(text);
+        sentences += split_sentences(text)
+            .iter()
+            .filter(|sentence| !tokenize(sentence).is_empty())
+            .count();
+        let bucket = (((segment.start_time - start) / bucket_seconds) as usize).min(bucket_words.len() - 1);
+        bucket_words[bucket] += segment_words.len();
+        words.extend(segment_words);
+    }
+
+    let rate_timeline = bucket_words
+        .iter()
+        .enumerate()
+        .map(|(index, &count)| {
+            let bucket_start = start + index as f64 * bucket_seconds;
+            let bucket_end = (bucket_start + bucket_seconds).min(end);
+            RateBucket {
+                start: bucket_start,
+                end: bucket_end,
+                words: count,
+                words_per_minute: per_minute(count, bucket_end - bucket_start),
+            }
+        })
+        .collect();
+
+    let unique_words = words.iter().collect::<HashSet<_>>().len();
+    let ratio = |count: usize, total: usize| if total == 0 { 0.0 } else { count as f64 / total as f64 };
+
+    TranscriptStats {
+        segments: segments.len(),
+        words: words.len(),
+        sentences,
+        duration_seconds: duration,
+        average_sentence_words: ratio(words.len(), sentences),
+        average_word_length: ratio(words.iter().map(|word| word.chars().count()).sum(), words.len()),
+        words_per_minute: per_minute(words.len(), duration),
+        unique_words,
+        type_token_ratio: ratio(unique_words, words.len()),
+        moving_type_token_ratio: moving_type_token_ratio(&words),
+        rate_timeline,
+    }
+}
+
+/// The most frequent content words with their timestamps, bucketed from the
+/// start of the recording so the UI can draw a topic heatmap.
+pub fn keyword_timeline(segments: &[TranscriptionSegment], default_duration: f64, options: &KeywordOptions) -> KeywordTimeline {
+    let bucket_seconds = (options.bucket_minutes * 60.0).max(1.0);
+    let stop_words: HashSet<String> = STOP_WORDS
+        .iter()
+        .map(|word| word.to_string())
+        .chain(options.stop_words.iter().map(|word| word.to_lowercase()))
+        .collect();
+
+    let mut occurrences: HashMap<String, Vec<f64>> = HashMap::new();
+    let mut end: f64 = 0.0;
+    for segment in segments.iter().filter(|segment| !segment.original_filename.is_empty()) {
+        end = end.max(segment.end_time.unwrap_or(segment.start_time + default_duration));
+        for word in tokenize(spoken_text(segment)) {
+            if word.chars().count() < options.min_word_length
+                || word.chars().all(|ch| ch.is_numeric())
+                || stop_words.contains(&word)
+            {
+                continue;
+            }
+            occurrences.entry(word).or_default().push(segment.start_time);
+        }
+    }
+
+    let bucket_count = (end / bucket_seconds).ceil().max(1.0) as usize;
+    let mut terms: Vec<TermTimeline> = occurrences
+        .into_iter()
+        .map(|(term, times)| {
+            let mut buckets = vec![0; bucket_count];
+            for time in &times {
+                buckets[((time / bucket_seconds) as usize).min(bucket_count - 1)] += 1;
+            }
+            TermTimeline { term, count: times.len(), occurrences: times, buckets }
+        })
+        .collect();
+    // Ties in alphabetical order so the result does not depend on hashing
+    terms.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.term.cmp(&b.term)));
+    terms.truncate(options.top_terms);
+
+    KeywordTimeline { bucket_seconds, bucket_count, terms }
+}
+
+/// Coverage of every chunk in the manifest by the merged segments, which are
+/// in source time like the chunks. A failed or truncated chunk transcript
+/// shows up as a chunk with little coverage.
+/// Duration, word and segment counts of the merged transcript, per file and
+/// per speaker. Speakers are listed by talk time, longest first.
+pub fn compute_merge_stats(segments: &[TranscriptionSegment], default_duration: f64) -> MergeStats {
+    let totals = compute_stats(segments, default_duration, f64::MAX);
+
+    let mut files: Vec<FileStats> = Vec::new();
+    let mut speakers: Vec<SpeakerTime> = Vec::new();
+    for segment in segments.iter().filter(|segment| !segment.original_filename.is_empty()) {
+        let words = tokenize(spoken_text(segment)).len();
+        let file = match files.iter_mut().find(|file| file.file_name == segment.original_filename) {
+            Some(file) => file,
+            None => {
+                files.push(FileStats { file_name: segment.original_filename.clone(), segments: 0, words: 0, language: None });
+                files.last_mut().unwrap()
+            }
+        };
+        file.segments += 1;
+        file.words += words;
+
+        if let Some(name) = &segment.speaker {
+            let seconds = (segment.end_time.unwrap_or(segment.start_time + default_duration) - segment.start_time).max(0.0);
+            match speakers.iter_mut().find(|speaker| &speaker.speaker == name) {
+                Some(speaker) => {
+                    speaker.seconds += seconds;
+                    speaker.segments += 1;
+                    speaker.words += words;
+                }
+                None => speakers.push(SpeakerTime { speaker: name.clone(), seconds, segments: 1, words }),
+            }
+        }
+    }
+    speakers.sort_by(|a, b| b.seconds.partial_cmp(&a.seconds).unwrap_or(std::cmp::Ordering::Equal));
+    for file in files.iter_mut() {
+        let file_segments: Vec<TranscriptionSegment> =
+            segments.iter().filter(|segment| segment.original_filename == file.file_name).cloned().collect();
+        file.language = detect_language(&file_segments);
+    }
+
+    MergeStats {
+        duration_seconds: totals.duration_seconds,
+        words: totals.words,
+        segments: totals.segments,
+        words_per_minute: totals.words_per_minute,
+        files,
+        speakers,
+    }
+}
+
+/// Gaps and overlaps between consecutive files of a merge, in merge order.
+/// Files without segments are skipped, so their neighbours are compared.
+pub fn file_boundaries(segments: &[TranscriptionSegment], file_names: &[String], default_duration: f64) -> Vec<FileBoundary> {
+    let spans: Vec<(&String, f64, f64)> = file_names
+        .iter()
+        .filter_map(|name| {
+            let file_segments = segments.iter().filter(|segment| &segment.original_filename == name);
+            file_segments.fold(None, |span: Option<(f64, f64)>, segment| {
+                let end = segment.end_time.unwrap_or(segment.start_time + default_duration);
+                Some(match span {
+                    Some((start, span_end)) => (start.min(segment.start_time), span_end.max(end)),
+                    None => (segment.start_time, end),
+                })
+            })
+            .map(|(start, end)| (name, start, end))
+        })
+        .collect();
+
+    spans
+        .windows(2)
+        .map(|pair| {
+            let (previous_file, _, previous_end) = pair[0];
+            let (next_file, next_start, _) = pair[1];
+            let gap_seconds = next_start - previous_end;
+            let kind = if gap_seconds < 0.0 { "overlap" } else { "gap" };
+            FileBoundary {
+                previous_file: previous_file.clone(),
+                next_file: next_file.clone(),
+                previous_end,
+                next_start,
+                gap_seconds,
+                message: format!("{:.1}s {} between {} and {}", gap_seconds.abs(), kind, previous_file, next_file),
+            }
+        })
+        .collect()
+}
+
+pub fn chunk_coverage(segments: &[TranscriptionSegment], manifest: &ChunkManifest, default_duration: f64) -> Vec<ChunkCoverage> {
+    let mut intervals: Vec<(f64, f64)> = segments
+        .iter()
+        .filter(|segment| !segment.original_filename.is_empty())
+        .map(|segment| (segment.start_time, segment.end_time.unwrap_or(segment.start_time + default_duration)))
+        .filter(|(start, end)| end > start)
+        .collect();
+    intervals.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    // Overlapping segments must not count twice
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in intervals {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let overlap = |ranges: &mut dyn Iterator<Item = (f64, f64)>, start: f64, end: f64| -> f64 {
+        ranges.map(|(from, to)| (to.min(end) - from.max(start)).max(0.0)).sum()
+    };
+
+    manifest
+        .chunks
+        .iter()
+        .map(|chunk| {
+            let start = chunk.start_time;
+            let end = chunk.start_time + chunk.duration;
+            let covered_seconds = overlap(&mut merged.iter().copied(), start, end);
+            let silence_seconds = overlap(&mut manifest.silences.iter().map(|silence| (silence.start, silence.end)), start, end);
+            let expected = (chunk.duration - silence_seconds).max(1.0);
+            ChunkCoverage {
+                chunk_number: chunk.chunk_number,
+                file_name: chunk.file_name.clone(),
+                duration: chunk.duration,
+                silence_seconds,
+                covered_seconds,
+                coverage: (covered_seconds / expected).min(1.0),
+            }
+        })
+        .collect()
+}
+
+/// Chunks covered less than `min_coverage` or containing suspected
+/// hallucinations, in chunk order.
+pub fn retranscription_candidates(
+    coverage: &[ChunkCoverage],
+    hallucinations: &[TranscriptionSegment],
+    manifest: &ChunkManifest,
+    min_coverage: f64,
+) -> Vec<RetranscriptionCandidate> {
+    manifest
+        .chunks
+        .iter()
+        .zip(coverage)
+        .filter_map(|(chunk, chunk_coverage)| {
+            let end = chunk.start_time + chunk.duration;
+            let hallucinations = hallucinations
+                .iter()
+                .filter(|segment| segment.start_time >= chunk.start_time && segment.start_time < end)
+                .count();
+            (chunk_coverage.coverage < min_coverage || hallucinations > 0).then(|| RetranscriptionCandidate {
+                chunk_number: chunk.chunk_number,
+                file_name: chunk.file_name.clone(),
+                coverage: chunk_coverage.coverage,
+                hallucinations,
+            })
+        })
+        .collect()
+}
+
+fn per_minute(words: usize, seconds: f64) -> f64 {
+    if seconds > 0.0 {
+        words as f64 * 60.0 / seconds
+    } else {
+        0.0
+    }
+}
+
+/// MATTR: the type-token ratio averaged over every window of `MATTR_WINDOW`
+/// consecutive words (plain TTR for shorter texts).
+fn moving_type_token_ratio(words: &[String]) -> f64 {
+    if words.is_empty() {
+        return 0.0;
+    }
+    if words.len() <= MATTR_WINDOW {
+        return words.iter().collect::<HashSet<_>>().len() as f64 / words.len() as f64;
+    }
+
+    let windows = words.windows(MATTR_WINDOW);
+    let count = windows.len();
+    windows
+        .map(|window| window.iter().collect::<HashSet<_>>().len() as f64 / MATTR_WINDOW as f64)
+        .sum::<f64>()
+        / count as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_stats() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 30.0, "Анна: Привет всем. Сегодня мы обсудим бюджет!").file("part1.txt").speaker("Анна"),
+            TranscriptionSegment::test(30.0, 60.0, "Бюджет на 2025-й год утверждён").file("part1.txt"),
+            TranscriptionSegment::test(60.0, 120.0, "[ad break]"),
+            TranscriptionSegment::test(120.0, 150.0, "Да, да, да.").file("part1.txt"),
+        ];
+
+        let stats = compute_stats(&segments, 5.0, 60.0);
+
+        assert_eq!(stats.segments, 3);
+        assert_eq!((stats.words, stats.sentences, stats.unique_words), (14, 4, 11));
+        assert_eq!(stats.duration_seconds, 150.0);
+        assert_eq!(stats.words_per_minute, 5.6);
+        assert_eq!(stats.average_sentence_words, 3.5);
+        assert!((stats.type_token_ratio - 11.0 / 14.0).abs() < 1e-9);
+        let timeline: Vec<(f64, f64, usize)> = stats.rate_timeline.iter().map(|b| (b.start, b.end, b.words)).collect();
+        assert_eq!(timeline, vec![(0.0, 60.0, 11), (60.0, 120.0, 0), (120.0, 150.0, 3)]);
+        assert_eq!(stats.rate_timeline[2].words_per_minute, 6.0);
+    }
+
+    #[test]
+    fn test_keyword_timeline() {
+        let segments = vec![
+            TranscriptionSegment::test(10.0, 20.0, "Анна: Бюджет проекта и бюджет отдела").file("part1.txt").speaker("Анна"),
+            TranscriptionSegment::test(400.0, 410.0, "Потому что бюджет утверждён, проект стартует").file("part1.txt"),
+            TranscriptionSegment::test(620.0, 630.0, "Проекта пока нет в плане").file("part1.txt"),
+        ];
+        let options = KeywordOptions { top_terms: 2, ..KeywordOptions::default() };
+
+        let timeline = keyword_timeline(&segments, 5.0, &options);
+
+        assert_eq!((timeline.bucket_seconds, timeline.bucket_count), (300.0, 3));
+        assert_eq!(timeline.terms, vec![
+            TermTimeline { term: "бюджет".to_string(), count: 3, occurrences: vec![10.0, 10.0, 400.0], buckets: vec![2, 1, 0] },
+            TermTimeline { term: "проекта".to_string(), count: 2, occurrences: vec![10.0, 620.0], buckets: vec![1, 0, 1] },
+        ]);
+    }
+
+    #[test]
+    fn test_compute_merge_stats() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 10.0, "Анна: Добрый день всем").file("part1.txt").speaker("Анна"),
+            TranscriptionSegment::test(10.0, 40.0, "Борис: Спасибо, начнём с бюджета").file("part2.txt").speaker("Борис"),
+            TranscriptionSegment::test(40.0, 50.0, "Анна: Хорошо").file("part1.txt").speaker("Анна"),
+        ];
+
+        let stats = compute_merge_stats(&segments, 5.0);
+        assert_eq!((stats.duration_seconds, stats.words, stats.segments, stats.words_per_minute), (50.0, 8, 3, 9.6));
+        let files: Vec<(&str, usize, usize)> = stats.files.iter().map(|file| (file.file_name.as_str(), file.segments, file.words)).collect();
+        assert_eq!(files, vec![("part1.txt", 2, 4), ("part2.txt", 1, 4)]);
+        let speakers: Vec<(&str, f64)> = stats.speakers.iter().map(|speaker| (speaker.speaker.as_str(), speaker.seconds)).collect();
+        assert_eq!(speakers, vec![("Борис", 30.0), ("Анна", 20.0)]);
+    }
+
+    #[test]
+    fn test_file_boundaries() {
+        let in_file = |name: &str, start_time: f64, end_time: f64| TranscriptionSegment::test(start_time, end_time, "Текст").file(name);
+        let segments = vec![
+            in_file("chunk_004.srt", 0.0, 60.0),
+            in_file("chunk_005.srt", 56.8, 70.0),
+            in_file("chunk_005.srt", 70.0, 120.0),
+            in_file("chunk_007.srt", 125.0, 130.0),
+        ];
+        let names: Vec<String> = ["chunk_004.srt", "chunk_005.srt", "chunk_006.srt", "chunk_007.srt"].map(String::from).to_vec();
+
+        let messages: Vec<String> = file_boundaries(&segments, &names, 5.0).into_iter().map(|boundary| boundary.message).collect();
+        assert_eq!(
+            messages,
+            vec!["3.2s overlap between chunk_004.srt and chunk_005.srt", "5.0s gap between chunk_005.srt and chunk_007.srt"]
+        );
+    }
+
+    #[test]
+    fn test_chunk_coverage() {
+        use crate::audio::{ManifestChunk, SilenceRange};
+
+        let chunk = |chunk_number: usize, start_time: f64| ManifestChunk {
+            file_name: format!("chunk_{:03}.mp3", chunk_number),
+            chunk_number,
+            start_time,
+            duration: 100.0,
+        };
+        let manifest = ChunkManifest {
+            source: "show.mp3".to_string(),
+            chunks: vec![chunk(1, 0.0), chunk(2, 100.0)],
+            silences: vec![SilenceRange { start: 80.0, end: 100.0 }],
+            assignments: Vec::new(),
+        };
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 50.0, "Первая часть").file("part1.txt"),
+            TranscriptionSegment::test(40.0, 80.0, "Перекрывается с первой").file("part1.txt"),
+            TranscriptionSegment::test(95.0, 140.0, "Через границу фрагментов").file("part1.txt"),
+        ];
+
+        let coverage = chunk_coverage(&segments, &manifest, 5.0);
+
+        let summary: Vec<(f64, f64, f64)> = coverage.iter().map(|c| (c.silence_seconds, c.covered_seconds, c.coverage)).collect();
+        assert_eq!(summary, vec![(20.0, 85.0, 1.0), (0.0, 40.0, 0.4)]);
+
+        let hallucinations = vec![TranscriptionSegment::test(20.0, 24.0, "Продолжение следует...").file("part1.txt")];
+        let candidates: Vec<(usize, usize)> = retranscription_candidates(&coverage, &hallucinations, &manifest, 0.5)
+            .iter()
+            .map(|candidate| (candidate.chunk_number, candidate.hallucinations))
+            .collect();
+        assert_eq!(candidates, vec![(1, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("It's 2025-й — «Год» ok?!"), vec!["it's", "2025-й", "год", "ok"]);
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::merger::TranscriptionSegment;
+use crate::supervisor::{run_supervised, Supervision};
+
+/// Stem name for segments without a detected speaker
+const UNKNOWN_SPEAKER: &str = "unknown";
+
+/// Where the per-speaker separation comes from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StemSource {
+    /// Speaker labels of the transcript: each stem keeps the full timeline with
+    /// everything outside that speaker's segments muted
+    Speakers,
+    /// One stem per input channel, e.g. a two-mic interview recorded to L/R.
+    /// `names[i]` is the speaker on channel `i`.
+    Channels { names: Vec<String> },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StemOptions {
+    pub source: StemSource,
+    /// Seconds kept open around each segment so word onsets are not cut
+    pub padding: f64,
+    /// Also write the transcript as an Audacity/Reaper-importable label file
+    pub markers: bool,
+}
+
+impl Default for StemOptions {
+    fn default() -> Self {
+        Self {
+            source: StemSource::Speakers,
+            padding: 0.25,
+            markers: true,
+        }
+    }
+}
+
+/// One stem to render: file name stem and the audio filter producing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StemPlan {
+    pub name: String,
+    pub filter: String,
+}
+
+/// Plans one stem per speaker, in order of first appearance.
+pub fn plan_stems(segments: &[TranscriptionSegment], options: &StemOptions, default_duration: f64) -> Vec<StemPlan> {
+    match &options.source {
+        StemSource::Channels { names } => names
+            .iter()
+            .enumerate()
+            .map(|(channel, name)| StemPlan {
+                name: name.clone(),
+                filter: format!("pan=mono|c0=c{}", channel),
+            })
+            .collect(),
+        StemSource::Speakers => {
+            let mut speakers: Vec<(String, Vec<(f64, f64)>)> = Vec::new();
+            for segment in segments {
+                let speaker = segment.speaker.as_deref().unwrap_or(UNKNOWN_SPEAKER);
+                let end = segment.end_time.unwrap_or(segment.start_time + default_duration);
+                let range = ((segment.start_time - options.padding).max(0.0), end + options.padding);
+                match speakers.iter_mut().find(|(name, _)| name == speaker) {
+                    Some((_, ranges)) => ranges.push(range),
+                    None => speakers.push((speaker.to_string(), vec![range])),
+                }
+            }
+
+            speakers
+                .into_iter()
+                .map(|(name, ranges)| StemPlan {
+                    name,
+                    filter: gate_filter(&ranges),
+                })
+                .collect()
+        }
+    }
+}
+
+/// Volume filter passing audio only inside the (merged) ranges.
+fn gate_filter(ranges: &[(f64, f64)]) -> String {
+    let mut sorted = ranges.to_vec();
+    sorted.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+    let mut merged: Vec<(f64, f64)> = Vec::new();
+    for (start, end) in sorted {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+
+    let condition = merged
+        .iter()
+        .map(|(start, end)| format!("between(t,{:.3},{:.3})", start, end))
+        .collect::<Vec<_>>()
+        .join("+");
+    format!("volume='gt({},0)':eval=frame", condition)
+}
+
+/// Tab-separated label track (`start end text`), importable by Audacity and
+/// as markers by most DAWs via their label/region import.
+pub fn marker_labels(segments: &[TranscriptionSegment], default_duration: f64) -> String {
+    segments
+        .iter()
+        .map(|segment| {
+            let end = segment.end_time.unwrap_or(segment.start_time + default_duration);
+            let text = segment.text.replace(['\t', '\n', '\r'], " ");
+            format!("{:.6}\t{:.6}\t{}\n", segment.start_time, end, text)
+        })
+        .collect()
+}
+
+/// Renders the stem as 16-bit WAV of the full source length, so all stems line
+/// up when dropped on the DAW timeline at zero.
+pub async fn render_stem(ffmpeg_path: &Path, input_path: &Path, plan: &StemPlan, output_path: &Path) -> Result<()> {
+    // Long transcripts give filter expressions beyond command line limits
+    let script_path = std::env::temp_dir().join(format!("stem_filter_{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&script_path, &plan.filter)?;
+
+    // A stem spans the whole recording, so only a stalled run is cut short
+    let run = run_supervised(
+        || {
+            let mut cmd = Command::new(ffmpeg_path);
+            cmd.arg("-i").arg(input_path);
+            cmd.arg("-filter_script:a").arg(&script_path);
+            cmd.args(["-acodec", "pcm_s16le", "-y"]);
+            cmd.arg(output_path);
+            cmd
+        },
+        Supervision { timeout: None, ..Supervision::default() },
+    )
+    .await;
+    let _ = std::fs::remove_file(&script_path);
+
+    if let Err(e) = &run {
+        println!("FFmpeg stem rendering failed: {}", e);
+    }
+    run.map(|_| ())
+}
+
+/// `<dir>/<index>_<speaker>.wav`, numbered so the DAW keeps the track order.
+pub fn stem_path(output_dir: &Path, index: usize, name: &str) -> PathBuf {
+    let safe_name: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    output_dir.join(format!("{:02}_{}.wav", index + 1, safe_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_speaker_stems() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 5.0, "text").speaker("Анна"),
+            TranscriptionSegment::test(5.0, 9.0, "text").speaker("Борис"),
+            TranscriptionSegment::test(9.2, 12.0, "text").speaker("Анна"),
+            TranscriptionSegment::test(30.0, 31.0, "text"),
+        ];
+        let options = StemOptions { padding: 0.25, ..StemOptions::default() };
+
+        let plans = plan_stems(&segments, &options, 3.0);
+
+        let names: Vec<&str> = plans.iter().map(|plan| plan.name.as_str()).collect();
+        assert_eq!(names, vec!["Анна", "Борис", "unknown"]);
+        // Padded ranges 0–5.25 and 8.95–12.25 stay separate
+        assert_eq!(
+            plans[0].filter,
+            "volume='gt(between(t,0.000,5.250)+between(t,8.950,12.250),0)':eval=frame"
+        );
+        assert_eq!(stem_path(Path::new("/out"), 0, "Анна К."), PathBuf::from("/out/01_Анна_К_.wav"));
+    }
+
+    #[test]
+    fn test_channel_stems_and_markers() {
+        let options = StemOptions {
+            source: StemSource::Channels { names: vec!["Host".to_string(), "Guest".to_string()] },
+            ..StemOptions::default()
+        };
+        let plans = plan_stems(&[], &options, 3.0);
+        assert_eq!(plans[1].filter, "pan=mono|c0=c1");
+
+        let labels = marker_labels(&[TranscriptionSegment::test(1.5, 2.0, "text")], 3.0);
+        assert_eq!(labels, "1.500000\t2.000000\ttext\n");
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::audio::CHUNK_MANIFEST_FILE;
+use crate::project::{OperationKind, Project};
+
+/// Name prefixes of the temporary files and folders the app creates; they are
+/// normally removed right away but stay behind when an operation is cut short.
+const TEMP_PREFIXES: &[&str] = &[
+    "transcription-assistant-drag",
+    "viewer_audio_",
+    "captions_",
+    "stem_filter_",
+    "quote_card_",
+    "diagnostics_tone_",
+    "sftp_batch_",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageCategory {
+    /// The downloaded FFmpeg build; downloaded again when needed
+    Ffmpeg,
+    /// Audio chunks listed in the processing history of the projects
+    Chunks,
+    /// Leftover temporary files
+    Cache,
+}
+
+impl StorageCategory {
+    pub const ALL: [StorageCategory; 3] = [Self::Ffmpeg, Self::Chunks, Self::Cache];
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct StorageUsage {
+    pub category: StorageCategory,
+    pub bytes: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+/// Where the app keeps things on disk; the projects provide the chunk lists.
+pub struct StorageLocations<'a> {
+    pub app_data_dir: &'a Path,
+    pub temp_dir: &'a Path,
+    pub projects: &'a [Project],
+}
+
+impl StorageLocations<'_> {
+    /// Existing files and folders belonging to `category`.
+    pub fn paths(&self, category: StorageCategory) -> Vec<PathBuf> {
+        let paths = match category {
+            StorageCategory::Ffmpeg => vec![self.app_data_dir.join("ffmpeg")],
+            StorageCategory::Chunks => self.chunk_paths(),
+            StorageCategory::Cache => std::fs::read_dir(self.temp_dir)
+                .map(|entries| {
+                    entries
+                        .filter_map(|entry| entry.ok())
+                        .filter(|entry| {
+                            let name = entry.file_name().to_string_lossy().to_string();
+                            TEMP_PREFIXES.iter().any(|prefix| name.starts_with(prefix))
+                        })
+                        .map(|entry| entry.path())
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        paths.into_iter().filter(|path| path.exists()).collect()
+    }
+
+    pub fn usage(&self, category: StorageCategory) -> StorageUsage {
+        let paths = self.paths(category);
+        StorageUsage {
+            category,
+            bytes: paths.iter().map(|path| disk_usage(path)).sum(),
+            paths,
+        }
+    }
+
+    /// Deletes everything in `category` and returns the bytes freed. Chunk
+    /// folders are removed once no other files are left in them.
+    pub fn clean(&self, category: StorageCategory) -> Result<u64> {
+        let usage = self.usage(category);
+        for path in &usage.paths {
+            if path.is_dir() {
+                std::fs::remove_dir_all(path)?;
+            } else {
+                std::fs::remove_file(path)?;
+            }
+        }
+
+        if category == StorageCategory::Chunks {
+            let folders: BTreeSet<&Path> = usage.paths.iter().filter_map(|path| path.parent()).collect();
+            for folder in folders {
+                // Fails, as intended, when the folder still holds other files
+                let _ = std::fs::remove_dir(folder);
+            }
+        }
+        Ok(usage.bytes)
+    }
+
+    /// The chunk files of every recorded processing run plus their manifests.
+    fn chunk_paths(&self) -> Vec<PathBuf> {
+        let mut paths = BTreeSet::new();
+        let processing_runs = self
+            .projects
+            .iter()
+            .flat_map(|project| &project.history)
+            .filter(|entry| entry.kind == OperationKind::Processing);
+        for entry in processing_runs {
+            let outputs = entry.details.get("outputs").and_then(|outputs| outputs.as_array());
+            for output in outputs.into_iter().flatten().filter_map(|output| output.as_str()) {
+                let path = PathBuf::from(output);
+                if let Some(folder) = path.parent() {
+                    paths.insert(folder.join(CHUNK_MANIFEST_FILE));
+                }
+                paths.insert(path);
+            }
+        }
+        paths.into_iter().collect()
+    }
+}
+
+fn disk_usage(path: &Path) -> u64 {
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.is_dir() => std::fs::read_dir(path)
+            .map(|entries| entries.filter_map(|entry| entry.ok()).map(|entry| disk_usage(&entry.path())).sum())
+            .unwrap_or(0),
+        Ok(metadata) => metadata.len(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_usage_and_cleanup() {
+        let root = std::env::temp_dir().join(format!("storage_{}", uuid::Uuid::new_v4()));
+        let (app_data_dir, temp_dir, chunk_dir) = (root.join("data"), root.join("tmp"), root.join("lecture_chunks"));
+        for directory in [app_data_dir.join("ffmpeg"), temp_dir.join("transcription-assistant-drag"), chunk_dir.clone()] {
+            std::fs::create_dir_all(directory).unwrap();
+        }
+        std::fs::write(app_data_dir.join("ffmpeg").join("ffmpeg"), vec![0u8; 100]).unwrap();
+        std::fs::write(temp_dir.join("transcription-assistant-drag").join("segments.srt"), vec![0u8; 10]).unwrap();
+        std::fs::write(temp_dir.join("unrelated.txt"), vec![0u8; 1000]).unwrap();
+        let chunk = chunk_dir.join("chunk_001.mp3");
+        std::fs::write(&chunk, vec![0u8; 40]).unwrap();
+        std::fs::write(chunk_dir.join(CHUNK_MANIFEST_FILE), vec![0u8; 2]).unwrap();
+
+        let mut project = Project::new("Lecture", root.join("lecture.taproj"));
+        project.record(
+            OperationKind::Processing,
+            "Split",
+            serde_json::json!({ "outputs": [chunk, chunk_dir.join("chunk_002.mp3")] }),
+        );
+        let projects = vec![project];
+        let locations = StorageLocations { app_data_dir: &app_data_dir, temp_dir: &temp_dir, projects: &projects };
+
+        let bytes: Vec<u64> = StorageCategory::ALL.iter().map(|category| locations.usage(*category).bytes).collect();
+        assert_eq!(bytes, vec![100, 42, 10]);
+
+        assert_eq!(locations.clean(StorageCategory::Chunks).unwrap(), 42);
+        assert!(!chunk_dir.exists());
+        assert_eq!(locations.clean(StorageCategory::Cache).unwrap(), 10);
+        assert!(temp_dir.join("unrelated.txt").exists());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+use anyhow::{anyhow, Result};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::time::{timeout, Instant};
+
+/// Limits one FFmpeg run is held to. A run that takes too long or stops
+/// writing to stderr (FFmpeg reports progress there) is killed and started
+/// again; one that exits with an error is not, as it would fail the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Supervision {
+    /// No limit when `None`, for runs over a whole recording
+    pub timeout: Option<Duration>,
+    pub stall_timeout: Duration,
+    pub max_retries: u32,
+}
+
+impl Default for Supervision {
+    fn default() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(600)),
+            stall_timeout: Duration::from_secs(120),
+            max_retries: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SupervisedRun {
+    pub stderr: String,
+    /// Runs that were killed before the one that finished
+    pub retries: u32,
+}
+
+enum Attempt {
+    Finished(std::process::ExitStatus, String),
+    Killed(String),
+}
+
+/// Runs the command built by `build` until it finishes, retrying hung runs.
+/// Stdout is discarded, so FFmpeg has to write its output to a file.
+pub async fn run_supervised(build: impl Fn() -> Command, supervision: Supervision) -> Result<SupervisedRun> {
+    let mut reasons = Vec::new();
+    for retries in 0..=supervision.max_retries {
+        match run_once(build(), supervision).await? {
+            Attempt::Finished(status, stderr) if status.success() => return Ok(SupervisedRun { stderr, retries }),
+            Attempt::Finished(status, stderr) => return Err(anyhow!("FFmpeg failed ({}): {}", status, stderr)),
+            Attempt::Killed(reason) => {
+                println!("FFmpeg run {} killed: {}", retries + 1, reason);
+                reasons.push(reason);
+            }
+        }
+    }
+    Err(anyhow!("FFmpeg hung on all {} attempts: {}", reasons.len(), reasons.join("; ")))
+}
+
+async fn run_once(mut command: Command, supervision: Supervision) -> Result<Attempt> {
+    command.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::piped()).kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let mut child = command.spawn()?;
+    let mut stderr = child.stderr.take().ok_or_else(|| anyhow!("FFmpeg stderr is not available"))?;
+    let deadline = supervision.timeout.map(|limit| Instant::now() + limit);
+
+    let mut output = Vec::new();
+    let mut buffer = [0u8; 4096];
+    loop {
+        let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        let wait = remaining.map_or(supervision.stall_timeout, |remaining| remaining.min(supervision.stall_timeout));
+        match timeout(wait, stderr.read(&mut buffer)).await {
+            Ok(Ok(0)) => break,
+            Ok(Ok(read)) => output.extend_from_slice(&buffer[..read]),
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => {
+                let _ = child.kill().await;
+                let reason = match deadline {
+                    Some(deadline) if Instant::now() >= deadline => {
+                        format!("still running after {:?}", supervision.timeout.unwrap_or_default())
+                    }
+                    _ => format!("no progress for {:?}", supervision.stall_timeout),
+                };
+                return Ok(Attempt::Killed(reason));
+            }
+        }
+    }
+
+    let status = child.wait().await?;
+    Ok(Attempt::Finished(status, String::from_utf8_lossy(&output).to_string()))
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_run_supervised() {
+        let shell = |script: &'static str| {
+            move || {
+                let mut command = Command::new("sh");
+                command.args(["-c", script]);
+                command
+            }
+        };
+        let supervision = Supervision {
+            timeout: Some(Duration::from_secs(5)),
+            stall_timeout: Duration::from_millis(200),
+            max_retries: 1,
+        };
+
+        let run = run_supervised(shell("printf 'size=1kB' >&2"), supervision).await.unwrap();
+        assert_eq!((run.stderr.as_str(), run.retries), ("size=1kB", 0));
+
+        let error = run_supervised(shell("printf 'size=1kB' >&2; sleep 5"), supervision).await.unwrap_err();
+        assert_eq!(error.to_string(), "FFmpeg hung on all 2 attempts: no progress for 200ms; no progress for 200ms");
+
+        assert!(run_supervised(shell("echo 'Invalid data' >&2; exit 1"), supervision).await.is_err());
+    }
+}
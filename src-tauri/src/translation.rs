@@ -0,0 +1,227 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cloud::KEYRING_SERVICE;
+use crate::merger::TranscriptionSegment;
+use crate::stats::spoken_text;
+
+/// Texts sent per request; DeepL accepts at most 50
+const BATCH_SIZE: usize = 50;
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TranslationProvider {
+    Deepl,
+    Openai,
+}
+
+impl TranslationProvider {
+    fn keyring_user(&self) -> &'static str {
+        match self {
+            Self::Deepl => "translation-deepl",
+            Self::Openai => "translation-openai",
+        }
+    }
+}
+
+/// The translation step of a merge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslationOptions {
+    pub provider: TranslationProvider,
+    /// Language code of the translation ("en", "de", "pt-BR")
+    pub target_language: String,
+    /// Detected by the provider when not given
+    #[serde(default)]
+    pub source_language: Option<String>,
+    /// OpenAI model; `gpt-4o-mini` when not given
+    #[serde(default)]
+    pub model: Option<String>,
+}
+
+/// The merged segments in another language, with the original timing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranslatedTranscript {
+    pub language: String,
+    pub segments: Vec<TranscriptionSegment>,
+}
+
+fn keyring_entry(provider: TranslationProvider) -> Result<keyring::Entry> {
+    keyring::Entry::new(KEYRING_SERVICE, provider.keyring_user())
+        .map_err(|e| anyhow!("Keychain is not available: {}", e))
+}
+
+/// Stores the provider's API key in the OS keychain; an empty key removes it.
+pub fn set_api_key(provider: TranslationProvider, api_key: &str) -> Result<()> {
+    let entry = keyring_entry(provider)?;
+    if api_key.trim().is_empty() {
+        return match entry.delete_password() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(anyhow!("Failed to remove the API key: {}", e)),
+        };
+    }
+    entry
+        .set_password(api_key.trim())
+        .map_err(|e| anyhow!("Failed to store the API key: {}", e))
+}
+
+fn api_key(provider: TranslationProvider) -> Result<String> {
+    keyring_entry(provider)?
+        .get_password()
+        .map_err(|_| anyhow!("No API key stored for {:?}, please add one first", provider))
+}
+
+/// Translates the text of every segment, keeping times, speakers and the
+/// "Name:" labels. Merger-inserted markers are copied unchanged.
+pub async fn translate_segments(segments: &[TranscriptionSegment], options: &TranslationOptions) -> Result<TranslatedTranscript> {
+    if options.target_language.trim().is_empty() {
+        return Err(anyhow!("No target language given for the translation"));
+    }
+    let key = api_key(options.provider)?;
+
+    let indices: Vec<usize> = (0..segments.len()).filter(|&index| !segments[index].original_filename.is_empty()).collect();
+    let mut translations = Vec::with_capacity(indices.len());
+    for batch in indices.chunks(BATCH_SIZE) {
+        let texts: Vec<String> = batch.iter().map(|&index| spoken_text(&segments[index]).to_string()).collect();
+        let translated = match options.provider {
+            TranslationProvider::Deepl => translate_with_deepl(&key, &texts, options).await?,
+            TranslationProvider::Openai => translate_with_openai(&key, &texts, options).await?,
+        };
+        translations.extend(translated);
+    }
+
+    Ok(TranslatedTranscript {
+        language: options.target_language.trim().to_string(),
+        segments: apply_translations(segments, &indices, translations),
+    })
+}
+
+/// Puts `translations[i]` into the segment at `indices[i]`.
+fn apply_translations(segments: &[TranscriptionSegment], indices: &[usize], translations: Vec<String>) -> Vec<TranscriptionSegment> {
+    let mut translated = segments.to_vec();
+    for (&index, text) in indices.iter().zip(translations) {
+        let segment = &mut translated[index];
+        let labelled = spoken_text(segment) != segment.text.trim();
+        segment.text = match segment.speaker.as_deref() {
+            Some(speaker) if labelled => format!("{}: {}", speaker, text.trim()),
+            _ => text.trim().to_string(),
+        };
+        segment.words.clear();
+        segment.original_text = None;
+    }
+    translated
+}
+
+async fn translate_with_deepl(key: &str, texts: &[String], options: &TranslationOptions) -> Result<Vec<String>> {
+    // Keys of the free plan end in ":fx" and only work on the free endpoint
+    let url = if key.ends_with(":fx") {
+        "https://api-free.deepl.com/v2/translate"
+    } else {
+        "https://api.deepl.com/v2/translate"
+    };
+    let mut params: Vec<(&str, String)> = texts.iter().map(|text| ("text", text.clone())).collect();
+    params.push(("target_lang", options.target_language.trim().to_uppercase()));
+    if let Some(source) = &options.source_language {
+        params.push(("source_lang", source.trim().to_uppercase()));
+    }
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .header("Authorization", format!("DeepL-Auth-Key {}", key))
+        .form(&params)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("DeepL returned {}: {}", status, text));
+    }
+
+    let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+    let translations: Vec<String> = body["translations"]
+        .as_array()
+        .map(|items| items.iter().filter_map(|item| item["text"].as_str().map(str::to_string)).collect())
+        .unwrap_or_default();
+    check_count(translations, texts.len())
+}
+
+async fn translate_with_openai(key: &str, texts: &[String], options: &TranslationOptions) -> Result<Vec<String>> {
+    let source = options
+        .source_language
+        .as_deref()
+        .map(|source| format!(" from {}", source))
+        .unwrap_or_default();
+    let body = serde_json::json!({
+        "model": options.model.as_deref().unwrap_or(DEFAULT_OPENAI_MODEL),
+        "temperature": 0,
+        "messages": [
+            {
+                "role": "system",
+                "content": format!(
+                    "Translate each string of the JSON array{} into the language with the code '{}'. \
+                     The strings are consecutive subtitle cues. Reply with only a JSON array of the \
+                     translations, in the same order and with the same number of strings.",
+                    source,
+                    options.target_language.trim()
+                ),
+            },
+            { "role": "user", "content": serde_json::to_string(texts)? },
+        ],
+    });
+
+    let response = reqwest::Client::new()
+        .post("https://api.openai.com/v1/chat/completions")
+        .bearer_auth(key)
+        .header("Content-Type", "application/json")
+        .body(body.to_string())
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        return Err(anyhow!("OpenAI returned {}: {}", status, text));
+    }
+
+    let reply: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+    let content = reply["choices"][0]["message"]["content"].as_str().unwrap_or_default();
+    check_count(parse_json_array_reply(content)?, texts.len())
+}
+
+/// The JSON array of a chat reply, which models like to wrap in a code fence.
+fn parse_json_array_reply(content: &str) -> Result<Vec<String>> {
+    let start = content.find('[').ok_or_else(|| anyhow!("The translation reply has no JSON array"))?;
+    let end = content.rfind(']').ok_or_else(|| anyhow!("The translation reply has no JSON array"))?;
+    serde_json::from_str(&content[start..=end]).map_err(|e| anyhow!("Invalid translation reply: {}", e))
+}
+
+/// A lost or extra line would shift every later cue, so a count mismatch fails the translation.
+fn check_count(translations: Vec<String>, expected: usize) -> Result<Vec<String>> {
+    if translations.len() != expected {
+        return Err(anyhow!("Expected {} translations, received {}", expected, translations.len()));
+    }
+    Ok(translations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_translations() {
+        let segments = vec![
+            TranscriptionSegment::test(0.0, 2.0, "Анна: Добрый день.").file("a.srt").speaker("Анна"),
+            TranscriptionSegment::test(2.0, 4.0, "--- Реклама ---"),
+            TranscriptionSegment::test(4.0, 6.0, "Начнём.").file("a.srt"),
+        ];
+
+        let reply = "```json\n[\"Good afternoon.\", \"Let's begin.\"]\n```";
+        let translations = check_count(parse_json_array_reply(reply).unwrap(), 2).unwrap();
+        let translated = apply_translations(&segments, &[0, 2], translations);
+        let texts: Vec<&str> = translated.iter().map(|segment| segment.text.as_str()).collect();
+        assert_eq!(texts, vec!["Анна: Good afternoon.", "--- Реклама ---", "Let's begin."]);
+        assert_eq!(translated[2].end_time, Some(6.0));
+
+        assert!(check_count(vec!["One".to_string()], 2).is_err());
+        assert!(parse_json_array_reply("Sorry, I can't").is_err());
+    }
+}
@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransliterationScheme {
+    /// Cyrillic to plain ASCII Latin as in ICAO Doc 9303 (Russian passports):
+    /// "Щукин" → "Shchukin", "Юлия" → "Iuliia"
+    Icao,
+}
+
+/// Replaces Cyrillic letters with Latin ones; other characters are kept.
+/// Multi-letter replacements follow the case of the word, so "ЩИ" becomes
+/// "SHCHI" and "Щи" becomes "Shchi".
+pub fn transliterate(text: &str, scheme: TransliterationScheme) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut result = String::with_capacity(text.len());
+
+    for (index, &ch) in chars.iter().enumerate() {
+        let lower = ch.to_lowercase().next().unwrap_or(ch);
+        let Some(latin) = latin_for(lower, scheme) else {
+            result.push(ch);
+            continue;
+        };
+        if lower == ch {
+            result.push_str(latin);
+            continue;
+        }
+
+        let neighbour_upper = |offset: isize| {
+            index
+                .checked_add_signed(offset)
+                .and_then(|position| chars.get(position))
+                .is_some_and(|neighbour| neighbour.is_uppercase())
+        };
+        if neighbour_upper(-1) || neighbour_upper(1) {
+            result.push_str(&latin.to_uppercase());
+        } else {
+            let mut letters = latin.chars();
+            if let Some(first) = letters.next() {
+                result.extend(first.to_uppercase());
+                result.push_str(letters.as_str());
+            }
+        }
+    }
+
+    result
+}
+
+fn latin_for(ch: char, scheme: TransliterationScheme) -> Option<&'static str> {
+    match scheme {
+        TransliterationScheme::Icao => Some(match ch {
+            'а' => "a",
+            'б' => "b",
+            'в' => "v",
+            'г' => "g",
+            'ґ' => "g",
+            'д' => "d",
+            'е' | 'ё' | 'э' => "e",
+            'є' => "ie",
+            'ж' => "zh",
+            'з' => "z",
+            'и' | 'й' | 'і' | 'ї' => "i",
+            'к' => "k",
+            'л' => "l",
+            'м' => "m",
+            'н' => "n",
+            'о' => "o",
+            'п' => "p",
+            'р' => "r",
+            'с' => "s",
+            'т' => "t",
+            'у' => "u",
+            'ф' => "f",
+            'х' => "kh",
+            'ц' => "ts",
+            'ч' => "ch",
+            'ш' => "sh",
+            'щ' => "shch",
+            'ъ' => "ie",
+            'ы' => "y",
+            'ь' => "",
+            'ю' => "iu",
+            'я' => "ia",
+            _ => return None,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_icao_transliteration() {
+        assert_eq!(
+            transliterate("Юлия Щукина: «Объём — 5 МБ», ok", TransliterationScheme::Icao),
+            "Iuliia Shchukina: «Obieem — 5 MB», ok"
+        );
+        assert_eq!(transliterate("ЩИ и Щи", TransliterationScheme::Icao), "SHCHI i Shchi");
+    }
+}
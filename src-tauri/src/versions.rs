@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::merger::{MergeOptions, TranscriptionSegment};
+
+/// Times closer than this count as the same cue boundary
+const TIME_TOLERANCE: f64 = 0.001;
+
+/// A snapshot of the merged result kept in the project, so a later cleanup or
+/// edit can be compared with it or undone.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MergeVersion {
+    pub number: u32,
+    /// What the version is ("Merge", "After cleanup", ...)
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub options: MergeOptions,
+    pub segments: Vec<TranscriptionSegment>,
+    /// Set once the version went to the client; it cannot be edited after that
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<Delivery>,
+}
+
+/// When and to whom a version was delivered, for the audit trail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delivery {
+    pub delivered_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// SHA-256 of the delivered segments, to show later that they are unchanged
+    pub checksum: String,
+}
+
+/// A version without its segments, for listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub number: u32,
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    pub segments: usize,
+    pub delivery: Option<Delivery>,
+}
+
+impl MergeVersion {
+    pub fn info(&self) -> VersionInfo {
+        VersionInfo {
+            number: self.number,
+            label: self.label.clone(),
+            created_at: self.created_at,
+            segments: self.segments.len(),
+            delivery: self.delivery.clone(),
+        }
+    }
+}
+
+/// Appends a version numbered one past the latest.
+pub fn add_version(
+    versions: &mut Vec<MergeVersion>,
+    label: &str,
+    options: &MergeOptions,
+    segments: &[TranscriptionSegment],
+) -> VersionInfo {
+    let version = MergeVersion {
+        number: versions.iter().map(|version| version.number).max().unwrap_or(0) + 1,
+        label: label.trim().to_string(),
+        created_at: Utc::now(),
+        options: options.clone(),
+        segments: segments.to_vec(),
+        delivery: None,
+    };
+    let info = version.info();
+    versions.push(version);
+    info
+}
+
+pub fn find_version(versions: &[MergeVersion], number: u32) -> Result<&MergeVersion> {
+    versions
+        .iter()
+        .find(|version| version.number == number)
+        .ok_or_else(|| anyhow!("Version {} does not exist", number))
+}
+
+/// Marks a version as delivered. Delivery is final, so a version cannot be
+/// delivered twice.
+pub fn mark_delivered(
+    versions: &mut [MergeVersion],
+    number: u32,
+    recipient: Option<String>,
+    note: Option<String>,
+) -> Result<Delivery> {
+    let version = versions
+        .iter_mut()
+        .find(|version| version.number == number)
+        .ok_or_else(|| anyhow!("Version {} does not exist", number))?;
+    if let Some(delivery) = &version.delivery {
+        return Err(anyhow!("Version {} was already delivered on {}", number, delivery.delivered_at.format("%Y-%m-%d")));
+    }
+
+    let non_empty = |value: Option<String>| value.map(|value| value.trim().to_string()).filter(|value| !value.is_empty());
+    let delivery = Delivery {
+        delivered_at: Utc::now(),
+        recipient: non_empty(recipient),
+        note: non_empty(note),
+        checksum: format!("{:x}", Sha256::digest(serde_json::to_vec(&version.segments)?)),
+    };
+    version.delivery = Some(delivery.clone());
+    Ok(delivery)
+}
+
+/// Fails when `current`, the version the merged result was saved as or
+/// restored from, has been delivered.
+pub fn check_editable(versions: &[MergeVersion], current: Option<u32>) -> Result<()> {
+    let delivered = versions
+        .iter()
+        .find(|version| Some(version.number) == current)
+        .and_then(|version| version.delivery.as_ref().map(|delivery| (version.number, delivery)));
+    match delivered {
+        Some((number, delivery)) => Err(anyhow!(
+            "Version {} was delivered on {} and is read-only; save it as a new version to keep editing",
+            number,
+            delivery.delivered_at.format("%Y-%m-%d")
+        )),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    /// Same start, different text or end time
+    Changed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionChange {
+    pub kind: ChangeKind,
+    pub start_time: f64,
+    pub old_text: Option<String>,
+    pub new_text: Option<String>,
+}
+
+/// Differences between two merged results, in timeline order. Cues are paired
+/// by start time, so an edited cue shows as changed while a re-timed one
+/// shows as removed and added.
+pub fn diff_segments(old: &[TranscriptionSegment], new: &[TranscriptionSegment]) -> Vec<VersionChange> {
+    let change = |kind, segment: &TranscriptionSegment| VersionChange {
+        kind,
+        start_time: segment.start_time,
+        old_text: (kind == ChangeKind::Removed).then(|| segment.text.clone()),
+        new_text: (kind == ChangeKind::Added).then(|| segment.text.clone()),
+    };
+
+    let mut changes = Vec::new();
+    let (mut old_index, mut new_index) = (0, 0);
+    while old_index < old.len() || new_index < new.len() {
+        match (old.get(old_index), new.get(new_index)) {
+            (Some(before), Some(after)) if (before.start_time - after.start_time).abs() < TIME_TOLERANCE => {
+                let same_end = match (before.end_time, after.end_time) {
+                    (Some(a), Some(b)) => (a - b).abs() < TIME_TOLERANCE,
+                    (a, b) => a == b,
+                };
+                if before.text != after.text || !same_end {
+                    changes.push(VersionChange {
+                        kind: ChangeKind::Changed,
+                        start_time: after.start_time,
+                        old_text: Some(before.text.clone()),
+                        new_text: Some(after.text.clone()),
+                    });
+                }
+                old_index += 1;
+                new_index += 1;
+            }
+            (Some(before), Some(after)) if before.start_time < after.start_time => {
+                changes.push(change(ChangeKind::Removed, before));
+                old_index += 1;
+            }
+            (_, Some(after)) => {
+                changes.push(change(ChangeKind::Added, after));
+                new_index += 1;
+            }
+            (Some(before), None) => {
+                changes.push(change(ChangeKind::Removed, before));
+                old_index += 1;
+            }
+            (None, None) => break,
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_and_diff() {
+        let mut versions = Vec::new();
+        let raw = vec![
+            TranscriptionSegment::test(0.0, 2.0, "Добрый день"),
+            TranscriptionSegment::test(2.0, 4.0, "эээ ну начнём"),
+            TranscriptionSegment::test(4.0, 6.0, "Вопросы?"),
+        ];
+        let cleaned = vec![
+            TranscriptionSegment::test(0.0, 2.0, "Добрый день"),
+            TranscriptionSegment::test(2.0, 4.0, "Начнём."),
+            TranscriptionSegment::test(5.0, 7.0, "Вопросы?"),
+        ];
+        add_version(&mut versions, "Merge", &MergeOptions::default(), &raw);
+        let info = add_version(&mut versions, " After cleanup ", &MergeOptions::default(), &cleaned);
+        assert_eq!((info.number, info.label.as_str(), info.segments), (2, "After cleanup", 3));
+        assert!(find_version(&versions, 3).is_err());
+
+        let changes = diff_segments(&find_version(&versions, 1).unwrap().segments, &cleaned);
+        let summary: Vec<(ChangeKind, f64)> = changes.iter().map(|change| (change.kind, change.start_time)).collect();
+        assert_eq!(summary, vec![(ChangeKind::Changed, 2.0), (ChangeKind::Removed, 4.0), (ChangeKind::Added, 5.0)]);
+        assert_eq!(changes[0].old_text.as_deref(), Some("эээ ну начнём"));
+        assert!(diff_segments(&raw, &raw).is_empty());
+    }
+
+    #[test]
+    fn test_delivered_version_is_read_only() {
+        let mut versions = Vec::new();
+        add_version(&mut versions, "Merge", &MergeOptions::default(), &[TranscriptionSegment::test(0.0, 2.0, "Добрый день")]);
+        add_version(&mut versions, "Final", &MergeOptions::default(), &[TranscriptionSegment::test(0.0, 2.0, "Добрый день.")]);
+
+        let delivery = mark_delivered(&mut versions, 2, Some(" ООО Ромашка ".to_string()), Some(String::new())).unwrap();
+        assert_eq!((delivery.recipient.as_deref(), delivery.note.as_deref(), delivery.checksum.len()), (Some("ООО Ромашка"), None, 64));
+        assert!(mark_delivered(&mut versions, 2, None, None).is_err());
+
+        assert!(check_editable(&versions, Some(2)).is_err());
+        assert!(check_editable(&versions, Some(1)).is_ok());
+        assert!(check_editable(&versions, None).is_ok());
+    }
+}
@@ -0,0 +1,136 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::handoff::{render_sheet, SheetFormat};
+use crate::project::{OperationKind, Project};
+
+/// Prices for the work report; a line whose rate is zero is left out unless
+/// no rate is set at all.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkRates {
+    pub per_audio_minute: f64,
+    pub per_word: f64,
+    /// Written after the amounts ("EUR", "₽")
+    pub currency: String,
+    /// Bill every started minute, as most per-minute price lists do
+    pub round_up_minutes: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkReport {
+    pub project_name: String,
+    pub first_date: NaiveDate,
+    pub last_date: NaiveDate,
+    /// Audio split into chunks, or the length of the merged transcript when
+    /// the project has no processing runs
+    pub audio_minutes: f64,
+    /// Words of the latest merge
+    pub words: u64,
+    pub audio_amount: f64,
+    pub word_amount: f64,
+    pub total: f64,
+    pub currency: String,
+}
+
+/// Adds up the project history: the audio of every processing run and the
+/// words of the latest merge, priced with `rates`.
+pub fn work_report(project: &Project, rates: &WorkRates) -> WorkReport {
+    let details_of = |kind: OperationKind| project.history.iter().filter(move |entry| entry.kind == kind).map(|entry| &entry.details);
+    let last_merge = details_of(OperationKind::Merge).next_back();
+
+    let processed_seconds: f64 = details_of(OperationKind::Processing)
+        .filter_map(|details| details.get("audio_seconds").and_then(|seconds| seconds.as_f64()))
+        .sum();
+    let audio_seconds = if processed_seconds > 0.0 {
+        processed_seconds
+    } else {
+        last_merge
+            .and_then(|details| details.get("duration_seconds").and_then(|seconds| seconds.as_f64()))
+            .unwrap_or(0.0)
+    };
+    let audio_minutes = if rates.round_up_minutes {
+        (audio_seconds / 60.0).ceil()
+    } else {
+        audio_seconds / 60.0
+    };
+    let words = last_merge
+        .and_then(|details| details.get("words").and_then(|words| words.as_u64()))
+        .unwrap_or(0);
+
+    let dates: Vec<NaiveDate> = project.history.iter().map(|entry| entry.timestamp.date_naive()).collect();
+    let created = project.created_at.date_naive();
+
+    let audio_amount = round_cents(audio_minutes * rates.per_audio_minute);
+    let word_amount = round_cents(words as f64 * rates.per_word);
+    WorkReport {
+        project_name: project.name.clone(),
+        first_date: dates.iter().min().copied().unwrap_or(created),
+        last_date: dates.iter().max().copied().unwrap_or(created),
+        audio_minutes,
+        words,
+        audio_amount,
+        word_amount,
+        total: round_cents(audio_amount + word_amount),
+        currency: rates.currency.clone(),
+    }
+}
+
+/// The report as an Item / Quantity / Rate / Amount table.
+pub fn render_work_report(report: &WorkReport, rates: &WorkRates, format: SheetFormat) -> String {
+    let money = |amount: f64| format!("{:.2} {}", amount, report.currency).trim_end().to_string();
+    let text_row = |item: &str, value: String| vec![item.to_string(), value, String::new(), String::new()];
+
+    let mut rows = vec![
+        text_row("Project", report.project_name.clone()),
+        text_row("Period", format!("{} – {}", report.first_date, report.last_date)),
+    ];
+    if rates.per_audio_minute > 0.0 || rates.per_word == 0.0 {
+        rows.push(vec![
+            "Audio minutes".to_string(),
+            format!("{:.2}", report.audio_minutes),
+            money(rates.per_audio_minute),
+            money(report.audio_amount),
+        ]);
+    }
+    if rates.per_word > 0.0 || rates.per_audio_minute == 0.0 {
+        rows.push(vec!["Words".to_string(), report.words.to_string(), money(rates.per_word), money(report.word_amount)]);
+    }
+    rows.push(vec!["Total".to_string(), String::new(), String::new(), money(report.total)]);
+
+    render_sheet(&["Item", "Quantity", "Rate", "Amount"], &rows, format)
+}
+
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_work_report() {
+        let mut project = Project::new("Интервью", PathBuf::new());
+        project.record(OperationKind::Processing, "Split", serde_json::json!({ "audio_seconds": 1800.0 }));
+        project.record(OperationKind::Processing, "Split", serde_json::json!({ "audio_seconds": 1230.0 }));
+        project.record(OperationKind::Merge, "Merged", serde_json::json!({ "words": 4000, "duration_seconds": 3000.0 }));
+        project.record(OperationKind::Merge, "Merged again", serde_json::json!({ "words": 4210, "duration_seconds": 3000.0 }));
+        let rates = WorkRates { per_audio_minute: 1.5, currency: "EUR".to_string(), round_up_minutes: true, ..WorkRates::default() };
+
+        let report = work_report(&project, &rates);
+        assert_eq!((report.audio_minutes, report.words, report.total), (51.0, 4210, 76.5));
+
+        let csv = render_work_report(&report, &rates, SheetFormat::Csv);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "Item,Quantity,Rate,Amount");
+        assert_eq!(lines[3], "Audio minutes,51.00,1.50 EUR,76.50 EUR");
+        assert_eq!(lines[4], "Total,,,76.50 EUR");
+
+        // Without processing runs the merged transcript gives the duration
+        project.history.retain(|entry| entry.kind == OperationKind::Merge);
+        let report = work_report(&project, &WorkRates { per_word: 0.01, ..WorkRates::default() });
+        assert_eq!((report.audio_minutes, report.word_amount, report.total), (50.0, 42.1, 42.1));
+    }
+}
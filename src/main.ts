@@ -39,6 +39,8 @@ class TranscriptionAssistant {
   private open: any;
   private listen: any;
   private lastOutputPath: string | null = null;
+  private speakerChanges: number[] = [];
+  private chunkManifest: string | null = null;
 
   constructor(invoke: any, open: any, listen: any) {
     this.invoke = invoke;
@@ -94,6 +96,21 @@ class TranscriptionAssistant {
     await this.listen('ffmpeg-download-progress', (event: any) => {
       this.updateFFmpegDownloadProgress(event.payload.progress, event.payload.message);
     });
+
+    await this.listen('queue-finished', (event: any) => {
+      this.notifyQueueFinished(event.payload);
+    });
+  }
+
+  // Итог очереди по расписанию: системное уведомление, а без разрешения — окно с сообщением
+  private async notifyQueueFinished(summary: { completed: number; failed: number; lines: string[] }) {
+    const title = `Очередь обработана: готово ${summary.completed}, с ошибкой ${summary.failed}`;
+    const body = summary.lines.join('\n');
+    if ('Notification' in window && (Notification.permission === 'granted' || await Notification.requestPermission() === 'granted')) {
+      new Notification(title, { body });
+    } else {
+      alert(`${title}\n\n${body}`);
+    }
   }
 
   private async selectFile() {
@@ -164,6 +181,8 @@ class TranscriptionAssistant {
 
     const maxDuration = parseInt((document.getElementById('maxDuration') as HTMLInputElement).value);
     const useSilenceDetection = (document.getElementById('silenceDetection') as HTMLInputElement).checked;
+    const musicDetection = (document.getElementById('musicDetection') as HTMLSelectElement).value;
+    const speakerChangeHints = (document.getElementById('speakerChangeHints') as HTMLInputElement).checked;
 
     try {
       document.getElementById('progressSection')!.style.display = 'block';
@@ -172,7 +191,9 @@ class TranscriptionAssistant {
       await this.invoke('start_audio_processing', {
         filePath: this.selectedFile,
         maxDuration: maxDuration * 60, // Convert to seconds
-        useSilenceDetection
+        useSilenceDetection,
+        musicDetection,
+        speakerChangeHints
       });
     } catch (error) {
       console.error('Ошибка запуска обработки:', error);
@@ -184,7 +205,10 @@ class TranscriptionAssistant {
     const progressFill = document.getElementById('progressFill')!;
     const progressText = document.getElementById('progressText')!;
 
-    progressFill.style.width = `${progress}%`;
+    // Отрицательный прогресс — только сообщение (например, пауза на батарее)
+    if (progress >= 0) {
+      progressFill.style.width = `${progress}%`;
+    }
     progressText.textContent = message;
   }
 
@@ -216,6 +240,8 @@ class TranscriptionAssistant {
 
   private onProcessingComplete(result: any) {
     console.log('Обработка завершена:', result);
+    this.speakerChanges = result?.speaker_changes || [];
+    this.chunkManifest = result?.manifest_path || null;
     this.updateProgress(100, 'Обработка завершена!');
     
     // Add green gradient to completed progress bar
@@ -339,7 +365,7 @@ class TranscriptionAssistant {
         multiple: true,
         filters: [{
           name: 'Текстовые файлы',
-          extensions: ['txt', 'srt', 'md']
+          extensions: ['txt', 'srt', 'md', 'vtt', 'sbv', 'sub', 'json', 'docx']
         }]
       });
 
@@ -558,15 +584,69 @@ class TranscriptionAssistant {
       
       const result = await this.invoke('merge_transcriptions', {
         files: this.transcriptionFiles,
-        outputFormat
+        outputFormat,
+        options: {
+          // Порядок файлов задаётся в списке, а не номерами в именах
+          preserve_input_order: true,
+          hallucination_filter: (document.getElementById('removeHallucinations') as HTMLInputElement).checked ? 'remove' : 'off',
+          reproducible: (document.getElementById('reproducibleMerge') as HTMLInputElement).checked,
+          // Подсказки о смене говорящего из последней обработки аудио
+          ...(this.speakerChanges.length > 0
+            ? { speaker_change_markers: true, speaker_changes: this.speakerChanges }
+            : {})
+        },
+        // Точные начала фрагментов вместо оценки длительности файлов
+        chunkManifest: this.chunkManifest
       });
 
       console.log('Объединение завершено:', result);
+      if (result?.language) {
+        console.log('Язык транскрипции:', result.language);
+      }
+      // Перекрытия между файлами обычно означают неверное смещение или порядок фрагментов
+      const overlaps = (result?.file_boundaries || []).filter((b: any) => b.gap_seconds < 0);
+      if (overlaps.length > 0) {
+        console.warn('Перекрытия между файлами:', overlaps.map((b: any) => b.message));
+      }
+      // Файл на другом языке чаще всего попал в объединение по ошибке
+      if (result?.language_warnings?.length) {
+        console.warn('Файлы на другом языке:', result.language_warnings);
+      }
+      if (Array.isArray(result?.file_order)) {
+        this.transcriptionFiles = result.file_order;
+        this.displayTranscriptionFiles();
+      }
       mergeBtn.textContent = '✅ Объединено!';
       (document.getElementById('exportBtn') as HTMLButtonElement).disabled = false;
       
-      // Показать сообщение об успехе
-      this.showMergeStatus('✅ Транскрипции успешно объединены! Готово к экспорту.', 'success');
+      // Показать сообщение об успехе (и о пропущенных фрагментах, если они есть)
+      const warnings = (result as any)?.warnings || [];
+      if (warnings.length > 0) {
+        console.warn('Предупреждения разбора:', warnings);
+        const details = warnings
+          .slice(0, 3)
+          .map((w: any) => `${w.file}:${w.line} — ${w.reason}`)
+          .join('; ');
+        this.showMergeStatus(`⚠️ Транскрипции объединены, пропущено фрагментов: ${warnings.length}. ${details}`, 'success');
+      } else if (result?.hallucinations?.length > 0) {
+        this.showMergeStatus(`✅ Транскрипции объединены, убрано галлюцинаций: ${result.hallucinations.length}. Готово к экспорту.`, 'success');
+      } else {
+        this.showMergeStatus('✅ Транскрипции успешно объединены! Готово к экспорту.', 'success');
+      }
+
+      // Фрагменты, почти не покрытые текстом: вероятно, распознавание сбилось или оборвалось
+      if (this.chunkManifest) {
+        const coverage = await this.invoke('check_transcript_coverage', {});
+        if (coverage?.warnings?.length > 0) {
+          console.warn('Покрытие фрагментов:', coverage.chunks);
+          const details = coverage.chunks
+            .filter((chunk: any) => chunk.coverage < 0.5)
+            .slice(0, 3)
+            .map((chunk: any) => `фрагмент ${chunk.chunk_number}: ${Math.round(chunk.coverage * 100)}%`)
+            .join('; ');
+          this.showMergeStatus(`⚠️ Часть фрагментов почти без текста (${coverage.warnings.length}). ${details}`, 'success');
+        }
+      }
       
       setTimeout(() => {
         mergeBtn.textContent = originalText;
@@ -609,6 +689,11 @@ class TranscriptionAssistant {
     const timecodeFormatSelect = document.getElementById('timecodeFormat') as HTMLSelectElement;
     const customTimecodeFormatInput = document.getElementById('customTimecodeFormat') as HTMLInputElement;
     const includeExtendedInfoCheckbox = document.getElementById('includeExtendedInfo') as HTMLInputElement;
+    const exportProfileSelect = document.getElementById('exportProfile') as HTMLSelectElement;
+    const textCaseSelect = document.getElementById('textCase') as HTMLSelectElement;
+    const transliterateCheckbox = document.getElementById('transliterate') as HTMLInputElement;
+    const appendExportCheckbox = document.getElementById('appendExport') as HTMLInputElement;
+    const openAfterExportCheckbox = document.getElementById('openAfterExport') as HTMLInputElement;
     
     const originalText = exportBtn.textContent;
     
@@ -618,11 +703,6 @@ class TranscriptionAssistant {
       return;
     }
     
-    if (!outputFileNameInput.value.trim()) {
-      alert('Пожалуйста, укажите имя файла');
-      return;
-    }
-    
     try {
       exportBtn.disabled = true;
       exportBtn.textContent = '📤 Экспортируем...';
@@ -633,16 +713,30 @@ class TranscriptionAssistant {
         outputFormat: outputFormatSelect.value,
         timecodeFormat: timecodeFormatSelect.value,
         customTimecodeFormat: timecodeFormatSelect.value === 'custom' ? customTimecodeFormatInput.value : null,
-        includeExtendedInfo: includeExtendedInfoCheckbox.checked
+        includeExtendedInfo: includeExtendedInfoCheckbox.checked,
+        exportOptions: {
+          profile: exportProfileSelect.value || null,
+          // Термины из глоссария сохраняют своё написание
+          text_case: textCaseSelect.value,
+          transliteration: transliterateCheckbox.checked ? 'icao' : null,
+          append: appendExportCheckbox.checked,
+          open_after_export: openAfterExportCheckbox.checked ? 'open' : null,
+          // HTML-страница проигрывает исходный файл, выбранный для обработки
+          media_path: outputFormatSelect.value === 'html' ? this.selectedFile : null
+        }
       });
       console.log('Экспорт завершен:', result);
       
       exportBtn.textContent = '✅ Экспортировано!';
       
       // Показать сообщение об успехе
-      const message = result?.path 
+      let message = result?.path 
         ? `✅ Файл успешно экспортирован в: ${result.path}`
         : '✅ Экспорт завершен успешно!';
+      if (result?.changes?.length > 0) {
+        console.log('Изменения после замен:', result.changes);
+        message += ` (изменено сегментов: ${result.changes.length})`;
+      }
       this.showExportStatus(message, 'success');
       
       setTimeout(() => {
@@ -699,7 +793,7 @@ class TranscriptionAssistant {
           
           // Check if it's a media file
           const mediaExtensions = ['mp4', 'avi', 'mov', 'mkv', 'webm', 'flv', 'wmv', 'mp3', 'wav', 'aac', 'flac', 'ogg', 'm4a', 'wma', 'opus'];
-          const transcriptionExtensions = ['txt', 'srt', 'md'];
+          const transcriptionExtensions = ['txt', 'srt', 'md', 'vtt', 'sbv', 'sub', 'json', 'docx'];
           
           if (mediaExtensions.includes(extension || '')) {
             mediaFiles.push(filePath);